@@ -32,25 +32,35 @@
 //!
 //! - Resilient fetching with exponential backoff
 //! - High-performance in-memory buffering
-//! - Cryptographic packet signing (HMAC-SHA256)
+//! - Cryptographic packet signing (HMAC with the hash chosen by `HmacAlgo`, or Ed25519)
 //! - Graceful shutdown with buffer flushing
 //! - Comprehensive metrics and logging
+//! - Pluggable push transports (HTTP, file/FIFO, UDP)
+
+mod transport;
 
 use anyhow::{Context, Result};
+use axum::{extract::State, http::StatusCode, routing::get, Router};
+use bytes::Bytes;
 use clap::Parser;
 use qrng_core::{
     buffer::EntropyBuffer,
     config::CollectorConfig,
     crypto::PacketSigner,
-    fetcher::{EntropyFetcher, FetcherConfig},
+    fetcher::{source_for_appliance, EntropySource},
     metrics::Metrics,
     mixer::EntropyMixer,
-    protocol::EntropyPacket,
+    protocol::{EntropyPacket, GatewayStatus},
+    retry::next_backoff,
+    spool::SpoolStore,
 };
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::interval;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
+use transport::{transport_for_config, GatewayFull, PushTransport};
+use zeroize::Zeroizing;
 
 #[derive(Parser, Debug)]
 #[command(name = "qrng-collector")]
@@ -59,42 +69,248 @@ struct Args {
     /// Log level (trace, debug, info, warn, error)
     #[arg(short, long, default_value = "info")]
     log_level: String,
+
+    /// TOML configuration file; QRNG_* environment variables override its values
+    #[arg(short, long)]
+    config: Option<std::path::PathBuf>,
+}
+
+/// Maximum bytes sent in a single push
+const MAX_PUSH_BATCH: usize = 1024 * 1024;
+
+/// Push that failed after its first `delivered` bytes were accepted
+#[derive(Debug)]
+struct PushFailure {
+    delivered: usize,
+    error: anyhow::Error,
+}
+
+/// Attempts at flushing the buffer to the gateway during graceful shutdown
+const SHUTDOWN_FLUSH_ATTEMPTS: u32 = 3;
+
+/// Upper bound of the backoff between retries of failed pushes
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Upper bound of the fetch backoff while every source is failing
+const MAX_FETCH_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Initial backoff after a gateway reports its buffer full (507)
+const GATEWAY_FULL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound of the backoff while a gateway keeps reporting its buffer full
+const MAX_GATEWAY_FULL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Proportional gain of the gateway fill controller
+const FILL_KP: f64 = 2.0;
+
+/// Integral gain of the gateway fill controller
+const FILL_KI: f64 = 0.1;
+
+/// Buffer fill below which the fetch interval shortens toward its floor
+const LOW_FILL_PERCENT: f64 = 30.0;
+
+/// Buffer fill above which the fetch interval lengthens toward its ceiling
+const HIGH_FILL_PERCENT: f64 = 90.0;
+
+/// Fetch or push retry backoff at or beyond which `/health` reports the collector unhealthy
+const UNHEALTHY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Consecutive failures after which a source is left out of the mix
+const SOURCE_QUARANTINE_FAILURES: u32 = 5;
+
+/// How long a failing source is left out before it is tried again
+const SOURCE_QUARANTINE: Duration = Duration::from_secs(60);
+
+/// How often per-source fetch statistics are logged
+const SOURCE_SUMMARY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Holds small accumulations back until they fill a worthwhile packet
+///
+/// Data is pushed once `min_bytes` are buffered, or once some data has been
+/// waiting `max_latency` so a slow trickle still reaches the gateway.
+struct PushCoalescer {
+    min_bytes: usize,
+    max_latency: Duration,
+    /// When data was first seen waiting below `min_bytes`
+    pending_since: Option<std::time::Instant>,
+}
+
+impl PushCoalescer {
+    fn new(min_bytes: usize, max_latency: Duration) -> Self {
+        Self {
+            min_bytes,
+            max_latency,
+            pending_since: None,
+        }
+    }
+
+    /// Whether `buffered` bytes should be pushed at `now`
+    fn ready(&mut self, buffered: usize, now: std::time::Instant) -> bool {
+        if buffered == 0 {
+            self.pending_since = None;
+            return false;
+        }
+        let since = *self.pending_since.get_or_insert(now);
+        if buffered >= self.min_bytes || now.duration_since(since) >= self.max_latency {
+            self.pending_since = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// Fetch interval adapted to the collector's buffer fill
+///
+/// Between the low and high fill marks the base interval is used. Below the low
+/// mark it shrinks linearly to `min` at an empty buffer; above the high mark it
+/// grows linearly to `max` at a full buffer.
+fn adaptive_fetch_interval(fill_percent: f64, base: Duration, min: Duration, max: Duration) -> Duration {
+    let fill = fill_percent.clamp(0.0, 100.0);
+    if fill < LOW_FILL_PERCENT {
+        let ratio = fill / LOW_FILL_PERCENT;
+        min + (base - min).mul_f64(ratio)
+    } else if fill > HIGH_FILL_PERCENT {
+        let ratio = (fill - HIGH_FILL_PERCENT) / (100.0 - HIGH_FILL_PERCENT);
+        base + (max - base).mul_f64(ratio)
+    } else {
+        base
+    }
+}
+
+/// PI controller scaling push batches to hold the gateway buffer near a target fill
+///
+/// The output is the fraction of `MAX_PUSH_BATCH` to push on the next tick. The
+/// integral term settles at the gateway's consumption rate, so the fill converges
+/// to the target instead of stopping short of it.
+struct FillController {
+    target_percent: f64,
+    integral: f64,
+}
+
+impl FillController {
+    fn new(target_percent: f64) -> Self {
+        Self {
+            target_percent,
+            integral: 0.0,
+        }
+    }
+
+    /// Feed the gateway's reported fill and get the batch fraction (0.0-1.0) to push
+    fn update(&mut self, fill_percent: f64) -> f64 {
+        let error = (self.target_percent - fill_percent) / 100.0;
+        // Clamp the integral to avoid windup while the gateway is full or starving
+        self.integral = (self.integral + FILL_KI * error).clamp(0.0, 1.0);
+        (FILL_KP * error + self.integral).clamp(0.0, 1.0)
+    }
+}
+
+/// Fetch statistics of one entropy source
+#[derive(Default)]
+struct SourceHealth {
+    fetches: AtomicU64,
+    failures: AtomicU64,
+    consecutive_failures: AtomicU32,
+    last_error: std::sync::Mutex<Option<String>>,
+    quarantined_until: std::sync::Mutex<Option<std::time::Instant>>,
+}
+
+impl SourceHealth {
+    fn record_success(&self) {
+        self.fetches.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.quarantined_until.lock().unwrap() = None;
+    }
+
+    /// Record a failed fetch; returns true when this failure quarantines the source
+    fn record_failure(&self, error: String) -> bool {
+        self.fetches.fetch_add(1, Ordering::Relaxed);
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock().unwrap() = Some(error);
+
+        // Once quarantined, every failed retry extends the quarantine
+        let consecutive = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if consecutive >= SOURCE_QUARANTINE_FAILURES {
+            *self.quarantined_until.lock().unwrap() = Some(std::time::Instant::now() + SOURCE_QUARANTINE);
+        }
+        consecutive == SOURCE_QUARANTINE_FAILURES
+    }
+
+    fn is_quarantined(&self) -> bool {
+        self.quarantined_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| std::time::Instant::now() < until)
+    }
 }
 
 /// Main collector application state
 struct Collector {
     config: CollectorConfig,
-    fetchers: Vec<EntropyFetcher>,
+    sources: Vec<Box<dyn EntropySource>>,
+    source_health: Vec<SourceHealth>,
     mixer: Option<EntropyMixer>,
     buffer: EntropyBuffer,
+    retry_queue: EntropyBuffer,
+    spool: Option<SpoolStore>,
+    retry_after: Arc<tokio::sync::RwLock<Option<std::time::Instant>>>,
+    retry_backoff: Arc<tokio::sync::RwLock<Duration>>,
     signer: PacketSigner,
+    encryption_key: Option<Zeroizing<Vec<u8>>>,
+    transport: Box<dyn PushTransport>,
     http_client: reqwest::Client,
     metrics: Metrics,
     sequence: Arc<std::sync::atomic::AtomicU64>,
     backoff_until: Arc<tokio::sync::RwLock<Option<std::time::Instant>>>,
     fetch_backoff_duration: Arc<tokio::sync::RwLock<Duration>>,
+    gateway_full_backoff: Arc<tokio::sync::RwLock<Duration>>,
 }
 
 impl Collector {
     fn new(config: CollectorConfig) -> Result<Self> {
-        // Parse HMAC secret key
-        let hmac_key =
-            hex::decode(&config.hmac_secret_key).context("Failed to decode HMAC secret key")?;
-        let signer = PacketSigner::new(hmac_key);
+        // Create entropy sources (HTTPS appliances, files/FIFOs, or /dev/urandom)
+        let mut sources = Vec::new();
+        for appliance in &config.sources() {
+            sources.push(source_for_appliance(appliance, config.fetch_chunk_size)?);
+        }
 
-        // Create fetchers for all sources
-        let urls = config.get_appliance_urls();
-        let mut fetchers = Vec::new();
+        // Create HTTP client for pushing
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
 
-        for url in &urls {
-            let fetcher_config = FetcherConfig::new(url.parse()?, config.fetch_chunk_size);
-            let fetcher = EntropyFetcher::new(fetcher_config)?;
-            fetchers.push(fetcher);
-        }
+        Self::with_sources(config, sources, http_client)
+    }
+
+    /// Build a collector around the given entropy sources and push client
+    ///
+    /// `sources` are indexed like `config.sources()`, whose weights drive the mixer.
+    fn with_sources(
+        config: CollectorConfig,
+        sources: Vec<Box<dyn EntropySource>>,
+        http_client: reqwest::Client,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            sources.len() == config.sources().len(),
+            "Expected {} entropy source(s), got {}",
+            config.sources().len(),
+            sources.len()
+        );
 
-        // Create mixer if multiple sources
-        let mixer = if config.has_multiple_sources() {
-            Some(EntropyMixer::new(config.mixing_strategy))
+        // Build the packet signer (HMAC or Ed25519)
+        let signer = config.packet_signer().context("Failed to load packet signing key")?;
+
+        // Parse optional payload encryption key
+        let encryption_key = config.encryption_key_bytes()?;
+
+        // Create mixer if multiple sources or whitening
+        let mixer = if config.has_multiple_sources() || config.whiten_output {
+            let weights = config.sources().iter().map(|appliance| appliance.weight).collect();
+            Some(
+                EntropyMixer::new(config.mixing_strategy)
+                    .with_weights(weights)
+                    .with_length_policy(config.mixing_length_policy)
+                    .with_whitening(config.whiten_output),
+            )
         } else {
             None
         };
@@ -102,22 +318,46 @@ impl Collector {
         // Create buffer
         let buffer = EntropyBuffer::new(config.buffer_size);
 
-        // Create HTTP client for pushing
-        let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
+        // Create retry queue for failed pushes, restoring payloads saved at shutdown
+        let retry_queue = Self::restore_retry_queue(&config)
+            .unwrap_or_else(|| EntropyBuffer::new(config.push_retry_queue_size));
+        let retry_backoff = Duration::from_millis(config.initial_backoff_ms);
+
+        // Open the disk spool catching failed pushes that overflow the retry queue
+        let spool = config
+            .spool_dir
+            .as_ref()
+            .map(|dir| SpoolStore::open(dir, config.spool_max_bytes))
+            .transpose()
+            .context("Failed to open spool directory")?;
+        if let Some(spool) = spool.as_ref().filter(|spool| !spool.is_empty()) {
+            info!("Found {} spooled payloads ({} bytes) to replay", spool.len(), spool.size_bytes());
+        }
+
+        // Channel packets leave the collector on
+        let transport = transport_for_config(&config, http_client.clone())?;
+
+        let source_health = sources.iter().map(|_| SourceHealth::default()).collect();
 
         Ok(Self {
             config,
-            fetchers,
+            sources,
+            source_health,
             mixer,
             buffer,
+            retry_queue,
+            spool,
+            retry_after: Arc::new(tokio::sync::RwLock::new(None)),
+            retry_backoff: Arc::new(tokio::sync::RwLock::new(retry_backoff)),
             signer,
+            encryption_key,
+            transport,
             http_client,
             metrics: Metrics::new(),
             sequence: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             backoff_until: Arc::new(tokio::sync::RwLock::new(None)),
             fetch_backoff_duration: Arc::new(tokio::sync::RwLock::new(Duration::from_secs(1))),
+            gateway_full_backoff: Arc::new(tokio::sync::RwLock::new(GATEWAY_FULL_BACKOFF)),
         })
     }
 
@@ -127,17 +367,34 @@ impl Collector {
         info!("The collector runs in the same network as the Quantis Appliance and pushes data to the gateway via unidirectional flow.");
         info!("Developed by Valer BOCAN, PhD, CSSLP - www.bocan.ro");
 
-        let urls = self.config.get_appliance_urls();
-        info!("Configured {} source(s)", urls.len());
-        for (i, url) in urls.iter().enumerate() {
-            info!("  Source {}: {}", i + 1, url);
+        let sources = self.config.sources();
+        info!("Configured {} source(s)", sources.len());
+        for (i, source) in sources.iter().enumerate() {
+            info!(
+                "  Source {} ({}): {} [weight {}, format {:?}]",
+                i + 1,
+                source.name,
+                source.url,
+                source.weight,
+                source.format
+            );
         }
         
-        if urls.len() > 1 {
+        if sources.len() > 1 {
             info!("Mixing strategy: {:?}", self.config.mixing_strategy);
         }
+        if self.config.whiten_output {
+            info!("SHA-256 output whitening enabled");
+        }
 
-        info!("Random data is pushed to URL: {}", self.config.push_url);
+        info!("Random data is pushed to {}", self.transport.destination());
+        match self.signer.ed25519_public_key() {
+            Some(public_key) => info!("Packets are signed with Ed25519, public key: {}", hex::encode(public_key)),
+            None => info!(
+                "Packets are signed with {}",
+                self.signer.hmac_algo().unwrap_or_default().name()
+            ),
+        }
         info!("Buffer size: {} bytes", self.config.buffer_size);
         info!("Fetch interval: {:?} sec.", self.config.fetch_interval());
         info!("Push interval: {:?} sec.", self.config.push_interval());
@@ -154,19 +411,40 @@ impl Collector {
             tokio::spawn(async move { collector.push_loop().await })
         };
 
+        // Serve /metrics and /health if configured
+        let metrics_handle = match &self.config.collector_metrics_addr {
+            Some(addr) => {
+                let listener = tokio::net::TcpListener::bind(addr)
+                    .await
+                    .with_context(|| format!("Failed to bind metrics listener on {}", addr))?;
+                info!("Serving /metrics and /health on {}", addr);
+                let router = Arc::clone(&self).http_router();
+                Some(tokio::spawn(async move {
+                    if let Err(e) = axum::serve(listener, router).await {
+                        error!("Metrics server failed: {}", e);
+                    }
+                }))
+            }
+            None => None,
+        };
+
         // Wait for shutdown signal
         Self::wait_for_shutdown().await;
 
         info!("Shutdown signal received, flushing buffer...");
 
-        // Attempt final push
-        if let Err(e) = self.push_buffer().await {
-            error!("Failed to flush buffer on shutdown: {}", e);
-        }
+        // Push everything left, keeping what the gateway will not take
+        self.flush_on_shutdown().await;
+
+        // Keep payloads of failed pushes for the next start
+        self.save_retry_queue();
 
         // Clean up
         fetch_handle.abort();
         push_handle.abort();
+        if let Some(handle) = metrics_handle {
+            handle.abort();
+        }
 
         info!("Collector shut down gracefully");
         Ok(())
@@ -174,11 +452,25 @@ impl Collector {
 
     /// Fetch loop: continuously fetch data from appliances
     async fn fetch_loop(self: Arc<Self>) {
-        let mut ticker = interval(self.config.fetch_interval());
         const HIGH_WATER_MARK: f64 = 98.0;
+        let base_interval = self.config.fetch_interval();
+        let (min_interval, max_interval) = self.config.fetch_interval_bounds();
+        let mut last_summary = std::time::Instant::now();
 
         loop {
-            ticker.tick().await;
+            if self.sources.len() > 1 && last_summary.elapsed() >= SOURCE_SUMMARY_INTERVAL {
+                self.log_source_summary();
+                last_summary = std::time::Instant::now();
+            }
+
+            // Fetch faster while the buffer drains and slower while it is nearly full
+            let delay = adaptive_fetch_interval(
+                self.buffer.fill_percent(),
+                base_interval,
+                min_interval,
+                max_interval,
+            );
+            tokio::time::sleep(delay).await;
 
             // Check if we're in backoff period
             let backoff = self.backoff_until.read().await;
@@ -196,7 +488,7 @@ impl Collector {
                 info!("Buffer at {:.1}%, triggering immediate push", fill_percent);
                 let self_clone = Arc::clone(&self);
                 tokio::spawn(async move {
-                    if let Err(e) = self_clone.push_buffer().await {
+                    if let Err(e) = self_clone.push_buffer(MAX_PUSH_BATCH).await {
                         error!("Emergency push failed: {}", e);
                     }
                 });
@@ -208,75 +500,31 @@ impl Collector {
                 continue;
             }
 
-            // Fetch from all sources in parallel
-            let fetch_results = {
-                let mut handles = Vec::new();
-                for (i, fetcher) in self.fetchers.iter().enumerate() {
-                    let fetcher = fetcher.clone();
-                    let handle = tokio::spawn(async move {
-                        (i, fetcher.fetch().await)
-                    });
-                    handles.push(handle);
-                }
-
-                let mut results = Vec::new();
-                for handle in handles {
-                    if let Ok(result) = handle.await {
-                        results.push(result);
-                    }
-                }
-                results
-            };
-
-            // Process results
-            let mut chunks = Vec::new();
-            let mut failed_sources = Vec::new();
-
-            for (i, result) in fetch_results {
-                match result {
-                    Ok(data) => {
-                        chunks.push(data);
-                    }
-                    Err(e) => {
-                        failed_sources.push((i, e));
-                    }
-                }
-            }
-
-            // Log failures
-            for (i, e) in &failed_sources {
-                warn!("Source {} fetch failed: {}", i + 1, e);
-            }
+            let chunks = self.fetch_sources(self.config.fetch_chunk_size).await;
 
             // Mix if we have multiple chunks
             let final_data = if chunks.is_empty() {
                 self.metrics.record_fetch_failure();
                 
-                // Apply exponential backoff when all sources fail
+                // Apply jittered exponential backoff when all sources fail
                 let current_backoff = *self.fetch_backoff_duration.read().await;
-                let next_backoff = (current_backoff * 2).min(Duration::from_secs(300)); // Cap at 5 minutes
-                *self.fetch_backoff_duration.write().await = next_backoff;
+                let (next, wait) = next_backoff(current_backoff, MAX_FETCH_BACKOFF);
+                *self.fetch_backoff_duration.write().await = next;
                 
-                let backoff_until = std::time::Instant::now() + current_backoff;
+                let backoff_until = std::time::Instant::now() + wait;
                 *self.backoff_until.write().await = Some(backoff_until);
                 
                 error!(
-                    "All sources failed to fetch, backing off for {} seconds",
-                    current_backoff.as_secs()
+                    "All sources failed to fetch, backing off for {:.1} seconds",
+                    wait.as_secs_f64()
                 );
                 continue;
-            } else if chunks.len() == 1 {
-                // Reset backoff on successful fetch
-                *self.fetch_backoff_duration.write().await = Duration::from_secs(1);
-                *self.backoff_until.write().await = None;
-                
-                chunks.into_iter().next().unwrap()
             } else if let Some(mixer) = &self.mixer {
                 // Reset backoff on successful fetch
                 *self.fetch_backoff_duration.write().await = Duration::from_secs(1);
                 *self.backoff_until.write().await = None;
                 
-                match mixer.mix(&chunks) {
+                match mixer.mix_sources(&chunks) {
                     Ok(mixed) => {
                         info!("Mixed {} sources into {} bytes", chunks.len(), mixed.len());
                         mixed
@@ -288,8 +536,11 @@ impl Collector {
                     }
                 }
             } else {
-                // Shouldn't happen, but fallback to first chunk
-                chunks.into_iter().next().unwrap()
+                // Reset backoff on successful fetch
+                *self.fetch_backoff_duration.write().await = Duration::from_secs(1);
+                *self.backoff_until.write().await = None;
+                
+                chunks.into_iter().next().unwrap().1
             };
 
             // Push to buffer
@@ -309,10 +560,70 @@ impl Collector {
         }
     }
 
+    /// Fetch from every source not in quarantine, in parallel, tracking per-source health
+    ///
+    /// Returns the chunks that arrived, tagged with their source index. While
+    /// every source is quarantined all of them are tried, so a single flaky
+    /// appliance is never left out entirely.
+    async fn fetch_sources(&self, chunk_size: usize) -> Vec<(usize, Vec<u8>)> {
+        let mut active: Vec<usize> = (0..self.sources.len())
+            .filter(|&i| !self.source_health[i].is_quarantined())
+            .collect();
+        if active.is_empty() {
+            active = (0..self.sources.len()).collect();
+        }
+
+        let fetch_results = futures::future::join_all(active.into_iter().map(|i| async move {
+            (i, self.sources[i].fetch(chunk_size).await)
+        }))
+        .await;
+
+        let mut chunks = Vec::new();
+        for (i, result) in fetch_results {
+            match result {
+                Ok(data) => {
+                    self.source_health[i].record_success();
+                    chunks.push((i, data));
+                }
+                Err(e) => {
+                    warn!("Source {} fetch failed: {}", i + 1, e);
+                    if self.source_health[i].record_failure(e.to_string()) {
+                        warn!(
+                            "Source {} failed {} times in a row, leaving it out of the mix for {:?}",
+                            i + 1,
+                            SOURCE_QUARANTINE_FAILURES,
+                            SOURCE_QUARANTINE
+                        );
+                    }
+                }
+            }
+        }
+        chunks
+    }
+
+    /// Log fetch and failure counts of every source
+    fn log_source_summary(&self) {
+        for (i, health) in self.source_health.iter().enumerate() {
+            let last_error = health.last_error.lock().unwrap().clone();
+            info!(
+                "Source {}: {} fetches, {} failed{}{}",
+                i + 1,
+                health.fetches.load(Ordering::Relaxed),
+                health.failures.load(Ordering::Relaxed),
+                if health.is_quarantined() { ", quarantined" } else { "" },
+                last_error.map(|e| format!(", last error: {}", e)).unwrap_or_default()
+            );
+        }
+    }
+
     /// Push loop: periodically push buffered data to gateway
     async fn push_loop(self: Arc<Self>) {
         let mut ticker = interval(self.config.push_interval());
         const MIN_PUSH_THRESHOLD: f64 = 1.0;
+        let mut controller = self.config.target_fill_percent.map(FillController::new);
+        let mut coalescer = (self.config.min_push_bytes > 0).then(|| {
+            PushCoalescer::new(self.config.min_push_bytes, self.config.max_push_latency())
+        });
 
         loop {
             ticker.tick().await;
@@ -327,32 +638,86 @@ impl Collector {
             }
             drop(backoff);
 
-            let fill_percent = self.buffer.fill_percent();
+            // Redeliver payloads of earlier failed pushes first
+            if let Err(e) = self.retry_failed_pushes().await {
+                warn!("Retry of failed push failed: {}", e);
+            }
 
-            if self.buffer.is_empty() {
+            // Coalesce small fetches into fewer, larger packets when configured
+            let buffered = self.buffer.len();
+            let ready = match coalescer.as_mut() {
+                Some(coalescer) => coalescer.ready(buffered, std::time::Instant::now()),
+                None => buffered > 0 && self.buffer.fill_percent() >= MIN_PUSH_THRESHOLD,
+            };
+            if !ready {
                 continue;
             }
 
-            if fill_percent >= MIN_PUSH_THRESHOLD {
-                if let Err(e) = self.push_buffer().await {
-                    error!("Push failed: {}", e);
-                }
+            // Steer toward the gateway fill target, pushing at full rate if status is unavailable
+            let max_batch = match controller.as_mut() {
+                Some(controller) => match self.query_gateway_fill().await {
+                    Ok(gateway_fill) => {
+                        let fraction = controller.update(gateway_fill);
+                        debug!(
+                            "Gateway fill {:.1}%, pushing {:.0}% of max batch",
+                            gateway_fill,
+                            fraction * 100.0
+                        );
+                        (MAX_PUSH_BATCH as f64 * fraction) as usize
+                    }
+                    Err(e) => {
+                        warn!("Failed to query gateway status: {}", e);
+                        MAX_PUSH_BATCH
+                    }
+                },
+                None => MAX_PUSH_BATCH,
+            };
+
+            if max_batch == 0 {
+                continue;
+            }
+
+            if let Err(e) = self.push_buffer(max_batch).await {
+                error!("Push failed: {}", e);
             }
         }
     }
 
+    /// Query the gateway's current buffer fill percentage
+    async fn query_gateway_fill(&self) -> Result<f64> {
+        let push_url = self
+            .transport
+            .gateway_url()
+            .context("Gateway fill is only available over the http push transport")?;
+        let url = CollectorConfig::status_url_for(&push_url)?;
+        let api_key = self.config.gateway_api_key.as_deref().unwrap_or_default();
+
+        let response = self
+            .http_client
+            .get(url)
+            .bearer_auth(api_key)
+            .send()
+            .await?
+            .error_for_status()?;
+        let body = response.bytes().await?;
+        let status: GatewayStatus =
+            serde_json::from_slice(&body).context("Invalid gateway status response")?;
+
+        Ok(status.buffer_fill_percent)
+    }
+
     /// Push accumulated data to gateway
-    async fn push_buffer(&self) -> Result<()> {
+    async fn push_buffer(&self, max_batch: usize) -> Result<()> {
         // Calculate batch size dynamically to allow partial packet accumulation
         // This ensures the gateway buffer can reach 100% regardless of packet/buffer size ratios
-        // Use available data up to 1MB, allowing any size (not constrained to fixed packets)
+        // Use available data up to max_batch, allowing any size (not constrained to fixed packets)
         let available = self.buffer.len();
         if available == 0 {
             warn!("No data available to push");
             return Ok(());
         }
         
-        let batch_size = available.min(1024 * 1024);
+        let batch_size = available.min(max_batch);
         let data = match self.buffer.pop(batch_size) {
             Some(d) => d,
             None => {
@@ -361,77 +726,368 @@ impl Collector {
             }
         };
 
+        self.send_entropy(&data).await.map_err(|failure| {
+            self.requeue_failed(data.slice(failure.delivered..));
+            failure.error
+        })
+    }
+
+    /// Push the whole buffer before exiting, retrying a busy gateway a few times
+    ///
+    /// The buffer is sent in batches of at most `MAX_PUSH_BATCH` until it is
+    /// empty or `shutdown_flush_timeout_secs` has passed; a push already in
+    /// flight at the deadline is bounded by the HTTP request timeout.
+    /// Success is only reported once every batch was acknowledged with 2xx.
+    /// Otherwise the unsent data goes to the disk spool when configured, or
+    /// to the retry queue saved at shutdown.
+    async fn flush_on_shutdown(&self) -> bool {
+        let mut pending: Option<Bytes> = None;
+        let mut flushed = 0;
+        let mut backoff = Duration::from_millis(self.config.initial_backoff_ms);
+        let timeout = self.config.shutdown_flush_timeout();
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        for attempt in 1..=SHUTDOWN_FLUSH_ATTEMPTS {
+            let result = loop {
+                if tokio::time::Instant::now() >= deadline {
+                    break Err(anyhow::anyhow!("flush deadline of {:?} passed", timeout));
+                }
+                let data = match pending.take() {
+                    Some(data) => data,
+                    None => match self.buffer.pop(self.buffer.len().min(MAX_PUSH_BATCH)) {
+                        Some(data) if !data.is_empty() => data,
+                        _ => break Ok(()),
+                    },
+                };
+                if let Err(failure) = self.send_entropy(&data).await {
+                    flushed += failure.delivered;
+                    pending = Some(data.slice(failure.delivered..));
+                    break Err(failure.error);
+                }
+                flushed += data.len();
+            };
+
+            match result {
+                Ok(()) => {
+                    info!("Flushed {} bytes to the gateway on shutdown", flushed);
+                    return true;
+                }
+                Err(e) if attempt < SHUTDOWN_FLUSH_ATTEMPTS
+                    && tokio::time::Instant::now() + backoff < deadline =>
+                {
+                    warn!(
+                        "Shutdown flush attempt {}/{} failed: {}; retrying in {:?}",
+                        attempt, SHUTDOWN_FLUSH_ATTEMPTS, e, backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to flush buffer on shutdown after {} attempts ({} bytes flushed): {}",
+                        attempt, flushed, e
+                    );
+                    break;
+                }
+            }
+        }
+
+        // Oldest first: the batch that failed, then the rest of the buffer
+        let mut unsent = pending.map(|data| data.to_vec()).unwrap_or_default();
+        unsent.extend_from_slice(&self.buffer.drain_all());
+        if unsent.is_empty() {
+            return false;
+        }
+        match &self.spool {
+            Some(spool) => match spool.enqueue(&unsent) {
+                Ok(_) => info!("Spooled {} unflushed bytes", unsent.len()),
+                Err(e) => error!("Failed to spool unflushed buffer: {}", e),
+            },
+            None => self.requeue_failed(Bytes::from(unsent)),
+        }
+        false
+    }
+
+    /// Retry the oldest payloads of failed pushes once their backoff has elapsed
+    async fn retry_failed_pushes(&self) -> Result<()> {
+        let spool_empty = self.spool.as_ref().is_none_or(SpoolStore::is_empty);
+        if self.retry_queue.is_empty() && spool_empty {
+            return Ok(());
+        }
+        if let Some(until) = *self.retry_after.read().await {
+            if std::time::Instant::now() < until {
+                return Ok(());
+            }
+        }
+
+        let Some(data) = self.take_failed_batch() else {
+            return Ok(());
+        };
+
+        match self.send_entropy(&data).await {
+            Ok(()) => {
+                info!("Redelivered {} bytes from retry queue", data.len());
+                *self.retry_backoff.write().await = Duration::from_millis(self.config.initial_backoff_ms);
+                *self.retry_after.write().await = None;
+                Ok(())
+            }
+            Err(failure) => {
+                self.requeue_failed(data.slice(failure.delivered..));
+
+                let mut backoff = self.retry_backoff.write().await;
+                let (next, wait) = next_backoff(*backoff, MAX_RETRY_BACKOFF);
+                *self.retry_after.write().await = Some(std::time::Instant::now() + wait);
+                *backoff = next;
+                Err(failure.error)
+            }
+        }
+    }
+
+    /// Next batch awaiting redelivery: the retry queue first, then the oldest spooled payloads
+    fn take_failed_batch(&self) -> Option<Bytes> {
+        if !self.retry_queue.is_empty() {
+            let batch_size = self.retry_queue.len().min(MAX_PUSH_BATCH);
+            return self.retry_queue.pop(batch_size);
+        }
+
+        let spooled = self.spool.as_ref()?.drain(MAX_PUSH_BATCH);
+        if spooled.is_empty() {
+            return None;
+        }
+        debug!("Replaying {} spooled payloads", spooled.len());
+        Some(Bytes::from(spooled.concat()))
+    }
+
+    /// Hold the payload of a failed push for redelivery
+    ///
+    /// The retry queue is separate from the main buffer so failed entropy does not
+    /// compete with new fetches for space. Overflow goes to the disk spool when
+    /// configured, otherwise it spills into the main buffer.
+    fn requeue_failed(&self, data: Bytes) {
+        let queued = self.retry_queue.push(data.clone()).unwrap_or(0);
+        if queued == data.len() {
+            return;
+        }
+
+        if let Some(spool) = &self.spool {
+            match spool.enqueue(&data[queued..]) {
+                Ok(0) => return,
+                Ok(evicted) => {
+                    warn!("Spool full, evicted {} bytes of the oldest failed pushes", evicted);
+                    return;
+                }
+                Err(e) => error!("Failed to spool failed push: {}", e),
+            }
+        }
+
+        let spilled = self.buffer.push(data.slice(queued..)).unwrap_or(0);
+        let dropped = data.len() - queued - spilled;
+        if dropped > 0 {
+            warn!("Retry queue and buffer full, dropped {} bytes of failed push", dropped);
+        }
+    }
+
+    /// Restore the retry queue saved at the last shutdown, if any
+    ///
+    /// The file is deleted once loaded so the same entropy is never pushed twice.
+    fn restore_retry_queue(config: &CollectorConfig) -> Option<EntropyBuffer> {
+        let path = config.push_retry_queue_path.as_ref()?;
+        if !std::path::Path::new(path).exists() {
+            return None;
+        }
+
+        let restored = EntropyBuffer::load_from_path(path, config.push_retry_queue_size, None);
+        // Re-pushing restored bytes while the snapshot survives would let a later restart push them again
+        if let Err(e) = std::fs::remove_file(path) {
+            error!("Failed to remove retry queue snapshot {}, discarding its contents: {}", path, e);
+            return None;
+        }
+
+        match restored {
+            Ok(queue) => {
+                info!("Restored {} bytes of failed pushes from {}", queue.len(), path);
+                Some(queue)
+            }
+            Err(e) => {
+                warn!("Ignoring unreadable retry queue snapshot {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Save the retry queue to disk if persistence is configured
+    ///
+    /// Without a snapshot path the queue is moved to the disk spool instead, if configured.
+    fn save_retry_queue(&self) {
+        if self.retry_queue.is_empty() {
+            return;
+        }
+        let Some(path) = self.config.push_retry_queue_path.as_ref() else {
+            if let Some(spool) = &self.spool {
+                let data = self.retry_queue.pop(self.retry_queue.len()).unwrap_or_default();
+                match spool.enqueue(&data) {
+                    Ok(_) => info!("Spooled {} bytes of failed pushes", data.len()),
+                    Err(e) => error!("Failed to spool retry queue: {}", e),
+                }
+            }
+            return;
+        };
+
+        match self.retry_queue.save_to_path(path) {
+            Ok(bytes) => info!("Saved {} bytes of failed pushes to {}", bytes, path),
+            Err(e) => error!("Failed to save retry queue to {}: {}", path, e),
+        }
+    }
+
+    /// Sign and send `data` to the gateway in packets of at most `max_packet_bytes`
+    ///
+    /// Every fragment is its own sequenced packet with a checksum and signature;
+    /// the gateway buffer is byte-oriented, so arriving fragments need no
+    /// reassembly. On failure, the bytes delivered before the failing fragment
+    /// are reported so only the remainder is retried.
+    async fn send_entropy(&self, data: &Bytes) -> std::result::Result<(), PushFailure> {
+        let mut delivered = 0;
+        for fragment in data.chunks(self.config.max_packet_bytes) {
+            if let Err(error) = self.send_packet(fragment).await {
+                return Err(PushFailure { delivered, error });
+            }
+            delivered += fragment.len();
+        }
+        Ok(())
+    }
+
+    /// Sign and send one entropy packet to the gateway
+    async fn send_packet(&self, data: &[u8]) -> Result<()> {
         // Create packet
         let sequence = self
             .sequence
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         let mut packet = EntropyPacket::new(sequence, data.to_vec());
+        packet.source_tag = self.config.source_tag.clone();
+
+        // Encrypt payload (checksum and signature then cover the ciphertext)
+        if let Some(key) = &self.encryption_key {
+            packet.encrypt_payload(key)?;
+        }
 
         // Add checksum
-        packet.checksum = Some(packet.calculate_checksum());
+        packet.set_checksum(self.config.checksum_algo);
 
         // Sign packet
         self.signer.sign_packet(&mut packet)?;
 
         // Serialize
-        let serialized = packet.to_msgpack()?;
+        let serialized = if self.config.packet_compression {
+            packet.encode_compressed(self.config.packet_format)?
+        } else {
+            packet.encode(self.config.packet_format)?
+        };
 
         info!(
-            "Pushing packet #{} ({} bytes, checksum: {:08x})",
+            "Pushing packet #{} ({} bytes, {:?} checksum: {})",
             packet.sequence,
             packet.payload_size(),
-            packet.checksum.unwrap()
+            packet.checksum_algo,
+            hex::encode(packet.calculate_checksum())
         );
 
-        // Send to gateway
-        let response = self
-            .http_client
-            .post(&self.config.push_url)
-            .header("Content-Type", "application/msgpack")
-            .body(serialized.clone())
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            self.metrics.record_push(packet.payload_size());
-            info!("Push successful ({})", response.status());
-            
-            // Clear backoff on success
-            *self.backoff_until.write().await = None;
-            Ok(())
-        } else {
-            self.metrics.record_push_failure();
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            
-            // Apply exponential backoff for 507 Insufficient Storage
-            if status == 507 {
-                let backoff_read = self.backoff_until.read().await;
-                let current_backoff = *backoff_read;
-                drop(backoff_read);
-                
-                let backoff_duration = if current_backoff.is_none() {
-                    Duration::from_secs(1)
-                } else {
-                    Duration::from_secs(5)
-                };
-                
-                let backoff_until = std::time::Instant::now() + backoff_duration;
-                *self.backoff_until.write().await = Some(backoff_until);
-                
-                warn!(
-                    "Gateway buffer full (507), backing off for {} seconds",
-                    backoff_duration.as_secs()
-                );
+        match self.transport.push(&serialized).await {
+            Ok(()) => {
+                self.metrics.record_push(data.len());
+
+                // Clear backoff on success
+                *self.backoff_until.write().await = None;
+                *self.gateway_full_backoff.write().await = GATEWAY_FULL_BACKOFF;
+                Ok(())
+            }
+            Err(e) => {
+                self.metrics.record_push_failure();
+
+                // Apply jittered exponential backoff for 507 Insufficient Storage
+                if e.is::<GatewayFull>() {
+                    let mut backoff = self.gateway_full_backoff.write().await;
+                    let (next, wait) = next_backoff(*backoff, MAX_GATEWAY_FULL_BACKOFF);
+                    *backoff = next;
+                    drop(backoff);
+
+                    *self.backoff_until.write().await = Some(std::time::Instant::now() + wait);
+                    warn!(
+                        "Gateway buffer full (507), backing off for {:.1} seconds",
+                        wait.as_secs_f64()
+                    );
+                }
+                Err(e)
             }
-            
-            error!("Push failed with status {}: {}", status, body);
+        }
+    }
+
+    /// Router of the collector's monitoring endpoints
+    fn http_router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/metrics", get(serve_metrics))
+            .route("/health", get(serve_health))
+            .with_state(self)
+    }
+
+    /// Prometheus metrics: fetch/push counters, buffer, retry queue and spool levels, and per-source health
+    fn prometheus_format(&self) -> String {
+        let mut output = self.metrics.collector_prometheus_format();
 
-            // Put data back in buffer
-            self.buffer.push(packet.data)?;
+        let gauges = [
+            ("qrng_collector_buffer_fill_percent", "Collector buffer fill percentage", self.buffer.fill_percent()),
+            ("qrng_collector_buffer_bytes", "Bytes held in the collector buffer", self.buffer.len() as f64),
+            ("qrng_retry_queue_bytes", "Bytes of failed pushes awaiting redelivery", self.retry_queue.len() as f64),
+            (
+                "qrng_spool_bytes",
+                "Bytes of failed pushes spooled to disk",
+                self.spool.as_ref().map_or(0, SpoolStore::size_bytes) as f64,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+            output.push_str(&format!("# TYPE {} gauge\n", name));
+            output.push_str(&format!("{} {}\n", name, value));
+        }
 
-            Err(anyhow::anyhow!("Push failed: {}", status))
+        // Per-source series, labelled with the source index in configuration order
+        let health = &self.source_health;
+        let per_source = [
+            (
+                "qrng_source_fetches_total",
+                "Fetch attempts per source",
+                "counter",
+                health.iter().map(|h| h.fetches.load(Ordering::Relaxed)).collect::<Vec<_>>(),
+            ),
+            (
+                "qrng_source_fetch_failures_total",
+                "Failed fetches per source",
+                "counter",
+                health.iter().map(|h| h.failures.load(Ordering::Relaxed)).collect(),
+            ),
+            (
+                "qrng_source_quarantined",
+                "Whether the source is left out of the mix (0/1)",
+                "gauge",
+                health.iter().map(|h| h.is_quarantined() as u64).collect(),
+            ),
+        ];
+        for (name, help, kind, values) in per_source {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+            output.push_str(&format!("# TYPE {} {}\n", name, kind));
+            for (i, value) in values.into_iter().enumerate() {
+                output.push_str(&format!("{}{{source=\"{}\"}} {}\n", name, i, value));
+            }
         }
+
+        output
+    }
+
+    /// Healthy while the collector holds or has delivered entropy and is not stuck in backoff
+    async fn is_healthy(&self) -> bool {
+        let has_entropy = !self.buffer.is_empty() || self.metrics.pushes_total() > 0;
+        let fetch_backing_off = *self.fetch_backoff_duration.read().await >= UNHEALTHY_BACKOFF;
+        let push_backing_off = *self.retry_backoff.read().await >= UNHEALTHY_BACKOFF;
+        has_entropy && !fetch_backing_off && !push_backing_off
     }
 
     /// Wait for shutdown signal (SIGINT or SIGTERM)
@@ -443,7 +1099,7 @@ impl Collector {
             use signal_hook_tokio::Signals;
 
             let mut signals =
-                Signals::new(&[SIGINT, SIGTERM]).expect("Failed to register signal handlers");
+                Signals::new([SIGINT, SIGTERM]).expect("Failed to register signal handlers");
 
             if let Some(signal) = signals.next().await {
                 info!("Received signal: {:?}", signal);
@@ -460,6 +1116,20 @@ impl Collector {
     }
 }
 
+/// GET /metrics - Prometheus metrics
+async fn serve_metrics(State(collector): State<Arc<Collector>>) -> String {
+    collector.prometheus_format()
+}
+
+/// GET /health - 200 while entropy is flowing, 503 when empty or in prolonged backoff
+async fn serve_health(State(collector): State<Arc<Collector>>) -> StatusCode {
+    if collector.is_healthy().await {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command-line arguments
@@ -481,11 +1151,782 @@ async fn main() -> Result<()> {
     info!("QRNG Collector v{}", env!("CARGO_PKG_VERSION"));
 
     // Load configuration from environment variables
-    info!("Loading configuration from environment variables");
-    let config =
-        CollectorConfig::from_env().context("Failed to load configuration from environment")?;
+    let config = match &args.config {
+        Some(path) => {
+            info!("Loading configuration from {}", path.display());
+            CollectorConfig::from_file(path)
+                .with_context(|| format!("Failed to load configuration from {}", path.display()))?
+        }
+        None => {
+            info!("Loading configuration from environment variables");
+            CollectorConfig::from_env().context("Failed to load configuration from environment")?
+        }
+    };
 
     // Create and run collector
     let collector = Arc::new(Collector::new(config)?);
     collector.run().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrng_core::crypto::HmacAlgo;
+    use qrng_core::fetcher::MockSource;
+    use qrng_core::protocol::read_framed_packet;
+    use std::sync::atomic::AtomicUsize;
+
+    fn test_config(push_url: String) -> serde_json::Value {
+        serde_json::json!({
+            "appliance_urls": ["urandom:"],
+            "push_url": push_url,
+            "hmac_secret_key": "00112233445566778899aabbccddeeff",
+            "fetch_chunk_size": 16,
+            "buffer_size": 64,
+            "initial_backoff_ms": 1,
+            "push_retry_queue_size": 1024,
+        })
+    }
+
+    fn test_collector(push_url: String, retry_queue_path: Option<String>) -> Collector {
+        let mut config = test_config(push_url);
+        config["push_retry_queue_path"] = retry_queue_path.into();
+        Collector::new(serde_json::from_value(config).unwrap()).unwrap()
+    }
+
+    /// Matches pushed packets carrying exactly `expected` as payload
+    fn payload_matcher(expected: Vec<u8>) -> impl Fn(&mockito::Request) -> bool {
+        move |request| {
+            request
+                .body()
+                .ok()
+                .and_then(|body| EntropyPacket::from_msgpack(body).ok())
+                .is_some_and(|packet| packet.data == expected)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_push_is_retried_despite_buffer_pressure() {
+        let mut server = mockito::Server::new_async().await;
+        let collector = test_collector(format!("{}/push", server.url()), None);
+
+        let payload: Vec<u8> = (0..32).collect();
+        collector.buffer.push(payload.clone()).unwrap();
+
+        // First push fails; the payload moves to the retry queue
+        let failing = server
+            .mock("POST", "/push")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        assert!(collector.push_buffer(MAX_PUSH_BATCH).await.is_err());
+        failing.assert_async().await;
+        failing.remove_async().await;
+        assert_eq!(collector.retry_queue.len(), 32);
+
+        // New fetches fill the main buffer to capacity without evicting it
+        collector.buffer.push(vec![0xAA; 128]).unwrap();
+        assert_eq!(collector.buffer.fill_percent(), 100.0);
+        assert_eq!(collector.retry_queue.len(), 32);
+
+        // A second failure backs off (with full jitter, up to the 1ms initial backoff)
+        let failing = server
+            .mock("POST", "/push")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        assert!(collector.retry_failed_pushes().await.is_err());
+        let retry_after = collector.retry_after.read().await.expect("backing off");
+        assert!(retry_after <= std::time::Instant::now() + Duration::from_millis(1));
+        assert_eq!(*collector.retry_backoff.read().await, Duration::from_millis(2));
+        failing.assert_async().await;
+        failing.remove_async().await;
+
+        // Once the gateway recovers, the original payload is delivered
+        let delivered = server
+            .mock("POST", "/push")
+            .match_request(payload_matcher(payload))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        collector.retry_failed_pushes().await.unwrap();
+        delivered.assert_async().await;
+        assert!(collector.retry_queue.is_empty());
+        assert_eq!(collector.buffer.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_retry_queue_persisted_across_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("qrng-retry-queue-{}.snapshot", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let push_url = "http://127.0.0.1:9/push".to_string();
+
+        let collector = test_collector(push_url.clone(), Some(path.clone()));
+        collector.requeue_failed(Bytes::from(vec![7u8; 48]));
+        collector.save_retry_queue();
+
+        // The restored queue is consumed from disk so it is never pushed twice
+        let restarted = test_collector(push_url.clone(), Some(path.clone()));
+        assert_eq!(restarted.retry_queue.pop(48).unwrap().as_ref(), &[7u8; 48]);
+        assert!(!std::path::Path::new(&path).exists());
+        assert!(test_collector(push_url, Some(path.clone())).retry_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_pushes_spool_to_disk_and_replay() {
+        let dir = std::env::temp_dir().join(format!("qrng-collector-spool-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut server = mockito::Server::new_async().await;
+        let mut config = test_config(format!("{}/push", server.url()));
+        config["push_retry_queue_size"] = 16.into();
+        config["spool_dir"] = dir.to_string_lossy().into_owned().into();
+        let collector = Collector::new(serde_json::from_value(config.clone()).unwrap()).unwrap();
+
+        // The gateway is out of space; overflow of the retry queue goes to disk
+        let failing = server
+            .mock("POST", "/push")
+            .with_status(507)
+            .expect(2)
+            .create_async()
+            .await;
+        collector.buffer.push(vec![1u8; 40]).unwrap();
+        assert!(collector.push_buffer(MAX_PUSH_BATCH).await.is_err());
+        collector.buffer.push(vec![2u8; 40]).unwrap();
+        assert!(collector.push_buffer(MAX_PUSH_BATCH).await.is_err());
+        failing.assert_async().await;
+        failing.remove_async().await;
+
+        assert_eq!(collector.retry_queue.len(), 16);
+        assert!(collector.buffer.is_empty());
+        let spool = collector.spool.as_ref().unwrap();
+        assert_eq!(spool.len(), 2);
+        assert_eq!(spool.size_bytes(), 64);
+
+        // The spool survives a restart and replays oldest first after the retry queue
+        collector.save_retry_queue();
+        drop(collector);
+        let restarted = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+        assert!(restarted.retry_queue.is_empty());
+
+        let mut expected = vec![1u8; 24];
+        expected.extend_from_slice(&[2u8; 40]);
+        expected.extend_from_slice(&[1u8; 16]);
+        let delivered = server
+            .mock("POST", "/push")
+            .match_request(payload_matcher(expected))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        restarted.retry_failed_pushes().await.unwrap();
+        delivered.assert_async().await;
+        assert!(restarted.spool.as_ref().unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flush_retries_busy_gateway() {
+        let mut server = mockito::Server::new_async().await;
+        let collector = test_collector(format!("{}/push", server.url()), None);
+
+        let payload: Vec<u8> = (0..48).collect();
+        collector.buffer.push(payload.clone()).unwrap();
+
+        // Busy twice, then the same payload is accepted
+        let busy = server
+            .mock("POST", "/push")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let accepted = server
+            .mock("POST", "/push")
+            .match_request(payload_matcher(payload))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        assert!(collector.flush_on_shutdown().await);
+        busy.assert_async().await;
+        accepted.assert_async().await;
+        assert!(collector.buffer.is_empty());
+        assert!(collector.retry_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flush_pushes_whole_buffer() {
+        let (push_url, received) = spawn_mock_gateway().await;
+        let mut config = test_config(push_url);
+        config["buffer_size"] = (3 * 1024 * 1024).into();
+        config["max_packet_bytes"] = (1024 * 1024).into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        assert_eq!(collector.buffer.push(data.clone()).unwrap(), data.len());
+
+        assert!(collector.flush_on_shutdown().await);
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received.concat(), data);
+        assert!(collector.buffer.is_empty());
+        assert!(collector.retry_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flush_stops_at_deadline() {
+        let (push_url, received) = spawn_mock_gateway().await;
+        let mut config = test_config(push_url);
+        config["shutdown_flush_timeout_secs"] = 0.into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+        collector.buffer.push(vec![5u8; 40]).unwrap();
+
+        // Nothing is pushed once the deadline has passed; the data is kept for the next start
+        assert!(!collector.flush_on_shutdown().await);
+        assert!(received.lock().unwrap().is_empty());
+        assert!(collector.buffer.is_empty());
+        assert_eq!(collector.retry_queue.len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flush_spools_when_gateway_stays_down() {
+        let dir = std::env::temp_dir().join(format!("qrng-collector-flush-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut server = mockito::Server::new_async().await;
+        let mut config = test_config(format!("{}/push", server.url()));
+        config["spool_dir"] = dir.to_string_lossy().into_owned().into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+        collector.buffer.push(vec![7u8; 40]).unwrap();
+
+        let failing = server
+            .mock("POST", "/push")
+            .with_status(503)
+            .expect(SHUTDOWN_FLUSH_ATTEMPTS as usize)
+            .create_async()
+            .await;
+
+        assert!(!collector.flush_on_shutdown().await);
+        failing.assert_async().await;
+        assert!(collector.buffer.is_empty());
+        assert!(collector.retry_queue.is_empty());
+        assert_eq!(collector.spool.as_ref().unwrap().drain(1024), vec![Bytes::from(vec![7u8; 40])]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_adaptive_fetch_interval() {
+        let base = Duration::from_millis(100);
+        let min = Duration::from_millis(20);
+        let max = Duration::from_millis(500);
+        let at = |fill| adaptive_fetch_interval(fill, base, min, max);
+
+        // Drained buffer fetches at the floor, shortening linearly up to the low mark
+        assert_eq!(at(0.0), min);
+        assert_eq!(at(15.0), Duration::from_millis(60));
+        assert_eq!(at(LOW_FILL_PERCENT), base);
+
+        // Normal fill keeps the configured interval
+        assert_eq!(at(50.0), base);
+        assert_eq!(at(HIGH_FILL_PERCENT), base);
+
+        // Nearly full buffer backs off toward the ceiling
+        assert_eq!(at(95.0), Duration::from_millis(300));
+        assert_eq!(at(100.0), max);
+        assert_eq!(at(120.0), max);
+        assert_eq!(at(-5.0), min);
+    }
+
+    fn multi_gateway_collector(push_urls: Vec<String>, push_mode: &str) -> Collector {
+        let mut config = test_config(String::new());
+        config["push_urls"] = push_urls.into();
+        config["push_mode"] = push_mode.into();
+        Collector::new(serde_json::from_value(config).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_failover_push_remembers_last_good_url() {
+        let mut primary = mockito::Server::new_async().await;
+        let mut secondary = mockito::Server::new_async().await;
+        let collector = multi_gateway_collector(
+            vec![format!("{}/push", primary.url()), format!("{}/push", secondary.url())],
+            "failover",
+        );
+
+        // The primary is down; the packet goes to the secondary
+        let primary_down = primary
+            .mock("POST", "/push")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let secondary_up = secondary
+            .mock("POST", "/push")
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+        collector.send_entropy(&Bytes::from(vec![1u8; 16])).await.unwrap();
+        assert_eq!(collector.transport.gateway_url(), Some(format!("{}/push", secondary.url())));
+
+        // Later pushes go straight to the last good gateway
+        collector.send_entropy(&Bytes::from(vec![2u8; 16])).await.unwrap();
+        primary_down.assert_async().await;
+        secondary_up.assert_async().await;
+
+        // When every gateway fails, so does the push
+        secondary_up.remove_async().await;
+        let secondary_down = secondary
+            .mock("POST", "/push")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        let primary_down = primary
+            .mock("POST", "/push")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        assert!(collector.send_entropy(&Bytes::from(vec![3u8; 16])).await.is_err());
+        secondary_down.assert_async().await;
+        primary_down.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_replicate_push_redelivers_only_to_failed_gateways() {
+        let mut first = mockito::Server::new_async().await;
+        let mut second = mockito::Server::new_async().await;
+        let collector = multi_gateway_collector(
+            vec![format!("{}/push", first.url()), format!("{}/push", second.url())],
+            "replicate",
+        );
+        let packet_matcher = |expected: Vec<u8>, sequence: u64| {
+            move |request: &mockito::Request| {
+                request
+                    .body()
+                    .ok()
+                    .and_then(|body| EntropyPacket::from_msgpack(body).ok())
+                    .is_some_and(|packet| packet.data == expected && packet.sequence == sequence)
+            }
+        };
+        let mock = |server: &mut mockito::Server, payload: &[u8], sequence: u64, status: usize| {
+            server
+                .mock("POST", "/push")
+                .match_request(packet_matcher(payload.to_vec(), sequence))
+                .with_status(status)
+                .expect(1)
+                .create()
+        };
+        let (a, b, c, d) = (vec![1u8; 32], vec![2u8; 32], vec![3u8; 32], vec![4u8; 32]);
+
+        // Every gateway receives the packet
+        let mocks = [mock(&mut first, &a, 0, 200), mock(&mut second, &a, 0, 200)];
+        collector.buffer.push(a).unwrap();
+        collector.push_buffer(MAX_PUSH_BATCH).await.unwrap();
+
+        // One gateway failing does not resend the payload to the one that accepted it
+        let first_b = first
+            .mock("POST", "/push")
+            .match_request(payload_matcher(b.clone()))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        let second_down = mock(&mut second, &b, 1, 500);
+        collector.buffer.push(b.clone()).unwrap();
+        collector.push_buffer(MAX_PUSH_BATCH).await.unwrap();
+        assert!(collector.retry_queue.is_empty());
+        second_down.assert_async().await;
+        second_down.remove_async().await;
+
+        // The failed gateway gets the original signed packet before the next one;
+        // 409 from its replay guard means it had stored it after all
+        let later = [
+            mock(&mut second, &b, 1, 409),
+            mock(&mut second, &c, 2, 200),
+            mock(&mut first, &c, 2, 200),
+        ];
+        collector.buffer.push(c).unwrap();
+        collector.push_buffer(MAX_PUSH_BATCH).await.unwrap();
+        // The accepting gateway received the bytes exactly once, under any sequence
+        first_b.assert_async().await;
+        for mock in mocks.iter().chain(&later) {
+            mock.assert_async().await;
+        }
+
+        // Only a push no gateway accepted goes to the retry queue
+        let down = [mock(&mut first, &d, 3, 500), mock(&mut second, &d, 3, 500)];
+        collector.buffer.push(d).unwrap();
+        assert!(collector.push_buffer(MAX_PUSH_BATCH).await.is_err());
+        for mock in &down {
+            mock.assert_async().await;
+        }
+        assert_eq!(collector.retry_queue.len(), 32);
+    }
+
+    /// Simulated gateway buffer drained by clients at a constant rate
+    struct MockGateway {
+        capacity: usize,
+        level: usize,
+        consumption_per_tick: usize,
+        rejected_pushes: usize,
+    }
+
+    impl MockGateway {
+        fn fill_percent(&self) -> f64 {
+            self.level as f64 / self.capacity as f64 * 100.0
+        }
+
+        fn push(&mut self, bytes: usize) {
+            if self.level + bytes > self.capacity {
+                self.rejected_pushes += 1;
+            }
+            self.level = (self.level + bytes).min(self.capacity);
+        }
+
+        fn consume(&mut self) {
+            self.level = self.level.saturating_sub(self.consumption_per_tick);
+        }
+    }
+
+    fn run_controller(target: f64, consumption_per_tick: usize, initial_level: usize) -> MockGateway {
+        let mut gateway = MockGateway {
+            capacity: 10 * 1024 * 1024,
+            level: initial_level,
+            consumption_per_tick,
+            rejected_pushes: 0,
+        };
+        let mut controller = FillController::new(target);
+
+        for _ in 0..500 {
+            let fraction = controller.update(gateway.fill_percent());
+            gateway.push((MAX_PUSH_BATCH as f64 * fraction) as usize);
+            gateway.consume();
+        }
+        gateway
+    }
+
+    #[test]
+    fn test_fill_controller_converges_from_empty() {
+        let gateway = run_controller(60.0, 200 * 1024, 0);
+        assert!((gateway.fill_percent() - 60.0).abs() < 2.0, "fill {}", gateway.fill_percent());
+        assert_eq!(gateway.rejected_pushes, 0);
+    }
+
+    #[test]
+    fn test_fill_controller_converges_from_full() {
+        let gateway = run_controller(40.0, 300 * 1024, 10 * 1024 * 1024);
+        assert!((gateway.fill_percent() - 40.0).abs() < 2.0, "fill {}", gateway.fill_percent());
+    }
+
+    #[test]
+    fn test_fill_controller_idle_gateway() {
+        // Without consumption the controller stops pushing once the target is reached
+        let mut controller = FillController::new(50.0);
+        assert_eq!(controller.update(0.0), 1.0);
+        let gateway = run_controller(50.0, 0, 0);
+        assert!(gateway.fill_percent() < 60.0, "fill {}", gateway.fill_percent());
+        assert_eq!(gateway.rejected_pushes, 0);
+    }
+
+    #[test]
+    fn test_push_coalescer() {
+        let start = std::time::Instant::now();
+        let mut coalescer = PushCoalescer::new(1024, Duration::from_secs(5));
+        assert!(!coalescer.ready(0, start));
+
+        // Small accumulations wait, measured from when data first appeared
+        let first = start + Duration::from_secs(1);
+        assert!(!coalescer.ready(100, first));
+        assert!(!coalescer.ready(900, first + Duration::from_secs(4)));
+        assert!(coalescer.ready(1023, first + Duration::from_secs(5)));
+
+        // The threshold pushes at once, and every push restarts the wait
+        assert!(coalescer.ready(1024, first + Duration::from_secs(5)));
+        assert!(!coalescer.ready(10, first + Duration::from_secs(6)));
+
+        // Draining the buffer elsewhere (emergency push) also restarts it
+        assert!(!coalescer.ready(0, first + Duration::from_secs(20)));
+        assert!(!coalescer.ready(10, first + Duration::from_secs(21)));
+    }
+
+    #[tokio::test]
+    async fn test_min_push_bytes_coalesces_small_fetches() {
+        let (push_url, received) = spawn_mock_gateway().await;
+        let mut config = test_config(push_url);
+        config["push_interval_ms"] = 5.into();
+        config["buffer_size"] = 4096.into();
+        config["min_push_bytes"] = 256.into();
+        config["max_push_latency_ms"] = 300.into();
+        let collector = Arc::new(
+            Collector::with_sources(
+                serde_json::from_value(config).unwrap(),
+                vec![Box::new(MockSource::repeating(vec![7]))],
+                reqwest::Client::new(),
+            )
+            .unwrap(),
+        );
+        let pusher = tokio::spawn(Arc::clone(&collector).push_loop());
+
+        // 48 bytes are held back until the latency deadline, then pushed once
+        collector.buffer.push(vec![7u8; 48]).unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(received.lock().unwrap().is_empty());
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(*received.lock().unwrap(), vec![vec![7u8; 48]]);
+
+        // Reaching the threshold pushes without waiting out the deadline
+        collector.buffer.push(vec![8u8; 200]).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(received.lock().unwrap().len(), 1);
+        collector.buffer.push(vec![8u8; 56]).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        pusher.abort();
+        assert_eq!(*received.lock().unwrap(), vec![vec![7u8; 48], vec![8u8; 256]]);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_health_endpoints() {
+        let collector = Arc::new(test_collector("http://127.0.0.1:9/push".to_string(), None));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let router = Arc::clone(&collector).http_router();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let client = reqwest::Client::new();
+        let health = || async { client.get(format!("{}/health", base)).send().await.unwrap().status() };
+
+        // Nothing fetched or pushed yet
+        assert_eq!(health().await, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        collector.buffer.push(vec![0u8; 32]).unwrap();
+        collector.metrics.record_fetch(32);
+        assert_eq!(health().await, reqwest::StatusCode::OK);
+
+        let metrics = client.get(format!("{}/metrics", base)).send().await.unwrap();
+        assert_eq!(metrics.status(), reqwest::StatusCode::OK);
+        let metrics = metrics.text().await.unwrap();
+        assert!(metrics.contains("qrng_fetches_total 1\n"));
+        assert!(metrics.contains("qrng_bytes_fetched 32\n"));
+        assert!(metrics.contains("qrng_pushes_failed 0\n"));
+        assert!(metrics.contains("qrng_collector_buffer_fill_percent 50\n"));
+        assert!(metrics.contains("qrng_retry_queue_bytes 0\n"));
+
+        // Pushes that keep failing lead to a long retry backoff
+        *collector.retry_backoff.write().await = UNHEALTHY_BACKOFF;
+        assert_eq!(health().await, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// Payloads received by the mock gateway
+    type ReceivedPayloads = Arc<std::sync::Mutex<Vec<Vec<u8>>>>;
+
+    /// In-process gateway that verifies pushed packets and records their payloads
+    async fn spawn_mock_gateway() -> (String, ReceivedPayloads) {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let key = hex::decode("00112233445566778899aabbccddeeff").unwrap();
+        let signer = PacketSigner::new(key, HmacAlgo::Sha256);
+        let state = (Arc::new(signer), Arc::clone(&received));
+        let router = Router::new()
+            .route(
+                "/push",
+                axum::routing::post(
+                    |State((signer, received)): State<(Arc<PacketSigner>, ReceivedPayloads)>,
+                     body: Bytes| async move {
+                        let Ok((packet, _)) = EntropyPacket::decode(&body) else {
+                            return StatusCode::BAD_REQUEST;
+                        };
+                        if !packet.verify_checksum() || !signer.verify_packet(&packet).unwrap_or(false) {
+                            return StatusCode::UNAUTHORIZED;
+                        }
+                        received.lock().unwrap().push(packet.data);
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let push_url = format!("http://{}/push", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (push_url, received)
+    }
+
+    #[tokio::test]
+    async fn test_mock_source_packets_arrive_signed() {
+        let (push_url, received) = spawn_mock_gateway().await;
+        let mut config = test_config(push_url);
+        config["fetch_interval_ms"] = 5.into();
+        config["push_interval_ms"] = 10.into();
+        // Large enough that no emergency push races the push loop and reorders payloads
+        config["buffer_size"] = 4096.into();
+        let pattern: Vec<u8> = (0..=250).collect();
+        let collector = Arc::new(
+            Collector::with_sources(
+                serde_json::from_value(config).unwrap(),
+                vec![Box::new(MockSource::repeating(pattern.clone()))],
+                reqwest::Client::new(),
+            )
+            .unwrap(),
+        );
+
+        let fetcher = tokio::spawn(Arc::clone(&collector).fetch_loop());
+        let pusher = tokio::spawn(Arc::clone(&collector).push_loop());
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while collector.metrics.pushes_total() < 3 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("mock gateway received no packets");
+        fetcher.abort();
+        pusher.abort();
+
+        // Every packet passed checksum and HMAC verification, and the payloads
+        // continue the mock pattern without gaps
+        let payload: Vec<u8> = received.lock().unwrap().concat();
+        assert!(received.lock().unwrap().len() >= 3);
+        assert_eq!(collector.metrics.pushes_failed(), 0);
+        assert!(payload.iter().enumerate().all(|(i, &byte)| byte == pattern[i % pattern.len()]));
+    }
+
+    #[tokio::test]
+    async fn test_file_transport_writes_verifiable_packets() {
+        let path = std::env::temp_dir().join(format!("qrng-collector-transport-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut config = test_config(String::new());
+        config["push_transport"] = "file".into();
+        config["push_file_path"] = path.to_string_lossy().into_owned().into();
+        config["max_packet_bytes"] = 100.into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+        assert!(collector.transport.gateway_url().is_none());
+
+        let data: Vec<u8> = (0..250).collect();
+        collector.send_entropy(&Bytes::from(data.clone())).await.unwrap();
+        collector.send_entropy(&Bytes::from(vec![9u8; 10])).await.unwrap();
+
+        // Each frame holds one signed packet, in push order
+        let key = hex::decode("00112233445566778899aabbccddeeff").unwrap();
+        let signer = PacketSigner::new(key, HmacAlgo::Sha256);
+        let contents = std::fs::read(&path).unwrap();
+        let mut reader = contents.as_slice();
+        let mut payload = Vec::new();
+        let mut sequences = Vec::new();
+        while let Some(frame) = read_framed_packet(&mut reader).unwrap() {
+            let (packet, _) = EntropyPacket::decode(&frame).unwrap();
+            assert!(packet.verify_checksum());
+            assert!(signer.verify_packet(&packet).unwrap());
+            sequences.push(packet.sequence);
+            payload.extend(packet.data);
+        }
+        assert_eq!(sequences, vec![0, 1, 2, 3]);
+        assert_eq!(payload[..250], data[..]);
+        assert_eq!(payload[250..], [9u8; 10]);
+        assert_eq!(collector.metrics.pushes_total(), 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_large_push_is_fragmented() {
+        let (push_url, received) = spawn_mock_gateway().await;
+        let mut config = test_config(push_url);
+        config["max_packet_bytes"] = (1024 * 1024).into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        collector.send_entropy(&Bytes::from(data.clone())).await.unwrap();
+
+        // Three independently verified packets whose payloads join up in order
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        assert!(received.iter().all(|payload| payload.len() == 1024 * 1024));
+        assert_eq!(received.concat(), data);
+        assert_eq!(collector.metrics.pushes_total(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_fragment_requeues_only_the_remainder() {
+        // Accepts two packets, then reports itself overloaded
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        let router = Router::new().route(
+            "/push",
+            axum::routing::post(move || async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let push_url = format!("http://{}/push", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut config = test_config(push_url);
+        config["max_packet_bytes"] = 16.into();
+        let collector = Collector::new(serde_json::from_value(config).unwrap()).unwrap();
+        let data: Vec<u8> = (0..64).collect();
+        collector.buffer.push(data.clone()).unwrap();
+
+        assert!(collector.push_buffer(MAX_PUSH_BATCH).await.is_err());
+        assert_eq!(collector.retry_queue.pop(32).unwrap().to_vec(), data[32..]);
+        assert!(collector.retry_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_source_is_tracked_and_quarantined() {
+        let mut config = test_config("http://127.0.0.1:9/push".to_string());
+        config["appliance_urls"] = serde_json::json!(["urandom:", "urandom:"]);
+        let sources: Vec<Box<dyn EntropySource>> = vec![
+            Box::new(MockSource::repeating(vec![7])),
+            Box::new(MockSource::new(|_| Err(qrng_core::Error::Unavailable("appliance down".to_string())))),
+        ];
+        let collector =
+            Collector::with_sources(serde_json::from_value(config).unwrap(), sources, reqwest::Client::new()).unwrap();
+
+        // The healthy source keeps feeding the mix while the other one fails
+        for _ in 0..SOURCE_QUARANTINE_FAILURES {
+            assert_eq!(collector.fetch_sources(16).await, vec![(0, vec![7; 16])]);
+        }
+        let failing = &collector.source_health[1];
+        assert!(failing.is_quarantined());
+        assert_eq!(failing.last_error.lock().unwrap().as_deref(), Some("Service unavailable: appliance down"));
+
+        // A quarantined source is no longer fetched
+        collector.fetch_sources(16).await;
+        assert_eq!(failing.fetches.load(Ordering::Relaxed), SOURCE_QUARANTINE_FAILURES as u64);
+
+        let metrics = collector.prometheus_format();
+        assert!(metrics.contains("qrng_source_fetches_total{source=\"0\"} 6\n"));
+        assert!(metrics.contains("qrng_source_fetch_failures_total{source=\"0\"} 0\n"));
+        assert!(metrics.contains("qrng_source_fetch_failures_total{source=\"1\"} 5\n"));
+        assert!(metrics.contains("qrng_source_quarantined{source=\"1\"} 1\n"));
+
+        // With every source quarantined, all of them are tried again
+        *collector.source_health[0].quarantined_until.lock().unwrap() =
+            Some(std::time::Instant::now() + SOURCE_QUARANTINE);
+        assert_eq!(collector.fetch_sources(16).await, vec![(0, vec![7; 16])]);
+        assert!(!collector.source_health[0].is_quarantined());
+        assert_eq!(failing.failures.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn test_with_sources_checks_source_count() {
+        let config = serde_json::from_value(test_config("http://127.0.0.1:9/push".to_string())).unwrap();
+        let sources: Vec<Box<dyn EntropySource>> = vec![
+            Box::new(MockSource::repeating(vec![1])),
+            Box::new(MockSource::repeating(vec![2])),
+        ];
+        assert!(Collector::with_sources(config, sources, reqwest::Client::new()).is_err());
+    }
+}
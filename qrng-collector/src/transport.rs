@@ -0,0 +1,415 @@
+// SPDX-License-Identifier: MIT
+//
+// QRNG Data Diode: High-Performance Quantum Entropy Bridge
+// Copyright (c) 2025 Valer Bocan, PhD, CSSLP
+// Email: valer.bocan@upt.ro
+//
+// Department of Computer and Information Technology
+// Politehnica University of Timisoara
+//
+// https://github.com/vbocan/qrng-data-diode
+
+//! Push transports carrying serialized packets out of the collector
+//!
+//! HTTP POST to the gateway is the default. Hardware data diodes often expose
+//! only a one-way byte stream or a UDP link, so packets can instead be written
+//! length-prefixed to a file, FIFO or serial device, or sent as datagrams.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use qrng_core::{
+    config::{CollectorConfig, PushMode, PushTransportKind},
+    protocol::{frame_packet, SerializationFormat},
+};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info, warn};
+
+/// Channel that delivers serialized packets towards the gateway
+#[async_trait]
+pub trait PushTransport: Send + Sync {
+    /// Deliver one serialized packet
+    async fn push(&self, bytes: &[u8]) -> Result<()>;
+
+    /// Human-readable destination for logs
+    fn destination(&self) -> String;
+
+    /// Push URL of the gateway currently receiving packets, if pushing over HTTP
+    fn gateway_url(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Push error raised when a gateway answers 507 Insufficient Storage
+#[derive(Debug)]
+pub struct GatewayFull;
+
+impl std::fmt::Display for GatewayFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("gateway buffer full (507)")
+    }
+}
+
+impl std::error::Error for GatewayFull {}
+
+/// Push error raised when a gateway answers 409 Conflict: it already holds the packet
+#[derive(Debug)]
+struct AlreadyAccepted;
+
+impl std::fmt::Display for AlreadyAccepted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("packet already accepted (409)")
+    }
+}
+
+impl std::error::Error for AlreadyAccepted {}
+
+/// Most packets held per gateway for redelivery in replicate mode; older ones are dropped
+const MAX_REPLICA_BACKLOG: usize = 1024;
+
+/// Create the push transport selected by `config.push_transport`
+pub fn transport_for_config(
+    config: &CollectorConfig,
+    http_client: reqwest::Client,
+) -> Result<Box<dyn PushTransport>> {
+    Ok(match config.push_transport {
+        PushTransportKind::Http => Box::new(HttpTransport::new(
+            http_client,
+            config.push_targets(),
+            config.push_mode,
+            config.packet_format,
+        )),
+        PushTransportKind::File => Box::new(FileTransport::new(
+            config.push_file_path.as_deref().context("push_file_path is not set")?,
+        )),
+        PushTransportKind::Udp => Box::new(UdpTransport::new(
+            config.push_udp_addr.as_deref().context("push_udp_addr is not set")?,
+        )),
+    })
+}
+
+/// POSTs packets to one or more gateway `/push` endpoints
+pub struct HttpTransport {
+    client: reqwest::Client,
+    urls: Vec<String>,
+    mode: PushMode,
+    format: SerializationFormat,
+    active: AtomicUsize,
+    /// Signed packets each gateway missed in replicate mode, oldest first
+    backlogs: Vec<tokio::sync::Mutex<VecDeque<Bytes>>>,
+}
+
+impl HttpTransport {
+    /// Push to `urls`, in failover order or to all of them depending on `mode`
+    pub fn new(
+        client: reqwest::Client,
+        urls: Vec<String>,
+        mode: PushMode,
+        format: SerializationFormat,
+    ) -> Self {
+        let backlogs = urls.iter().map(|_| tokio::sync::Mutex::default()).collect();
+        Self {
+            client,
+            urls,
+            mode,
+            format,
+            active: AtomicUsize::new(0),
+            backlogs,
+        }
+    }
+
+    /// Index of the gateway that last accepted a packet in failover mode
+    pub fn active_index(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Push to the last gateway that accepted a packet, trying the others in order on failure
+    ///
+    /// If any gateway reported a full buffer, that error is returned so the
+    /// collector backs off instead of hammering the remaining gateways.
+    async fn push_failover(&self, body: &Bytes) -> Result<()> {
+        let active = self.active.load(Ordering::Relaxed);
+        let mut last_error = None;
+        let mut gateway_full = false;
+
+        for offset in 0..self.urls.len() {
+            let index = (active + offset) % self.urls.len();
+            match self.post_packet(&self.urls[index], body.clone()).await {
+                Ok(()) => {
+                    if index != active {
+                        warn!("Failed over to push URL {}", self.urls[index]);
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(());
+                }
+                Err(e) => {
+                    gateway_full |= e.is::<GatewayFull>();
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if gateway_full {
+            return Err(GatewayFull.into());
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No push URL configured")))
+    }
+
+    /// Push to every gateway; the push succeeds once any of them accepts it
+    ///
+    /// Gateways that fail keep this exact signed packet in their backlog and
+    /// receive it, in order, before their next packet. Redelivering the original
+    /// packet rather than re-signing the payload means a gateway that did store
+    /// it answers 409 from its replay guard, so no gateway buffers the same
+    /// entropy twice. Only a push every gateway rejected fails, leaving the
+    /// payload to the collector's retry queue.
+    async fn push_replicate(&self, body: &Bytes) -> Result<()> {
+        let results = futures::future::join_all(
+            self.urls.iter().zip(&self.backlogs).map(|(url, backlog)| async move {
+                let mut backlog = backlog.lock().await;
+                let result = self.deliver_replica(url, &mut backlog, body).await;
+                (result, backlog)
+            }),
+        )
+        .await;
+
+        let accepted = results.iter().filter(|(result, _)| result.is_ok()).count();
+        if accepted == results.len() {
+            return Ok(());
+        }
+        if accepted > 0 {
+            for (url, (result, mut backlog)) in self.urls.iter().zip(results) {
+                if result.is_ok() {
+                    continue;
+                }
+                if backlog.len() == MAX_REPLICA_BACKLOG {
+                    backlog.pop_front();
+                    warn!("Redelivery backlog for {} full, dropped its oldest packet", url);
+                }
+                backlog.push_back(body.clone());
+            }
+            warn!(
+                "Push accepted by {} of {} gateways; the rest receive it with their next push",
+                accepted,
+                self.urls.len()
+            );
+            return Ok(());
+        }
+
+        let errors: Vec<_> = results.into_iter().filter_map(|(result, _)| result.err()).collect();
+        if errors.iter().any(|e| e.is::<GatewayFull>()) {
+            return Err(GatewayFull.into());
+        }
+        Err(anyhow::anyhow!("Push failed on all {} gateways", self.urls.len()))
+    }
+
+    /// Send one gateway the packets it missed, then `body`
+    ///
+    /// A backlogged packet answered with 409 was already stored and counts as delivered.
+    async fn deliver_replica(&self, url: &str, backlog: &mut VecDeque<Bytes>, body: &Bytes) -> Result<()> {
+        while let Some(packet) = backlog.front() {
+            match self.post_packet(url, packet.clone()).await {
+                Ok(()) => {}
+                Err(e) if e.is::<AlreadyAccepted>() => {}
+                Err(e) => return Err(e),
+            }
+            backlog.pop_front();
+        }
+        self.post_packet(url, body.clone()).await
+    }
+
+    /// Send a serialized packet to one gateway push endpoint
+    async fn post_packet(&self, push_url: &str, body: Bytes) -> Result<()> {
+        let response = self
+            .client
+            .post(push_url)
+            .header("Content-Type", self.format.mime_type())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Push to {} failed: {}", push_url, e);
+                e
+            })?;
+
+        if response.status().is_success() {
+            info!("Push to {} successful ({})", push_url, response.status());
+            return Ok(());
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!("Push to {} failed with status {}: {}", push_url, status, body);
+
+        if status == 507 {
+            return Err(GatewayFull.into());
+        }
+        if status == 409 {
+            return Err(AlreadyAccepted.into());
+        }
+        Err(anyhow::anyhow!("Push to {} failed: {}", push_url, status))
+    }
+}
+
+#[async_trait]
+impl PushTransport for HttpTransport {
+    async fn push(&self, bytes: &[u8]) -> Result<()> {
+        let body = Bytes::copy_from_slice(bytes);
+        match self.mode {
+            PushMode::Failover => self.push_failover(&body).await,
+            PushMode::Replicate => self.push_replicate(&body).await,
+        }
+    }
+
+    fn destination(&self) -> String {
+        match self.mode {
+            PushMode::Failover if self.urls.len() > 1 => format!("{} (failover)", self.urls.join(", ")),
+            PushMode::Failover => self.urls.join(", "),
+            PushMode::Replicate => format!("{} (replicate)", self.urls.join(", ")),
+        }
+    }
+
+    fn gateway_url(&self) -> Option<String> {
+        self.urls.get(self.active_index()).cloned()
+    }
+}
+
+/// Appends length-prefixed packets to a file, FIFO or serial device
+///
+/// The file is opened on first use and kept open, so a reader on the far end
+/// of a FIFO sees one continuous stream. After a write error the handle is
+/// dropped and reopened on the next push.
+pub struct FileTransport {
+    path: PathBuf,
+    file: tokio::sync::Mutex<Option<tokio::fs::File>>,
+}
+
+impl FileTransport {
+    /// Write packets to `path`, creating it as a regular file if missing
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl PushTransport for FileTransport {
+    async fn push(&self, bytes: &[u8]) -> Result<()> {
+        let frame = frame_packet(bytes)?;
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            let opened = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .with_context(|| format!("Failed to open {}", self.path.display()))?;
+            *file = Some(opened);
+        }
+
+        let handle = file.as_mut().expect("file handle opened above");
+        let written = async {
+            handle.write_all(&frame).await?;
+            handle.flush().await
+        }
+        .await;
+        if let Err(e) = written {
+            *file = None;
+            error!("Write to {} failed: {}", self.path.display(), e);
+            return Err(e).with_context(|| format!("Failed to write to {}", self.path.display()));
+        }
+        Ok(())
+    }
+
+    fn destination(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+}
+
+/// Sends each packet as a single UDP datagram
+///
+/// Delivery is fire-and-forget: a send succeeds once the datagram leaves the
+/// local socket, as befits a one-way link with no return path.
+pub struct UdpTransport {
+    addr: String,
+    socket: tokio::sync::OnceCell<tokio::net::UdpSocket>,
+}
+
+impl UdpTransport {
+    /// Send datagrams to `addr` (`host:port`)
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            socket: tokio::sync::OnceCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl PushTransport for UdpTransport {
+    async fn push(&self, bytes: &[u8]) -> Result<()> {
+        let socket = self
+            .socket
+            .get_or_try_init(|| async {
+                let target = tokio::net::lookup_host(&self.addr)
+                    .await?
+                    .next()
+                    .with_context(|| format!("{} did not resolve to an address", self.addr))?;
+                let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+                let socket = tokio::net::UdpSocket::bind(local).await?;
+                socket.connect(target).await?;
+                anyhow::Ok(socket)
+            })
+            .await
+            .with_context(|| format!("Failed to open UDP socket to {}", self.addr))?;
+
+        let sent = socket
+            .send(bytes)
+            .await
+            .with_context(|| format!("UDP send to {} failed", self.addr))?;
+        anyhow::ensure!(
+            sent == bytes.len(),
+            "UDP send to {} truncated: {} of {} bytes",
+            self.addr,
+            sent,
+            bytes.len()
+        );
+        Ok(())
+    }
+
+    fn destination(&self) -> String {
+        format!("udp {}", self.addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_udp_transport_sends_one_datagram_per_packet() {
+        let receiver = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let transport = UdpTransport::new(receiver.local_addr().unwrap().to_string());
+
+        transport.push(b"first packet").await.unwrap();
+        transport.push(&[7u8; 4096]).await.unwrap();
+
+        let mut datagram = vec![0u8; 65536];
+        let len = receiver.recv(&mut datagram).await.unwrap();
+        assert_eq!(&datagram[..len], b"first packet");
+        let len = receiver.recv(&mut datagram).await.unwrap();
+        assert_eq!(&datagram[..len], &[7u8; 4096][..]);
+    }
+
+    #[tokio::test]
+    async fn test_file_transport_reports_unwritable_path() {
+        let transport = FileTransport::new("/nonexistent-qrng-dir/diode.fifo");
+        assert!(transport.push(b"packet").await.is_err());
+        assert_eq!(transport.destination(), "file /nonexistent-qrng-dir/diode.fifo");
+    }
+}
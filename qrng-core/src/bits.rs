@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: MIT
+//
+// QRNG Data Diode: High-Performance Quantum Entropy Bridge
+// Copyright (c) 2025 Valer Bocan, PhD, CSSLP
+// Email: valer.bocan@upt.ro
+//
+// Department of Computer and Information Technology
+// Politehnica University of Timisoara
+//
+// https://github.com/vbocan/qrng-data-diode
+
+//! Bit-level reads from the entropy buffer
+//!
+//! Small-value generators (booleans, dice, short choices) need only a few bits
+//! per value. [`EntropyBitReader`] pops whole bytes from the buffer on demand
+//! and keeps the bits it has not handed out for the next read, so no entropy
+//! is discarded between values.
+
+use crate::buffer::EntropyBuffer;
+
+/// Dispenses exactly the requested number of bits from an [`EntropyBuffer`]
+///
+/// Bits are taken most significant first within each byte, in buffer order.
+/// Only the bytes needed to complete a read are popped; leftover bits are
+/// cached across calls and discarded when the reader is dropped.
+pub struct EntropyBitReader<'a> {
+    buffer: &'a EntropyBuffer,
+    /// Cached bits, right-aligned
+    cache: u8,
+    cached_bits: u32,
+    bytes_consumed: usize,
+}
+
+impl<'a> EntropyBitReader<'a> {
+    /// Create a reader drawing from `buffer`
+    pub fn new(buffer: &'a EntropyBuffer) -> Self {
+        Self {
+            buffer,
+            cache: 0,
+            cached_bits: 0,
+            bytes_consumed: 0,
+        }
+    }
+
+    /// Read `n` bits (at most 64) as the low bits of the result
+    ///
+    /// Returns `None` if `n` exceeds 64 or the buffer cannot supply the bytes
+    /// needed; in that case nothing is consumed and the cached bits are kept.
+    pub fn read_bits(&mut self, n: u32) -> Option<u64> {
+        if n > u64::BITS {
+            return None;
+        }
+        if n <= self.cached_bits {
+            return Some(self.take_cached(n));
+        }
+
+        let missing = n - self.cached_bits;
+        let data = self.buffer.pop(missing.div_ceil(8) as usize)?;
+        self.bytes_consumed += data.len();
+
+        // At most 7 cached bits plus 8 bytes, so the accumulator cannot overflow
+        let mut acc = self.cache as u128;
+        for &byte in data.iter() {
+            acc = (acc << 8) | byte as u128;
+        }
+        let total = self.cached_bits + 8 * data.len() as u32;
+        let leftover = total - n;
+
+        self.cache = (acc & ((1u128 << leftover) - 1)) as u8;
+        self.cached_bits = leftover;
+        Some((acc >> leftover) as u64 & low_mask(n))
+    }
+
+    /// Uniform value in `0..n` using the fewest bits that cover the range
+    ///
+    /// Draws `ceil(log2(n))` bits and rejects values outside the range, so a
+    /// d6 costs 3 bits per attempt. Returns `None` for `n == 0` or when the
+    /// buffer runs dry.
+    pub fn read_below(&mut self, n: u64) -> Option<u64> {
+        if n == 0 {
+            return None;
+        }
+        let bits = u64::BITS - (n - 1).leading_zeros();
+        loop {
+            let value = self.read_bits(bits)?;
+            if value < n {
+                return Some(value);
+            }
+        }
+    }
+
+    /// Bits cached from popped bytes and not yet handed out
+    pub fn bits_remaining(&self) -> u32 {
+        self.cached_bits
+    }
+
+    /// Bytes popped from the buffer so far
+    pub fn bytes_consumed(&self) -> usize {
+        self.bytes_consumed
+    }
+
+    fn take_cached(&mut self, n: u32) -> u64 {
+        self.cached_bits -= n;
+        let value = (self.cache >> self.cached_bits) as u64 & low_mask(n);
+        self.cache &= low_mask(self.cached_bits) as u8;
+        value
+    }
+}
+
+/// Mask of the `n` lowest bits (all bits for `n == 64`)
+fn low_mask(n: u32) -> u64 {
+    u64::MAX.checked_shr(u64::BITS - n).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_ordering() {
+        let buffer = EntropyBuffer::new(64);
+        buffer.push(vec![0b1011_0010, 0b0111_1111]).unwrap();
+        let mut reader = EntropyBitReader::new(&buffer);
+
+        assert_eq!(reader.read_bits(1), Some(1));
+        assert_eq!(reader.read_bits(3), Some(0b011));
+        assert_eq!(reader.bits_remaining(), 4);
+        // Spans the byte boundary: 0010 then 0111
+        assert_eq!(reader.read_bits(8), Some(0b0010_0111));
+        assert_eq!(reader.read_bits(4), Some(0b1111));
+        assert_eq!(reader.read_bits(0), Some(0));
+        assert_eq!(reader.bits_remaining(), 0);
+    }
+
+    #[test]
+    fn test_byte_consumption() {
+        let buffer = EntropyBuffer::new(64);
+        buffer.push((0..32).collect::<Vec<u8>>()).unwrap();
+        let mut reader = EntropyBitReader::new(&buffer);
+
+        // Eight 3-bit reads use exactly three bytes
+        for _ in 0..8 {
+            reader.read_bits(3).unwrap();
+        }
+        assert_eq!(reader.bytes_consumed(), 3);
+        assert_eq!(reader.bits_remaining(), 0);
+        assert_eq!(buffer.len(), 29);
+
+        // A 64-bit read with 3 cached bits pops only the eight bytes it needs
+        assert_eq!(reader.read_bits(5), Some(0));
+        assert_eq!(reader.bits_remaining(), 3);
+        assert_eq!(reader.read_bits(64), Some(0x6080_a0c0_e101_2141));
+        assert_eq!(reader.bytes_consumed(), 12);
+        assert_eq!(reader.bits_remaining(), 3);
+        assert_eq!(reader.read_bits(65), None);
+    }
+
+    #[test]
+    fn test_buffer_runs_dry_mid_read() {
+        let buffer = EntropyBuffer::new(64);
+        buffer.push(vec![0xF0]).unwrap();
+        let mut reader = EntropyBitReader::new(&buffer);
+
+        assert_eq!(reader.read_bits(2), Some(0b11));
+        // 6 cached bits cannot satisfy 12; nothing is consumed
+        assert_eq!(reader.read_bits(12), None);
+        assert_eq!(reader.bits_remaining(), 6);
+        assert_eq!(reader.bytes_consumed(), 1);
+
+        // Smaller reads still succeed from the cache, and refills resume
+        assert_eq!(reader.read_bits(6), Some(0b11_0000));
+        buffer.push(vec![0xAB]).unwrap();
+        assert_eq!(reader.read_bits(8), Some(0xAB));
+        assert_eq!(reader.read_bits(1), None);
+    }
+
+    #[test]
+    fn test_read_below() {
+        let buffer = EntropyBuffer::new(1024);
+        buffer.push(vec![0xFF, 0x00]).unwrap();
+        let mut reader = EntropyBitReader::new(&buffer);
+
+        // For a d6, 111, 111 and 110 are rejected before 000 is accepted
+        assert_eq!(reader.read_below(6), Some(0));
+        assert_eq!(reader.bytes_consumed(), 2);
+        assert_eq!(reader.read_below(0), None);
+        assert_eq!(reader.read_below(1), Some(0));
+
+        buffer.push((0..=255).collect::<Vec<u8>>()).unwrap();
+        let mut counts = [0usize; 6];
+        while let Some(value) = reader.read_below(6) {
+            counts[value as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| count > 0));
+    }
+}
@@ -14,18 +14,30 @@
 //! This module implements a thread-safe, efficient circular buffer for storing
 //! random entropy with automatic age-based eviction and watermark monitoring.
 
-use crate::Result;
+use crate::{Error, Result};
 use bytes::{BufMut, Bytes, BytesMut};
 use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::warn;
+
+/// Magic header of persisted buffer snapshots (format version 1)
+const SNAPSHOT_MAGIC: &[u8; 8] = b"QRNGBUF1";
+
+/// Per-entry snapshot header: timestamp (i64 µs) + length (u32) + CRC32 (u32)
+const SNAPSHOT_ENTRY_HEADER: usize = 16;
 
 /// Entry in the entropy buffer with timestamp tracking
 #[derive(Debug, Clone)]
 struct BufferEntry {
     data: Bytes,
     timestamp: DateTime<Utc>,
+    /// Source tag of the packet the data arrived in
+    source_tag: Option<Arc<str>>,
 }
 
 /// Thread-safe entropy buffer with FIFO semantics
@@ -36,6 +48,7 @@ struct BufferEntry {
 /// - Stores data in chunks with timestamps for TTL enforcement
 /// - Implements automatic eviction policies (age-based, overflow)
 /// - Provides watermark-based monitoring
+/// - Wakes async waiters on every push (see [`wait_until`](Self::wait_until))
 ///
 /// # Performance
 ///
@@ -45,6 +58,7 @@ struct BufferEntry {
 #[derive(Clone)]
 pub struct EntropyBuffer {
     inner: Arc<RwLock<BufferInner>>,
+    pushed: Arc<Notify>,
 }
 
 struct BufferInner {
@@ -54,9 +68,13 @@ struct BufferInner {
     ttl: Option<Duration>,
     overflow_policy: OverflowPolicy,
     stats: BufferStats,
+    on_evict: Option<EvictionHook>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// Callback invoked with the reason and byte count of every eviction
+pub type EvictionHook = Arc<dyn Fn(EvictionReason, usize) + Send + Sync>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BufferStats {
     pub total_pushes: u64,
     pub total_pops: u64,
@@ -67,7 +85,8 @@ pub struct BufferStats {
 }
 
 /// Buffer watermark levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum WatermarkLevel {
     Low,      // < 10%
     Medium,   // 10-80%
@@ -75,6 +94,26 @@ pub enum WatermarkLevel {
     Critical, // > 95%
 }
 
+/// Why buffered entropy was discarded without being served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionReason {
+    /// Older than the buffer TTL
+    Ttl,
+    /// Replaced by incoming data under [`OverflowPolicy::Replace`]
+    Overflow,
+}
+
+impl EvictionReason {
+    /// Lowercase name, as used in metric labels
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ttl => "ttl",
+            Self::Overflow => "overflow",
+        }
+    }
+}
+
 /// Buffer overflow policy when buffer is full
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OverflowPolicy {
@@ -95,7 +134,9 @@ impl EntropyBuffer {
                 ttl: None,
                 overflow_policy: OverflowPolicy::Discard,
                 stats: BufferStats::default(),
+                on_evict: None,
             })),
+            pushed: Arc::new(Notify::new()),
         }
     }
 
@@ -112,12 +153,40 @@ impl EntropyBuffer {
         self
     }
 
+    /// Call `hook` with the reason and byte count whenever data is evicted
+    ///
+    /// The hook is shared by all clones of the buffer and runs after the
+    /// buffer lock is released, so it may inspect the buffer itself.
+    pub fn with_eviction_hook(
+        self,
+        hook: impl Fn(EvictionReason, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.inner.write().on_evict = Some(Arc::new(hook));
+        self
+    }
+
     /// Push entropy data into buffer
     ///
     /// Automatically evicts stale or overflow data as needed.
     /// Returns the number of bytes actually stored.
     pub fn push(&self, data: impl Into<Bytes>) -> Result<usize> {
-        let data = data.into();
+        self.push_with_timestamp(data, Utc::now())
+    }
+
+    /// Push entropy data received at `timestamp` (which ages and expires it accordingly)
+    pub fn push_with_timestamp(&self, data: impl Into<Bytes>, timestamp: DateTime<Utc>) -> Result<usize> {
+        self.insert(data.into(), timestamp, None)
+    }
+
+    /// Push entropy data labelled with the source tag of the packet it arrived in
+    ///
+    /// The tag stays with the bytes until they are consumed or evicted and is
+    /// reported by [`source_tags`](Self::source_tags).
+    pub fn push_tagged(&self, data: impl Into<Bytes>, source_tag: Option<&str>) -> Result<usize> {
+        self.insert(data.into(), Utc::now(), source_tag.map(Arc::from))
+    }
+
+    fn insert(&self, data: Bytes, timestamp: DateTime<Utc>, source_tag: Option<Arc<str>>) -> Result<usize> {
         let data_len = data.len();
 
         if data_len == 0 {
@@ -125,10 +194,11 @@ impl EntropyBuffer {
         }
 
         let mut inner = self.inner.write();
+        let mut evicted = [(EvictionReason::Ttl, 0), (EvictionReason::Overflow, 0)];
 
         // Evict stale data based on TTL
         if let Some(ttl) = inner.ttl {
-            inner.evict_stale(ttl);
+            evicted[0].1 = inner.evict_stale(ttl);
         }
 
         // Calculate available space
@@ -139,6 +209,9 @@ impl EntropyBuffer {
             OverflowPolicy::Discard => {
                 // Discard policy: only use available space
                 if available_space == 0 {
+                    let on_evict = inner.on_evict.clone();
+                    drop(inner);
+                    report_evictions(on_evict, evicted);
                     return Ok(0);
                 }
             }
@@ -146,7 +219,7 @@ impl EntropyBuffer {
                 // Replace policy: evict oldest data if needed to fit incoming data
                 if available_space < data_len {
                     let bytes_needed = data_len - available_space;
-                    inner.evict_oldest(bytes_needed);
+                    evicted[1].1 = inner.evict_oldest(bytes_needed);
                 }
             }
         }
@@ -162,15 +235,41 @@ impl EntropyBuffer {
         // Push new entry
         inner.entries.push_back(BufferEntry {
             data: data_to_push,
-            timestamp: Utc::now(),
+            timestamp,
+            source_tag,
         });
         inner.current_size += bytes_to_push;
         inner.stats.total_pushes += 1;
         inner.stats.bytes_pushed += bytes_to_push as u64;
+        let on_evict = inner.on_evict.clone();
+        drop(inner);
 
+        report_evictions(on_evict, evicted);
+        self.pushed.notify_waiters();
         Ok(bytes_to_push)
     }
 
+    /// Wait until `ready` holds for the buffer or `timeout` elapses
+    ///
+    /// The condition is checked immediately and again after every push, so
+    /// callers do not need to poll. Returns whether it held when the wait ended.
+    pub async fn wait_until(&self, timeout: std::time::Duration, ready: impl Fn(&Self) -> bool) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Register before checking so a push in between is not missed
+            let notified = self.pushed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if ready(self) {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return ready(self);
+            }
+        }
+    }
+
     /// Pop exactly N bytes from buffer (FIFO)
     ///
     /// Returns None if insufficient data available.
@@ -213,6 +312,47 @@ impl EntropyBuffer {
         Some(result.freeze())
     }
 
+    /// Remove and return everything currently buffered, oldest first
+    ///
+    /// Entries are taken in a single critical section, so concurrent pushes
+    /// land either wholly in the result or wholly in the buffer afterwards.
+    pub fn drain_all(&self) -> Bytes {
+        let entries = self.take_all();
+        let mut result = BytesMut::with_capacity(entries.iter().map(|entry| entry.data.len()).sum());
+        for entry in entries {
+            result.put(entry.data);
+        }
+        result.freeze()
+    }
+
+    /// Remove everything currently buffered and write it to `writer`, oldest first
+    ///
+    /// Returns the number of bytes drained. The bytes leave the buffer before
+    /// they are written, so on a write error the unwritten remainder is
+    /// discarded rather than risk serving bytes that were already exported.
+    pub fn drain_into(&self, mut writer: impl std::io::Write) -> Result<usize> {
+        let entries = self.take_all();
+        let mut written = 0;
+        for entry in entries {
+            writer.write_all(&entry.data)?;
+            written += entry.data.len();
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Detach all entries, counting them as one pop
+    fn take_all(&self) -> VecDeque<BufferEntry> {
+        let mut inner = self.inner.write();
+        let entries = std::mem::take(&mut inner.entries);
+        if inner.current_size > 0 {
+            inner.stats.total_pops += 1;
+            inner.stats.bytes_popped += inner.current_size as u64;
+        }
+        inner.current_size = 0;
+        entries
+    }
+
     /// Peek at N bytes without consuming
     pub fn peek(&self, n: usize) -> Option<Bytes> {
         let inner = self.inner.read();
@@ -246,6 +386,13 @@ impl EntropyBuffer {
         self.inner.read().current_size
     }
 
+    /// Get current utilization, or `None` if the lock is not acquired within `timeout`
+    ///
+    /// Used by liveness probes to detect a wedged buffer without blocking forever.
+    pub fn try_len(&self, timeout: std::time::Duration) -> Option<usize> {
+        self.inner.try_read_for(timeout).map(|inner| inner.current_size)
+    }
+
     /// Check if buffer is empty
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -288,6 +435,20 @@ impl EntropyBuffer {
         })
     }
 
+    /// Distinct source tags of the buffered entropy, sorted
+    ///
+    /// Untagged data (from collectors without a tag, or restored from a
+    /// snapshot) contributes nothing.
+    pub fn source_tags(&self) -> Vec<String> {
+        let inner = self.inner.read();
+        let tags: std::collections::BTreeSet<&str> = inner
+            .entries
+            .iter()
+            .filter_map(|entry| entry.source_tag.as_deref())
+            .collect();
+        tags.into_iter().map(str::to_string).collect()
+    }
+
     /// Get buffer statistics
     pub fn stats(&self) -> BufferStats {
         self.inner.read().stats.clone()
@@ -299,24 +460,170 @@ impl EntropyBuffer {
         inner.entries.clear();
         inner.current_size = 0;
     }
+
+    /// Save buffered entries with their timestamps to a snapshot file
+    ///
+    /// The snapshot is written to a temporary file and renamed into place, so a
+    /// crash mid-write never leaves a half-written snapshot at `path`.
+    /// Returns the number of entropy bytes saved.
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+
+        let (snapshot, saved) = {
+            let inner = self.inner.read();
+            let mut snapshot = Vec::with_capacity(
+                SNAPSHOT_MAGIC.len()
+                    + inner.current_size
+                    + inner.entries.len() * SNAPSHOT_ENTRY_HEADER,
+            );
+            snapshot.extend_from_slice(SNAPSHOT_MAGIC);
+            for entry in &inner.entries {
+                snapshot.extend_from_slice(&entry.timestamp.timestamp_micros().to_le_bytes());
+                snapshot.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+                snapshot.extend_from_slice(&crc32fast::hash(&entry.data).to_le_bytes());
+                snapshot.extend_from_slice(&entry.data);
+            }
+            (snapshot, inner.current_size)
+        };
+
+        let tmp_path = path.with_extension("tmp");
+        write_private_file(&tmp_path, &snapshot)?;
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(saved)
+    }
+
+    /// Restore a buffer from a snapshot written by [`save_to_path`](Self::save_to_path)
+    ///
+    /// Entries older than `ttl` are discarded. A truncated or corrupt snapshot
+    /// keeps the intact entries before the damage; only an unrecognizable file
+    /// is an error.
+    pub fn load_from_path(
+        path: impl AsRef<Path>,
+        max_size: usize,
+        ttl: Option<Duration>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let snapshot = std::fs::read(path)?;
+
+        let Some(mut rest) = snapshot.strip_prefix(SNAPSHOT_MAGIC.as_slice()) else {
+            return Err(Error::Serialization(format!(
+                "{} is not a buffer snapshot",
+                path.display()
+            )));
+        };
+
+        let buffer = match ttl {
+            Some(ttl) => Self::with_ttl(max_size, ttl),
+            None => Self::new(max_size),
+        };
+        let cutoff = ttl.map(|ttl| Utc::now() - ttl);
+
+        {
+            let mut inner = buffer.inner.write();
+            while !rest.is_empty() {
+                let Some(entry) = read_snapshot_entry(&mut rest) else {
+                    warn!(
+                        "Buffer snapshot {} is truncated or corrupt, keeping {} bytes",
+                        path.display(),
+                        inner.current_size
+                    );
+                    break;
+                };
+
+                if cutoff.is_some_and(|cutoff| entry.timestamp < cutoff) {
+                    inner.stats.evictions_ttl += 1;
+                    continue;
+                }
+
+                let available_space = inner.max_size - inner.current_size;
+                if available_space == 0 {
+                    break;
+                }
+                let data = entry.data.slice(0..entry.data.len().min(available_space));
+                inner.current_size += data.len();
+                inner.entries.push_back(BufferEntry {
+                    data,
+                    timestamp: entry.timestamp,
+                    source_tag: None,
+                });
+            }
+        }
+
+        Ok(buffer)
+    }
+}
+
+/// Pass non-empty evictions to the hook; called once the buffer lock is released
+fn report_evictions(on_evict: Option<EvictionHook>, evicted: [(EvictionReason, usize); 2]) {
+    if let Some(on_evict) = on_evict {
+        for (reason, bytes) in evicted {
+            if bytes > 0 {
+                on_evict(reason, bytes);
+            }
+        }
+    }
+}
+
+/// Parse one snapshot entry, advancing `rest`; `None` if truncated or corrupt
+fn read_snapshot_entry(rest: &mut &[u8]) -> Option<BufferEntry> {
+    let header = rest.get(..SNAPSHOT_ENTRY_HEADER)?;
+    let timestamp = i64::from_le_bytes(header[0..8].try_into().ok()?);
+    let len = u32::from_le_bytes(header[8..12].try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(header[12..16].try_into().ok()?);
+
+    let data = rest.get(SNAPSHOT_ENTRY_HEADER..SNAPSHOT_ENTRY_HEADER + len)?;
+    if crc32fast::hash(data) != checksum {
+        return None;
+    }
+
+    let entry = BufferEntry {
+        data: Bytes::copy_from_slice(data),
+        timestamp: DateTime::from_timestamp_micros(timestamp)?,
+        source_tag: None,
+    };
+    *rest = &rest[SNAPSHOT_ENTRY_HEADER + len..];
+    Some(entry)
+}
+
+/// Write a file readable only by the owner (entropy must not leak to other users)
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
 }
 
 impl BufferInner {
-    fn evict_stale(&mut self, ttl: Duration) {
+    /// Drop entries older than `ttl`, returning the bytes evicted
+    fn evict_stale(&mut self, ttl: Duration) -> usize {
         let cutoff = Utc::now() - ttl;
+        let mut bytes_freed = 0;
         
         while let Some(entry) = self.entries.front() {
             if entry.timestamp < cutoff {
                 let removed = self.entries.pop_front().unwrap();
+                bytes_freed += removed.data.len();
                 self.current_size -= removed.data.len();
                 self.stats.evictions_ttl += 1;
             } else {
                 break;
             }
         }
+        bytes_freed
     }
 
-    fn evict_oldest(&mut self, bytes_needed: usize) {
+    /// Drop the oldest entries until `bytes_needed` are freed, returning the bytes evicted
+    fn evict_oldest(&mut self, bytes_needed: usize) -> usize {
         let mut bytes_freed = 0;
         
         while bytes_freed < bytes_needed && !self.entries.is_empty() {
@@ -326,6 +633,7 @@ impl BufferInner {
                 self.stats.evictions_overflow += 1;
             }
         }
+        bytes_freed
     }
 }
 
@@ -338,6 +646,7 @@ mod tests {
         let buffer = EntropyBuffer::new(1024);
         buffer.push(vec![1, 2, 3, 4]).unwrap();
         assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.try_len(std::time::Duration::from_millis(10)), Some(4));
 
         let data = buffer.pop(4).unwrap();
         assert_eq!(data.as_ref(), &[1, 2, 3, 4]);
@@ -391,6 +700,44 @@ mod tests {
         assert_eq!(stats.evictions_overflow, 1); // One entry evicted
     }
 
+    #[test]
+    fn test_eviction_hook() {
+        use std::sync::Mutex;
+
+        let evictions = Arc::new(Mutex::new(Vec::new()));
+        let recorded = evictions.clone();
+        let buffer = EntropyBuffer::with_ttl(10, Duration::seconds(60))
+            .with_overflow_policy(OverflowPolicy::Replace)
+            .with_eviction_hook(move |reason, bytes| recorded.lock().unwrap().push((reason, bytes)));
+
+        // A stale entry expires on the next push
+        let stale = Utc::now() - Duration::seconds(120);
+        buffer.push_with_timestamp(vec![1; 5], stale).unwrap();
+        buffer.push(vec![2; 6]).unwrap();
+        assert_eq!(*evictions.lock().unwrap(), vec![(EvictionReason::Ttl, 5)]);
+
+        // Overflow replaces the 6-byte entry to fit 5 more bytes
+        buffer.push(vec![3; 4]).unwrap();
+        buffer.push(vec![4; 5]).unwrap();
+        assert_eq!(
+            *evictions.lock().unwrap(),
+            vec![(EvictionReason::Ttl, 5), (EvictionReason::Overflow, 6)]
+        );
+
+        // Clones share the hook; totals match the buffer's own counters
+        let clone = buffer.clone();
+        clone.push(vec![5; 4]).unwrap();
+        let evictions = evictions.lock().unwrap();
+        let overflow_bytes: usize = evictions
+            .iter()
+            .filter(|(reason, _)| *reason == EvictionReason::Overflow)
+            .map(|(_, bytes)| bytes)
+            .sum();
+        assert_eq!(overflow_bytes, 6 + 4);
+        assert_eq!(buffer.stats().evictions_overflow, 2);
+        assert_eq!(buffer.stats().evictions_ttl, 1);
+    }
+
     #[test]
     fn test_watermark() {
         let buffer = EntropyBuffer::new(100);
@@ -412,4 +759,157 @@ mod tests {
         assert_eq!(peeked.as_ref(), &[1, 2, 3]);
         assert_eq!(buffer.len(), 5); // Not consumed
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_until() {
+        let buffer = EntropyBuffer::new(100);
+        let half_full = |buffer: &EntropyBuffer| buffer.len() >= 50;
+
+        assert!(!buffer.wait_until(std::time::Duration::from_secs(1), half_full).await);
+
+        let pusher = buffer.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            pusher.push(vec![0; 20]).unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            pusher.push(vec![0; 40]).unwrap();
+        });
+
+        let start = tokio::time::Instant::now();
+        assert!(buffer.wait_until(std::time::Duration::from_secs(10), half_full).await);
+        assert_eq!(start.elapsed(), std::time::Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_drain_all() {
+        let buffer = EntropyBuffer::new(100);
+        buffer.push(vec![1, 2, 3]).unwrap();
+        buffer.push(vec![4, 5]).unwrap();
+        buffer.push(vec![6, 7, 8, 9]).unwrap();
+        buffer.pop(1).unwrap();
+
+        assert_eq!(buffer.drain_all().as_ref(), &[2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(buffer.is_empty());
+        let stats = buffer.stats();
+        assert_eq!(stats.total_pops, 2);
+        assert_eq!(stats.bytes_popped, 9);
+
+        // Draining an empty buffer is not a pop
+        assert!(buffer.drain_all().is_empty());
+        assert_eq!(buffer.stats().total_pops, 2);
+
+        // The drained space is available again
+        assert_eq!(buffer.push(vec![0xAB; 100]).unwrap(), 100);
+    }
+
+    #[test]
+    fn test_drain_into() {
+        let buffer = EntropyBuffer::new(100);
+        buffer.push(vec![1, 2]).unwrap();
+        buffer.push(vec![3]).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(buffer.drain_into(&mut out).unwrap(), 3);
+        assert_eq!(out, [1, 2, 3]);
+        assert!(buffer.is_empty());
+
+        // A failed write loses the drained bytes instead of serving them again
+        buffer.push(vec![4, 5, 6]).unwrap();
+        let mut full = [0u8; 2];
+        assert!(buffer.drain_into(&mut full[..]).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_source_tags() {
+        let buffer = EntropyBuffer::new(100);
+        buffer.push(vec![0; 10]).unwrap();
+        buffer.push_tagged(vec![1; 10], Some("us-east")).unwrap();
+        buffer.push_tagged(vec![2; 10], Some("eu-west")).unwrap();
+        buffer.push_tagged(vec![3; 10], Some("us-east")).unwrap();
+        assert_eq!(buffer.source_tags(), ["eu-west", "us-east"]);
+
+        // A tag disappears once all of its bytes are consumed
+        buffer.pop(15).unwrap();
+        assert_eq!(buffer.source_tags(), ["eu-west", "us-east"]);
+        buffer.pop(15).unwrap();
+        assert_eq!(buffer.source_tags(), ["us-east"]);
+        buffer.pop(10).unwrap();
+        assert!(buffer.source_tags().is_empty());
+    }
+
+    fn snapshot_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("qrng-buffer-{}-{}.snapshot", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let path = snapshot_path("roundtrip");
+        let buffer = EntropyBuffer::new(1024);
+        buffer.push(vec![1, 2, 3]).unwrap();
+        buffer.push(vec![4, 5]).unwrap();
+        let oldest = buffer.oldest_timestamp().unwrap();
+
+        assert_eq!(buffer.save_to_path(&path).unwrap(), 5);
+
+        let restored = EntropyBuffer::load_from_path(&path, 1024, None).unwrap();
+        assert_eq!(restored.len(), 5);
+        assert_eq!(restored.capacity(), 1024);
+        assert_eq!(restored.oldest_timestamp().unwrap().timestamp_micros(), oldest.timestamp_micros());
+        assert_eq!(restored.pop(5).unwrap().as_ref(), &[1, 2, 3, 4, 5]);
+
+        // A smaller buffer keeps only what fits
+        let restored = EntropyBuffer::load_from_path(&path, 4, None).unwrap();
+        assert_eq!(restored.pop(4).unwrap().as_ref(), &[1, 2, 3, 4]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_ttl_on_reload() {
+        let path = snapshot_path("ttl");
+        let buffer = EntropyBuffer::new(1024);
+        buffer.push(vec![1; 10]).unwrap();
+        buffer.push(vec![2; 10]).unwrap();
+        buffer.inner.write().entries[0].timestamp = Utc::now() - Duration::seconds(120);
+        buffer.save_to_path(&path).unwrap();
+
+        let restored = EntropyBuffer::load_from_path(&path, 1024, Some(Duration::seconds(60))).unwrap();
+        assert_eq!(restored.len(), 10);
+        assert_eq!(restored.stats().evictions_ttl, 1);
+        assert_eq!(restored.pop(10).unwrap().as_ref(), &[2; 10]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_truncated_and_corrupt() {
+        let path = snapshot_path("damaged");
+        let buffer = EntropyBuffer::new(1024);
+        buffer.push(vec![1; 10]).unwrap();
+        buffer.push(vec![2; 10]).unwrap();
+        buffer.save_to_path(&path).unwrap();
+        let snapshot = std::fs::read(&path).unwrap();
+
+        // Truncated mid-entry: the intact first entry survives
+        std::fs::write(&path, &snapshot[..snapshot.len() - 3]).unwrap();
+        let restored = EntropyBuffer::load_from_path(&path, 1024, None).unwrap();
+        assert_eq!(restored.pop(10).unwrap().as_ref(), &[1; 10]);
+        assert!(restored.is_empty());
+
+        // Corrupted payload: checksum mismatch stops at the damaged entry
+        let mut corrupt = snapshot.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        std::fs::write(&path, &corrupt).unwrap();
+        let restored = EntropyBuffer::load_from_path(&path, 1024, None).unwrap();
+        assert_eq!(restored.len(), 10);
+
+        // Unrecognizable files are rejected
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(EntropyBuffer::load_from_path(&path, 1024, None).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(EntropyBuffer::load_from_path(&path, 1024, None).is_err());
+    }
 }
@@ -11,10 +11,17 @@
 
 //! Configuration management for QRNG components
 
-use crate::{Error, Result};
+use crate::{
+    crypto::{HmacAlgo, PacketSigner, SignatureScheme},
+    protocol::{ChecksumAlgorithm, SerializationFormat, MAX_SOURCE_TAG_LEN},
+    Error, Result,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::time::Duration;
 use url::Url;
+use zeroize::Zeroizing;
 
 /// Entropy mixing strategy
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
@@ -27,11 +34,116 @@ pub enum MixingStrategy {
     Xor,
     /// Use HKDF (HMAC-based Key Derivation Function) for mixing
     Hkdf,
+    /// Append all chunks in source order
+    ///
+    /// Keeps every fetched byte for maximum throughput; it does not improve
+    /// statistical quality, so each source must be trusted on its own.
+    Concat,
+}
+
+/// How the mixer handles sources that returned chunks of different lengths
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthPolicy {
+    /// Reject chunks of unequal length
+    #[default]
+    Strict,
+    /// Truncate every chunk to the shortest one before mixing
+    Truncate,
+    /// Feed all bytes into HKDF and expand to the shortest length
+    PadAndHash,
+}
+
+/// How the collector delivers packets when several push URLs are configured
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PushMode {
+    /// Push to one gateway, moving to the next URL when it fails
+    #[default]
+    Failover,
+    /// Push every packet to all gateways; gateways that miss one receive the same signed packet later
+    Replicate,
+}
+
+/// Channel the collector pushes packets over
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PushTransportKind {
+    /// HTTP POST to the gateway's `/push` endpoint(s)
+    #[default]
+    Http,
+    /// Length-prefixed packets appended to a file, FIFO or serial device
+    File,
+    /// One UDP datagram per packet
+    Udp,
+}
+
+/// Largest packet payload that still fits a UDP datagram with its framing
+pub const MAX_UDP_PACKET_BYTES: usize = 60 * 1024;
+
+/// Response body format expected from a QRNG appliance
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Try a JSON array of bytes first, fall back to raw binary
+    #[default]
+    Auto,
+    /// Raw binary body
+    Binary,
+    /// JSON array of integers in 0..=255 (Quantis API v2.0)
+    JsonArray,
+    /// ANU Quantum Numbers wrapper: `{"success":true,"type":"uint8","data":[...]}`
+    AnuJson,
+}
+
+/// A named QRNG appliance with source metadata
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ApplianceConfig {
+    /// Human-readable source name used in logs and metrics
+    pub name: String,
+
+    /// Appliance endpoint URL
+    pub url: String,
+
+    /// Relative trust weight; sources with weight 0 are fetched and
+    /// validated for monitoring but excluded from mixing
+    #[serde(default = "default_source_weight")]
+    pub weight: f64,
+
+    /// Expected response body format
+    #[serde(default)]
+    pub format: ResponseFormat,
+
+    /// Optional bearer token sent in the Authorization header
+    #[serde(default)]
+    pub auth_token: Option<String>,
+
+    /// Trim responses longer than requested instead of rejecting them
+    #[serde(default)]
+    pub allow_oversized: bool,
+}
+
+impl ApplianceConfig {
+    /// Create a source with default metadata from a bare URL
+    pub fn from_url(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            weight: default_source_weight(),
+            format: ResponseFormat::default(),
+            auth_token: None,
+            allow_oversized: false,
+        }
+    }
 }
 
 /// Entropy Collector configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CollectorConfig {
+    /// Structured appliance definitions (take precedence over `appliance_urls`)
+    #[serde(default)]
+    pub appliances: Vec<ApplianceConfig>,
+
     /// URLs of QRNG appliances (supports single or multiple sources)
     #[serde(default)]
     pub appliance_urls: Vec<String>,
@@ -40,6 +152,14 @@ pub struct CollectorConfig {
     #[serde(default)]
     pub mixing_strategy: MixingStrategy,
 
+    /// Handling of sources returning chunks of different lengths
+    #[serde(default)]
+    pub mixing_length_policy: LengthPolicy,
+
+    /// Whiten fetched entropy with SHA-256 before buffering (single or multiple sources)
+    #[serde(default)]
+    pub whiten_output: bool,
+
     /// Bytes to fetch per request
     #[serde(default = "default_chunk_size")]
     pub fetch_chunk_size: usize,
@@ -48,19 +168,93 @@ pub struct CollectorConfig {
     #[serde(default = "default_fetch_interval_ms")]
     pub fetch_interval_ms: u64,
 
+    /// Shortest adaptive fetch interval, used while the buffer drains (default: fetch_interval_ms / 4)
+    #[serde(default)]
+    pub min_fetch_interval_ms: Option<u64>,
+
+    /// Longest adaptive fetch interval, used while the buffer is nearly full (default: fetch_interval_ms * 4)
+    #[serde(default)]
+    pub max_fetch_interval_ms: Option<u64>,
+
     /// Internal buffer size in bytes
     #[serde(default = "default_buffer_size")]
     pub buffer_size: usize,
 
-    /// URL of Entropy Gateway push endpoint
+    /// URL of Entropy Gateway push endpoint (used when `push_urls` is empty)
+    #[serde(default)]
     pub push_url: String,
 
+    /// Push endpoints of redundant gateways, in failover order
+    #[serde(default)]
+    pub push_urls: Vec<String>,
+
+    /// Delivery strategy across `push_urls`
+    #[serde(default)]
+    pub push_mode: PushMode,
+
+    /// Channel packets are pushed over
+    #[serde(default)]
+    pub push_transport: PushTransportKind,
+
+    /// File, FIFO or device written by the `file` push transport
+    #[serde(default)]
+    pub push_file_path: Option<String>,
+
+    /// `host:port` receiving datagrams from the `udp` push transport
+    #[serde(default)]
+    pub push_udp_addr: Option<String>,
+
     /// Push interval in milliseconds
     #[serde(default = "default_push_interval_ms")]
     pub push_interval_ms: u64,
 
-    /// HMAC secret key (hex-encoded)
-    pub hmac_secret_key: String,
+    /// Bytes to accumulate before pushing a packet (0 = push once the buffer is 1% full)
+    #[serde(default)]
+    pub min_push_bytes: usize,
+
+    /// Longest time buffered data waits for `min_push_bytes` before it is pushed anyway
+    #[serde(default = "default_max_push_latency_ms")]
+    pub max_push_latency_ms: u64,
+
+    /// Largest entropy payload per packet; bigger pushes are split into several packets
+    #[serde(default = "default_max_packet_bytes")]
+    pub max_packet_bytes: usize,
+
+    /// Packet authentication scheme
+    #[serde(default)]
+    pub signature_scheme: SignatureScheme,
+
+    /// Hash function of the `hmac` scheme; must match the gateway's
+    #[serde(default)]
+    pub hmac_algo: HmacAlgo,
+
+    /// HMAC secret key (hex-encoded, required for the `hmac` scheme)
+    #[serde(default = "empty_secret")]
+    pub hmac_secret_key: Zeroizing<String>,
+
+    /// Ed25519 private key seed (hex-encoded, 32 bytes, required for the `ed25519` scheme)
+    #[serde(default)]
+    pub ed25519_private_key: Option<Zeroizing<String>>,
+
+    /// Wire format of pushed packets
+    #[serde(default)]
+    pub packet_format: SerializationFormat,
+
+    /// Compress pushed packets with zstd when that makes them smaller
+    #[serde(default)]
+    pub packet_compression: bool,
+
+    /// Payload checksum algorithm of pushed packets
+    #[serde(default)]
+    pub checksum_algo: ChecksumAlgorithm,
+
+    /// ChaCha20-Poly1305 key for payload encryption (hex-encoded, 32 bytes)
+    #[serde(default)]
+    pub encryption_key: Option<Zeroizing<String>>,
+
+    /// Label of the region or site this collector serves, carried in every packet
+    #[serde(default)]
+    pub source_tag: Option<String>,
 
     /// Maximum retry attempts
     #[serde(default = "default_max_retries")]
@@ -69,30 +263,114 @@ pub struct CollectorConfig {
     /// Initial backoff in milliseconds
     #[serde(default = "default_initial_backoff_ms")]
     pub initial_backoff_ms: u64,
+
+    /// Gateway buffer fill percentage the push loop steers toward (unset = push at full rate)
+    #[serde(default)]
+    pub target_fill_percent: Option<f64>,
+
+    /// API key used to query the gateway status when a fill target is set
+    #[serde(default)]
+    pub gateway_api_key: Option<String>,
+
+    /// Capacity in bytes of the queue holding payloads of failed pushes (0 = disabled)
+    #[serde(default = "default_push_retry_queue_size")]
+    pub push_retry_queue_size: usize,
+
+    /// File the push retry queue is saved to on shutdown and restored from on startup
+    #[serde(default)]
+    pub push_retry_queue_path: Option<String>,
+
+    /// Seconds the shutdown flush may keep pushing before the rest is spooled or saved
+    #[serde(default = "default_shutdown_flush_timeout_secs")]
+    pub shutdown_flush_timeout_secs: u64,
+
+    /// Directory spooling failed pushes to disk when the retry queue is full (unset = disabled)
+    #[serde(default)]
+    pub spool_dir: Option<String>,
+
+    /// Maximum total bytes kept in the spool directory; oldest files are evicted first
+    #[serde(default = "default_spool_max_bytes")]
+    pub spool_max_bytes: u64,
+
+    /// Address serving the collector's `/metrics` and `/health` endpoints (unset = disabled)
+    #[serde(default)]
+    pub collector_metrics_addr: Option<String>,
 }
 
 impl CollectorConfig {
+    /// Get all configured sources
+    ///
+    /// Structured `appliances` entries are used when present; otherwise each
+    /// bare URL becomes a source named `source-N` with default metadata.
+    pub fn sources(&self) -> Vec<ApplianceConfig> {
+        if !self.appliances.is_empty() {
+            return self.appliances.clone();
+        }
+
+        self.appliance_urls
+            .iter()
+            .enumerate()
+            .map(|(i, url)| ApplianceConfig::from_url(format!("source-{}", i + 1), url.clone()))
+            .collect()
+    }
+
     /// Get all appliance URLs
     pub fn get_appliance_urls(&self) -> Vec<String> {
-        self.appliance_urls.clone()
+        self.sources().into_iter().map(|source| source.url).collect()
     }
 
     /// Returns true if multiple sources are configured
     pub fn has_multiple_sources(&self) -> bool {
-        self.appliance_urls.len() > 1
+        self.sources().len() > 1
+    }
+
+    /// Get all gateway push endpoints
+    ///
+    /// `push_urls` is used when present; otherwise the single `push_url`.
+    pub fn push_targets(&self) -> Vec<String> {
+        if !self.push_urls.is_empty() {
+            return self.push_urls.clone();
+        }
+        if self.push_url.is_empty() {
+            return Vec::new();
+        }
+        vec![self.push_url.clone()]
     }
 }
 
 impl CollectorConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Load configuration from a TOML file; `QRNG_*` environment variables override its fields
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&read_config_file(path.as_ref())?, std::env::vars())
+    }
+
+    /// Parse a TOML document, with `QRNG_*` entries of `env` overriding individual fields
+    pub fn from_toml(contents: &str, env: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        Self::from_vars(merge_toml_with_env(contents, env)?)
+    }
+
+    /// Build configuration from `QRNG_*` variables
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let vars: BTreeMap<String, String> = vars.into_iter().collect();
         let mut config: Self = envy::prefixed("QRNG_")
-            .from_env()
+            .from_iter(vars.clone())
             .map_err(|e| Error::Config(format!("Failed to parse environment variables: {}", e)))?;
         
+        // Structured appliance definitions as a JSON array
+        if let Some(json) = vars.get("QRNG_APPLIANCES_JSON") {
+            config.appliances = serde_json::from_str(json).map_err(|e| {
+                Error::Config(format!("Failed to parse QRNG_APPLIANCES_JSON: {}", e))
+            })?;
+        }
+
         // Handle comma-separated APPLIANCE_URLS if provided as single string
         if config.appliance_urls.is_empty() {
-            if let Ok(urls_str) = std::env::var("QRNG_APPLIANCE_URLS") {
+            if let Some(urls_str) = vars.get("QRNG_APPLIANCE_URLS") {
                 config.appliance_urls = urls_str
                     .split(',')
                     .map(|s| s.trim().to_string())
@@ -107,26 +385,80 @@ impl CollectorConfig {
 
     /// Validate configuration
     pub fn validate(&self) -> Result<()> {
-        // Validate appliance URLs
-        if self.appliance_urls.is_empty() {
+        // Validate appliance sources
+        let sources = self.sources();
+        if sources.is_empty() {
             return Err(Error::Config(
-                "Must provide at least one appliance URL via QRNG_APPLIANCE_URLS".to_string()
+                "Must provide at least one appliance URL via QRNG_APPLIANCE_URLS or QRNG_APPLIANCES_JSON".to_string()
             ));
         }
 
-        for url in &self.appliance_urls {
-            Url::parse(url)
-                .map_err(|e| Error::Config(format!("Invalid appliance URL '{}': {}", url, e)))?;
+        let mut names = std::collections::HashSet::new();
+        for source in &sources {
+            Url::parse(&source.url)
+                .map_err(|e| Error::Config(format!("Invalid appliance URL '{}': {}", source.url, e)))?;
+
+            if source.name.is_empty() || !names.insert(source.name.as_str()) {
+                return Err(Error::Config(format!(
+                    "Appliance names must be non-empty and unique (got '{}')",
+                    source.name
+                )));
+            }
+
+            if !source.weight.is_finite() || source.weight < 0.0 {
+                return Err(Error::Config(format!(
+                    "Appliance '{}' weight must be a non-negative number",
+                    source.name
+                )));
+            }
+        }
+
+        if sources.iter().all(|source| source.weight == 0.0) {
+            return Err(Error::Config(
+                "At least one appliance must have a weight > 0".to_string()
+            ));
         }
 
-        // Validate push URL
-        Url::parse(&self.push_url)
-            .map_err(|e| Error::Config(format!("Invalid push_url: {}", e)))?;
+        // Validate the push destination of the selected transport
+        match self.push_transport {
+            PushTransportKind::Http => {
+                let push_targets = self.push_targets();
+                if push_targets.is_empty() {
+                    return Err(Error::Config(
+                        "Must provide a gateway push URL via QRNG_PUSH_URL or QRNG_PUSH_URLS".to_string()
+                    ));
+                }
+                for push_url in &push_targets {
+                    Url::parse(push_url)
+                        .map_err(|e| Error::Config(format!("Invalid push URL '{}': {}", push_url, e)))?;
+                }
+            }
+            PushTransportKind::File => {
+                if self.push_file_path.as_deref().unwrap_or_default().is_empty() {
+                    return Err(Error::Config(
+                        "push_file_path is required for the file push transport".to_string()
+                    ));
+                }
+            }
+            PushTransportKind::Udp => {
+                if self.push_udp_addr.as_deref().unwrap_or_default().is_empty() {
+                    return Err(Error::Config(
+                        "push_udp_addr is required for the udp push transport".to_string()
+                    ));
+                }
+                if self.max_packet_bytes > MAX_UDP_PACKET_BYTES {
+                    return Err(Error::Config(format!(
+                        "max_packet_bytes must be <= {} for the udp push transport",
+                        MAX_UDP_PACKET_BYTES
+                    )));
+                }
+            }
+        }
 
         // Validate mixing strategy
         if self.has_multiple_sources() && self.mixing_strategy == MixingStrategy::None {
             return Err(Error::Config(
-                "Multiple sources configured but mixing_strategy is 'none'. Set to 'xor' or 'hkdf'".to_string()
+                "Multiple sources configured but mixing_strategy is 'none'. Set to 'xor', 'hkdf' or 'concat'".to_string()
             ));
         }
 
@@ -144,21 +476,151 @@ impl CollectorConfig {
             ));
         }
 
-        // Validate secret key
-        if self.hmac_secret_key.is_empty() {
-            return Err(Error::Config("hmac_secret_key cannot be empty".to_string()));
+        // Validate adaptive fetch interval bounds
+        let (min_interval, max_interval) = self.fetch_interval_bounds();
+        if min_interval.is_zero() || min_interval > self.fetch_interval() || self.fetch_interval() > max_interval {
+            return Err(Error::Config(
+                "Fetch intervals must satisfy 0 < min_fetch_interval_ms <= fetch_interval_ms <= max_fetch_interval_ms".to_string()
+            ));
+        }
+
+        // Validate signing key
+        match self.signature_scheme {
+            SignatureScheme::Hmac if self.hmac_secret_key.is_empty() => {
+                return Err(Error::Config("hmac_secret_key cannot be empty".to_string()));
+            }
+            SignatureScheme::Hmac => {}
+            SignatureScheme::Ed25519 => {
+                self.packet_signer()?;
+            }
+        }
+
+        // Validate encryption key
+        parse_encryption_key(secret_str(&self.encryption_key))?;
+
+        // Validate source tag
+        if let Some(tag) = &self.source_tag {
+            if tag.is_empty() || tag.len() > MAX_SOURCE_TAG_LEN {
+                return Err(Error::Config(format!(
+                    "source_tag must be 1 to {} bytes",
+                    MAX_SOURCE_TAG_LEN
+                )));
+            }
+        }
+
+        // Validate push coalescing
+        if self.min_push_bytes > self.buffer_size {
+            return Err(Error::Config("min_push_bytes cannot exceed buffer_size".to_string()));
+        }
+        if self.max_push_latency_ms == 0 {
+            return Err(Error::Config("max_push_latency_ms must be > 0".to_string()));
+        }
+        if self.max_packet_bytes == 0 {
+            return Err(Error::Config("max_packet_bytes must be > 0".to_string()));
+        }
+
+        // Validate fill target
+        if let Some(target) = self.target_fill_percent {
+            if !(target > 0.0 && target <= 100.0) {
+                return Err(Error::Config(
+                    "target_fill_percent must be > 0 and <= 100".to_string()
+                ));
+            }
+            if self.gateway_api_key.as_deref().unwrap_or_default().is_empty() {
+                return Err(Error::Config(
+                    "gateway_api_key is required when target_fill_percent is set".to_string()
+                ));
+            }
+            if self.push_transport != PushTransportKind::Http {
+                return Err(Error::Config(
+                    "target_fill_percent requires the http push transport".to_string()
+                ));
+            }
+            self.gateway_status_url()?;
+        }
+
+        // Validate spool
+        if self.spool_dir.is_some() && self.spool_max_bytes == 0 {
+            return Err(Error::Config(
+                "spool_max_bytes must be > 0 when spool_dir is set".to_string()
+            ));
+        }
+
+        // Validate metrics listener
+        if let Some(addr) = &self.collector_metrics_addr {
+            addr.parse::<std::net::SocketAddr>().map_err(|e| {
+                Error::Config(format!("Invalid collector_metrics_addr '{}': {}", addr, e))
+            })?;
         }
 
         Ok(())
     }
 
+    /// Decoded payload encryption key, if configured
+    pub fn encryption_key_bytes(&self) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        parse_encryption_key(secret_str(&self.encryption_key))
+    }
+
+    /// Packet signer for the configured signature scheme
+    pub fn packet_signer(&self) -> Result<PacketSigner> {
+        match self.signature_scheme {
+            SignatureScheme::Hmac => {
+                if self.hmac_secret_key.is_empty() {
+                    return Err(Error::Config("hmac_secret_key cannot be empty".to_string()));
+                }
+                let key = crate::crypto::decode_hex(&self.hmac_secret_key)
+                    .map_err(|e| Error::Config(format!("Invalid hmac_secret_key: {}", e)))?;
+                Ok(PacketSigner::new(key, self.hmac_algo))
+            }
+            SignatureScheme::Ed25519 => {
+                let key = parse_hex_key(
+                    "ed25519_private_key",
+                    secret_str(&self.ed25519_private_key),
+                    crate::crypto::ED25519_KEY_LEN,
+                )?
+                .ok_or_else(|| {
+                    Error::Config("ed25519_private_key is required for the ed25519 signature scheme".to_string())
+                })?;
+                PacketSigner::ed25519(&key)
+            }
+        }
+    }
+
+    /// Status endpoint of the primary gateway, resolved as a sibling of its push endpoint
+    pub fn gateway_status_url(&self) -> Result<Url> {
+        let push_url = self.push_targets().into_iter().next().unwrap_or_default();
+        Self::status_url_for(&push_url)
+    }
+
+    /// Status endpoint of the gateway serving `push_url`
+    pub fn status_url_for(push_url: &str) -> Result<Url> {
+        Url::parse(push_url)
+            .and_then(|url| url.join("api/status"))
+            .map_err(|e| Error::Config(format!("Invalid push URL '{}': {}", push_url, e)))
+    }
+
     pub fn fetch_interval(&self) -> Duration {
         Duration::from_millis(self.fetch_interval_ms)
     }
 
+    /// Floor and ceiling of the adaptive fetch interval
+    pub fn fetch_interval_bounds(&self) -> (Duration, Duration) {
+        let min = self.min_fetch_interval_ms.unwrap_or((self.fetch_interval_ms / 4).max(1));
+        let max = self.max_fetch_interval_ms.unwrap_or(self.fetch_interval_ms.saturating_mul(4));
+        (Duration::from_millis(min), Duration::from_millis(max))
+    }
+
     pub fn push_interval(&self) -> Duration {
         Duration::from_millis(self.push_interval_ms)
     }
+
+    pub fn max_push_latency(&self) -> Duration {
+        Duration::from_millis(self.max_push_latency_ms)
+    }
+
+    pub fn shutdown_flush_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_flush_timeout_secs)
+    }
 }
 
 /// Entropy Gateway configuration
@@ -179,20 +641,103 @@ pub struct GatewayConfig {
     /// Buffer overflow policy: "discard" or "replace"
     #[serde(default = "default_overflow_policy")]
     pub buffer_overflow_policy: String,
+
+    /// File the buffer is saved to on shutdown and restored from on startup
+    #[serde(default)]
+    pub buffer_persist_path: Option<String>,
+
+    /// Seconds in-flight requests may take to finish once shutdown begins
+    #[serde(default = "default_shutdown_grace_period_secs")]
+    pub shutdown_grace_period_secs: u64,
+
+    /// Milliseconds a handler may take to produce its response before a 504
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+
+    /// PEM certificate chain for serving HTTPS (requires `tls_key_path`)
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+
+    /// PEM private key for serving HTTPS (requires `tls_cert_path`)
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
     
     /// Valid API keys for authentication
     pub api_keys: Vec<String>,
+
+    /// Key for the `/admin` endpoints; must differ from every client key (admin endpoints are disabled if unset)
+    #[serde(default)]
+    pub admin_api_key: Option<Zeroizing<String>>,
     
     /// Rate limit: requests per second per key
     #[serde(default = "default_rate_limit")]
     pub rate_limit_per_second: u32,
+
+    /// Tokens a key can accumulate while idle (default: `rate_limit_per_second`)
+    #[serde(default)]
+    pub rate_limit_burst: Option<u32>,
+
+    /// Largest single `/api/random` request in bytes (default: 64 KiB)
+    #[serde(default)]
+    pub max_request_bytes: Option<usize>,
+
+    /// Refuse to serve entropy while the oldest buffered entry is older than this many seconds
+    ///
+    /// Requires `buffer_ttl_secs` between 1 and this limit, so fresh pushes evict stale entries.
+    #[serde(default)]
+    pub max_served_age_secs: Option<u64>,
+
+    /// Buffer fill percentage below which a growing share of entropy requests get 429
+    #[serde(default)]
+    pub throttle_fill_percent: Option<f64>,
+
+    /// Pass `/api/random` output through HKDF keyed by the caller's API key
+    #[serde(default)]
+    pub per_key_isolation: bool,
+
+    /// Append-only JSON-lines ledger of served entropy (unset = no file)
+    #[serde(default)]
+    pub audit_log: Option<String>,
+
+    /// Recent ledger entries kept in memory for `GET /admin/audit` (0 = none)
+    #[serde(default)]
+    pub audit_ring_size: usize,
     
+    /// Packet authentication scheme expected from the collector
+    #[serde(default)]
+    pub signature_scheme: SignatureScheme,
+
+    /// Hash function of the `hmac` scheme expected from the collector
+    #[serde(default)]
+    pub hmac_algo: HmacAlgo,
+
     /// HMAC secret key for push mode (hex-encoded)
     #[serde(default)]
-    pub hmac_secret_key: Option<String>,
+    pub hmac_secret_key: Option<Zeroizing<String>>,
+
+    /// Additional accepted HMAC keys (hex-encoded), for rotating the shared secret
+    #[serde(default)]
+    pub hmac_secret_keys: Vec<Zeroizing<String>>,
+
+    /// Collector's Ed25519 public key for push mode (hex-encoded, 32 bytes)
+    #[serde(default)]
+    pub ed25519_public_key: Option<String>,
+
+    /// ChaCha20-Poly1305 key for decrypting pushed payloads (hex-encoded, 32 bytes)
+    #[serde(default)]
+    pub encryption_key: Option<Zeroizing<String>>,
+
+    /// Number of recent packet sequence numbers tracked for replay detection
+    #[serde(default = "default_replay_window_size")]
+    pub replay_window_size: usize,
     
-    /// Direct mode configuration (only used if deployment_mode = DirectAccess)
+    /// Fetch directly from an appliance into the buffer, bypassing the collector
+    #[serde(default)]
     pub direct_mode: Option<DirectModeConfig>,
+
+    /// Serve from a ChaCha20 CSPRNG, labeled `X-Entropy-Source: fallback`, when the buffer runs dry
+    #[serde(default)]
+    pub fallback_csprng: bool,
     
     /// Enable MCP server
     #[serde(default)]
@@ -201,6 +746,22 @@ pub struct GatewayConfig {
     /// Enable Prometheus metrics
     #[serde(default = "default_true")]
     pub metrics_enabled: bool,
+
+    /// Compress hex/base64 responses when the client sends `Accept-Encoding`
+    #[serde(default = "default_true")]
+    pub compression_enabled: bool,
+
+    /// OTLP/HTTP collector base URL (e.g. `http://otel-collector:4318`); enables span and metric export
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Seconds between OTLP metric exports
+    #[serde(default = "default_otlp_export_interval_secs")]
+    pub otlp_export_interval_secs: u64,
+
+    /// URL receiving a JSON alert when the buffer enters the low watermark
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
 }
 
 /// Direct access mode configuration
@@ -218,18 +779,69 @@ pub struct DirectModeConfig {
     pub fetch_interval_ms: u64,
 }
 
+impl DirectModeConfig {
+    /// Validate the appliance URL, chunk size and interval
+    pub fn validate(&self) -> Result<()> {
+        url::Url::parse(&self.appliance_url).map_err(|e| {
+            Error::Config(format!("Invalid direct_mode appliance_url '{}': {}", self.appliance_url, e))
+        })?;
+        if self.fetch_chunk_size == 0 || self.fetch_chunk_size > crate::MAX_REQUEST_SIZE {
+            return Err(Error::Config(format!(
+                "direct_mode fetch_chunk_size must be between 1 and {}",
+                crate::MAX_REQUEST_SIZE
+            )));
+        }
+        if self.fetch_interval_ms == 0 {
+            return Err(Error::Config("direct_mode fetch_interval_ms must be > 0".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The appliance as a source definition
+    pub fn appliance(&self) -> ApplianceConfig {
+        ApplianceConfig::from_url("direct", &self.appliance_url)
+    }
+
+    pub fn fetch_interval(&self) -> Duration {
+        Duration::from_millis(self.fetch_interval_ms)
+    }
+}
+
 impl GatewayConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Load configuration from a TOML file; `QRNG_*` environment variables override its fields
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&read_config_file(path.as_ref())?, std::env::vars())
+    }
+
+    /// Parse a TOML document, with `QRNG_*` entries of `env` overriding individual fields
+    pub fn from_toml(contents: &str, env: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        Self::from_vars(merge_toml_with_env(contents, env)?)
+    }
+
+    /// Build configuration from `QRNG_*` variables
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
         // Parse basic config from env
+        let vars: BTreeMap<String, String> = vars.into_iter().collect();
         let mut config: Self = envy::prefixed("QRNG_")
-            .from_env()
+            .from_iter(vars.clone())
             .map_err(|e| Error::Config(format!("Failed to parse environment variables: {}", e)))?;
 
         // Parse API keys from comma-separated string
-        if let Ok(keys) = std::env::var("QRNG_API_KEYS") {
+        if let Some(keys) = vars.get("QRNG_API_KEYS") {
             config.api_keys = keys.split(',').map(|s| s.trim().to_string()).collect();
         }
+
+        // Direct mode settings as a JSON object
+        if let Some(json) = vars.get("QRNG_DIRECT_MODE_JSON") {
+            config.direct_mode = Some(serde_json::from_str(json).map_err(|e| {
+                Error::Config(format!("Failed to parse QRNG_DIRECT_MODE_JSON: {}", e))
+            })?);
+        }
         config.validate()?;
         Ok(config)
     }
@@ -245,9 +857,174 @@ impl GatewayConfig {
         if self.api_keys.is_empty() {
             return Err(Error::Config("At least one API key required".to_string()));
         }
+
+        // Validate admin key; a client key must never grant admin access
+        if let Some(admin_key) = secret_str(&self.admin_api_key) {
+            if admin_key.is_empty() || self.api_keys.iter().any(|key| key == admin_key) {
+                return Err(Error::Config(
+                    "admin_api_key must be non-empty and distinct from the client API keys".to_string()
+                ));
+            }
+        }
+
+        // Validate burst capacity; a smaller bucket would lower the sustained rate
+        if self.rate_limit_burst.is_some_and(|burst| burst < self.rate_limit_per_second) {
+            return Err(Error::Config(format!(
+                "rate_limit_burst must be >= rate_limit_per_second ({})",
+                self.rate_limit_per_second
+            )));
+        }
+
+        // Validate request size limit; larger requests could never be served
+        if let Some(max) = self.max_request_bytes {
+            if max == 0 || max > self.buffer_size {
+                return Err(Error::Config(format!(
+                    "max_request_bytes must be between 1 and buffer_size ({})",
+                    self.buffer_size
+                )));
+            }
+        }
+
+        // Validate served entropy age limit
+        if let Some(max_age) = self.max_served_age_secs {
+            if max_age == 0 {
+                return Err(Error::Config("max_served_age_secs must be > 0".to_string()));
+            }
+            // Stale entries are only evicted by the TTL as fresh data arrives;
+            // without one the gateway would refuse requests until the buffer drains
+            if self.buffer_ttl_secs == 0 || self.buffer_ttl_secs > max_age {
+                return Err(Error::Config(format!(
+                    "max_served_age_secs requires buffer_ttl_secs between 1 and {}",
+                    max_age
+                )));
+            }
+        }
+
+        // Validate low-buffer throttle watermark
+        if let Some(watermark) = self.throttle_fill_percent {
+            if !(watermark > 0.0 && watermark <= 100.0) {
+                return Err(Error::Config(
+                    "throttle_fill_percent must be > 0 and <= 100".to_string()
+                ));
+            }
+        }
+
+        // Validate request deadline
+        if self.request_timeout_ms == 0 {
+            return Err(Error::Config("request_timeout_ms must be > 0".to_string()));
+        }
+
+        // Validate replay window
+        if self.replay_window_size == 0 {
+            return Err(Error::Config("replay_window_size must be > 0".to_string()));
+        }
+
+        // Validate direct mode
+        if let Some(direct) = &self.direct_mode {
+            direct.validate()?;
+        }
+
+        // Validate encryption key
+        parse_encryption_key(secret_str(&self.encryption_key))?;
+
+        // Validate push verification keys
+        self.packet_verifier()?;
+
+        // Validate TLS settings
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err(Error::Config(
+                "tls_cert_path and tls_key_path must be set together".to_string()
+            ));
+        }
+
+        // Validate OTLP export
+        if let Some(endpoint) = &self.otlp_endpoint {
+            let url = url::Url::parse(endpoint)
+                .map_err(|e| Error::Config(format!("Invalid otlp_endpoint '{}': {}", endpoint, e)))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(Error::Config(format!(
+                    "otlp_endpoint '{}' must be an http or https URL",
+                    endpoint
+                )));
+            }
+        }
+        if self.otlp_export_interval_secs == 0 {
+            return Err(Error::Config("otlp_export_interval_secs must be > 0".to_string()));
+        }
+
+        // Validate alert webhook
+        if let Some(webhook) = &self.alert_webhook_url {
+            let url = url::Url::parse(webhook)
+                .map_err(|e| Error::Config(format!("Invalid alert_webhook_url '{}': {}", webhook, e)))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(Error::Config(format!(
+                    "alert_webhook_url '{}' must be an http or https URL",
+                    webhook
+                )));
+            }
+        }
         Ok(())
     }
 
+    /// Verifier for pushed packets, or `None` when no key for the scheme is configured
+    pub fn packet_verifier(&self) -> Result<Option<PacketSigner>> {
+        match self.signature_scheme {
+            SignatureScheme::Hmac => {
+                let keys = secret_str(&self.hmac_secret_key)
+                    .into_iter()
+                    .chain(self.hmac_secret_keys.iter().map(|key| key.as_str()))
+                    .map(|key| parse_hmac_key(key.trim()))
+                    .collect::<Result<Vec<_>>>()?;
+                if keys.is_empty() {
+                    return Ok(None);
+                }
+                PacketSigner::with_hmac_keys(keys, self.hmac_algo).map(Some)
+            }
+            SignatureScheme::Ed25519 => parse_hex_key(
+                "ed25519_public_key",
+                self.ed25519_public_key.as_deref(),
+                crate::crypto::ED25519_KEY_LEN,
+            )?
+            .map(|key| PacketSigner::ed25519_verifier(&key))
+            .transpose(),
+        }
+    }
+
+    /// Token bucket capacity of each API key
+    pub fn rate_limit_burst(&self) -> u32 {
+        self.rate_limit_burst.unwrap_or(self.rate_limit_per_second)
+    }
+
+    /// Largest number of bytes a single `/api/random` request may ask for
+    pub fn max_request_bytes(&self) -> usize {
+        self.max_request_bytes.unwrap_or(crate::MAX_REQUEST_SIZE)
+    }
+
+    /// Interval between OTLP metric exports
+    pub fn otlp_export_interval(&self) -> Duration {
+        Duration::from_secs(self.otlp_export_interval_secs)
+    }
+
+    /// Time in-flight requests may take to finish once shutdown begins
+    pub fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_period_secs)
+    }
+
+    /// Deadline for a handler to produce its response
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    /// Certificate and key paths when HTTPS is enabled
+    pub fn tls_paths(&self) -> Option<(&str, &str)> {
+        self.tls_cert_path.as_deref().zip(self.tls_key_path.as_deref())
+    }
+
+    /// Decoded payload encryption key, if configured
+    pub fn encryption_key_bytes(&self) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        parse_encryption_key(secret_str(&self.encryption_key))
+    }
+
     pub fn buffer_ttl(&self) -> Option<chrono::Duration> {
         if self.buffer_ttl_secs > 0 {
             Some(chrono::Duration::seconds(self.buffer_ttl_secs as i64))
@@ -264,7 +1041,113 @@ impl GatewayConfig {
     }
 }
 
+/// Read a configuration file
+fn read_config_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+/// Flatten a TOML document into `QRNG_*` variables and overlay `env` on top
+///
+/// File values are rendered the way they would be written in the environment
+/// (arrays comma-separated, nested tables as `QRNG_<KEY>_JSON`), so environment
+/// variables override individual fields with the same parsing rules as `from_env`.
+fn merge_toml_with_env(
+    contents: &str,
+    env: impl IntoIterator<Item = (String, String)>,
+) -> Result<BTreeMap<String, String>> {
+    let table: toml::Table = toml::from_str(contents)
+        .map_err(|e| Error::Config(format!("Failed to parse TOML config: {}", e)))?;
+
+    let mut vars = BTreeMap::new();
+    for (key, value) in table {
+        let name = format!("QRNG_{}", key.to_uppercase());
+        match value {
+            toml::Value::String(s) => {
+                vars.insert(name, s);
+            }
+            toml::Value::Array(items) if items.iter().all(|item| !item.is_table()) => {
+                let items: Vec<String> = items
+                    .into_iter()
+                    .map(|item| match item {
+                        toml::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .collect();
+                vars.insert(name, items.join(","));
+            }
+            toml::Value::Array(_) | toml::Value::Table(_) => {
+                let json = serde_json::to_string(&value).map_err(|e| {
+                    Error::Config(format!("Invalid value for '{}': {}", key, e))
+                })?;
+                vars.insert(format!("{}_JSON", name), json);
+            }
+            other => {
+                vars.insert(name, other.to_string());
+            }
+        }
+    }
+
+    vars.extend(env.into_iter().filter(|(key, _)| key.starts_with("QRNG_")));
+    Ok(vars)
+}
+
+/// Default for required secrets that are validated later
+fn empty_secret() -> Zeroizing<String> {
+    Zeroizing::new(String::new())
+}
+
+/// Borrow an optional secret as a string slice
+fn secret_str(secret: &Option<Zeroizing<String>>) -> Option<&str> {
+    secret.as_ref().map(|secret| secret.as_str())
+}
+
+/// Decode a hex-encoded ChaCha20-Poly1305 key
+fn parse_encryption_key(key: Option<&str>) -> Result<Option<Zeroizing<Vec<u8>>>> {
+    parse_hex_key("encryption_key", key, crate::crypto::ENCRYPTION_KEY_LEN)
+}
+
+/// Decode an optional hex-encoded key of exactly `len` bytes
+fn parse_hex_key(field: &str, key: Option<&str>, len: usize) -> Result<Option<Zeroizing<Vec<u8>>>> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+
+    if key.len() != len * 2 {
+        return Err(Error::Config(format!(
+            "{} must be {} hex characters ({} bytes)",
+            field,
+            len * 2,
+            len
+        )));
+    }
+
+    crate::crypto::decode_hex(key)
+        .map(|key| Some(Zeroizing::new(key)))
+        .map_err(|e| Error::Config(format!("Invalid {}: {}", field, e)))
+}
+
+/// Decode a hex-encoded HMAC key accepted for push verification
+///
+/// An empty key (e.g. from a trailing comma in `QRNG_HMAC_SECRET_KEYS`) would
+/// let anyone forge packets, so empty and short keys are refused.
+fn parse_hmac_key(key: &str) -> Result<Vec<u8>> {
+    let min_len = crate::crypto::MIN_HMAC_KEY_LEN;
+    if key.len() < min_len * 2 {
+        return Err(Error::Config(format!(
+            "HMAC secret keys must be at least {} hex characters ({} bytes)",
+            min_len * 2,
+            min_len
+        )));
+    }
+    crate::crypto::decode_hex(key).map_err(|e| Error::Config(format!("Invalid HMAC secret key: {}", e)))
+}
+
 // Default value functions
+fn default_source_weight() -> f64 {
+    1.0
+}
+
 fn default_chunk_size() -> usize {
     crate::DEFAULT_CHUNK_SIZE
 }
@@ -285,6 +1168,14 @@ fn default_push_interval_ms() -> u64 {
     500  // 500ms = 2 pushes per second
 }
 
+fn default_max_push_latency_ms() -> u64 {
+    5000
+}
+
+fn default_max_packet_bytes() -> usize {
+    1024 * 1024
+}
+
 fn default_max_retries() -> u32 {
     5
 }
@@ -293,6 +1184,18 @@ fn default_initial_backoff_ms() -> u64 {
     100
 }
 
+fn default_push_retry_queue_size() -> usize {
+    4 * 1024 * 1024 // 4 MB
+}
+
+fn default_shutdown_flush_timeout_secs() -> u64 {
+    30
+}
+
+fn default_spool_max_bytes() -> u64 {
+    256 * 1024 * 1024 // 256 MB
+}
+
 fn default_listen_address() -> String {
     "0.0.0.0:8080".to_string()
 }
@@ -301,6 +1204,22 @@ fn default_rate_limit() -> u32 {
     100
 }
 
+fn default_replay_window_size() -> usize {
+    64
+}
+
+fn default_shutdown_grace_period_secs() -> u64 {
+    30
+}
+
+fn default_request_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_otlp_export_interval_secs() -> u64 {
+    60
+}
+
 fn default_true() -> bool {
     true
 }
@@ -316,16 +1235,45 @@ mod tests {
     #[test]
     fn test_collector_config_validation() {
         let config = CollectorConfig {
+            appliances: vec![],
             appliance_urls: vec!["https://example.com/random".to_string()],
             mixing_strategy: MixingStrategy::None,
+            mixing_length_policy: LengthPolicy::Strict,
             fetch_chunk_size: 1024,
             fetch_interval_ms: 100,
+            min_fetch_interval_ms: None,
+            max_fetch_interval_ms: None,
             buffer_size: 10240,
             push_url: "https://gateway.com/push".to_string(),
+            push_urls: Vec::new(),
+            push_mode: PushMode::Failover,
+            push_transport: PushTransportKind::Http,
+            push_file_path: None,
+            push_udp_addr: None,
             push_interval_ms: 500,
-            hmac_secret_key: "secret123".to_string(),
+            min_push_bytes: 0,
+            max_push_latency_ms: 5000,
+            max_packet_bytes: 1024 * 1024,
+            signature_scheme: SignatureScheme::Hmac,
+            hmac_algo: HmacAlgo::Sha256,
+            hmac_secret_key: "secret123".to_string().into(),
+            ed25519_private_key: None,
+            packet_format: SerializationFormat::MessagePack,
+            packet_compression: false,
+            whiten_output: false,
+            checksum_algo: ChecksumAlgorithm::Crc32,
+            encryption_key: None,
+            source_tag: None,
             max_retries: 5,
             initial_backoff_ms: 100,
+            target_fill_percent: None,
+            gateway_api_key: None,
+            push_retry_queue_size: 4 * 1024 * 1024,
+            push_retry_queue_path: None,
+            shutdown_flush_timeout_secs: 30,
+            spool_dir: None,
+            spool_max_bytes: 256 * 1024 * 1024,
+            collector_metrics_addr: None,
         };
         assert!(config.validate().is_ok());
     }
@@ -333,23 +1281,58 @@ mod tests {
     #[test]
     fn test_multi_source_config() {
         let config = CollectorConfig {
+            appliances: vec![],
             appliance_urls: vec![
                 "https://source1.com/random".to_string(),
                 "https://source2.com/random".to_string(),
             ],
             mixing_strategy: MixingStrategy::Xor,
+            mixing_length_policy: LengthPolicy::Strict,
             fetch_chunk_size: 1024,
             fetch_interval_ms: 100,
+            min_fetch_interval_ms: None,
+            max_fetch_interval_ms: None,
             buffer_size: 10240,
             push_url: "https://gateway.com/push".to_string(),
+            push_urls: Vec::new(),
+            push_mode: PushMode::Failover,
+            push_transport: PushTransportKind::Http,
+            push_file_path: None,
+            push_udp_addr: None,
             push_interval_ms: 500,
-            hmac_secret_key: "secret123".to_string(),
+            min_push_bytes: 0,
+            max_push_latency_ms: 5000,
+            max_packet_bytes: 1024 * 1024,
+            signature_scheme: SignatureScheme::Hmac,
+            hmac_algo: HmacAlgo::Sha256,
+            hmac_secret_key: "secret123".to_string().into(),
+            ed25519_private_key: None,
+            packet_format: SerializationFormat::MessagePack,
+            packet_compression: false,
+            whiten_output: false,
+            checksum_algo: ChecksumAlgorithm::Crc32,
+            encryption_key: None,
+            source_tag: None,
             max_retries: 5,
             initial_backoff_ms: 100,
+            target_fill_percent: None,
+            gateway_api_key: None,
+            push_retry_queue_size: 4 * 1024 * 1024,
+            push_retry_queue_path: None,
+            shutdown_flush_timeout_secs: 30,
+            spool_dir: None,
+            spool_max_bytes: 256 * 1024 * 1024,
+            collector_metrics_addr: None,
         };
         assert!(config.validate().is_ok());
         assert!(config.has_multiple_sources());
         assert_eq!(config.get_appliance_urls().len(), 2);
+
+        let concat = CollectorConfig {
+            mixing_strategy: MixingStrategy::Concat,
+            ..config
+        };
+        assert!(concat.validate().is_ok());
     }
 
     #[test]
@@ -359,13 +1342,575 @@ mod tests {
             buffer_size: 10240,
             buffer_ttl_secs: 3600,
             buffer_overflow_policy: "discard".to_string(),
+            buffer_persist_path: None,
+            shutdown_grace_period_secs: 30,
+            request_timeout_ms: 30_000,
+            tls_cert_path: None,
+            tls_key_path: None,
             api_keys: vec!["key1".to_string()],
+            admin_api_key: None,
             rate_limit_per_second: 100,
-            hmac_secret_key: Some("secret".to_string()),
+            rate_limit_burst: None,
+            max_request_bytes: None,
+            max_served_age_secs: None,
+            throttle_fill_percent: None,
+            per_key_isolation: false,
+            audit_log: None,
+            audit_ring_size: 0,
+            signature_scheme: SignatureScheme::Hmac,
+            hmac_algo: HmacAlgo::Sha256,
+            hmac_secret_key: Some("00112233445566778899aabbccddeeff".to_string().into()),
+            hmac_secret_keys: Vec::new(),
+            ed25519_public_key: None,
+            encryption_key: None,
+            replay_window_size: 64,
             direct_mode: None,
+            fallback_csprng: false,
             mcp_enabled: false,
             metrics_enabled: true,
+            compression_enabled: true,
+            otlp_endpoint: None,
+            otlp_export_interval_secs: 60,
+            alert_webhook_url: None,
         };
         assert!(config.validate().is_ok());
+        assert_eq!(config.max_request_bytes(), crate::MAX_REQUEST_SIZE);
+
+        // An explicit request limit must fit in the buffer
+        let mut config = config;
+        config.max_request_bytes = Some(10240);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.max_request_bytes(), 10240);
+        config.max_request_bytes = Some(10241);
+        assert!(config.validate().is_err());
+        config.max_request_bytes = Some(0);
+        assert!(config.validate().is_err());
+        config.max_request_bytes = None;
+
+        config.max_served_age_secs = Some(0);
+        assert!(config.validate().is_err());
+        config.max_served_age_secs = Some(3600);
+        assert!(config.validate().is_ok());
+        config.max_served_age_secs = Some(60);
+        assert!(config.validate().is_err());
+        config.buffer_ttl_secs = 0;
+        assert!(config.validate().is_err());
+        config.buffer_ttl_secs = 60;
+        assert!(config.validate().is_ok());
+        config.buffer_ttl_secs = 3600;
+        config.max_served_age_secs = None;
+
+        for watermark in [0.0, -5.0, 150.0] {
+            config.throttle_fill_percent = Some(watermark);
+            assert!(config.validate().is_err());
+        }
+        config.throttle_fill_percent = Some(20.0);
+        assert!(config.validate().is_ok());
+        config.throttle_fill_percent = None;
+
+        // The admin key cannot double as a client key
+        config.admin_api_key = Some("admin".to_string().into());
+        assert!(config.validate().is_ok());
+        config.admin_api_key = Some("key1".to_string().into());
+        assert!(config.validate().is_err());
+        config.admin_api_key = Some(String::new().into());
+        assert!(config.validate().is_err());
+        config.admin_api_key = None;
+
+        // OTLP export needs an http(s) collector URL
+        config.otlp_endpoint = Some("http://otel-collector:4318".to_string());
+        assert!(config.validate().is_ok());
+        config.otlp_endpoint = Some("otel-collector:4318".to_string());
+        assert!(config.validate().is_err());
+        config.otlp_endpoint = Some("not a url".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    fn structured_config() -> CollectorConfig {
+        let mut config = CollectorConfig {
+            appliances: vec![],
+            appliance_urls: vec![],
+            mixing_strategy: MixingStrategy::Hkdf,
+            mixing_length_policy: LengthPolicy::Strict,
+            fetch_chunk_size: 1024,
+            fetch_interval_ms: 100,
+            min_fetch_interval_ms: None,
+            max_fetch_interval_ms: None,
+            buffer_size: 10240,
+            push_url: "https://gateway.com/push".to_string(),
+            push_urls: Vec::new(),
+            push_mode: PushMode::Failover,
+            push_transport: PushTransportKind::Http,
+            push_file_path: None,
+            push_udp_addr: None,
+            push_interval_ms: 500,
+            min_push_bytes: 0,
+            max_push_latency_ms: 5000,
+            max_packet_bytes: 1024 * 1024,
+            signature_scheme: SignatureScheme::Hmac,
+            hmac_algo: HmacAlgo::Sha256,
+            hmac_secret_key: "secret123".to_string().into(),
+            ed25519_private_key: None,
+            packet_format: SerializationFormat::MessagePack,
+            packet_compression: false,
+            whiten_output: false,
+            checksum_algo: ChecksumAlgorithm::Crc32,
+            encryption_key: None,
+            source_tag: None,
+            max_retries: 5,
+            initial_backoff_ms: 100,
+            target_fill_percent: None,
+            gateway_api_key: None,
+            push_retry_queue_size: 4 * 1024 * 1024,
+            push_retry_queue_path: None,
+            shutdown_flush_timeout_secs: 30,
+            spool_dir: None,
+            spool_max_bytes: 256 * 1024 * 1024,
+            collector_metrics_addr: None,
+        };
+        config.appliances = serde_json::from_str(r#"[
+            {"name": "lab-a", "url": "https://qrng-a.example.com/random", "weight": 2.0,
+             "format": "json_array", "auth_token": "token-a"},
+            {"name": "lab-b", "url": "https://qrng-b.example.com/random", "format": "binary"},
+            {"name": "canary", "url": "https://qrng-c.example.com/random", "weight": 0.0}
+        ]"#).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_structured_appliances() {
+        let config = structured_config();
+        assert!(config.validate().is_ok());
+        assert!(config.has_multiple_sources());
+
+        let sources = config.sources();
+        assert_eq!(sources.len(), 3);
+        assert_eq!(sources[0].name, "lab-a");
+        assert_eq!(sources[0].weight, 2.0);
+        assert_eq!(sources[0].format, ResponseFormat::JsonArray);
+        assert_eq!(sources[0].auth_token.as_deref(), Some("token-a"));
+        assert_eq!(sources[1].weight, 1.0);
+        assert_eq!(sources[1].format, ResponseFormat::Binary);
+        assert_eq!(sources[2].format, ResponseFormat::Auto);
+        assert_eq!(config.get_appliance_urls()[1], "https://qrng-b.example.com/random");
+    }
+
+    #[test]
+    fn test_structured_appliances_take_precedence() {
+        let mut config = structured_config();
+        config.appliance_urls = vec!["https://legacy.example.com/random".to_string()];
+        assert_eq!(config.sources().len(), 3);
+
+        // Bare URLs are still used when no structured entries exist
+        config.appliances.clear();
+        let sources = config.sources();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].name, "source-1");
+        assert_eq!(sources[0].url, "https://legacy.example.com/random");
+    }
+
+    #[test]
+    fn test_structured_appliances_validation() {
+        let mut config = structured_config();
+        config.appliances[1].name = "lab-a".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = structured_config();
+        config.appliances[0].weight = -1.0;
+        assert!(config.validate().is_err());
+
+        let mut config = structured_config();
+        for source in &mut config.appliances {
+            source.weight = 0.0;
+        }
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_encryption_key_validation() {
+        let mut config = structured_config();
+        config.encryption_key = Some("ab".repeat(32).into());
+        assert!(config.validate().is_ok());
+        assert_eq!(*config.encryption_key_bytes().unwrap().unwrap(), vec![0xAB; 32]);
+
+        // Wrong length and non-hex keys are rejected
+        config.encryption_key = Some("ab".repeat(16).into());
+        assert!(config.validate().is_err());
+        config.encryption_key = Some("zz".repeat(32).into());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_signature_scheme_config() {
+        let mut config = structured_config();
+        config.hmac_secret_key = "0011".to_string().into();
+        assert_eq!(config.packet_signer().unwrap().scheme(), SignatureScheme::Hmac);
+
+        // Ed25519 needs a 32-byte private key; the HMAC key is no longer required
+        config.signature_scheme = SignatureScheme::Ed25519;
+        config.hmac_secret_key.clear();
+        assert!(config.validate().is_err());
+        config.ed25519_private_key = Some("ab".repeat(16).into());
+        assert!(config.validate().is_err());
+        config.ed25519_private_key = Some("ab".repeat(32).into());
+        assert!(config.validate().is_ok());
+        let signer = config.packet_signer().unwrap();
+        assert_eq!(signer.scheme(), SignatureScheme::Ed25519);
+
+        let mut gateway = GatewayConfig::from_vars([
+            ("QRNG_API_KEYS".to_string(), "key".to_string()),
+            ("QRNG_SIGNATURE_SCHEME".to_string(), "ed25519".to_string()),
+        ])
+        .unwrap();
+        assert!(gateway.packet_verifier().unwrap().is_none());
+
+        gateway.ed25519_public_key = Some(crate::crypto::encode_hex(&signer.ed25519_public_key().unwrap()));
+        assert!(gateway.validate().is_ok());
+        let verifier = gateway.packet_verifier().unwrap().unwrap();
+        assert_eq!(verifier.ed25519_public_key(), signer.ed25519_public_key());
+
+        gateway.ed25519_public_key = Some("zz".repeat(32));
+        assert!(gateway.validate().is_err());
+    }
+
+    #[test]
+    fn test_gateway_hmac_key_rotation() {
+        // 16-byte keys filled with a single byte value
+        let key = |byte: u8| vec![byte; 16];
+        let hex = |byte: u8| crate::crypto::encode_hex(&key(byte));
+
+        let gateway = GatewayConfig::from_vars([
+            ("QRNG_API_KEYS".to_string(), "key".to_string()),
+            ("QRNG_HMAC_SECRET_KEY".to_string(), hex(0x11)),
+            ("QRNG_HMAC_SECRET_KEYS".to_string(), format!("{},{}", hex(0x22), hex(0x33))),
+        ])
+        .unwrap();
+        assert_eq!(gateway.hmac_secret_keys.len(), 2);
+        let verifier = gateway.packet_verifier().unwrap().unwrap();
+
+        let data = b"rotating";
+        for byte in [0x11, 0x22, 0x33] {
+            let signature = PacketSigner::new(key(byte), HmacAlgo::Sha256).sign(data).unwrap();
+            assert!(verifier.verify(data, &signature).unwrap());
+        }
+        let unknown = PacketSigner::new(key(0x44), HmacAlgo::Sha256).sign(data).unwrap();
+        assert!(!verifier.verify(data, &unknown).unwrap());
+
+        // A SHA-512 gateway rejects tags made with SHA-256 under the same keys
+        let gateway = GatewayConfig::from_vars([
+            ("QRNG_API_KEYS".to_string(), "key".to_string()),
+            ("QRNG_HMAC_SECRET_KEY".to_string(), hex(0x11)),
+            ("QRNG_HMAC_ALGO".to_string(), "sha512".to_string()),
+        ])
+        .unwrap();
+        let verifier = gateway.packet_verifier().unwrap().unwrap();
+        assert_eq!(verifier.hmac_algo(), Some(HmacAlgo::Sha512));
+        let sha256 = PacketSigner::new(key(0x11), HmacAlgo::Sha256).sign(data).unwrap();
+        assert!(!verifier.verify(data, &sha256).unwrap());
+        let sha512 = PacketSigner::new(key(0x11), HmacAlgo::Sha512).sign(data).unwrap();
+        assert!(verifier.verify(data, &sha512).unwrap());
+
+        // The list alone is enough
+        let gateway = GatewayConfig::from_vars([
+            ("QRNG_API_KEYS".to_string(), "key".to_string()),
+            ("QRNG_HMAC_SECRET_KEYS".to_string(), hex(0x22)),
+        ])
+        .unwrap();
+        assert!(gateway.packet_verifier().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_gateway_rejects_weak_hmac_keys() {
+        let strong = crate::crypto::encode_hex(&[0x22; 16]);
+        for keys in [
+            // A trailing comma would add an empty key anyone can sign with
+            format!("{},", strong),
+            "aabb,".to_string(),
+            "abc".to_string(),
+            // Too short, and not hex
+            "aabbccdd".to_string(),
+            format!("{},{}", strong, "zz".repeat(16)),
+        ] {
+            let result = GatewayConfig::from_vars([
+                ("QRNG_API_KEYS".to_string(), "key".to_string()),
+                ("QRNG_HMAC_SECRET_KEYS".to_string(), keys.clone()),
+            ]);
+            assert!(matches!(result, Err(Error::Config(_))), "{:?} accepted", keys);
+        }
+
+        let mut gateway = GatewayConfig::from_vars([("QRNG_API_KEYS".to_string(), "key".to_string())]).unwrap();
+        gateway.hmac_secret_key = Some(Zeroizing::new(String::new()));
+        assert!(gateway.validate().is_err());
+        gateway.hmac_secret_key = Some(Zeroizing::new(strong));
+        assert!(gateway.validate().is_ok());
+    }
+
+    #[test]
+    fn test_fill_target_validation() {
+        let mut config = structured_config();
+        config.push_url = "https://gateway.example.com/qrng/push".to_string();
+        config.target_fill_percent = Some(60.0);
+        assert!(config.validate().is_err(), "gateway_api_key is required");
+
+        config.gateway_api_key = Some("key".to_string());
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.gateway_status_url().unwrap().as_str(),
+            "https://gateway.example.com/qrng/api/status"
+        );
+
+        config.target_fill_percent = Some(0.0);
+        assert!(config.validate().is_err());
+        config.target_fill_percent = Some(150.0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_push_coalescing_validation() {
+        let mut config = structured_config();
+        config.min_push_bytes = config.buffer_size;
+        assert!(config.validate().is_ok());
+        config.min_push_bytes = config.buffer_size + 1;
+        assert!(config.validate().is_err());
+
+        config.min_push_bytes = 0;
+        config.max_push_latency_ms = 0;
+        assert!(config.validate().is_err());
+
+        config.max_push_latency_ms = 5000;
+        config.max_packet_bytes = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_push_targets() {
+        let mut config = structured_config();
+        assert_eq!(config.push_targets(), vec!["https://gateway.com/push"]);
+
+        // The list takes precedence over the single URL
+        let toml = r#"
+push_urls = ["https://gw-a.example.com/push", "https://gw-b.example.com/push"]
+push_mode = "replicate"
+"#;
+        let env = [
+            ("QRNG_APPLIANCE_URLS".to_string(), "https://qrng.example.com/random".to_string()),
+            ("QRNG_HMAC_SECRET_KEY".to_string(), "secret".to_string()),
+        ];
+        let parsed = CollectorConfig::from_toml(toml, env.clone()).unwrap();
+        assert_eq!(parsed.push_mode, PushMode::Replicate);
+        assert_eq!(parsed.push_targets().len(), 2);
+        assert!(parsed.validate().is_ok());
+        assert_eq!(
+            parsed.gateway_status_url().unwrap().as_str(),
+            "https://gw-a.example.com/api/status"
+        );
+
+        let mut env = env.to_vec();
+        env.push((
+            "QRNG_PUSH_URLS".to_string(),
+            "https://gw-c.example.com/push,https://gw-d.example.com/push".to_string(),
+        ));
+        let parsed = CollectorConfig::from_toml("", env).unwrap();
+        assert_eq!(parsed.push_mode, PushMode::Failover);
+        assert_eq!(
+            parsed.push_targets(),
+            vec!["https://gw-c.example.com/push", "https://gw-d.example.com/push"]
+        );
+
+        config.push_urls = vec!["not a url".to_string()];
+        assert!(config.validate().is_err());
+        config.push_urls.clear();
+        config.push_url.clear();
+        assert!(config.validate().is_err(), "a push URL is required");
+    }
+
+    #[test]
+    fn test_push_transport_validation() {
+        let mut config = structured_config();
+        assert_eq!(config.push_transport, PushTransportKind::Http);
+
+        // Non-HTTP transports need no push URL, only their own destination
+        config.push_url.clear();
+        config.push_transport = PushTransportKind::File;
+        assert!(config.validate().is_err(), "file transport requires a path");
+        config.push_file_path = Some("/var/spool/qrng/diode.fifo".to_string());
+        assert!(config.validate().is_ok());
+
+        config.push_transport = PushTransportKind::Udp;
+        assert!(config.validate().is_err(), "udp transport requires an address");
+        config.push_udp_addr = Some("10.0.0.2:9000".to_string());
+        config.max_packet_bytes = MAX_UDP_PACKET_BYTES + 1;
+        assert!(config.validate().is_err(), "packets must fit a datagram");
+        config.max_packet_bytes = MAX_UDP_PACKET_BYTES;
+        assert!(config.validate().is_ok());
+
+        let env = [
+            ("QRNG_APPLIANCE_URLS".to_string(), "https://qrng.example.com/random".to_string()),
+            ("QRNG_HMAC_SECRET_KEY".to_string(), "secret".to_string()),
+            ("QRNG_PUSH_TRANSPORT".to_string(), "file".to_string()),
+            ("QRNG_PUSH_FILE_PATH".to_string(), "/dev/ttyS0".to_string()),
+        ];
+        let parsed = CollectorConfig::from_toml("", env).unwrap();
+        assert_eq!(parsed.push_transport, PushTransportKind::File);
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_fetch_interval_bounds() {
+        let mut config = structured_config();
+        assert_eq!(
+            config.fetch_interval_bounds(),
+            (Duration::from_millis(25), Duration::from_millis(400))
+        );
+
+        config.min_fetch_interval_ms = Some(10);
+        config.max_fetch_interval_ms = Some(1000);
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.fetch_interval_bounds(),
+            (Duration::from_millis(10), Duration::from_millis(1000))
+        );
+
+        config.min_fetch_interval_ms = Some(0);
+        assert!(config.validate().is_err());
+        config.min_fetch_interval_ms = Some(200);
+        assert!(config.validate().is_err(), "floor above the base interval");
+        config.min_fetch_interval_ms = None;
+        config.max_fetch_interval_ms = Some(50);
+        assert!(config.validate().is_err(), "ceiling below the base interval");
+    }
+
+    const COLLECTOR_TOML: &str = r#"
+push_url = "https://gateway.example.com/push"
+hmac_secret_key = "secret123"
+mixing_strategy = "xor"
+fetch_chunk_size = 2048
+buffer_size = 65536
+packet_format = "cbor"
+
+[[appliances]]
+name = "lab-a"
+url = "https://qrng-a.example.com/random"
+weight = 2.0
+format = "json_array"
+
+[[appliances]]
+name = "lab-b"
+url = "https://qrng-b.example.com/random"
+"#;
+
+    const GATEWAY_TOML: &str = r#"
+listen_address = "0.0.0.0:7764"
+api_keys = ["key-one", "key-two"]
+buffer_size = 1048576
+rate_limit_per_second = 250
+hmac_secret_key = "5f21dde6f44182da494e698ec24ea3c9"
+"#;
+
+    fn no_env() -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    #[test]
+    fn test_collector_from_toml() {
+        let config = CollectorConfig::from_toml(COLLECTOR_TOML, no_env()).unwrap();
+        assert_eq!(config.mixing_strategy, MixingStrategy::Xor);
+        assert_eq!(config.fetch_chunk_size, 2048);
+        assert_eq!(config.packet_format, SerializationFormat::Cbor);
+        assert_eq!(config.push_interval_ms, 500); // default
+
+        let sources = config.sources();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].name, "lab-a");
+        assert_eq!(sources[0].weight, 2.0);
+        assert_eq!(sources[0].format, ResponseFormat::JsonArray);
+        assert_eq!(sources[1].weight, 1.0);
+    }
+
+    #[test]
+    fn test_gateway_from_toml() {
+        let config = GatewayConfig::from_toml(GATEWAY_TOML, no_env()).unwrap();
+        assert_eq!(config.listen_address, "0.0.0.0:7764");
+        assert_eq!(config.api_keys, vec!["key-one", "key-two"]);
+        assert_eq!(config.rate_limit_per_second, 250);
+        assert_eq!(secret_str(&config.hmac_secret_key), Some("5f21dde6f44182da494e698ec24ea3c9"));
+    }
+
+    #[test]
+    fn test_gateway_direct_mode() {
+        assert!(GatewayConfig::from_toml(GATEWAY_TOML, no_env()).unwrap().direct_mode.is_none());
+
+        let toml = format!(
+            "{}\n[direct_mode]\nappliance_url = \"https://qrng.example.com/random\"\nfetch_interval_ms = 250\n",
+            GATEWAY_TOML
+        );
+        let direct = GatewayConfig::from_toml(&toml, no_env()).unwrap().direct_mode.unwrap();
+        assert_eq!(direct.appliance_url, "https://qrng.example.com/random");
+        assert_eq!(direct.fetch_interval(), Duration::from_millis(250));
+        assert_eq!(direct.fetch_chunk_size, default_chunk_size());
+        assert_eq!(direct.appliance().url, direct.appliance_url);
+
+        let env = vec![(
+            "QRNG_DIRECT_MODE_JSON".to_string(),
+            r#"{"appliance_url":"urandom:","fetch_chunk_size":0}"#.to_string(),
+        )];
+        assert!(GatewayConfig::from_toml(GATEWAY_TOML, env).is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_toml() {
+        let env = vec![
+            ("QRNG_RATE_LIMIT_PER_SECOND".to_string(), "10".to_string()),
+            ("QRNG_API_KEYS".to_string(), "env-key".to_string()),
+            ("UNRELATED".to_string(), "ignored".to_string()),
+        ];
+        let config = GatewayConfig::from_toml(GATEWAY_TOML, env).unwrap();
+        assert_eq!(config.rate_limit_per_second, 10);
+        assert_eq!(config.rate_limit_burst(), 10);
+        assert_eq!(config.api_keys, vec!["env-key"]);
+        assert_eq!(config.buffer_size, 1048576);
+
+        // Burst capacity may exceed the refill rate but not fall below it
+        let env = vec![("QRNG_RATE_LIMIT_BURST".to_string(), "500".to_string())];
+        assert_eq!(GatewayConfig::from_toml(GATEWAY_TOML, env).unwrap().rate_limit_burst(), 500);
+        let env = vec![("QRNG_RATE_LIMIT_BURST".to_string(), "5".to_string())];
+        assert!(GatewayConfig::from_toml(GATEWAY_TOML, env).is_err());
+
+        let env = vec![("QRNG_FETCH_CHUNK_SIZE".to_string(), "512".to_string())];
+        let config = CollectorConfig::from_toml(COLLECTOR_TOML, env).unwrap();
+        assert_eq!(config.fetch_chunk_size, 512);
+    }
+
+    #[test]
+    fn test_gateway_tls_paths() {
+        let mut config = GatewayConfig::from_toml(GATEWAY_TOML, no_env()).unwrap();
+        assert_eq!(config.tls_paths(), None);
+
+        config.tls_cert_path = Some("cert.pem".to_string());
+        assert!(config.validate().is_err(), "key path is required");
+
+        config.tls_key_path = Some("key.pem".to_string());
+        assert!(config.validate().is_ok());
+        assert_eq!(config.tls_paths(), Some(("cert.pem", "key.pem")));
+    }
+
+    #[test]
+    fn test_invalid_toml_config() {
+        // Syntax errors
+        assert!(GatewayConfig::from_toml("api_keys = [", no_env()).is_err());
+
+        // Missing required field
+        assert!(GatewayConfig::from_toml("buffer_size = 1024", no_env()).is_err());
+
+        // Parses but fails validation
+        let invalid = GATEWAY_TOML.replace("buffer_size = 1048576", "buffer_size = 0");
+        assert!(GatewayConfig::from_toml(&invalid, no_env()).is_err());
+
+        let invalid = COLLECTOR_TOML.replace("mixing_strategy = \"xor\"", "mixing_strategy = \"none\"");
+        assert!(CollectorConfig::from_toml(&invalid, no_env()).is_err());
+
+        // Wrong value type
+        let invalid = GATEWAY_TOML.replace("250", "\"fast\"");
+        assert!(GatewayConfig::from_toml(&invalid, no_env()).is_err());
     }
 }
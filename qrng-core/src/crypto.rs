@@ -9,24 +9,180 @@
 //
 // https://github.com/vbocan/qrng-data-diode
 
-//! Cryptographic utilities for packet signing and verification
+//! Cryptographic utilities for packet signing, verification and payload encryption
 
 use crate::{Error, Result};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Nonce};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
 
 type HmacSha256 = Hmac<Sha256>;
+type HmacSha512 = Hmac<Sha512>;
 
-/// HMAC signer for entropy packets
+/// ChaCha20-Poly1305 key length in bytes
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// ChaCha20-Poly1305 nonce length in bytes
+pub const ENCRYPTION_NONCE_LEN: usize = 12;
+
+/// Ed25519 private (seed) and public key length in bytes
+pub const ED25519_KEY_LEN: usize = 32;
+
+/// Shortest HMAC key in bytes the gateway accepts for verifying pushes
+pub const MIN_HMAC_KEY_LEN: usize = 16;
+
+/// Packet authentication scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureScheme {
+    /// HMAC with a secret shared by collector and gateway; the hash is chosen by [`HmacAlgo`]
+    #[default]
+    Hmac,
+    /// Ed25519: the collector signs with a private key, the gateway only holds the public key
+    Ed25519,
+}
+
+/// Hash function of the HMAC signature scheme
+///
+/// Only the MAC differs: both sign the same canonical packet bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HmacAlgo {
+    /// HMAC-SHA256, 32-byte tags
+    #[default]
+    Sha256,
+    /// HMAC-SHA512, 64-byte tags
+    Sha512,
+}
+
+impl HmacAlgo {
+    /// Display name, as used in logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "HMAC-SHA256",
+            Self::Sha512 => "HMAC-SHA512",
+        }
+    }
+
+    /// Compute the tag of `data` under `key`
+    fn tag(self, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Sha256 => Ok(keyed_mac::<HmacSha256>(key, data)?.finalize().into_bytes().to_vec()),
+            Self::Sha512 => Ok(keyed_mac::<HmacSha512>(key, data)?.finalize().into_bytes().to_vec()),
+        }
+    }
+
+    /// Check `tag` against `data` in constant time
+    fn verify(self, key: &[u8], data: &[u8], tag: &[u8]) -> Result<bool> {
+        match self {
+            Self::Sha256 => Ok(keyed_mac::<HmacSha256>(key, data)?.verify_slice(tag).is_ok()),
+            Self::Sha512 => Ok(keyed_mac::<HmacSha512>(key, data)?.verify_slice(tag).is_ok()),
+        }
+    }
+}
+
+/// HMAC state keyed with `key` that has absorbed `data`
+fn keyed_mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Result<M> {
+    let mut mac = <M as Mac>::new_from_slice(key)
+        .map_err(|e| Error::Crypto(format!("Invalid key length: {}", e)))?;
+    mac.update(data);
+    Ok(mac)
+}
+
+/// Key material of a [`PacketSigner`]; secret keys are wiped on drop
+#[derive(Clone)]
+enum SignerKey {
+    /// The first key signs; any of them verifies (for key rotation)
+    Hmac {
+        keys: Vec<Zeroizing<Vec<u8>>>,
+        algo: HmacAlgo,
+    },
+    /// `SigningKey` zeroizes its secret scalar itself
+    Ed25519(SigningKey),
+    /// Public key only: can verify but not sign
+    Ed25519Public(VerifyingKey),
+}
+
+/// Signer for entropy packets (HMAC-SHA256, HMAC-SHA512 or Ed25519)
 #[derive(Clone)]
 pub struct PacketSigner {
-    key: Vec<u8>,
+    key: SignerKey,
 }
 
+/// Every secret variant of [`SignerKey`] wipes itself on drop, including clones
+impl ZeroizeOnDrop for PacketSigner {}
+
 impl PacketSigner {
-    /// Create a new signer with the given secret key
-    pub fn new(key: impl Into<Vec<u8>>) -> Self {
-        Self { key: key.into() }
+    /// Create a new HMAC signer with the given secret key and hash function
+    pub fn new(key: impl Into<Vec<u8>>, algo: HmacAlgo) -> Self {
+        Self {
+            key: SignerKey::Hmac {
+                keys: vec![Zeroizing::new(key.into())],
+                algo,
+            },
+        }
+    }
+
+    /// Create an HMAC signer accepting signatures made with any of `keys`
+    ///
+    /// Signing uses the first key. Accepting several keys lets the gateway
+    /// verify packets during a rolling rotation of the shared secret.
+    pub fn with_hmac_keys(keys: Vec<Vec<u8>>, algo: HmacAlgo) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(Error::Crypto("At least one HMAC key is required".to_string()));
+        }
+        Ok(Self {
+            key: SignerKey::Hmac {
+                keys: keys.into_iter().map(Zeroizing::new).collect(),
+                algo,
+            },
+        })
+    }
+
+    /// Create an Ed25519 signer from a 32-byte private key seed
+    pub fn ed25519(private_key: &[u8]) -> Result<Self> {
+        let seed: Zeroizing<[u8; ED25519_KEY_LEN]> = Zeroizing::new(private_key.try_into().map_err(|_| {
+            Error::Crypto(format!("Ed25519 private key must be {} bytes", ED25519_KEY_LEN))
+        })?);
+        Ok(Self { key: SignerKey::Ed25519(SigningKey::from_bytes(&seed)) })
+    }
+
+    /// Create a verify-only Ed25519 signer from a 32-byte public key
+    pub fn ed25519_verifier(public_key: &[u8]) -> Result<Self> {
+        let bytes: [u8; ED25519_KEY_LEN] = public_key.try_into().map_err(|_| {
+            Error::Crypto(format!("Ed25519 public key must be {} bytes", ED25519_KEY_LEN))
+        })?;
+        let key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|e| Error::Crypto(format!("Invalid Ed25519 public key: {}", e)))?;
+        Ok(Self { key: SignerKey::Ed25519Public(key) })
+    }
+
+    /// Authentication scheme of this signer
+    pub fn scheme(&self) -> SignatureScheme {
+        match self.key {
+            SignerKey::Hmac { .. } => SignatureScheme::Hmac,
+            SignerKey::Ed25519(_) | SignerKey::Ed25519Public(_) => SignatureScheme::Ed25519,
+        }
+    }
+
+    /// Hash function of an HMAC signer (`None` for Ed25519)
+    pub fn hmac_algo(&self) -> Option<HmacAlgo> {
+        match self.key {
+            SignerKey::Hmac { algo, .. } => Some(algo),
+            SignerKey::Ed25519(_) | SignerKey::Ed25519Public(_) => None,
+        }
+    }
+
+    /// Ed25519 public key to configure on the gateway (`None` for HMAC)
+    pub fn ed25519_public_key(&self) -> Option<[u8; ED25519_KEY_LEN]> {
+        match &self.key {
+            SignerKey::Hmac { .. } => None,
+            SignerKey::Ed25519(key) => Some(key.verifying_key().to_bytes()),
+            SignerKey::Ed25519Public(key) => Some(key.to_bytes()),
+        }
     }
 
     /// Generate a random secret key
@@ -37,24 +193,41 @@ impl PacketSigner {
         key
     }
 
-    /// Sign data and return HMAC-SHA256 signature
+    /// Sign data and return the HMAC tag or Ed25519 signature
     pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut mac = HmacSha256::new_from_slice(&self.key)
-            .map_err(|e| Error::Crypto(format!("Invalid key length: {}", e)))?;
-        mac.update(data);
-        Ok(mac.finalize().into_bytes().to_vec())
+        match &self.key {
+            SignerKey::Hmac { keys, algo } => algo.tag(&keys[0], data),
+            SignerKey::Ed25519(key) => Ok(key.sign(data).to_bytes().to_vec()),
+            SignerKey::Ed25519Public(_) => Err(Error::Crypto(
+                "Cannot sign with an Ed25519 public key".to_string(),
+            )),
+        }
     }
 
-    /// Verify HMAC-SHA256 signature using constant-time comparison
+    /// Verify a signature (HMAC tags are compared in constant time)
+    ///
+    /// With several HMAC keys every key is tried, so timing does not reveal
+    /// which one matched.
     pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<bool> {
-        let mut mac = HmacSha256::new_from_slice(&self.key)
-            .map_err(|e| Error::Crypto(format!("Invalid key length: {}", e)))?;
-        mac.update(data);
-        
-        Ok(mac.verify_slice(signature).is_ok())
+        let verifying_key = match &self.key {
+            SignerKey::Hmac { keys, algo } => {
+                let mut verified = false;
+                for key in keys {
+                    verified |= algo.verify(key, data, signature)?;
+                }
+                return Ok(verified);
+            }
+            SignerKey::Ed25519(key) => key.verifying_key(),
+            SignerKey::Ed25519Public(key) => *key,
+        };
+
+        let Ok(signature) = Signature::from_slice(signature) else {
+            return Ok(false);
+        };
+        Ok(verifying_key.verify_strict(data, &signature).is_ok())
     }
 
-    /// Sign an entropy packet by computing HMAC over canonical representation
+    /// Sign an entropy packet over its canonical representation
     pub fn sign_packet(&self, packet: &mut crate::protocol::EntropyPacket) -> Result<()> {
         let canonical = self.canonical_packet_bytes(packet)?;
         packet.signature = self.sign(&canonical)?;
@@ -68,7 +241,11 @@ impl PacketSigner {
     }
 
     /// Create canonical byte representation for signing
-    /// Format: version || sequence || data || timestamp_nanos
+    /// Format: version || sequence || data || timestamp_nanos [|| nonce] [|| tag_len || tag]
+    ///
+    /// The nonce is only present for encrypted packets, in which case `data`
+    /// holds the ciphertext. Untagged packets sign exactly as they did before
+    /// source tags existed.
     fn canonical_packet_bytes(&self, packet: &crate::protocol::EntropyPacket) -> Result<Vec<u8>> {
         let mut bytes = Vec::new();
         bytes.push(packet.version);
@@ -77,10 +254,69 @@ impl PacketSigner {
         bytes.extend_from_slice(&packet.timestamp.timestamp_nanos_opt()
             .ok_or_else(|| Error::Crypto("Invalid timestamp".to_string()))?
             .to_be_bytes());
+        if let Some(nonce) = &packet.nonce {
+            bytes.extend_from_slice(nonce);
+        }
+        if let Some(tag) = &packet.source_tag {
+            bytes.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(tag.as_bytes());
+        }
         Ok(bytes)
     }
 }
 
+/// Encrypt a payload with ChaCha20-Poly1305 under a fresh random nonce
+///
+/// Returns `(nonce, ciphertext)`; the ciphertext includes the 16-byte
+/// Poly1305 authentication tag.
+pub fn encrypt_payload(key: &[u8], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    use chacha20poly1305::KeyInit;
+    use rand::Rng;
+
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|_| Error::Crypto(format!("Encryption key must be {} bytes", ENCRYPTION_KEY_LEN)))?;
+
+    let mut nonce = vec![0u8; ENCRYPTION_NONCE_LEN];
+    rand::rng().fill(&mut nonce[..]);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| Error::Crypto("Payload encryption failed".to_string()))?;
+
+    Ok((nonce, ciphertext))
+}
+
+/// Decrypt and authenticate a ChaCha20-Poly1305 payload
+pub fn decrypt_payload(key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    use chacha20poly1305::KeyInit;
+
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|_| Error::Crypto(format!("Encryption key must be {} bytes", ENCRYPTION_KEY_LEN)))?;
+
+    if nonce.len() != ENCRYPTION_NONCE_LEN {
+        return Err(Error::Crypto(format!(
+            "Nonce must be {} bytes, got {}",
+            ENCRYPTION_NONCE_LEN,
+            nonce.len()
+        )));
+    }
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::Crypto("Payload decryption failed (wrong key or tampered data)".to_string()))
+}
+
+/// Compare two secrets in constant time
+///
+/// Both sides are reduced to SHA-256 digests first, so the comparison time
+/// reveals neither the length of the secret nor how long a prefix matched.
+pub fn secrets_equal(a: &[u8], b: &[u8]) -> bool {
+    use sha2::Digest;
+    use subtle::ConstantTimeEq;
+
+    Sha256::digest(a).ct_eq(&Sha256::digest(b)).into()
+}
+
 /// Encode bytes to hexadecimal string
 pub fn encode_hex(data: &[u8]) -> String {
     data.iter()
@@ -89,7 +325,12 @@ pub fn encode_hex(data: &[u8]) -> String {
 }
 
 /// Decode hexadecimal string to bytes
+///
+/// Odd-length and non-ASCII input is rejected rather than sliced mid-character.
 pub fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(Error::Crypto("Invalid hex: odd length or non-ASCII input".to_string()));
+    }
     (0..s.len())
         .step_by(2)
         .map(|i| {
@@ -113,14 +354,88 @@ pub fn decode_base64(s: &str) -> Result<Vec<u8>> {
         .map_err(|e| Error::Crypto(format!("Invalid base64: {}", e)))
 }
 
+/// Encode bytes to base32 string (RFC 4648 alphabet, padded)
+pub fn encode_base32(data: &[u8]) -> String {
+    base32::encode(base32::Alphabet::Rfc4648 { padding: true }, data)
+}
+
+/// Decode base32 string to bytes
+pub fn decode_base32(s: &str) -> Result<Vec<u8>> {
+    base32::decode(base32::Alphabet::Rfc4648 { padding: true }, s)
+        .ok_or_else(|| Error::Crypto("Invalid base32".to_string()))
+}
+
+/// Encode bytes to base85 string (Ascii85, without `<~ ~>` delimiters)
+///
+/// Every 4-byte group becomes 5 characters; the `z` shorthand for zero groups
+/// is not emitted, so the output length depends only on the input length.
+pub fn encode_base85(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = b'!' + (value % 85) as u8;
+            value /= 85;
+        }
+        // A partial group of n bytes is written as its first n + 1 characters
+        out.extend(digits[..chunk.len() + 1].iter().map(|&d| d as char));
+    }
+    out
+}
+
+/// Decode base85 string to bytes (Ascii85, `z` shorthand and whitespace accepted)
+pub fn decode_base85(s: &str) -> Result<Vec<u8>> {
+    let invalid = |reason: &str| Error::Crypto(format!("Invalid base85: {}", reason));
+
+    let mut out = Vec::with_capacity(s.len() / 5 * 4 + 4);
+    let mut digits = Vec::with_capacity(5);
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        match c {
+            b'z' if digits.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                digits.push(c - b'!');
+                if digits.len() == 5 {
+                    out.extend_from_slice(&base85_group(&digits).ok_or_else(|| invalid("group overflow"))?);
+                    digits.clear();
+                }
+            }
+            _ => return Err(invalid(&format!("unexpected character {:?}", c as char))),
+        }
+    }
+
+    match digits.len() {
+        0 => {}
+        1 => return Err(invalid("truncated group")),
+        n => {
+            // Pad with the highest digit, then keep the n - 1 decoded bytes
+            digits.resize(5, 84);
+            let group = base85_group(&digits).ok_or_else(|| invalid("group overflow"))?;
+            out.extend_from_slice(&group[..n - 1]);
+        }
+    }
+    Ok(out)
+}
+
+/// Combine five base85 digits into four bytes, or `None` if above `u32::MAX`
+fn base85_group(digits: &[u8]) -> Option<[u8; 4]> {
+    let value = digits
+        .iter()
+        .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d as u32))?;
+    Some(value.to_be_bytes())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::EntropyPacket;
+    use crate::protocol::{ChecksumAlgorithm, EntropyPacket};
 
     #[test]
     fn test_signing() {
-        let signer = PacketSigner::new(b"test-secret-key");
+        let signer = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
         let data = b"hello world";
         let sig = signer.sign(data).unwrap();
         assert!(signer.verify(data, &sig).unwrap());
@@ -129,13 +444,116 @@ mod tests {
 
     #[test]
     fn test_packet_signing() {
-        let signer = PacketSigner::new(b"test-secret-key");
+        let signer = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
         let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
         signer.sign_packet(&mut packet).unwrap();
         assert!(!packet.signature.is_empty());
         assert!(signer.verify_packet(&packet).unwrap());
     }
 
+    #[test]
+    fn test_hmac_algorithms() {
+        let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
+        let sha256 = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
+        let sha512 = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha512);
+        assert_eq!(sha512.hmac_algo(), Some(HmacAlgo::Sha512));
+        assert_eq!(HmacAlgo::Sha512.name(), "HMAC-SHA512");
+        assert_eq!(HmacAlgo::default().name(), "HMAC-SHA256");
+
+        sha256.sign_packet(&mut packet).unwrap();
+        assert_eq!(packet.signature.len(), 32);
+        assert!(sha256.verify_packet(&packet).unwrap());
+        assert!(!sha512.verify_packet(&packet).unwrap());
+
+        sha512.sign_packet(&mut packet).unwrap();
+        assert_eq!(packet.signature.len(), 64);
+        assert!(sha512.verify_packet(&packet).unwrap());
+        assert!(!sha256.verify_packet(&packet).unwrap());
+
+        // RFC 4231 test case 2: only the MAC differs, over the same input
+        let data = b"what do ya want for nothing?";
+        let tag = PacketSigner::new(b"Jefe", HmacAlgo::Sha512).sign(data).unwrap();
+        assert_eq!(
+            encode_hex(&tag),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+        let tag = PacketSigner::new(b"Jefe", HmacAlgo::Sha256).sign(data).unwrap();
+        assert_eq!(
+            encode_hex(&tag),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_secrets_equal() {
+        assert!(secrets_equal(b"key-1234567890", b"key-1234567890"));
+        assert!(!secrets_equal(b"key-1234567890", b"key-1234567891"));
+        assert!(!secrets_equal(b"key-1234567890", b"key-123456789"));
+        assert!(!secrets_equal(b"", b"key"));
+        assert!(secrets_equal(b"", b""));
+    }
+
+    #[test]
+    fn test_source_tag_is_signed() {
+        let signer = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
+        let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
+        packet.source_tag = Some("eu-west".to_string());
+        signer.sign_packet(&mut packet).unwrap();
+        assert!(signer.verify_packet(&packet).unwrap());
+
+        for tag in [Some("us-east".to_string()), None] {
+            let mut relabeled = packet.clone();
+            relabeled.source_tag = tag;
+            assert!(!signer.verify_packet(&relabeled).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_multi_key_verification() {
+        let keys = vec![b"new-key".to_vec(), b"old-key".to_vec()];
+        let verifier = PacketSigner::with_hmac_keys(keys, HmacAlgo::Sha256).unwrap();
+
+        // Packets signed with any configured key pass
+        for key in [&b"old-key"[..], b"new-key"] {
+            let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
+            PacketSigner::new(key, HmacAlgo::Sha256).sign_packet(&mut packet).unwrap();
+            assert!(verifier.verify_packet(&packet).unwrap());
+        }
+
+        // Unknown keys fail
+        let mut packet = EntropyPacket::new(2, vec![1, 2, 3, 4]);
+        PacketSigner::new(b"retired-key", HmacAlgo::Sha256).sign_packet(&mut packet).unwrap();
+        assert!(!verifier.verify_packet(&packet).unwrap());
+
+        // The first key signs
+        verifier.sign_packet(&mut packet).unwrap();
+        assert!(PacketSigner::new(b"new-key", HmacAlgo::Sha256).verify_packet(&packet).unwrap());
+        assert!(!PacketSigner::new(b"old-key", HmacAlgo::Sha256).verify_packet(&packet).unwrap());
+
+        assert!(PacketSigner::with_hmac_keys(Vec::new(), HmacAlgo::Sha256).is_err());
+    }
+
+    #[test]
+    fn test_signer_keys_zeroize_on_drop() {
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<PacketSigner>();
+        assert_zeroize_on_drop::<SigningKey>();
+
+        // Clones own their key copy and still sign and verify interchangeably
+        let signers = [
+            PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256),
+            PacketSigner::ed25519(&[7u8; 32]).unwrap(),
+        ];
+        for signer in signers {
+            let clone = signer.clone();
+            let sig = clone.sign(b"data").unwrap();
+            drop(clone);
+            assert!(signer.verify(b"data", &sig).unwrap());
+            assert_eq!(signer.sign(b"data").unwrap(), sig);
+        }
+    }
+
     #[test]
     fn test_hex_encoding() {
         let data = b"hello";
@@ -143,6 +561,12 @@ mod tests {
         assert_eq!(hex, "68656c6c6f");
         let decoded = decode_hex(&hex).unwrap();
         assert_eq!(decoded, data);
+
+        // Malformed input is an error, never a panic
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("aé").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
     }
 
     #[test]
@@ -152,4 +576,155 @@ mod tests {
         let decoded = decode_base64(&b64).unwrap();
         assert_eq!(decoded, data);
     }
+
+    #[test]
+    fn test_base32_encoding() {
+        assert_eq!(encode_base32(b"hello"), "NBSWY3DP");
+        assert_eq!(encode_base32(b"hi"), "NBUQ====");
+
+        for len in 0..=11 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37) as u8).collect();
+            assert_eq!(decode_base32(&encode_base32(&data)).unwrap(), data);
+        }
+        assert!(decode_base32("NBSW!").is_err());
+    }
+
+    #[test]
+    fn test_base85_encoding() {
+        assert_eq!(encode_base85(b"Man "), "9jqo^");
+        assert_eq!(encode_base85(b"sure."), "F*2M7/c");
+        // Zero groups are spelled out in full
+        assert_eq!(encode_base85(&[0; 4]), "!!!!!");
+        assert_eq!(decode_base85("z/c").unwrap(), b"\0\0\0\0.");
+
+        for len in 0..=11 {
+            let data: Vec<u8> = (0..len).map(|i| 255 - (i * 37) as u8).collect();
+            let encoded = encode_base85(&data);
+            assert_eq!(encoded.len(), len / 4 * 5 + if len % 4 > 0 { len % 4 + 1 } else { 0 });
+            assert_eq!(decode_base85(&encoded).unwrap(), data);
+        }
+        assert!(decode_base85("9jqo^F").is_err());
+        assert!(decode_base85("s8W-\"").is_err());
+        assert!(decode_base85("9jq~").is_err());
+    }
+
+    #[test]
+    fn test_payload_encryption() {
+        let key = PacketSigner::generate_key();
+        let plaintext = b"quantum entropy payload";
+
+        let (nonce, ciphertext) = encrypt_payload(&key, plaintext).unwrap();
+        assert_eq!(nonce.len(), ENCRYPTION_NONCE_LEN);
+        assert_ne!(&ciphertext[..plaintext.len()], plaintext);
+
+        let decrypted = decrypt_payload(&key, &nonce, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        // A fresh nonce is used for every encryption
+        let (nonce2, _) = encrypt_payload(&key, plaintext).unwrap();
+        assert_ne!(nonce, nonce2);
+    }
+
+    #[test]
+    fn test_payload_tamper_detection() {
+        let key = PacketSigner::generate_key();
+        let (nonce, mut ciphertext) = encrypt_payload(&key, b"quantum entropy").unwrap();
+
+        // Wrong key
+        let other_key = PacketSigner::generate_key();
+        assert!(decrypt_payload(&other_key, &nonce, &ciphertext).is_err());
+
+        // Flipped ciphertext bit
+        ciphertext[0] ^= 0x01;
+        assert!(decrypt_payload(&key, &nonce, &ciphertext).is_err());
+
+        // Invalid key length
+        assert!(encrypt_payload(b"short", b"data").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_packet_roundtrip() {
+        let signer = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
+        let key = PacketSigner::generate_key();
+        let payload = vec![7u8; 64];
+
+        let mut packet = EntropyPacket::new(3, payload.clone());
+        packet.encrypt_payload(&key).unwrap();
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+        signer.sign_packet(&mut packet).unwrap();
+        assert!(packet.is_encrypted());
+        assert_ne!(packet.data, payload);
+
+        let mut decoded = EntropyPacket::from_msgpack(&packet.to_msgpack().unwrap()).unwrap();
+        assert!(signer.verify_packet(&decoded).unwrap());
+        assert!(decoded.verify_checksum());
+        decoded.decrypt_payload(&key).unwrap();
+        assert_eq!(decoded.data, payload);
+        assert!(!decoded.is_encrypted());
+    }
+
+    #[test]
+    fn test_encrypted_packet_nonce_is_signed() {
+        let signer = PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256);
+        let key = PacketSigner::generate_key();
+
+        let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
+        packet.encrypt_payload(&key).unwrap();
+        signer.sign_packet(&mut packet).unwrap();
+
+        packet.nonce.as_mut().unwrap()[0] ^= 0x01;
+        assert!(!signer.verify_packet(&packet).unwrap());
+    }
+
+    #[test]
+    fn test_ed25519_packet_signing() {
+        let signer = PacketSigner::ed25519(&PacketSigner::generate_key()).unwrap();
+        assert_eq!(signer.scheme(), SignatureScheme::Ed25519);
+        let verifier = PacketSigner::ed25519_verifier(&signer.ed25519_public_key().unwrap()).unwrap();
+
+        let mut packet = EntropyPacket::new(1, vec![1, 2, 3, 4]);
+        signer.sign_packet(&mut packet).unwrap();
+        assert_eq!(packet.signature.len(), 64);
+        assert!(verifier.verify_packet(&packet).unwrap());
+        assert!(signer.verify_packet(&packet).unwrap());
+
+        // The gateway's public key cannot forge packets
+        assert!(verifier.sign_packet(&mut packet.clone()).is_err());
+    }
+
+    #[test]
+    fn test_ed25519_rejects_wrong_key_and_tampering() {
+        let signer = PacketSigner::ed25519(&PacketSigner::generate_key()).unwrap();
+        let verifier = PacketSigner::ed25519_verifier(&signer.ed25519_public_key().unwrap()).unwrap();
+        let mut packet = EntropyPacket::new(7, vec![0xAB; 32]);
+        signer.sign_packet(&mut packet).unwrap();
+
+        // Signed by a different private key
+        let other = PacketSigner::ed25519(&PacketSigner::generate_key()).unwrap();
+        let other_verifier = PacketSigner::ed25519_verifier(&other.ed25519_public_key().unwrap()).unwrap();
+        assert!(!other_verifier.verify_packet(&packet).unwrap());
+
+        // Tampered payload, sequence, and truncated signature
+        let mut tampered = packet.clone();
+        tampered.data[0] ^= 0x01;
+        assert!(!verifier.verify_packet(&tampered).unwrap());
+        let mut tampered = packet.clone();
+        tampered.sequence += 1;
+        assert!(!verifier.verify_packet(&tampered).unwrap());
+        let mut tampered = packet.clone();
+        tampered.signature.truncate(32);
+        assert!(!verifier.verify_packet(&tampered).unwrap());
+
+        // An HMAC tag is not a valid Ed25519 signature
+        let mut hmac_signed = packet.clone();
+        PacketSigner::new(b"test-secret-key", HmacAlgo::Sha256).sign_packet(&mut hmac_signed).unwrap();
+        assert!(!verifier.verify_packet(&hmac_signed).unwrap());
+    }
+
+    #[test]
+    fn test_ed25519_key_validation() {
+        assert!(PacketSigner::ed25519(&[0u8; 16]).is_err());
+        assert!(PacketSigner::ed25519_verifier(&[0u8; 31]).is_err());
+        assert_eq!(PacketSigner::new(b"key", HmacAlgo::Sha256).ed25519_public_key(), None);
+    }
 }
@@ -58,6 +58,10 @@ pub enum Error {
     #[error("Operation timed out")]
     Timeout,
 
+    /// Upstream service temporarily unavailable (HTTP 502, 503 or 504)
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
     /// I/O error
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -72,7 +76,7 @@ impl Error {
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
-            Error::Network(_) | Error::Timeout | Error::RateLimit
+            Error::Network(_) | Error::Timeout | Error::RateLimit | Error::Unavailable(_)
         )
     }
 
@@ -12,13 +12,70 @@
 //! HTTPS client for fetching entropy from Quantis appliance
 //!
 //! Implements resilient fetching with connection pooling, retry logic, and rate limiting.
+//! Non-HTTP sources (files, FIFOs, `/dev/urandom`) implement the same [`EntropySource`]
+//! trait so the collector pipeline can run without a real appliance.
 
-use crate::{Error, Result, retry::RetryPolicy};
+use crate::{
+    config::{ApplianceConfig, ResponseFormat},
+    retry::RetryPolicy,
+    Error, Result,
+};
+use async_trait::async_trait;
 use reqwest::{Client, ClientBuilder};
+use std::path::PathBuf;
 use std::time::Duration;
+use tokio::io::AsyncReadExt;
 use tracing::{debug, instrument, warn};
 use url::Url;
 
+/// A source of raw entropy bytes
+#[async_trait]
+pub trait EntropySource: Send + Sync {
+    /// Fetch exactly `n` bytes of entropy
+    async fn fetch(&self, n: usize) -> Result<Vec<u8>>;
+}
+
+/// Create the entropy source for an appliance definition
+///
+/// The URL scheme selects the implementation: `https` for a QRNG appliance,
+/// `file:///path` for a file or FIFO, and `urandom:` for the OS generator.
+pub fn source_for_appliance(
+    source: &ApplianceConfig,
+    chunk_size: usize,
+) -> Result<Box<dyn EntropySource>> {
+    let url = Url::parse(&source.url)
+        .map_err(|e| Error::Config(format!("Invalid appliance URL '{}': {}", source.url, e)))?;
+
+    match url.scheme() {
+        "file" => {
+            let path = url
+                .to_file_path()
+                .map_err(|_| Error::Config(format!("Invalid file source path '{}'", source.url)))?;
+            Ok(Box::new(FileSource::new(path)))
+        }
+        "urandom" => Ok(Box::new(DevRandomSource::new())),
+        _ => {
+            let config = FetcherConfig::for_appliance(source, chunk_size)?;
+            Ok(Box::new(EntropyFetcher::new(config)?))
+        }
+    }
+}
+
+/// Classify a non-success appliance response
+///
+/// Rate limiting and gateway/overload responses are transient and retried by
+/// the [`RetryPolicy`]; any other status is a non-retryable validation error.
+fn status_error(status: reqwest::StatusCode) -> Error {
+    let reason = status.canonical_reason().unwrap_or("Unknown");
+    match status {
+        reqwest::StatusCode::TOO_MANY_REQUESTS => Error::RateLimit,
+        reqwest::StatusCode::BAD_GATEWAY
+        | reqwest::StatusCode::SERVICE_UNAVAILABLE
+        | reqwest::StatusCode::GATEWAY_TIMEOUT => Error::Unavailable(format!("HTTP {} {}", status.as_u16(), reason)),
+        _ => Error::Validation(format!("HTTP {} {}", status, reason)),
+    }
+}
+
 /// Configuration for the entropy fetcher
 #[derive(Debug, Clone)]
 pub struct FetcherConfig {
@@ -30,6 +87,23 @@ pub struct FetcherConfig {
     pub timeout: Duration,
     /// Retry policy
     pub retry_policy: RetryPolicy,
+    /// Expected response body format
+    pub response_format: ResponseFormat,
+    /// Optional bearer token sent with each request
+    pub auth_token: Option<String>,
+    /// Minimum per-byte min-entropy (bits) a chunk must show; `None` disables the check
+    ///
+    /// The estimate is based on the most common byte value, so it needs a few
+    /// thousand bytes per chunk to be meaningful against a threshold near 8.
+    pub min_entropy_threshold: Option<f64>,
+    /// Largest accepted absolute lag-1 serial correlation; `None` disables the check
+    ///
+    /// Random data stays within about ±2.6/√n (99%) of zero, so a threshold
+    /// of 0.1 leaves ample margin for chunks of a few thousand bytes.
+    pub max_serial_correlation: Option<f64>,
+    /// Accept responses longer than requested (trailing padding or newline)
+    /// and trim them to the requested size; shorter responses are always rejected
+    pub allow_oversized: bool,
 }
 
 impl FetcherConfig {
@@ -39,8 +113,26 @@ impl FetcherConfig {
             chunk_size,
             timeout: Duration::from_secs(30),
             retry_policy: RetryPolicy::default(),
+            response_format: ResponseFormat::default(),
+            auth_token: None,
+            min_entropy_threshold: None,
+            max_serial_correlation: None,
+            allow_oversized: false,
         }
     }
+
+    /// Create a fetcher configuration from a named appliance definition
+    pub fn for_appliance(source: &ApplianceConfig, chunk_size: usize) -> Result<Self> {
+        let base_url = Url::parse(&source.url)
+            .map_err(|e| Error::Config(format!("Invalid appliance URL '{}': {}", source.url, e)))?;
+
+        Ok(Self {
+            response_format: source.format,
+            auth_token: source.auth_token.clone(),
+            allow_oversized: source.allow_oversized,
+            ..Self::new(base_url, chunk_size)
+        })
+    }
 }
 
 /// HTTP client for fetching entropy from QRNG appliance
@@ -53,12 +145,7 @@ pub struct EntropyFetcher {
 impl EntropyFetcher {
     /// Create a new fetcher with configuration
     pub fn new(config: FetcherConfig) -> Result<Self> {
-        let client = ClientBuilder::new()
-            .timeout(config.timeout)
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60))
-            .use_rustls_tls()
+        let client = Self::client_builder(&config)
             .https_only(true)
             .build()
             .map_err(Error::Network)?;
@@ -66,24 +153,48 @@ impl EntropyFetcher {
         Ok(Self { client, config })
     }
 
+    /// Create a fetcher that also accepts plain HTTP, for tests against a local mock server
+    #[cfg(test)]
+    fn new_plain_http(config: FetcherConfig) -> Result<Self> {
+        let client = Self::client_builder(&config).build().map_err(Error::Network)?;
+        Ok(Self { client, config })
+    }
+
+    fn client_builder(config: &FetcherConfig) -> ClientBuilder {
+        ClientBuilder::new()
+            .timeout(config.timeout)
+            .pool_max_idle_per_host(10)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60))
+            .use_rustls_tls()
+    }
+
     /// Fetch entropy bytes from the appliance
     ///
     /// This method automatically retries transient failures according to the retry policy.
     #[instrument(skip(self), fields(chunk_size = self.config.chunk_size))]
     pub async fn fetch(&self) -> Result<Vec<u8>> {
-        self.config.retry_policy.execute(|| self.fetch_once()).await
+        self.fetch_bytes(self.config.chunk_size).await
+    }
+
+    /// Fetch `n` entropy bytes from the appliance, retrying transient failures
+    pub async fn fetch_bytes(&self, n: usize) -> Result<Vec<u8>> {
+        self.config.retry_policy.execute(|| self.fetch_once(n)).await
     }
 
     /// Fetch entropy once without retry
-    async fn fetch_once(&self) -> Result<Vec<u8>> {
+    async fn fetch_once(&self, n: usize) -> Result<Vec<u8>> {
         // Construct request URL with query parameter for byte count
-        let url = self.build_request_url()?;
+        let url = self.build_request_url(n)?;
         
-        debug!("Fetching {} bytes from {}", self.config.chunk_size, url);
+        debug!("Fetching {} bytes from {}", n, url);
+
+        let mut request = self.client.get(url.clone());
+        if let Some(token) = &self.config.auth_token {
+            request = request.bearer_auth(token);
+        }
 
-        let response = self
-            .client
-            .get(url.clone())
+        let response = request
             .send()
             .await
             .map_err(|e| {
@@ -93,56 +204,81 @@ impl EntropyFetcher {
 
         // Check HTTP status
         if !response.status().is_success() {
-            let status = response.status();            
-            let reason = status.canonical_reason().unwrap_or("Unknown");
-            warn!("HTTP error {}: {}", status, reason);
-            return Err(Error::Validation(format!("HTTP {} {}", status, reason)));
+            let status = response.status();
+            warn!("HTTP error {}: {}", status, status.canonical_reason().unwrap_or("Unknown"));
+            return Err(status_error(status));
         }
 
         // Read response body
         let data = response.bytes().await.map_err(Error::Network)?;
         
-        // Try to parse as JSON array of integers first (Quantis API v2.0 format)
-        // If that fails, treat as raw binary data
-        let data_vec = match serde_json::from_slice::<Vec<u8>>(&data) {
-            Ok(json_array) => {
-                debug!("Parsed JSON array of {} bytes", json_array.len());
-                json_array
-            }
-            Err(_) => {
-                // Not JSON, use as raw binary
-                debug!("Using raw binary data");
-                data.to_vec()
-            }
-        };
+        let mut data_vec = self.parse_body(&data)?;
 
-        // Validate response
-        self.validate_response(&data_vec)?;
+        // Validate response, then drop any tolerated excess
+        self.validate_response(&data_vec, n)?;
+        data_vec.truncate(n);
 
         debug!("Successfully fetched {} bytes", data_vec.len());
         Ok(data_vec)
     }
 
+    /// Extract entropy bytes from a response body according to the configured format
+    fn parse_body(&self, body: &[u8]) -> Result<Vec<u8>> {
+        match self.config.response_format {
+            ResponseFormat::Auto => {
+                // Try to parse as JSON array of integers first (Quantis API v2.0 format)
+                // If that fails, treat as raw binary data
+                match serde_json::from_slice::<Vec<u8>>(body) {
+                    Ok(json_array) => {
+                        debug!("Parsed JSON array of {} bytes", json_array.len());
+                        Ok(json_array)
+                    }
+                    Err(_) if serde_json::from_slice::<AnuResponse>(body).is_ok() => {
+                        debug!("Parsed ANU JSON response");
+                        parse_anu_body(body)
+                    }
+                    Err(_) => {
+                        // Not JSON, use as raw binary
+                        debug!("Using raw binary data");
+                        Ok(body.to_vec())
+                    }
+                }
+            }
+            ResponseFormat::Binary => Ok(body.to_vec()),
+            ResponseFormat::JsonArray => serde_json::from_slice::<Vec<u8>>(body).map_err(|e| {
+                Error::Validation(format!("Expected JSON array of bytes: {}", e))
+            }),
+            ResponseFormat::AnuJson => parse_anu_body(body),
+        }
+    }
+
     /// Build request URL with proper query parameters
-    fn build_request_url(&self) -> Result<Url> {
+    fn build_request_url(&self, n: usize) -> Result<Url> {
         let mut url = self.config.base_url.clone();
         
         // Add query parameter for byte count
-        // Quantis Appliance API v2.0 uses "size" parameter
-        url.query_pairs_mut()
-            .append_pair("size", &self.config.chunk_size.to_string());
+        // Quantis Appliance API v2.0 uses "size"; the ANU API uses "length" and "type"
+        if self.config.response_format == ResponseFormat::AnuJson {
+            url.query_pairs_mut()
+                .append_pair("length", &n.to_string())
+                .append_pair("type", "uint8");
+        } else {
+            url.query_pairs_mut()
+                .append_pair("size", &n.to_string());
+        }
         
         Ok(url)
     }
 
     /// Validate fetched data
-    fn validate_response(&self, data: &[u8]) -> Result<()> {
+    fn validate_response(&self, data: &[u8], expected_len: usize) -> Result<()> {
         // Check if we got expected amount of data
-        if data.len() != self.config.chunk_size {
+        let oversized_ok = self.config.allow_oversized && data.len() > expected_len;
+        if data.len() != expected_len && !oversized_ok {
             return Err(Error::Validation(format!(
                 "Received {} bytes, expected {}",
                 data.len(),
-                self.config.chunk_size
+                expected_len
             )));
         }
 
@@ -187,6 +323,26 @@ impl EntropyFetcher {
             )));
         }
 
+        if let Some(threshold) = self.config.min_entropy_threshold {
+            let estimate = min_entropy_per_byte(data);
+            if estimate < threshold {
+                return Err(Error::Validation(format!(
+                    "Min-entropy estimate {:.3} bits/byte is below the threshold of {:.3}",
+                    estimate, threshold
+                )));
+            }
+        }
+
+        if let Some(threshold) = self.config.max_serial_correlation {
+            let correlation = serial_correlation(data);
+            if correlation.abs() > threshold {
+                return Err(Error::Validation(format!(
+                    "Lag-1 serial correlation {:.4} exceeds the threshold of ±{:.4}; the source may be malfunctioning",
+                    correlation, threshold
+                )));
+            }
+        }
+
         Ok(())
     }
 
@@ -196,6 +352,211 @@ impl EntropyFetcher {
     }
 }
 
+/// Response body of the ANU Quantum Numbers API
+#[derive(Debug, serde::Deserialize)]
+struct AnuResponse {
+    success: bool,
+    #[serde(rename = "type", default)]
+    data_type: Option<String>,
+    #[serde(default)]
+    data: Vec<u64>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Extract the bytes of an ANU response, rejecting unsuccessful or non-`uint8` responses
+fn parse_anu_body(body: &[u8]) -> Result<Vec<u8>> {
+    let response: AnuResponse = serde_json::from_slice(body)
+        .map_err(|e| Error::Validation(format!("Expected ANU JSON response: {}", e)))?;
+
+    if !response.success {
+        return Err(Error::Validation(format!(
+            "ANU response reported failure: {}",
+            response.message.as_deref().unwrap_or("no message")
+        )));
+    }
+    if let Some(data_type) = response.data_type.as_deref().filter(|&t| t != "uint8") {
+        return Err(Error::Validation(format!(
+            "ANU response type '{}' is not uint8",
+            data_type
+        )));
+    }
+
+    response
+        .data
+        .into_iter()
+        .map(|value| {
+            u8::try_from(value)
+                .map_err(|_| Error::Validation(format!("ANU value {} is not a byte", value)))
+        })
+        .collect()
+}
+
+/// Estimate per-byte min-entropy from the most common value: H∞ = -log2(p_max)
+///
+/// Returns 0.0 for empty input.
+pub fn min_entropy_per_byte(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut byte_counts = [0usize; 256];
+    for &byte in data {
+        byte_counts[byte as usize] += 1;
+    }
+    let max_count = byte_counts.iter().copied().max().unwrap_or(0);
+    -(max_count as f64 / data.len() as f64).log2()
+}
+
+/// Circular lag-1 serial correlation of a byte stream, as computed by `ent`
+///
+/// Near 0 for random data, approaching ±1 when each byte predicts the next.
+/// Constant (or empty) input is reported as fully correlated, 1.0.
+pub fn serial_correlation(data: &[u8]) -> f64 {
+    let n = data.len() as f64;
+    let (mut sum, mut sum_squares, mut sum_products) = (0.0, 0.0, 0.0);
+    for (i, &byte) in data.iter().enumerate() {
+        let x = byte as f64;
+        sum += x;
+        sum_squares += x * x;
+        sum_products += x * data[(i + 1) % data.len()] as f64;
+    }
+    let denominator = n * sum_squares - sum * sum;
+    if denominator == 0.0 {
+        1.0
+    } else {
+        (n * sum_products - sum * sum) / denominator
+    }
+}
+
+#[async_trait]
+impl EntropySource for EntropyFetcher {
+    async fn fetch(&self, n: usize) -> Result<Vec<u8>> {
+        self.fetch_bytes(n).await
+    }
+}
+
+/// Entropy source reading sequentially from a file or FIFO
+///
+/// The file is opened on first use and kept open, so a FIFO fed by an external
+/// generator is consumed as a stream. A regular file errors once exhausted.
+pub struct FileSource {
+    path: PathBuf,
+    file: tokio::sync::Mutex<Option<tokio::fs::File>>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl EntropySource for FileSource {
+    async fn fetch(&self, n: usize) -> Result<Vec<u8>> {
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            *file = Some(tokio::fs::File::open(&self.path).await?);
+        }
+
+        let mut data = vec![0u8; n];
+        if let Some(file) = file.as_mut() {
+            file.read_exact(&mut data).await?;
+        }
+
+        debug!("Read {} bytes from {}", n, self.path.display());
+        Ok(data)
+    }
+}
+
+/// Entropy source reading from the operating system's `/dev/urandom`
+///
+/// Not quantum entropy; intended for testing the pipeline without an appliance.
+pub struct DevRandomSource {
+    inner: FileSource,
+}
+
+impl DevRandomSource {
+    pub fn new() -> Self {
+        Self {
+            inner: FileSource::new("/dev/urandom"),
+        }
+    }
+}
+
+impl Default for DevRandomSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EntropySource for DevRandomSource {
+    async fn fetch(&self, n: usize) -> Result<Vec<u8>> {
+        self.inner.fetch(n).await
+    }
+}
+
+/// Generator behind a [`MockSource`]: given the requested length, produce the bytes or an error
+type MockGenerator = Box<dyn Fn(usize) -> Result<Vec<u8>> + Send + Sync>;
+
+/// Programmable entropy source for tests of the collection pipeline
+///
+/// Serves canned, deliberately biased or failing data without an appliance.
+pub struct MockSource {
+    generator: MockGenerator,
+    fetches: std::sync::atomic::AtomicUsize,
+}
+
+impl MockSource {
+    /// Source producing each fetch with `generator`
+    pub fn new(generator: impl Fn(usize) -> Result<Vec<u8>> + Send + Sync + 'static) -> Self {
+        Self {
+            generator: Box::new(generator),
+            fetches: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// Source repeating `pattern` endlessly, continuing where the previous fetch stopped
+    pub fn repeating(pattern: Vec<u8>) -> Self {
+        let offset = std::sync::atomic::AtomicUsize::new(0);
+        Self::new(move |n| {
+            if pattern.is_empty() {
+                return Err(Error::Validation("Mock source pattern is empty".to_string()));
+            }
+            let start = offset.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+            Ok((start..start + n).map(|i| pattern[i % pattern.len()]).collect())
+        })
+    }
+
+    /// Source of random bytes in which about `fraction` of the bytes are replaced by `value`
+    pub fn biased(value: u8, fraction: f64) -> Self {
+        Self::new(move |n| {
+            use rand::Rng;
+            let mut rng = rand::rng();
+            Ok((0..n)
+                .map(|_| if rng.random_bool(fraction) { value } else { rng.random() })
+                .collect())
+        })
+    }
+
+    /// Number of fetches served so far, including failed ones
+    pub fn fetch_count(&self) -> usize {
+        self.fetches.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl EntropySource for MockSource {
+    async fn fetch(&self, n: usize) -> Result<Vec<u8>> {
+        self.fetches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        (self.generator)(n)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,7 +568,7 @@ mod tests {
             1024,
         );
         let fetcher = EntropyFetcher::new(config).unwrap();
-        let url = fetcher.build_request_url().unwrap();
+        let url = fetcher.build_request_url(1024).unwrap();
         assert!(url.to_string().contains("size=1024"));
     }
 
@@ -220,30 +581,340 @@ mod tests {
         let fetcher = EntropyFetcher::new(config).unwrap();
         
         // Valid random-looking data with correct size
-        let mut valid_data = vec![0u8; 100];
-        for i in 0..100 {
-            valid_data[i] = (i % 256) as u8; // Varied data
-        }
-        assert!(fetcher.validate_response(&valid_data).is_ok());
+        let valid_data: Vec<u8> = (0..100).map(|i| (i % 256) as u8).collect(); // Varied data
+        assert!(fetcher.validate_response(&valid_data, 100).is_ok());
         
         // Empty data
-        assert!(fetcher.validate_response(&[]).is_err());
+        assert!(fetcher.validate_response(&[], 100).is_err());
         
         // Wrong size
         let wrong_size = vec![1, 2, 3, 4, 5];
-        assert!(fetcher.validate_response(&wrong_size).is_err());
+        assert!(fetcher.validate_response(&wrong_size, 100).is_err());
         
         // HTML content
         let html = b"<!doctype html><html><body>Error</body></html>".to_vec();
-        assert!(fetcher.validate_response(&html).is_err());
+        assert!(fetcher.validate_response(&html, 100).is_err());
         
         // All same byte (non-random)
         let all_zeros = vec![0u8; 100];
-        assert!(fetcher.validate_response(&all_zeros).is_err());
+        assert!(fetcher.validate_response(&all_zeros, 100).is_err());
         
         // Low entropy (90% same byte)
         let mut low_entropy = vec![42u8; 95];
         low_entropy.extend_from_slice(&[1, 2, 3, 4, 5]);
-        assert!(fetcher.validate_response(&low_entropy).is_err());
+        assert!(fetcher.validate_response(&low_entropy, 100).is_err());
+    }
+
+    #[test]
+    fn test_min_entropy_threshold() {
+        let mut config = FetcherConfig::new(
+            Url::parse("https://example.com/random").unwrap(),
+            4096,
+        );
+        config.min_entropy_threshold = Some(6.5);
+        let fetcher = EntropyFetcher::new(config).unwrap();
+
+        // Every byte value equally frequent: 8 bits/byte
+        let uniform: Vec<u8> = (0..4096).map(|i| (i % 256) as u8).collect();
+        assert_eq!(min_entropy_per_byte(&uniform), 8.0);
+        assert!(fetcher.validate_response(&uniform, 4096).is_ok());
+
+        // One value at 1/16 of the stream: passes the 90% check, but only 4 bits/byte
+        let skewed: Vec<u8> = (0..4096)
+            .map(|i| if i % 16 == 0 { 0xAA } else { (i % 200) as u8 })
+            .collect();
+        let estimate = min_entropy_per_byte(&skewed);
+        assert!(estimate < 6.5);
+        let err = fetcher.validate_response(&skewed, 4096).unwrap_err();
+        assert!(err.to_string().contains(&format!("{:.3}", estimate)));
+
+        // Disabled by default
+        let fetcher = EntropyFetcher::new(FetcherConfig::new(
+            Url::parse("https://example.com/random").unwrap(),
+            4096,
+        ))
+        .unwrap();
+        assert!(fetcher.validate_response(&skewed, 4096).is_ok());
+
+        assert_eq!(min_entropy_per_byte(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_serial_correlation_threshold() {
+        use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+        let mut config = FetcherConfig::new(
+            Url::parse("https://example.com/random").unwrap(),
+            4096,
+        );
+        config.max_serial_correlation = Some(0.1);
+        let fetcher = EntropyFetcher::new(config).unwrap();
+
+        // Uniform random data is essentially uncorrelated
+        let mut uniform = vec![0u8; 4096];
+        StdRng::seed_from_u64(1848).fill_bytes(&mut uniform);
+        assert!(serial_correlation(&uniform).abs() < 0.05);
+        assert!(fetcher.validate_response(&uniform, 4096).is_ok());
+
+        // A ramp passes the frequency checks (every value equally common)
+        // but each byte predicts the next
+        let ramp: Vec<u8> = (0..4096).map(|i| (i % 256) as u8).collect();
+        let correlation = serial_correlation(&ramp);
+        assert!(correlation > 0.9);
+        let err = fetcher.validate_response(&ramp, 4096).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        assert!(err.to_string().contains(&format!("{:.4}", correlation)));
+
+        // Alternating bytes are strongly anti-correlated
+        let alternating: Vec<u8> = (0..4096)
+            .map(|i| if i % 2 == 0 { i as u8 / 2 } else { 255 - i as u8 / 2 })
+            .collect();
+        assert!(serial_correlation(&alternating) < -0.5);
+        assert!(fetcher.validate_response(&alternating, 4096).is_err());
+
+        // Disabled by default
+        let fetcher = EntropyFetcher::new(FetcherConfig::new(
+            Url::parse("https://example.com/random").unwrap(),
+            4096,
+        ))
+        .unwrap();
+        assert!(fetcher.validate_response(&ramp, 4096).is_ok());
+
+        assert_eq!(serial_correlation(&[7; 16]), 1.0);
+    }
+
+    #[test]
+    fn test_appliance_metadata() {
+        let mut source = ApplianceConfig::from_url("lab-a", "https://example.com/random");
+        source.format = ResponseFormat::JsonArray;
+        source.auth_token = Some("secret-token".to_string());
+
+        let config = FetcherConfig::for_appliance(&source, 256).unwrap();
+        assert_eq!(config.chunk_size, 256);
+        assert_eq!(config.response_format, ResponseFormat::JsonArray);
+        assert_eq!(config.auth_token.as_deref(), Some("secret-token"));
+
+        let invalid = ApplianceConfig::from_url("bad", "not a url");
+        assert!(FetcherConfig::for_appliance(&invalid, 256).is_err());
+    }
+
+    #[test]
+    fn test_anu_json_format() {
+        let mut config = FetcherConfig::new(Url::parse("https://example.com/API/jsonI.php").unwrap(), 4);
+        config.response_format = ResponseFormat::AnuJson;
+        let anu = EntropyFetcher::new(config).unwrap();
+        assert_eq!(
+            anu.build_request_url(4).unwrap().as_str(),
+            "https://example.com/API/jsonI.php?length=4&type=uint8"
+        );
+
+        let body = br#"{"type":"uint8","length":4,"data":[12,250,3,97],"success":true}"#;
+        let data = anu.parse_body(body).unwrap();
+        assert_eq!(data, vec![12, 250, 3, 97]);
+        assert!(anu.validate_response(&data, 4).is_ok());
+
+        // Auto-sniffing recognizes the wrapper too
+        let auto = EntropyFetcher::new(FetcherConfig::new(Url::parse("https://example.com/random").unwrap(), 4)).unwrap();
+        assert_eq!(auto.parse_body(body).unwrap(), data);
+
+        let failed = br#"{"success":false,"message":"Rate limit exceeded"}"#;
+        assert!(matches!(anu.parse_body(failed), Err(Error::Validation(_))));
+        assert!(matches!(auto.parse_body(failed), Err(Error::Validation(_))));
+
+        assert!(anu.parse_body(br#"{"success":true,"type":"uint16","data":[1,2]}"#).is_err());
+        assert!(anu.parse_body(b"[1,2,3,4]").is_err());
+    }
+
+    #[test]
+    fn test_response_formats() {
+        let url = Url::parse("https://example.com/random").unwrap();
+        let json_body = b"[1,2,3]";
+
+        let auto = EntropyFetcher::new(FetcherConfig::new(url.clone(), 3)).unwrap();
+        assert_eq!(auto.parse_body(json_body).unwrap(), vec![1, 2, 3]);
+        assert_eq!(auto.parse_body(&[0xFF, 0x00]).unwrap(), vec![0xFF, 0x00]);
+
+        let mut config = FetcherConfig::new(url.clone(), 3);
+        config.response_format = ResponseFormat::Binary;
+        let binary = EntropyFetcher::new(config).unwrap();
+        assert_eq!(binary.parse_body(json_body).unwrap(), json_body.to_vec());
+
+        let mut config = FetcherConfig::new(url, 3);
+        config.response_format = ResponseFormat::JsonArray;
+        let json = EntropyFetcher::new(config).unwrap();
+        assert_eq!(json.parse_body(json_body).unwrap(), vec![1, 2, 3]);
+        assert!(json.parse_body(&[0xFF, 0x00]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_source() {
+        let source = MockSource::repeating(vec![1, 2, 3]);
+        assert_eq!(source.fetch(4).await.unwrap(), vec![1, 2, 3, 1]);
+        assert_eq!(source.fetch(4).await.unwrap(), vec![2, 3, 1, 2]);
+        assert_eq!(source.fetch_count(), 2);
+
+        // A heavily biased source fails the min-entropy check of real fetches
+        let biased = MockSource::biased(0xAA, 0.9).fetch(4096).await.unwrap();
+        assert!(min_entropy_per_byte(&biased) < 1.0);
+
+        let failing = MockSource::new(|_| Err(Error::Timeout));
+        assert!(failing.fetch(16).await.is_err());
+        assert_eq!(failing.fetch_count(), 1);
+    }
+
+    fn temp_entropy_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("qrng-{}-{}", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_file_source() {
+        let data: Vec<u8> = (0..=255).collect();
+        let path = temp_entropy_file("file-source", &data);
+        let source = FileSource::new(&path);
+
+        // Reads continue where the previous one stopped
+        assert_eq!(source.fetch(100).await.unwrap(), data[..100]);
+        assert_eq!(source.fetch(100).await.unwrap(), data[100..200]);
+
+        // An exhausted file is an error, not a short read
+        assert!(source.fetch(100).await.is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mix_file_and_dev_random_sources() {
+        use crate::config::MixingStrategy;
+        use crate::mixer::EntropyMixer;
+
+        let file_data: Vec<u8> = (0..=255).cycle().take(1024).collect();
+        let path = temp_entropy_file("mix-sources", &file_data);
+
+        let sources: Vec<Box<dyn EntropySource>> = vec![
+            source_for_appliance(
+                &ApplianceConfig::from_url("file", Url::from_file_path(&path).unwrap().as_str()),
+                512,
+            )
+            .unwrap(),
+            source_for_appliance(&ApplianceConfig::from_url("os", "urandom:"), 512).unwrap(),
+        ];
+
+        let mut chunks = Vec::new();
+        for (i, source) in sources.iter().enumerate() {
+            chunks.push((i, source.fetch(512).await.unwrap()));
+        }
+        assert_eq!(chunks[0].1, file_data[..512]);
+        assert_eq!(chunks[1].1.len(), 512);
+
+        let mixed = EntropyMixer::new(MixingStrategy::Xor)
+            .mix_sources(&chunks)
+            .unwrap();
+        assert_eq!(mixed.len(), 512);
+        assert_ne!(mixed, chunks[0].1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_status_classification() {
+        use reqwest::StatusCode;
+
+        assert!(matches!(status_error(StatusCode::TOO_MANY_REQUESTS), Error::RateLimit));
+        for status in [StatusCode::BAD_GATEWAY, StatusCode::SERVICE_UNAVAILABLE, StatusCode::GATEWAY_TIMEOUT] {
+            let error = status_error(status);
+            assert!(matches!(error, Error::Unavailable(_)), "{}", status);
+            assert!(error.is_retryable());
+        }
+        for status in [StatusCode::BAD_REQUEST, StatusCode::UNAUTHORIZED, StatusCode::NOT_FOUND, StatusCode::INTERNAL_SERVER_ERROR] {
+            let error = status_error(status);
+            assert!(matches!(error, Error::Validation(_)), "{}", status);
+            assert!(!error.is_retryable());
+        }
+    }
+
+    fn mock_fetcher(url: &str) -> EntropyFetcher {
+        let mut config = FetcherConfig::new(Url::parse(&format!("{}/random", url)).unwrap(), 4);
+        config.retry_policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        EntropyFetcher::new_plain_http(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transient_status_is_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let fetcher = mock_fetcher(&server.url());
+
+        for status in [429, 503] {
+            let transient = server
+                .mock("GET", "/random")
+                .match_query(mockito::Matcher::Any)
+                .with_status(status)
+                .expect(1)
+                .create_async()
+                .await;
+            let ok = server
+                .mock("GET", "/random")
+                .match_query(mockito::Matcher::Any)
+                .with_body([1u8, 2, 3, 4])
+                .expect(1)
+                .create_async()
+                .await;
+
+            assert_eq!(fetcher.fetch().await.unwrap(), vec![1, 2, 3, 4]);
+            transient.assert_async().await;
+            ok.assert_async().await;
+            transient.remove_async().await;
+            ok.remove_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_responses() {
+        let mut server = mockito::Server::new_async().await;
+        let strict = mock_fetcher(&server.url());
+        let mut config = strict.config().clone();
+        config.allow_oversized = true;
+        let tolerant = EntropyFetcher::new_plain_http(config).unwrap();
+
+        for (body, strict_result, tolerant_result) in [
+            (vec![1u8, 2, 3, 4], Some(vec![1, 2, 3, 4]), Some(vec![1, 2, 3, 4])),
+            // Trailing newline: trimmed only when allowed
+            (vec![1, 2, 3, 4, b'\n'], None, Some(vec![1, 2, 3, 4])),
+            // Undersized is never accepted
+            (vec![1, 2, 3], None, None),
+        ] {
+            let mock = server
+                .mock("GET", "/random")
+                .match_query(mockito::Matcher::Any)
+                .with_body(&body)
+                .create_async()
+                .await;
+            assert_eq!(strict.fetch().await.ok(), strict_result, "{:?}", body);
+            assert_eq!(tolerant.fetch().await.ok(), tolerant_result, "{:?}", body);
+            mock.remove_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_error_is_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let fetcher = mock_fetcher(&server.url());
+
+        let forbidden = server
+            .mock("GET", "/random")
+            .match_query(mockito::Matcher::Any)
+            .with_status(403)
+            .expect(1)
+            .create_async()
+            .await;
+
+        assert!(matches!(fetcher.fetch().await, Err(Error::Validation(_))));
+        forbidden.assert_async().await;
     }
 }
@@ -21,8 +21,11 @@
 //! - `protocol`: Data packet format and serialization
 //! - `config`: Configuration management with validation
 //! - `buffer`: High-performance entropy buffer with FIFO semantics
+//! - `bits`: Bit-level reads from the buffer for small-value generators
 //! - `crypto`: Cryptographic primitives (HMAC, CRC32)
-//! - `fetcher`: Resilient HTTPS client for QRNG appliance
+//! - `sampling`: Unbiased bounded integer sampling (Lemire's method)
+//! - `fetcher`: Entropy sources (resilient HTTPS client for QRNG appliance, file, OS RNG)
+//! - `spool`: On-disk spool for entropy that could not be delivered
 //! - `error`: Unified error types
 //!
 //! # Design Principles
@@ -33,6 +36,7 @@
 //! 4. **Testability**: Mock-friendly designs with dependency injection
 //! 5. **Performance**: Lock-free data structures, zero-copy operations
 
+pub mod bits;
 pub mod buffer;
 pub mod config;
 pub mod crypto;
@@ -42,9 +46,11 @@ pub mod mixer;
 pub mod protocol;
 pub mod metrics;
 pub mod retry;
+pub mod sampling;
+pub mod spool;
 
 pub use error::{Error, Result};
-pub use buffer::OverflowPolicy;
+pub use buffer::{EvictionReason, OverflowPolicy};
 
 /// Library version for protocol compatibility
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
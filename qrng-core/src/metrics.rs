@@ -11,10 +11,65 @@
 
 //! Metrics collection and reporting
 
+use crate::buffer::EvictionReason;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+
+/// Upper bounds (microseconds) of the request latency histogram buckets; a final +Inf bucket is implied
+pub const LATENCY_BUCKETS_MICROS: [u64; 6] = [50, 100, 500, 1_000, 5_000, 10_000];
+
+/// Length of the sliding window behind `requests_per_second`
+pub const REQUEST_RATE_WINDOW_SECS: u64 = 60;
+
+/// Per-second event counts over the last [`REQUEST_RATE_WINDOW_SECS`] seconds
+///
+/// Seconds are counted from an arbitrary origin (the service start for
+/// [`Metrics`]). Each slot remembers which second it holds, so slots left
+/// over from an earlier lap of the ring are ignored rather than cleared.
+#[derive(Debug, Clone)]
+pub struct RateWindow {
+    slots: [(u64, u64); REQUEST_RATE_WINDOW_SECS as usize],
+}
+
+impl Default for RateWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateWindow {
+    pub fn new() -> Self {
+        Self {
+            slots: [(0, 0); REQUEST_RATE_WINDOW_SECS as usize],
+        }
+    }
+
+    /// Count one event during `second`
+    pub fn record(&mut self, second: u64) {
+        let slot = &mut self.slots[(second % REQUEST_RATE_WINDOW_SECS) as usize];
+        if slot.0 != second {
+            *slot = (second, 0);
+        }
+        slot.1 += 1;
+    }
+
+    /// Average events per second over the window ending at `now`
+    ///
+    /// Until a full window has elapsed, the average covers only the seconds
+    /// since the origin, so a fresh service reports its actual rate.
+    pub fn rate(&self, now: u64) -> f64 {
+        let events: u64 = self
+            .slots
+            .iter()
+            .filter(|(second, _)| *second <= now && now - second < REQUEST_RATE_WINDOW_SECS)
+            .map(|(_, count)| count)
+            .sum();
+        events as f64 / (now + 1).min(REQUEST_RATE_WINDOW_SECS) as f64
+    }
+}
 
 /// Global metrics collector
 #[derive(Clone)]
@@ -29,6 +84,8 @@ struct MetricsInner {
     requests_total: AtomicU64,
     requests_failed: AtomicU64,
     bytes_served: AtomicU64,
+    seeds_issued: AtomicU64,
+    seed_bits_issued: AtomicU64,
     
     // Push metrics (for collector)
     pushes_total: AtomicU64,
@@ -39,9 +96,23 @@ struct MetricsInner {
     fetches_total: AtomicU64,
     fetches_failed: AtomicU64,
     bytes_fetched: AtomicU64,
+
+    // Buffer evictions (bytes discarded unserved)
+    bytes_evicted_ttl: AtomicU64,
+    bytes_evicted_overflow: AtomicU64,
     
     // Latency tracking (microseconds)
     request_latencies: RwLock<Vec<u64>>,
+
+    // Latency histogram: per-bucket (non-cumulative) counts, the last being +Inf
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_MICROS.len() + 1],
+    latency_sum_micros: AtomicU64,
+
+    // Request counts by (endpoint, HTTP status)
+    endpoint_requests: RwLock<HashMap<(String, u16), u64>>,
+
+    // Recent request counts behind the windowed request rate
+    request_rate: Mutex<RateWindow>,
 }
 
 impl Default for Metrics {
@@ -58,13 +129,21 @@ impl Metrics {
                 requests_total: AtomicU64::new(0),
                 requests_failed: AtomicU64::new(0),
                 bytes_served: AtomicU64::new(0),
+                seeds_issued: AtomicU64::new(0),
+                seed_bits_issued: AtomicU64::new(0),
                 pushes_total: AtomicU64::new(0),
                 pushes_failed: AtomicU64::new(0),
                 bytes_pushed: AtomicU64::new(0),
                 fetches_total: AtomicU64::new(0),
                 fetches_failed: AtomicU64::new(0),
                 bytes_fetched: AtomicU64::new(0),
+                bytes_evicted_ttl: AtomicU64::new(0),
+                bytes_evicted_overflow: AtomicU64::new(0),
                 request_latencies: RwLock::new(Vec::with_capacity(10000)),
+                latency_buckets: Default::default(),
+                latency_sum_micros: AtomicU64::new(0),
+                endpoint_requests: RwLock::new(HashMap::new()),
+                request_rate: Mutex::new(RateWindow::new()),
             }),
         }
     }
@@ -73,6 +152,14 @@ impl Metrics {
     pub fn record_request(&self, bytes: usize, latency_micros: u64) {
         self.inner.requests_total.fetch_add(1, Ordering::Relaxed);
         self.inner.bytes_served.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.inner.request_rate.lock().record(self.uptime_seconds());
+
+        let bucket = LATENCY_BUCKETS_MICROS
+            .iter()
+            .position(|&bound| latency_micros <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MICROS.len());
+        self.inner.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.inner.latency_sum_micros.fetch_add(latency_micros, Ordering::Relaxed);
         
         let mut latencies = self.inner.request_latencies.write();
         latencies.push(latency_micros);
@@ -81,10 +168,59 @@ impl Metrics {
         }
     }
 
+    /// Record bytes served outside of a single request/response cycle (e.g. streaming)
+    pub fn record_bytes_served(&self, bytes: usize) {
+        self.inner.bytes_served.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a seed handed out by /api/seed
+    pub fn record_seed(&self, bits: u32) {
+        self.inner.seeds_issued.fetch_add(1, Ordering::Relaxed);
+        self.inner.seed_bits_issued.fetch_add(bits as u64, Ordering::Relaxed);
+    }
+
+    pub fn seeds_issued(&self) -> u64 {
+        self.inner.seeds_issued.load(Ordering::Relaxed)
+    }
+
     pub fn record_request_failure(&self) {
         self.inner.requests_failed.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Count a response from `endpoint` with the given HTTP status
+    pub fn record_endpoint(&self, endpoint: &str, status: u16) {
+        let mut counts = self.inner.endpoint_requests.write();
+        match counts.get_mut(&(endpoint.to_string(), status)) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert((endpoint.to_string(), status), 1);
+            }
+        }
+    }
+
+    /// Responses recorded for `endpoint` with the given HTTP status
+    pub fn endpoint_requests(&self, endpoint: &str, status: u16) -> u64 {
+        self.inner
+            .endpoint_requests
+            .read()
+            .get(&(endpoint.to_string(), status))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// All `(endpoint, status, count)` response counts, sorted by endpoint then status
+    pub fn endpoint_request_counts(&self) -> Vec<(String, u16, u64)> {
+        let mut counts: Vec<_> = self
+            .inner
+            .endpoint_requests
+            .read()
+            .iter()
+            .map(|((endpoint, status), count)| (endpoint.clone(), *status, *count))
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
+
     pub fn requests_total(&self) -> u64 {
         self.inner.requests_total.load(Ordering::Relaxed)
     }
@@ -111,7 +247,32 @@ impl Metrics {
         self.inner.pushes_total.load(Ordering::Relaxed)
     }
 
+    pub fn pushes_failed(&self) -> u64 {
+        self.inner.pushes_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_pushed(&self) -> u64 {
+        self.inner.bytes_pushed.load(Ordering::Relaxed)
+    }
+
     // Fetch metrics
+    /// Count `bytes` discarded from the entropy buffer for `reason`
+    pub fn record_eviction(&self, reason: EvictionReason, bytes: usize) {
+        let counter = match reason {
+            EvictionReason::Ttl => &self.inner.bytes_evicted_ttl,
+            EvictionReason::Overflow => &self.inner.bytes_evicted_overflow,
+        };
+        counter.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Bytes discarded from the entropy buffer for `reason`
+    pub fn bytes_evicted(&self, reason: EvictionReason) -> u64 {
+        match reason {
+            EvictionReason::Ttl => self.inner.bytes_evicted_ttl.load(Ordering::Relaxed),
+            EvictionReason::Overflow => self.inner.bytes_evicted_overflow.load(Ordering::Relaxed),
+        }
+    }
+
     pub fn record_fetch(&self, bytes: usize) {
         self.inner.fetches_total.fetch_add(1, Ordering::Relaxed);
         self.inner.bytes_fetched.fetch_add(bytes as u64, Ordering::Relaxed);
@@ -125,12 +286,26 @@ impl Metrics {
         self.inner.fetches_total.load(Ordering::Relaxed)
     }
 
+    pub fn fetches_failed(&self) -> u64 {
+        self.inner.fetches_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_fetched(&self) -> u64 {
+        self.inner.bytes_fetched.load(Ordering::Relaxed)
+    }
+
     // Derived metrics
     pub fn uptime_seconds(&self) -> u64 {
         self.inner.start_time.elapsed().as_secs()
     }
 
+    /// Requests per second over the last [`REQUEST_RATE_WINDOW_SECS`] seconds
     pub fn requests_per_second(&self) -> f64 {
+        self.inner.request_rate.lock().rate(self.uptime_seconds())
+    }
+
+    /// Average requests per second since the service started
+    pub fn lifetime_requests_per_second(&self) -> f64 {
         let uptime = self.uptime_seconds() as f64;
         if uptime > 0.0 {
             self.requests_total() as f64 / uptime
@@ -163,6 +338,20 @@ impl Metrics {
         self.latency_percentile(0.99)
     }
 
+    /// Cumulative latency histogram counts, one per `LATENCY_BUCKETS_MICROS` bound plus +Inf
+    pub fn latency_histogram(&self) -> [u64; LATENCY_BUCKETS_MICROS.len() + 1] {
+        let mut cumulative = 0;
+        self.inner.latency_buckets.each_ref().map(|count| {
+            cumulative += count.load(Ordering::Relaxed);
+            cumulative
+        })
+    }
+
+    /// Sum of all recorded request latencies in microseconds
+    pub fn latency_sum_micros(&self) -> u64 {
+        self.inner.latency_sum_micros.load(Ordering::Relaxed)
+    }
+
     /// Generate Prometheus-compatible metrics output
     pub fn prometheus_format(&self) -> String {
         let mut output = String::new();
@@ -171,30 +360,104 @@ impl Metrics {
         output.push_str("# TYPE qrng_requests_total counter\n");
         output.push_str(&format!("qrng_requests_total {}\n", self.requests_total()));
         
+        output.push_str(&format!(
+            "# HELP qrng_requests_per_second Requests per second over the last {} seconds\n",
+            REQUEST_RATE_WINDOW_SECS
+        ));
+        output.push_str("# TYPE qrng_requests_per_second gauge\n");
+        output.push_str(&format!("qrng_requests_per_second {}\n", self.requests_per_second()));
+
+        output.push_str("# HELP qrng_lifetime_requests_per_second Average requests per second since startup\n");
+        output.push_str("# TYPE qrng_lifetime_requests_per_second gauge\n");
+        output.push_str(&format!(
+            "qrng_lifetime_requests_per_second {}\n",
+            self.lifetime_requests_per_second()
+        ));
+
         output.push_str("# HELP qrng_requests_failed Total number of failed requests\n");
         output.push_str("# TYPE qrng_requests_failed counter\n");
         output.push_str(&format!("qrng_requests_failed {}\n", self.requests_failed()));
         
+        let endpoint_requests = self.endpoint_request_counts();
+        if !endpoint_requests.is_empty() {
+            output.push_str("# HELP qrng_endpoint_requests_total Requests by endpoint and HTTP status\n");
+            output.push_str("# TYPE qrng_endpoint_requests_total counter\n");
+            for (endpoint, status, count) in endpoint_requests {
+                output.push_str(&format!(
+                    "qrng_endpoint_requests_total{{endpoint=\"{}\",status=\"{}\"}} {}\n",
+                    endpoint, status, count
+                ));
+            }
+        }
+
         output.push_str("# HELP qrng_bytes_served Total bytes served\n");
         output.push_str("# TYPE qrng_bytes_served counter\n");
         output.push_str(&format!("qrng_bytes_served {}\n", self.bytes_served()));
         
+        output.push_str("# HELP qrng_buffer_evicted_bytes_total Buffered bytes discarded unserved, by reason\n");
+        output.push_str("# TYPE qrng_buffer_evicted_bytes_total counter\n");
+        for reason in [EvictionReason::Ttl, EvictionReason::Overflow] {
+            output.push_str(&format!(
+                "qrng_buffer_evicted_bytes_total{{reason=\"{}\"}} {}\n",
+                reason.as_str(),
+                self.bytes_evicted(reason)
+            ));
+        }
+
+        output.push_str("# HELP qrng_seeds_issued_total Seeds issued by /api/seed\n");
+        output.push_str("# TYPE qrng_seeds_issued_total counter\n");
+        output.push_str(&format!("qrng_seeds_issued_total {}\n", self.seeds_issued()));
+
+        output.push_str("# HELP qrng_seed_bits_issued_total Total bits of seeds issued by /api/seed\n");
+        output.push_str("# TYPE qrng_seed_bits_issued_total counter\n");
+        output.push_str(&format!(
+            "qrng_seed_bits_issued_total {}\n",
+            self.inner.seed_bits_issued.load(Ordering::Relaxed)
+        ));
+
         output.push_str("# HELP qrng_uptime_seconds Service uptime in seconds\n");
         output.push_str("# TYPE qrng_uptime_seconds gauge\n");
         output.push_str(&format!("qrng_uptime_seconds {}\n", self.uptime_seconds()));
         
-        if let Some(p50) = self.latency_p50() {
-            output.push_str("# HELP qrng_latency_p50_microseconds Request latency 50th percentile\n");
-            output.push_str("# TYPE qrng_latency_p50_microseconds gauge\n");
-            output.push_str(&format!("qrng_latency_p50_microseconds {}\n", p50));
+        output.push_str("# HELP qrng_request_latency_microseconds Request latency\n");
+        output.push_str("# TYPE qrng_request_latency_microseconds histogram\n");
+        let histogram = self.latency_histogram();
+        for (bound, count) in LATENCY_BUCKETS_MICROS.iter().zip(histogram) {
+            output.push_str(&format!(
+                "qrng_request_latency_microseconds_bucket{{le=\"{}\"}} {}\n",
+                bound, count
+            ));
         }
+        let count = histogram[LATENCY_BUCKETS_MICROS.len()];
+        output.push_str(&format!("qrng_request_latency_microseconds_bucket{{le=\"+Inf\"}} {}\n", count));
+        output.push_str(&format!("qrng_request_latency_microseconds_sum {}\n", self.latency_sum_micros()));
+        output.push_str(&format!("qrng_request_latency_microseconds_count {}\n", count));
         
-        if let Some(p99) = self.latency_p99() {
-            output.push_str("# HELP qrng_latency_p99_microseconds Request latency 99th percentile\n");
-            output.push_str("# TYPE qrng_latency_p99_microseconds gauge\n");
-            output.push_str(&format!("qrng_latency_p99_microseconds {}\n", p99));
+        output
+    }
+
+    /// Generate Prometheus-compatible fetch and push metrics for the collector
+    pub fn collector_prometheus_format(&self) -> String {
+        let mut output = String::new();
+
+        let counters = [
+            ("qrng_fetches_total", "Total number of successful fetches", self.fetches_total()),
+            ("qrng_fetches_failed", "Total number of failed fetches", self.fetches_failed()),
+            ("qrng_bytes_fetched", "Total bytes fetched from sources", self.bytes_fetched()),
+            ("qrng_pushes_total", "Total number of successful pushes", self.pushes_total()),
+            ("qrng_pushes_failed", "Total number of failed pushes", self.pushes_failed()),
+            ("qrng_bytes_pushed", "Total bytes pushed to gateways", self.bytes_pushed()),
+        ];
+        for (name, help, value) in counters {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+            output.push_str(&format!("# TYPE {} counter\n", name));
+            output.push_str(&format!("{} {}\n", name, value));
         }
-        
+
+        output.push_str("# HELP qrng_uptime_seconds Service uptime in seconds\n");
+        output.push_str("# TYPE qrng_uptime_seconds gauge\n");
+        output.push_str(&format!("qrng_uptime_seconds {}\n", self.uptime_seconds()));
+
         output
     }
 }
@@ -216,6 +479,29 @@ mod tests {
         assert_eq!(metrics.bytes_served(), 3072);
     }
 
+    #[test]
+    fn test_endpoint_counters() {
+        let metrics = Metrics::new();
+
+        metrics.record_endpoint("/api/integers", 200);
+        metrics.record_endpoint("/api/integers", 200);
+        metrics.record_endpoint("/api/integers", 503);
+        metrics.record_endpoint("/api/uuid", 429);
+
+        assert_eq!(metrics.endpoint_requests("/api/integers", 200), 2);
+        assert_eq!(metrics.endpoint_requests("/api/uuid", 200), 0);
+
+        let output = metrics.prometheus_format();
+        assert!(output.contains("# TYPE qrng_endpoint_requests_total counter\n"));
+        assert!(output.contains("qrng_endpoint_requests_total{endpoint=\"/api/integers\",status=\"200\"} 2\n"));
+        assert!(output.contains("qrng_endpoint_requests_total{endpoint=\"/api/integers\",status=\"503\"} 1\n"));
+        assert!(output.contains("qrng_endpoint_requests_total{endpoint=\"/api/uuid\",status=\"429\"} 1\n"));
+
+        // Aggregate counters are unaffected
+        assert_eq!(metrics.requests_total(), 0);
+        assert!(output.contains("qrng_requests_total 0\n"));
+    }
+
     #[test]
     fn test_latency_percentiles() {
         let metrics = Metrics::new();
@@ -230,4 +516,77 @@ mod tests {
         let p99 = metrics.latency_p99().unwrap();
         assert!((95..=100).contains(&p99));
     }
+
+    #[test]
+    fn test_latency_histogram() {
+        let metrics = Metrics::new();
+
+        // Bounds are inclusive: 50 falls in the 50µs bucket, 51 in the 100µs bucket
+        for latency in [10, 50, 51, 100, 700, 1_000, 4_999, 10_000, 10_001, 250_000] {
+            metrics.record_request(0, latency);
+        }
+
+        assert_eq!(metrics.latency_histogram(), [2, 4, 4, 6, 7, 8, 10]);
+        assert_eq!(metrics.latency_sum_micros(), 276_911);
+
+        let output = metrics.prometheus_format();
+        assert!(output.contains("# TYPE qrng_request_latency_microseconds histogram\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_bucket{le=\"50\"} 2\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_bucket{le=\"500\"} 4\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_bucket{le=\"10000\"} 8\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_bucket{le=\"+Inf\"} 10\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_sum 276911\n"));
+        assert!(output.contains("qrng_request_latency_microseconds_count 10\n"));
+    }
+
+    #[test]
+    fn test_request_rate_window() {
+        let mut window = RateWindow::new();
+        assert_eq!(window.rate(0), 0.0);
+
+        // A burst of 50 requests in the first second
+        for _ in 0..50 {
+            window.record(0);
+        }
+        assert_eq!(window.rate(0), 50.0);
+        assert_eq!(window.rate(4), 10.0);
+
+        // Steady traffic of 10/s lifts the average towards 10
+        for second in 100..160 {
+            for _ in 0..10 {
+                window.record(second);
+            }
+        }
+        assert_eq!(window.rate(159), 10.0);
+
+        // A burst raises the rate, which decays once the burst leaves the window
+        for _ in 0..600 {
+            window.record(160);
+        }
+        let peak = window.rate(160);
+        assert!((19.0..20.0).contains(&peak), "peak {}", peak);
+        assert!(window.rate(200) < peak);
+        assert_eq!(window.rate(219), 10.0);
+        assert_eq!(window.rate(220), 0.0);
+        assert_eq!(window.rate(1_000), 0.0);
+
+        // Slots from earlier laps of the ring do not leak into new seconds
+        window.record(1_020);
+        assert_eq!(window.rate(1_020), 1.0 / 60.0);
+    }
+
+    #[test]
+    fn test_request_rate_metrics() {
+        let metrics = Metrics::new();
+        for _ in 0..30 {
+            metrics.record_request(0, 10);
+        }
+        // Within the first second, the windowed rate reflects all 30 requests
+        assert_eq!(metrics.requests_per_second(), 30.0);
+
+        let output = metrics.prometheus_format();
+        assert!(output.contains("# TYPE qrng_requests_per_second gauge\n"));
+        assert!(output.contains("qrng_requests_per_second 30\n"));
+        assert!(output.contains("# TYPE qrng_lifetime_requests_per_second gauge\n"));
+    }
 }
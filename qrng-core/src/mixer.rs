@@ -13,28 +13,143 @@
 //!
 //! Provides algorithms to combine entropy from multiple quantum sources.
 
-use crate::{config::MixingStrategy, Error, Result};
+use crate::{
+    config::{LengthPolicy, MixingStrategy},
+    Error, Result,
+};
 use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
+use tracing::warn;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// SHA-256 output length in bytes
+const HASH_LEN: usize = 32;
+
+/// Largest HKDF-SHA256 output (RFC 5869: 255 blocks)
+pub const MAX_DERIVE_LEN: usize = 255 * HASH_LEN;
+
+/// Domain separation prefix of the whitening digest
+const WHITEN_DOMAIN: &[u8] = b"qrng-whiten-v1";
+
 /// Entropy mixer for combining multiple randomness sources
 pub struct EntropyMixer {
     strategy: MixingStrategy,
+    weights: Vec<f64>,
+    length_policy: LengthPolicy,
+    whiten: bool,
 }
 
 impl EntropyMixer {
     /// Create a new entropy mixer with the specified strategy
     pub fn new(strategy: MixingStrategy) -> Self {
-        Self { strategy }
+        Self {
+            strategy,
+            weights: Vec::new(),
+            length_policy: LengthPolicy::default(),
+            whiten: false,
+        }
+    }
+
+    /// Run the mixed output through SHA-256 whitening (off by default)
+    ///
+    /// Whitening also applies when only one chunk is mixed. See [`Self::whiten`].
+    pub fn with_whitening(mut self, whiten: bool) -> Self {
+        self.whiten = whiten;
+        self
+    }
+
+    /// Whether mixed output is whitened
+    pub fn whitening(&self) -> bool {
+        self.whiten
+    }
+
+    /// Set how chunks of different lengths are combined (strict by default)
+    pub fn with_length_policy(mut self, length_policy: LengthPolicy) -> Self {
+        self.length_policy = length_policy;
+        self
+    }
+
+    /// Policy applied to chunks of different lengths
+    pub fn length_policy(&self) -> LengthPolicy {
+        self.length_policy
+    }
+
+    /// Set per-source trust weights, indexed by source position
+    ///
+    /// Sources without an explicit weight default to 1.0.
+    pub fn with_weights(mut self, weights: Vec<f64>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Get the trust weight of a source
+    pub fn weight(&self, source: usize) -> f64 {
+        self.weights.get(source).copied().unwrap_or(1.0)
+    }
+
+    /// Mix chunks tagged with the index of the source that produced them
+    ///
+    /// Chunks from zero-weight sources are dropped before mixing.
+    pub fn mix_sources(&self, chunks: &[(usize, Vec<u8>)]) -> Result<Vec<u8>> {
+        let trusted: Vec<Vec<u8>> = chunks
+            .iter()
+            .filter(|(source, _)| self.weight(*source) > 0.0)
+            .map(|(_, chunk)| chunk.clone())
+            .collect();
+
+        if trusted.is_empty() {
+            return Err(Error::Validation("No trusted sources to mix".to_string()));
+        }
+
+        self.mix(&trusted)
     }
 
     /// Mix multiple entropy chunks into a single output
     ///
-    /// All chunks must have the same length. Returns error if chunks are empty
-    /// or have different lengths.
+    /// Chunks of different lengths are handled according to the length policy;
+    /// the output is then as long as the shortest chunk. Returns error if chunks
+    /// are empty, or differ in length under the strict policy. The concat
+    /// strategy accepts any lengths and returns all chunks end to end. With
+    /// whitening enabled the result is then whitened to the same length.
     pub fn mix(&self, chunks: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let mixed = self.combine(chunks)?;
+        Ok(if self.whiten { Self::whiten(&mixed) } else { mixed })
+    }
+
+    /// Whiten data with SHA-256 in counter mode, producing as many bytes as the input
+    ///
+    /// A digest of the whole input is computed first; each 32-byte output
+    /// block is then `SHA-256(digest || counter || input block)`, so every
+    /// output block depends on all input bytes while the cost stays linear.
+    /// The output is deterministic for a given input.
+    ///
+    /// Whitening hides subtle correlations but cannot add entropy: an output
+    /// byte carries at most as much min-entropy as the input had on average,
+    /// and a biased input no longer shows its bias. It breaks the 1:1
+    /// entropy-rate guarantee of the raw stream and is only appropriate when
+    /// the source is over-provisioned relative to what is served.
+    pub fn whiten(data: &[u8]) -> Vec<u8> {
+        let digest = Sha256::new()
+            .chain_update(WHITEN_DOMAIN)
+            .chain_update((data.len() as u64).to_le_bytes())
+            .chain_update(data)
+            .finalize();
+
+        let mut output = Vec::with_capacity(data.len());
+        for (counter, block) in data.chunks(HASH_LEN).enumerate() {
+            let hash = Sha256::new()
+                .chain_update(digest)
+                .chain_update((counter as u64).to_le_bytes())
+                .chain_update(block)
+                .finalize();
+            output.extend_from_slice(&hash[..block.len()]);
+        }
+        output
+    }
+
+    /// Combine the chunks according to the strategy and length policy
+    fn combine(&self, chunks: &[Vec<u8>]) -> Result<Vec<u8>> {
         if chunks.is_empty() {
             return Err(Error::Validation("No chunks to mix".to_string()));
         }
@@ -43,18 +158,47 @@ impl EntropyMixer {
             return Ok(chunks[0].clone());
         }
 
-        // Verify all chunks have the same length
-        let expected_len = chunks[0].len();
-        if chunks.iter().any(|chunk| chunk.len() != expected_len) {
-            return Err(Error::Validation(
-                "All chunks must have the same length for mixing".to_string(),
-            ));
+        if self.strategy == MixingStrategy::Concat {
+            return Ok(chunks.concat());
         }
 
+        let shortest = chunks.iter().map(Vec::len).min().unwrap_or_default();
+        let longest = chunks.iter().map(Vec::len).max().unwrap_or_default();
+        if shortest != longest {
+            match self.length_policy {
+                LengthPolicy::Strict => {
+                    return Err(Error::Validation(
+                        "All chunks must have the same length for mixing".to_string(),
+                    ));
+                }
+                LengthPolicy::Truncate => {
+                    warn!(
+                        "Truncating mixed chunks from up to {} to {} bytes",
+                        longest, shortest
+                    );
+                    let truncated: Vec<Vec<u8>> = chunks
+                        .iter()
+                        .map(|chunk| chunk[..shortest].to_vec())
+                        .collect();
+                    return self.mix_equal(&truncated);
+                }
+                LengthPolicy::PadAndHash => {
+                    // Every byte contributes; HKDF expands the pool to the shortest length
+                    return self.derive(chunks, &Self::mix_salt(chunks.len()), &[], shortest);
+                }
+            }
+        }
+
+        self.mix_equal(chunks)
+    }
+
+    /// Mix chunks already known to have equal lengths
+    fn mix_equal(&self, chunks: &[Vec<u8>]) -> Result<Vec<u8>> {
         match self.strategy {
             MixingStrategy::None => Ok(chunks[0].clone()),
             MixingStrategy::Xor => Ok(self.xor_mix(chunks)),
             MixingStrategy::Hkdf => self.hkdf_mix(chunks),
+            MixingStrategy::Concat => Ok(chunks.concat()),
         }
     }
 
@@ -77,38 +221,52 @@ impl EntropyMixer {
     /// This provides better mixing properties than simple XOR, especially
     /// if the sources have any correlation or bias.
     fn hkdf_mix(&self, chunks: &[Vec<u8>]) -> Result<Vec<u8>> {
-        let len = chunks[0].len();
+        self.derive(chunks, &Self::mix_salt(chunks.len()), &[], chunks[0].len())
+    }
 
-        // Concatenate all chunks
-        let mut input = Vec::new();
-        for chunk in chunks {
-            input.extend_from_slice(chunk);
+    /// Fixed HKDF salt derived from the number of sources
+    fn mix_salt(sources: usize) -> Vec<u8> {
+        format!("qrng-entropy-mix-{}-sources", sources).into_bytes()
+    }
+
+    /// Derive `out_len` bytes from the concatenated chunks with HKDF-SHA256 (RFC 5869)
+    ///
+    /// Distinct `info` strings yield independent outputs from the same entropy,
+    /// giving domain separation between derived keys. An empty salt is treated
+    /// as a string of zeros, as the RFC specifies.
+    pub fn derive(&self, chunks: &[Vec<u8>], salt: &[u8], info: &[u8], out_len: usize) -> Result<Vec<u8>> {
+        if out_len > MAX_DERIVE_LEN {
+            return Err(Error::Validation(format!(
+                "HKDF output length must be at most {} bytes",
+                MAX_DERIVE_LEN
+            )));
         }
 
-        // Use HKDF-Extract: HMAC(salt, input_key_material)
-        // We use a fixed salt derived from the number of sources
-        let salt = format!("qrng-entropy-mix-{}-sources", chunks.len());
-        let mut mac = HmacSha256::new_from_slice(salt.as_bytes())
+        // HKDF-Extract: PRK = HMAC(salt, input_key_material)
+        let mut mac = HmacSha256::new_from_slice(salt)
             .map_err(|e| Error::Crypto(format!("HMAC init failed: {}", e)))?;
-        mac.update(&input);
+        for chunk in chunks {
+            mac.update(chunk);
+        }
         let prk = mac.finalize().into_bytes();
 
-        // HKDF-Expand: derive output of desired length
-        let mut output = Vec::with_capacity(len);
+        // HKDF-Expand: T(i) = HMAC(PRK, T(i-1) | info | i)
+        let mut output = Vec::with_capacity(out_len + HASH_LEN);
         let mut counter = 1u8;
         let mut t = Vec::new();
 
-        while output.len() < len {
+        while output.len() < out_len {
             let mut mac = HmacSha256::new_from_slice(&prk)
                 .map_err(|e| Error::Crypto(format!("HMAC init failed: {}", e)))?;
             mac.update(&t);
+            mac.update(info);
             mac.update(&[counter]);
             t = mac.finalize().into_bytes().to_vec();
             output.extend_from_slice(&t);
-            counter += 1;
+            counter = counter.wrapping_add(1);
         }
 
-        output.truncate(len);
+        output.truncate(out_len);
         Ok(output)
     }
 }
@@ -157,6 +315,26 @@ mod tests {
         assert_eq!(result, result2);
     }
 
+    #[test]
+    fn test_concat_preserves_all_bytes_in_order() {
+        let mixer = EntropyMixer::new(MixingStrategy::Concat);
+
+        let chunk1 = vec![0x01, 0x02];
+        let chunk2 = vec![0x03, 0x04, 0x05];
+        let chunk3 = vec![0x06];
+
+        // Unequal lengths are accepted even under the strict policy
+        let result = mixer.mix(&[chunk1, chunk2, chunk3]).unwrap();
+        assert_eq!(result, vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+
+        // Zero-weight sources are still dropped
+        let mixer = mixer.with_weights(vec![1.0, 0.0, 1.0]);
+        let chunks = vec![(0, vec![0xAA; 4]), (1, vec![0xBB; 4]), (2, vec![0xCC; 8])];
+        let result = mixer.mix_sources(&chunks).unwrap();
+        assert_eq!(result.len(), 12);
+        assert_eq!(result, [vec![0xAA; 4], vec![0xCC; 8]].concat());
+    }
+
     #[test]
     fn test_different_lengths_error() {
         let mixer = EntropyMixer::new(MixingStrategy::Xor);
@@ -167,12 +345,55 @@ mod tests {
         assert!(mixer.mix(&[chunk1, chunk2]).is_err());
     }
 
+    #[test]
+    fn test_length_policy_truncate() {
+        let chunk1: Vec<u8> = (0..100).collect();
+        let chunk2: Vec<u8> = (0..120).map(|i| (i * 7) as u8).collect();
+        let chunks = vec![chunk1.clone(), chunk2.clone()];
+
+        let mixer = EntropyMixer::new(MixingStrategy::Xor).with_length_policy(LengthPolicy::Truncate);
+        let result = mixer.mix(&chunks).unwrap();
+        assert_eq!(result.len(), 100);
+        assert_eq!(result, mixer.mix(&chunks).unwrap());
+
+        // Identical to mixing the first 100 bytes of each chunk
+        let expected = mixer.mix(&[chunk1, chunk2[..100].to_vec()]).unwrap();
+        assert_eq!(result, expected);
+
+        let hkdf = EntropyMixer::new(MixingStrategy::Hkdf).with_length_policy(LengthPolicy::Truncate);
+        assert_eq!(hkdf.mix(&chunks).unwrap().len(), 100);
+    }
+
+    #[test]
+    fn test_length_policy_pad_and_hash() {
+        let chunk1 = vec![0x11; 100];
+        let chunk2 = vec![0x22; 120];
+        let chunks = vec![chunk1.clone(), chunk2.clone()];
+
+        let mixer = EntropyMixer::new(MixingStrategy::Xor).with_length_policy(LengthPolicy::PadAndHash);
+        let result = mixer.mix(&chunks).unwrap();
+        assert_eq!(result.len(), 100);
+        assert_eq!(result, mixer.mix(&chunks).unwrap());
+
+        // The tail of the longer chunk affects the output
+        let mut altered = chunk2.clone();
+        altered[119] ^= 0xFF;
+        assert_ne!(mixer.mix(&[chunk1.clone(), altered]).unwrap(), result);
+
+        // Equal-length chunks still use the configured strategy
+        assert_eq!(mixer.mix(&[vec![0xF0], vec![0x0F]]).unwrap(), vec![0xFF]);
+
+        // Strict is the default
+        assert_eq!(EntropyMixer::new(MixingStrategy::Xor).length_policy(), LengthPolicy::Strict);
+        assert!(EntropyMixer::new(MixingStrategy::Xor).mix(&[chunk1, chunk2]).is_err());
+    }
+
     #[test]
     fn test_single_chunk() {
         let mixer = EntropyMixer::new(MixingStrategy::Xor);
         let chunk = vec![0x01, 0x02, 0x03];
 
-        let result = mixer.mix(&[chunk.clone()]).unwrap();
+        let result = mixer.mix(std::slice::from_ref(&chunk)).unwrap();
         assert_eq!(result, chunk);
     }
 
@@ -181,4 +402,128 @@ mod tests {
         let mixer = EntropyMixer::new(MixingStrategy::Xor);
         assert!(mixer.mix(&[]).is_err());
     }
+
+    #[test]
+    fn test_source_weights() {
+        let mixer = EntropyMixer::new(MixingStrategy::Xor).with_weights(vec![2.0, 0.0, 1.0]);
+        assert_eq!(mixer.weight(0), 2.0);
+        assert_eq!(mixer.weight(1), 0.0);
+        assert_eq!(mixer.weight(3), 1.0);
+
+        // Source 1 has zero weight and is excluded from the mix
+        let chunks = vec![(0, vec![0xF0]), (1, vec![0xFF]), (2, vec![0x0F])];
+        assert_eq!(mixer.mix_sources(&chunks).unwrap(), vec![0xFF]);
+
+        // Only an untrusted source succeeded
+        assert!(mixer.mix_sources(&[(1, vec![0xFF])]).is_err());
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        crate::crypto::decode_hex(s).unwrap()
+    }
+
+    #[test]
+    fn test_derive_rfc5869_vectors() {
+        let mixer = EntropyMixer::new(MixingStrategy::Hkdf);
+
+        // Test Case 1: basic test case with SHA-256
+        let okm = mixer
+            .derive(
+                &[vec![0x0b; 22]],
+                &hex("000102030405060708090a0b0c"),
+                &hex("f0f1f2f3f4f5f6f7f8f9"),
+                42,
+            )
+            .unwrap();
+        assert_eq!(
+            okm,
+            hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865")
+        );
+
+        // Test Case 2: longer inputs/outputs
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+        let okm = mixer.derive(&[ikm], &salt, &info, 82).unwrap();
+        assert_eq!(
+            okm,
+            hex("b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+                 59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+                 cc30c58179ec3e87c14c01d5c1f3434f1d87")
+        );
+
+        // Test Case 3: zero-length salt and info
+        let okm = mixer.derive(&[vec![0x0b; 22]], &[], &[], 42).unwrap();
+        assert_eq!(
+            okm,
+            hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8")
+        );
+    }
+
+    #[test]
+    fn test_derive_domain_separation() {
+        let mixer = EntropyMixer::new(MixingStrategy::Hkdf);
+        let chunks = vec![vec![0x42; 32]];
+
+        let signing = mixer.derive(&chunks, &[], b"signing", 32).unwrap();
+        let encryption = mixer.derive(&chunks, &[], b"encryption", 32).unwrap();
+        assert_ne!(signing, encryption);
+
+        // Chunks are concatenated into the input key material
+        let split = mixer.derive(&[vec![0x42; 16], vec![0x42; 16]], &[], b"signing", 32).unwrap();
+        assert_eq!(signing, split);
+
+        assert_eq!(mixer.derive(&chunks, &[], &[], MAX_DERIVE_LEN).unwrap().len(), MAX_DERIVE_LEN);
+        assert!(mixer.derive(&chunks, &[], &[], MAX_DERIVE_LEN + 1).is_err());
+    }
+
+    #[test]
+    fn test_hkdf_mix_uses_source_count_salt() {
+        let mixer = EntropyMixer::new(MixingStrategy::Hkdf);
+        let chunks = vec![vec![0x01, 0x02, 0x03, 0x04], vec![0x05, 0x06, 0x07, 0x08]];
+
+        let expected = mixer.derive(&chunks, b"qrng-entropy-mix-2-sources", &[], 4).unwrap();
+        assert_eq!(mixer.mix(&chunks).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_whitening_is_deterministic_and_full_length() {
+        for len in [0, 1, 31, 32, 33, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let whitened = EntropyMixer::whiten(&data);
+            assert_eq!(whitened.len(), len);
+            assert_eq!(whitened, EntropyMixer::whiten(&data));
+            if len > 0 {
+                assert_ne!(whitened, data);
+            }
+        }
+
+        // Changing any input byte changes every output block
+        let data = vec![0u8; 96];
+        let mut flipped = data.clone();
+        flipped[95] ^= 1;
+        let (a, b) = (EntropyMixer::whiten(&data), EntropyMixer::whiten(&flipped));
+        for (block_a, block_b) in a.chunks(32).zip(b.chunks(32)) {
+            assert_ne!(block_a, block_b);
+        }
+
+        // Constant input no longer repeats in the output
+        assert_ne!(a[..32], a[32..64]);
+    }
+
+    #[test]
+    fn test_whitening_applies_after_mixing() {
+        let chunk = vec![0xAB; 48];
+        let mixer = EntropyMixer::new(MixingStrategy::None).with_whitening(true);
+        assert!(mixer.whitening());
+        assert!(!EntropyMixer::new(MixingStrategy::None).whitening());
+
+        // Works on a single source
+        assert_eq!(mixer.mix(std::slice::from_ref(&chunk)).unwrap(), EntropyMixer::whiten(&chunk));
+
+        let xor = EntropyMixer::new(MixingStrategy::Xor);
+        let chunks = vec![vec![0x0F; 48], vec![0xF0; 48]];
+        let whitened = xor.with_whitening(true).mix(&chunks).unwrap();
+        assert_eq!(whitened, EntropyMixer::whiten(&[0xFF; 48]));
+    }
 }
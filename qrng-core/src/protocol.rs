@@ -12,8 +12,9 @@
 //! Protocol data structures for entropy transmission
 //!
 //! Defines the wire format for entropy packets transmitted from Collector to Gateway.
-//! Uses MessagePack for efficient binary serialization.
+//! Uses MessagePack for efficient binary serialization, with CBOR as an alternative.
 
+use crate::buffer::BufferStats;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -21,7 +22,7 @@ use uuid::Uuid;
 /// Entropy packet transmitted from Collector to Gateway
 ///
 /// This structure is optimized for:
-/// - Integrity verification (HMAC + CRC32)
+/// - Integrity verification (HMAC + CRC32 or BLAKE3)
 /// - Ordering and gap detection (sequence numbers)
 /// - Freshness tracking (timestamps)
 /// - Efficient serialization (MessagePack)
@@ -49,6 +50,36 @@ pub struct EntropyPacket {
 
     /// Optional CRC32 checksum for additional integrity
     pub checksum: Option<u32>,
+
+    /// ChaCha20-Poly1305 nonce; present only when `data` is encrypted
+    #[serde(default, with = "serde_bytes")]
+    pub nonce: Option<Vec<u8>>,
+
+    /// Algorithm of the payload checksum
+    #[serde(default)]
+    pub checksum_algo: ChecksumAlgorithm,
+
+    /// BLAKE3 digest of the payload when `checksum_algo` is BLAKE3
+    #[serde(default, with = "serde_bytes")]
+    pub digest: Option<Vec<u8>>,
+
+    /// Region or site of the collector that produced the packet
+    #[serde(default)]
+    pub source_tag: Option<String>,
+}
+
+/// Payload checksum algorithm
+///
+/// CRC32 only catches accidental corruption; BLAKE3 is a cryptographic digest
+/// that is still far cheaper to verify than the HMAC signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// CRC32, stored in `checksum` (compatible with all gateways)
+    #[default]
+    Crc32,
+    /// BLAKE3-256, stored in `digest`
+    Blake3,
 }
 
 impl EntropyPacket {
@@ -65,22 +96,86 @@ impl EntropyPacket {
             timestamp: Utc::now(),
             signature: Vec::new(),
             checksum: None,
+            nonce: None,
+            checksum_algo: ChecksumAlgorithm::default(),
+            digest: None,
+            source_tag: None,
         }
     }
 
-    /// Calculate CRC32 checksum of payload
-    pub fn calculate_checksum(&self) -> u32 {
-        crc32fast::hash(&self.data)
+    /// Calculate the payload checksum with `checksum_algo`
+    ///
+    /// CRC32 is returned as 4 big-endian bytes, BLAKE3 as its 32-byte digest.
+    pub fn calculate_checksum(&self) -> Vec<u8> {
+        match self.checksum_algo {
+            ChecksumAlgorithm::Crc32 => crc32fast::hash(&self.data).to_be_bytes().to_vec(),
+            ChecksumAlgorithm::Blake3 => blake3::hash(&self.data).as_bytes().to_vec(),
+        }
     }
 
-    /// Verify CRC32 checksum if present
+    /// Compute the payload checksum with `algo` and store it in the packet
+    pub fn set_checksum(&mut self, algo: ChecksumAlgorithm) {
+        self.checksum_algo = algo;
+        match algo {
+            ChecksumAlgorithm::Crc32 => {
+                self.checksum = Some(crc32fast::hash(&self.data));
+                self.digest = None;
+            }
+            ChecksumAlgorithm::Blake3 => {
+                self.checksum = None;
+                self.digest = Some(self.calculate_checksum());
+            }
+        }
+    }
+
+    /// Verify the payload checksum
+    ///
+    /// A CRC32 packet without a checksum passes; a BLAKE3 packet must carry its digest.
     pub fn verify_checksum(&self) -> bool {
-        match self.checksum {
-            Some(expected) => expected == self.calculate_checksum(),
-            None => true, // No checksum to verify
+        match self.checksum_algo {
+            ChecksumAlgorithm::Crc32 => match self.checksum {
+                Some(expected) => expected == crc32fast::hash(&self.data),
+                None => true, // No checksum to verify
+            },
+            ChecksumAlgorithm::Blake3 => self
+                .digest
+                .as_ref()
+                .is_some_and(|expected| *expected == self.calculate_checksum()),
         }
     }
 
+    /// Returns true if the payload is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.nonce.is_some()
+    }
+
+    /// Encrypt the payload in place with ChaCha20-Poly1305
+    ///
+    /// Must be called before computing the checksum and signature so that
+    /// both cover the ciphertext.
+    pub fn encrypt_payload(&mut self, key: &[u8]) -> crate::Result<()> {
+        if self.is_encrypted() {
+            return Err(crate::Error::Crypto("Payload is already encrypted".to_string()));
+        }
+        let (nonce, ciphertext) = crate::crypto::encrypt_payload(key, &self.data)?;
+        self.data = ciphertext;
+        self.nonce = Some(nonce);
+        Ok(())
+    }
+
+    /// Decrypt the payload in place, verifying its authentication tag
+    ///
+    /// Signature and checksum verification must happen before decryption.
+    pub fn decrypt_payload(&mut self, key: &[u8]) -> crate::Result<()> {
+        let nonce = self
+            .nonce
+            .as_ref()
+            .ok_or_else(|| crate::Error::Crypto("Payload is not encrypted".to_string()))?;
+        self.data = crate::crypto::decrypt_payload(key, nonce, &self.data)?;
+        self.nonce = None;
+        Ok(())
+    }
+
     /// Get payload size in bytes
     pub fn payload_size(&self) -> usize {
         self.data.len()
@@ -100,6 +195,185 @@ impl EntropyPacket {
     pub fn from_msgpack(bytes: &[u8]) -> crate::Result<Self> {
         rmp_serde::from_slice(bytes).map_err(Into::into)
     }
+
+    /// Serialize to CBOR
+    pub fn to_cbor(&self) -> crate::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| crate::Error::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Deserialize from CBOR
+    pub fn from_cbor(bytes: &[u8]) -> crate::Result<Self> {
+        ciborium::from_reader(bytes).map_err(|e| crate::Error::Serialization(e.to_string()))
+    }
+
+    /// Serialize for the wire in the given format
+    ///
+    /// MessagePack is sent unframed so older gateways keep accepting it; other
+    /// formats are prefixed with [`FRAME_MARKER`] and a format byte.
+    pub fn encode(&self, format: SerializationFormat) -> crate::Result<Vec<u8>> {
+        match format {
+            SerializationFormat::MessagePack => self.to_msgpack(),
+            SerializationFormat::Cbor => {
+                let mut bytes = vec![FRAME_MARKER, format.tag()];
+                bytes.extend(self.to_cbor()?);
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Serialize for the wire, zstd-compressing the result when that makes it smaller
+    ///
+    /// Random payloads rarely shrink, so the packet is usually sent as
+    /// [`Self::encode`] would; otherwise it is framed with [`ZSTD_FRAME_TAG`].
+    pub fn encode_compressed(&self, format: SerializationFormat) -> crate::Result<Vec<u8>> {
+        let encoded = self.encode(format)?;
+        let compressed = zstd::bulk::compress(&encoded, ZSTD_LEVEL)
+            .map_err(|e| crate::Error::Serialization(format!("zstd compression failed: {}", e)))?;
+
+        if compressed.len() + 2 >= encoded.len() {
+            return Ok(encoded);
+        }
+        let mut bytes = Vec::with_capacity(compressed.len() + 2);
+        bytes.extend_from_slice(&[FRAME_MARKER, ZSTD_FRAME_TAG]);
+        bytes.extend(compressed);
+        Ok(bytes)
+    }
+
+    /// Deserialize a wire packet, detecting its format from the framing header
+    ///
+    /// Bytes without a frame header are legacy MessagePack. Compressed packets
+    /// are inflated first and report the format of the packet inside.
+    pub fn decode(bytes: &[u8]) -> crate::Result<(Self, SerializationFormat)> {
+        if let [FRAME_MARKER, ZSTD_FRAME_TAG, compressed @ ..] = bytes {
+            let inflated = zstd::bulk::decompress(compressed, MAX_DECOMPRESSED_PACKET_SIZE)
+                .map_err(|e| crate::Error::Serialization(format!("zstd decompression failed: {}", e)))?;
+            if inflated.starts_with(&[FRAME_MARKER, ZSTD_FRAME_TAG]) {
+                return Err(crate::Error::Serialization(
+                    "Nested compressed packet".to_string(),
+                ));
+            }
+            return Self::decode(&inflated);
+        }
+
+        let (format, payload) = match bytes {
+            [FRAME_MARKER, tag, payload @ ..] => {
+                let format = SerializationFormat::from_tag(*tag).ok_or_else(|| {
+                    crate::Error::Serialization(format!("Unknown packet format tag 0x{:02x}", tag))
+                })?;
+                (format, payload)
+            }
+            _ => (SerializationFormat::MessagePack, bytes),
+        };
+
+        let packet = match format {
+            SerializationFormat::MessagePack => Self::from_msgpack(payload)?,
+            SerializationFormat::Cbor => Self::from_cbor(payload)?,
+        };
+        Ok((packet, format))
+    }
+}
+
+/// First byte of a framed packet
+///
+/// 0xC1 is never used by MessagePack, so it cannot begin a legacy packet.
+pub const FRAME_MARKER: u8 = 0xC1;
+
+/// Frame byte following [`FRAME_MARKER`] for a zstd-compressed wire packet
+pub const ZSTD_FRAME_TAG: u8 = 0x80;
+
+/// zstd compression level of pushed packets
+const ZSTD_LEVEL: i32 = 3;
+
+/// Largest packet accepted after decompression, bounding decompression bombs
+pub const MAX_DECOMPRESSED_PACKET_SIZE: usize = 16 * 1024 * 1024;
+
+/// Longest accepted packet source tag in bytes
+pub const MAX_SOURCE_TAG_LEN: usize = 64;
+
+/// Prefix a wire packet with its big-endian `u32` length
+///
+/// Stream transports (files, FIFOs, serial lines) carry no message boundaries
+/// of their own, so each packet is written as one length-delimited frame.
+pub fn frame_packet(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_DECOMPRESSED_PACKET_SIZE)
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "packet too large to frame")
+        })?;
+    let mut framed = Vec::with_capacity(4 + bytes.len());
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(bytes);
+    Ok(framed)
+}
+
+/// Read one frame written by [`frame_packet`]
+///
+/// Returns `Ok(None)` at a clean end of stream; a stream that ends mid-frame
+/// or announces a frame larger than [`MAX_DECOMPRESSED_PACKET_SIZE`] is an error.
+pub fn read_framed_packet(reader: &mut impl std::io::Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            n => filled += n,
+        }
+    }
+
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_DECOMPRESSED_PACKET_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the packet size limit", len),
+        ));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+/// Packet serialization format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerializationFormat {
+    /// MessagePack (default)
+    #[default]
+    #[serde(alias = "messagepack")]
+    MessagePack,
+    /// CBOR (RFC 8949)
+    Cbor,
+}
+
+impl SerializationFormat {
+    /// Format byte following the frame marker
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::MessagePack => 0x00,
+            Self::Cbor => 0x01,
+        }
+    }
+
+    /// Parse a format byte
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x00 => Some(Self::MessagePack),
+            0x01 => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Get MIME type for this format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::MessagePack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
 }
 
 /// Health status for system monitoring
@@ -138,11 +412,48 @@ pub struct GatewayStatus {
     /// Total bytes served
     pub total_bytes_served: u64,
 
-    /// Current requests per second
+    /// Requests per second over the last minute
     pub requests_per_second: f64,
 
+    /// Average requests per second since startup
+    #[serde(default)]
+    pub lifetime_requests_per_second: f64,
+
+    /// Largest single `/api/random` request in bytes
+    #[serde(default)]
+    pub max_request_bytes: usize,
+
+    /// Source tags of the entropy currently buffered, sorted
+    #[serde(default)]
+    pub source_tags: Vec<String>,
+
     /// Any warnings or issues
     pub warnings: Vec<String>,
+
+    /// Buffer, latency and intake counters, present only with `detail=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<StatusDetail>,
+}
+
+/// Detailed section of [`GatewayStatus`] for dashboards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusDetail {
+    /// Lifetime buffer counters (pushes, pops, evictions)
+    pub buffer_stats: BufferStats,
+
+    /// Request latency percentiles in microseconds (`None` before the first request)
+    pub latency_p50_micros: Option<u64>,
+    pub latency_p95_micros: Option<u64>,
+    pub latency_p99_micros: Option<u64>,
+
+    /// Packets accepted on `/push` and the bytes they stored
+    pub pushes_total: u64,
+    pub bytes_pushed: u64,
+
+    /// Appliance fetches in direct mode
+    pub fetches_total: u64,
+    pub fetches_failed: u64,
+    pub bytes_fetched: u64,
 }
 
 /// Encoding format for served entropy
@@ -155,6 +466,10 @@ pub enum EncodingFormat {
     Hex,
     /// Base64 encoding
     Base64,
+    /// Base32 encoding (RFC 4648)
+    Base32,
+    /// Base85 encoding (Ascii85)
+    Base85,
 }
 
 impl EncodingFormat {
@@ -164,16 +479,117 @@ impl EncodingFormat {
             "binary" | "raw" => Some(Self::Binary),
             "hex" | "hexadecimal" => Some(Self::Hex),
             "base64" | "b64" => Some(Self::Base64),
+            "base32" | "b32" => Some(Self::Base32),
+            "base85" | "b85" | "ascii85" => Some(Self::Base85),
             _ => None,
         }
     }
 
+    /// Pick the encoding for an HTTP `Accept` header value
+    ///
+    /// Maps `application/octet-stream` to binary, `text/plain` to hex and
+    /// `application/base64` to base64, preferring the highest `q` value (the
+    /// first listed on ties). Returns `None` if no listed type is supported.
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let format = match parts.next().unwrap_or_default().trim().to_lowercase().as_str() {
+                "application/octet-stream" => Self::Binary,
+                "text/plain" => Self::Hex,
+                "application/base64" => Self::Base64,
+                _ => continue,
+            };
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((format, quality));
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+
+    /// Lowercase name as accepted by [`EncodingFormat::parse`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::Hex => "hex",
+            Self::Base64 => "base64",
+            Self::Base32 => "base32",
+            Self::Base85 => "base85",
+        }
+    }
+
     /// Get MIME type for this encoding
     pub fn mime_type(&self) -> &'static str {
         match self {
             Self::Binary => "application/octet-stream",
             Self::Hex => "text/plain; charset=utf-8",
             Self::Base64 => "text/plain; charset=utf-8",
+            Self::Base32 => "text/plain; charset=utf-8",
+            Self::Base85 => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// Target language for seed code snippets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedLanguage {
+    /// Python `random` module
+    Python,
+    /// Rust `rand` crate (`StdRng`)
+    Rust,
+    /// JavaScript `seedrandom` package
+    JavaScript,
+}
+
+impl SeedLanguage {
+    /// Parse from string (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "python" | "py" => Some(Self::Python),
+            "rust" | "rs" => Some(Self::Rust),
+            "js" | "javascript" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    /// Required seed length in bytes, if the language's RNG needs an exact size
+    pub fn required_seed_len(&self) -> Option<usize> {
+        match self {
+            // StdRng::from_seed takes a [u8; 32]
+            Self::Rust => Some(32),
+            Self::Python | Self::JavaScript => None,
+        }
+    }
+
+    /// Render a ready-to-paste snippet seeding this language's RNG with `seed`
+    pub fn snippet(&self, seed: &[u8]) -> String {
+        let hex = crate::crypto::encode_hex(seed);
+        match self {
+            Self::Python => format!("import random\n\nrandom.seed(0x{})\n", hex),
+            Self::Rust => {
+                let bytes = seed
+                    .iter()
+                    .map(|b| format!("0x{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "use rand::{{rngs::StdRng, SeedableRng}};\n\n\
+                     let seed: [u8; {}] = [{}];\n\
+                     let mut rng = StdRng::from_seed(seed);\n",
+                    seed.len(),
+                    bytes
+                )
+            }
+            Self::JavaScript => format!(
+                "// npm install seedrandom\n\
+                 import seedrandom from \"seedrandom\";\n\n\
+                 const rng = seedrandom(\"{}\");\n",
+                hex
+            ),
         }
     }
 }
@@ -191,11 +607,76 @@ mod tests {
         assert_eq!(packet.data, decoded.data);
     }
 
+    #[test]
+    fn test_legacy_packet_without_nonce() {
+        // Packets from collectors predating payload encryption lack the nonce field
+        #[derive(Serialize)]
+        struct LegacyPacket {
+            version: u8,
+            id: Uuid,
+            sequence: u64,
+            #[serde(with = "serde_bytes")]
+            data: Vec<u8>,
+            timestamp: DateTime<Utc>,
+            #[serde(with = "serde_bytes")]
+            signature: Vec<u8>,
+            checksum: Option<u32>,
+        }
+
+        let legacy = LegacyPacket {
+            version: 1,
+            id: Uuid::new_v4(),
+            sequence: 9,
+            data: vec![1, 2, 3],
+            timestamp: Utc::now(),
+            signature: vec![0; 32],
+            checksum: None,
+        };
+        let bytes = rmp_serde::to_vec(&legacy).unwrap();
+        let decoded = EntropyPacket::from_msgpack(&bytes).unwrap();
+        assert_eq!(decoded.sequence, 9);
+        assert!(!decoded.is_encrypted());
+    }
+
     #[test]
     fn test_checksum() {
         let mut packet = EntropyPacket::new(1, vec![0xDE, 0xAD, 0xBE, 0xEF]);
-        packet.checksum = Some(packet.calculate_checksum());
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
         assert!(packet.verify_checksum());
+        assert_eq!(packet.calculate_checksum(), packet.checksum.unwrap().to_be_bytes());
+    }
+
+    #[test]
+    fn test_checksums_detect_single_byte_flip() {
+        for algo in [ChecksumAlgorithm::Crc32, ChecksumAlgorithm::Blake3] {
+            let mut packet = EntropyPacket::new(1, (0..=255).collect());
+            packet.set_checksum(algo);
+            assert!(packet.verify_checksum(), "{:?}", algo);
+
+            for position in [0, 100, 255] {
+                let mut corrupted = packet.clone();
+                corrupted.data[position] ^= 0x01;
+                assert!(!corrupted.verify_checksum(), "{:?} missed flip at {}", algo, position);
+            }
+
+            // The algorithm survives serialization in both wire formats
+            for format in [SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+                let (decoded, _) = EntropyPacket::decode(&packet.encode(format).unwrap()).unwrap();
+                assert_eq!(decoded.checksum_algo, algo);
+                assert!(decoded.verify_checksum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_blake3_checksum_requires_digest() {
+        let mut packet = EntropyPacket::new(1, vec![1, 2, 3]);
+        packet.set_checksum(ChecksumAlgorithm::Blake3);
+        assert_eq!(packet.digest.as_ref().unwrap().len(), 32);
+        assert!(packet.checksum.is_none());
+
+        packet.digest = None;
+        assert!(!packet.verify_checksum());
     }
 
     #[test]
@@ -203,6 +684,164 @@ mod tests {
         assert_eq!(EncodingFormat::parse("hex"), Some(EncodingFormat::Hex));
         assert_eq!(EncodingFormat::parse("HEX"), Some(EncodingFormat::Hex));
         assert_eq!(EncodingFormat::parse("base64"), Some(EncodingFormat::Base64));
+        assert_eq!(EncodingFormat::parse("B32"), Some(EncodingFormat::Base32));
+        assert_eq!(EncodingFormat::parse("ascii85"), Some(EncodingFormat::Base85));
+        assert_eq!(EncodingFormat::Base85.name(), "base85");
         assert_eq!(EncodingFormat::parse("invalid"), None);
     }
+
+    #[test]
+    fn test_encoding_from_accept() {
+        assert_eq!(EncodingFormat::from_accept("application/octet-stream"), Some(EncodingFormat::Binary));
+        assert_eq!(EncodingFormat::from_accept("text/plain; charset=utf-8"), Some(EncodingFormat::Hex));
+        assert_eq!(EncodingFormat::from_accept("Application/Base64"), Some(EncodingFormat::Base64));
+
+        // Unsupported types are skipped; the highest quality wins, then list order
+        assert_eq!(
+            EncodingFormat::from_accept("application/json, application/base64"),
+            Some(EncodingFormat::Base64)
+        );
+        assert_eq!(
+            EncodingFormat::from_accept("text/plain;q=0.5, application/octet-stream;q=0.9"),
+            Some(EncodingFormat::Binary)
+        );
+        assert_eq!(
+            EncodingFormat::from_accept("application/base64, text/plain"),
+            Some(EncodingFormat::Base64)
+        );
+        assert_eq!(EncodingFormat::from_accept("application/octet-stream;q=0"), None);
+        assert_eq!(EncodingFormat::from_accept("*/*"), None);
+        assert_eq!(EncodingFormat::from_accept(""), None);
+    }
+
+    #[test]
+    fn test_seed_language_parse() {
+        assert_eq!(SeedLanguage::parse("python"), Some(SeedLanguage::Python));
+        assert_eq!(SeedLanguage::parse("Rust"), Some(SeedLanguage::Rust));
+        assert_eq!(SeedLanguage::parse("js"), Some(SeedLanguage::JavaScript));
+        assert_eq!(SeedLanguage::parse("cobol"), None);
+    }
+
+    #[test]
+    fn test_seed_snippets() {
+        let seed = [0x00, 0x1f, 0xa0, 0xff];
+
+        let python = SeedLanguage::Python.snippet(&seed);
+        assert!(python.contains("random.seed(0x001fa0ff)"));
+
+        let js = SeedLanguage::JavaScript.snippet(&seed);
+        assert!(js.contains("seedrandom(\"001fa0ff\")"));
+
+        let rust_seed: Vec<u8> = (0..32).collect();
+        let rust = SeedLanguage::Rust.snippet(&rust_seed);
+        assert!(rust.contains("let seed: [u8; 32] = [0x00, 0x01, 0x02,"));
+        assert!(rust.contains("0x1e, 0x1f];"));
+        assert!(rust.contains("StdRng::from_seed(seed)"));
+    }
+
+    #[test]
+    fn test_wire_format_roundtrip() {
+        let mut packet = EntropyPacket::new(7, vec![9, 8, 7, 6]);
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+
+        for format in [SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+            let bytes = packet.encode(format).unwrap();
+            let (decoded, detected) = EntropyPacket::decode(&bytes).unwrap();
+            assert_eq!(detected, format);
+            assert_eq!(decoded.id, packet.id);
+            assert_eq!(decoded.data, packet.data);
+            assert_eq!(decoded.timestamp, packet.timestamp);
+            assert_eq!(decoded.checksum, packet.checksum);
+        }
+
+        let cbor = packet.to_cbor().unwrap();
+        assert_eq!(EntropyPacket::from_cbor(&cbor).unwrap().sequence, 7);
+
+        // Unframed bytes are legacy MessagePack
+        let legacy = packet.to_msgpack().unwrap();
+        let (decoded, detected) = EntropyPacket::decode(&legacy).unwrap();
+        assert_eq!(detected, SerializationFormat::MessagePack);
+        assert_eq!(decoded.sequence, 7);
+
+        // An explicitly framed MessagePack packet is accepted too
+        let mut framed = vec![FRAME_MARKER, SerializationFormat::MessagePack.tag()];
+        framed.extend(&legacy);
+        assert_eq!(EntropyPacket::decode(&framed).unwrap().1, SerializationFormat::MessagePack);
+    }
+
+    #[test]
+    fn test_wire_format_cross_rejection() {
+        let packet = EntropyPacket::new(1, vec![1, 2, 3]);
+
+        // CBOR bodies are not valid MessagePack and vice versa
+        assert!(EntropyPacket::from_msgpack(&packet.to_cbor().unwrap()).is_err());
+        assert!(EntropyPacket::from_cbor(&packet.to_msgpack().unwrap()).is_err());
+
+        // A frame header that mislabels the payload is rejected
+        let mut mislabeled = vec![FRAME_MARKER, SerializationFormat::Cbor.tag()];
+        mislabeled.extend(packet.to_msgpack().unwrap());
+        assert!(EntropyPacket::decode(&mislabeled).is_err());
+
+        let unknown = [FRAME_MARKER, 0x7F, 0x00];
+        assert!(EntropyPacket::decode(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        let mut packet = EntropyPacket::new(3, vec![0x42; 4096]);
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+
+        for format in [SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+            let plain = packet.encode(format).unwrap();
+            let bytes = packet.encode_compressed(format).unwrap();
+            assert_eq!(&bytes[..2], &[FRAME_MARKER, ZSTD_FRAME_TAG]);
+            assert!(bytes.len() < plain.len() / 10);
+
+            let (decoded, detected) = EntropyPacket::decode(&bytes).unwrap();
+            assert_eq!(detected, format);
+            assert_eq!(decoded.data, packet.data);
+            assert_eq!(decoded.checksum, packet.checksum);
+            assert!(decoded.verify_checksum());
+        }
+
+        // Damaged compressed frames are rejected
+        let mut damaged = packet.encode_compressed(SerializationFormat::MessagePack).unwrap();
+        damaged.truncate(damaged.len() - 4);
+        assert!(EntropyPacket::decode(&damaged).is_err());
+    }
+
+    #[test]
+    fn test_incompressible_packet_stored_uncompressed() {
+        use rand::RngCore;
+        let mut data = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut data);
+        let packet = EntropyPacket::new(4, data);
+
+        for format in [SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+            let bytes = packet.encode_compressed(format).unwrap();
+            assert_eq!(bytes, packet.encode(format).unwrap());
+            assert_eq!(EntropyPacket::decode(&bytes).unwrap().0.data, packet.data);
+        }
+    }
+
+    #[test]
+    fn test_packet_framing() {
+        let first = EntropyPacket::new(1, vec![1, 2, 3]).to_msgpack().unwrap();
+        let second = EntropyPacket::new(2, vec![4; 100]).to_msgpack().unwrap();
+        let mut stream = frame_packet(&first).unwrap();
+        stream.extend(frame_packet(&second).unwrap());
+
+        let mut reader = stream.as_slice();
+        assert_eq!(read_framed_packet(&mut reader).unwrap().unwrap(), first);
+        assert_eq!(read_framed_packet(&mut reader).unwrap().unwrap(), second);
+        assert!(read_framed_packet(&mut reader).unwrap().is_none());
+
+        // Truncated frames and oversized length prefixes are rejected
+        let mut truncated = &stream[..stream.len() - 1];
+        read_framed_packet(&mut truncated).unwrap();
+        assert!(read_framed_packet(&mut truncated).is_err());
+        assert!(read_framed_packet(&mut &stream[..2]).is_err());
+        let oversized = u32::MAX.to_be_bytes();
+        assert!(read_framed_packet(&mut oversized.as_slice()).is_err());
+    }
 }
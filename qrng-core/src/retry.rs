@@ -12,7 +12,7 @@
 //! Retry logic with exponential backoff and jitter
 
 use crate::Result;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, warn};
 
@@ -29,6 +29,8 @@ pub struct RetryPolicy {
     pub multiplier: f64,
     /// Add jitter to prevent thundering herd
     pub jitter: bool,
+    /// Overall time budget across all attempts and backoffs (unbounded if `None`)
+    pub max_total_duration: Option<Duration>,
 }
 
 impl Default for RetryPolicy {
@@ -39,17 +41,22 @@ impl Default for RetryPolicy {
             max_backoff: Duration::from_secs(30),
             multiplier: 2.0,
             jitter: true,
+            max_total_duration: None,
         }
     }
 }
 
 impl RetryPolicy {
     /// Execute operation with retry logic
+    ///
+    /// With a `max_total_duration`, no retry is started that would begin after
+    /// the budget runs out; the last error is returned instead.
     pub async fn execute<F, Fut, T>(&self, mut operation: F) -> Result<T>
     where
         F: FnMut() -> Fut,
         Fut: std::future::Future<Output = Result<T>>,
     {
+        let start = Instant::now();
         let mut attempt = 0;
         let mut backoff = self.initial_backoff;
 
@@ -63,7 +70,7 @@ impl RetryPolicy {
                     }
                     return Ok(result);
                 }
-                Err(e) if e.is_retryable() && attempt < self.max_attempts => {
+                Err(e) if e.is_retryable() && attempt < self.max_attempts && !self.budget_exhausted(start, backoff) => {
                     warn!(
                         "Operation failed (attempt {}/{}): {}. Retrying after {:?}",
                         attempt, self.max_attempts, e, backoff
@@ -84,6 +91,13 @@ impl RetryPolicy {
                 Err(e) => {
                     if attempt >= self.max_attempts {
                         warn!("Operation failed after {} attempts: {}", attempt, e);
+                    } else if e.is_retryable() {
+                        warn!(
+                            "Operation failed after {} attempts in {:?}, retry budget exhausted: {}",
+                            attempt,
+                            start.elapsed(),
+                            e
+                        );
                     }
                     return Err(e);
                 }
@@ -91,6 +105,12 @@ impl RetryPolicy {
         }
     }
 
+    /// Whether waiting `backoff` before the next attempt would overrun the time budget
+    fn budget_exhausted(&self, start: Instant, backoff: Duration) -> bool {
+        self.max_total_duration
+            .is_some_and(|budget| start.elapsed() + backoff >= budget)
+    }
+
     fn add_jitter(&self, duration: Duration) -> Duration {
         use rand::Rng;
         let jitter_ms = rand::rng().random_range(0..=duration.as_millis() / 4);
@@ -98,6 +118,18 @@ impl RetryPolicy {
     }
 }
 
+/// One step of exponential backoff with full jitter
+///
+/// Returns `(next, wait)`: the backoff to use after the next failure (`current`
+/// doubled, capped at `cap`) and the time to wait now, drawn uniformly from
+/// `[0, current]` so that clients failing together do not retry in lockstep.
+pub fn next_backoff(current: Duration, cap: Duration) -> (Duration, Duration) {
+    use rand::Rng;
+    let next = current.saturating_mul(2).min(cap);
+    let wait = Duration::from_micros(rand::rng().random_range(0..=current.as_micros() as u64));
+    (next, wait)
+}
+
 /// Circuit breaker for preventing cascading failures
 pub struct CircuitBreaker {
     failure_threshold: u32,
@@ -198,6 +230,56 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_retry_budget_gives_up_early() {
+        let policy = RetryPolicy {
+            max_attempts: 100,
+            initial_backoff: Duration::from_millis(20),
+            max_total_duration: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let counter = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+
+        let start = Instant::now();
+        let counter_clone = counter.clone();
+        let result = policy
+            .execute(|| {
+                let counter = counter_clone.clone();
+                async move {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Err::<(), _>(Error::Timeout)
+                }
+            })
+            .await;
+
+        // 20ms, then at least 40ms and 80ms backoffs: the third retry would overrun the budget
+        assert!(matches!(result, Err(Error::Timeout)));
+        let attempts = counter.load(std::sync::atomic::Ordering::SeqCst);
+        assert!((2..=3).contains(&attempts), "{} attempts", attempts);
+        // Allow for timer overshoot on a loaded machine
+        assert!(start.elapsed() < Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_next_backoff_full_jitter() {
+        let current = Duration::from_secs(4);
+        let cap = Duration::from_secs(5);
+
+        let waits: Vec<Duration> = (0..100)
+            .map(|_| {
+                let (next, wait) = next_backoff(current, cap);
+                assert_eq!(next, cap);
+                assert!(wait <= current, "{:?} exceeds {:?}", wait, current);
+                wait
+            })
+            .collect();
+        // 100 draws from 4 million microseconds are essentially never all equal
+        assert!(waits.iter().any(|&wait| wait != waits[0]));
+
+        assert_eq!(next_backoff(Duration::from_secs(1), cap).0, Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::ZERO, cap), (Duration::ZERO, Duration::ZERO));
+    }
+
     #[test]
     fn test_circuit_breaker() {
         let breaker = CircuitBreaker::new(3, Duration::from_secs(1));
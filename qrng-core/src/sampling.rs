@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MIT
+//
+// QRNG Data Diode: High-Performance Quantum Entropy Bridge
+// Copyright (c) 2025 Valer Bocan, PhD, CSSLP
+// Email: valer.bocan@upt.ro
+//
+// Department of Computer and Information Technology
+// Politehnica University of Timisoara
+//
+// https://github.com/vbocan/qrng-data-diode
+
+//! Unbiased bounded integer sampling
+//!
+//! Maps uniform random words onto `[0, range)` with Lemire's multiply-shift
+//! reduction ("Fast Random Integer Generation in an Interval", 2019). Each
+//! value costs one widening multiplication; a division is only needed when the
+//! low half of the product falls below `range`, and a redraw only when it falls
+//! in the biased zone beneath `2^bits mod range`.
+
+/// Uniform value in `[0, range)` from a uniform 64-bit word
+///
+/// Returns `None` when the word must be rejected and redrawn, which happens
+/// with probability `(2^64 mod range) / 2^64` (never for powers of two).
+/// `range` must be non-zero.
+pub fn lemire_bounded(random: u64, range: u64) -> Option<u64> {
+    lemire_bounded_bits(random, range, u64::BITS)
+}
+
+/// [`lemire_bounded`] for a uniform value of `bits` bits (1 to 64)
+///
+/// Lets narrow draws (one byte for a die roll) skip widening to a full word.
+/// `random` must be below `2^bits` and `range` in `1..=2^bits`.
+pub fn lemire_bounded_bits(random: u64, range: u64, bits: u32) -> Option<u64> {
+    debug_assert!((1..=u64::BITS).contains(&bits));
+    debug_assert!(range > 0 && (bits == u64::BITS || range <= 1 << bits));
+    debug_assert!(bits == u64::BITS || random >> bits == 0);
+
+    let product = random as u128 * range as u128;
+    let low = product as u64 & (u64::MAX >> (u64::BITS - bits));
+    if low < range {
+        // 2^bits mod range, computed without overflowing for bits == 64
+        let threshold = if bits == u64::BITS {
+            range.wrapping_neg() % range
+        } else {
+            (1u64 << bits) % range
+        };
+        if low < threshold {
+            return None;
+        }
+    }
+    Some((product >> bits) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `bits`-bit input, mapped onto `range`, hits each value equally often
+    fn exhaustive_counts(range: u64, bits: u32) -> (Vec<u64>, u64) {
+        let mut counts = vec![0u64; range as usize];
+        let mut rejected = 0;
+        for random in 0..1u64 << bits {
+            match lemire_bounded_bits(random, range, bits) {
+                Some(value) => counts[value as usize] += 1,
+                None => rejected += 1,
+            }
+        }
+        (counts, rejected)
+    }
+
+    #[test]
+    fn test_exact_uniformity() {
+        // Powers of two, primes and awkward composites over 8- and 16-bit draws
+        for (range, bits) in [(1, 8), (2, 8), (6, 8), (7, 8), (64, 8), (100, 8), (251, 8), (256, 8), (1000, 16), (4096, 16), (65521, 16)] {
+            let (counts, rejected) = exhaustive_counts(range, bits);
+            let expected = (1u64 << bits) / range;
+            assert!(counts.iter().all(|&count| count == expected), "range {} is biased", range);
+            assert_eq!(rejected, (1u64 << bits) % range, "range {}", range);
+        }
+    }
+
+    #[test]
+    fn test_full_width_words() {
+        assert_eq!(lemire_bounded(u64::MAX, u64::MAX), Some(u64::MAX - 1));
+        assert_eq!(lemire_bounded(0, 1), Some(0));
+        assert_eq!(lemire_bounded(u64::MAX, 1), Some(0));
+        // The high bits select the value for powers of two, which never reject
+        assert_eq!(lemire_bounded(0xC000_0000_0000_0000, 4), Some(3));
+        assert_eq!(lemire_bounded(0x3FFF_FFFF_FFFF_FFFF, 4), Some(0));
+        // 2^64 mod 3 == 1: only the input whose product's low half is 0 is rejected
+        assert_eq!(lemire_bounded(0, 3), None);
+        assert_eq!(lemire_bounded(1, 3), Some(0));
+    }
+
+    #[test]
+    fn test_sampled_uniformity() {
+        use rand::{RngCore, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1855);
+        for range in [2u64, 3, 10, 13, 1 << 20, 1_000_003, (1 << 61) - 1] {
+            let buckets = range.min(16);
+            let samples = 160_000;
+            let mut counts = vec![0u64; buckets as usize];
+            let mut drawn = 0;
+            while drawn < samples {
+                if let Some(value) = lemire_bounded(rng.next_u64(), range) {
+                    assert!(value < range);
+                    counts[(value as u128 * buckets as u128 / range as u128) as usize] += 1;
+                    drawn += 1;
+                }
+            }
+
+            // Chi-squared against equal buckets; 50 is far beyond p = 0.001 for 15 degrees of freedom
+            let expected = samples as f64 / buckets as f64;
+            let chi_squared: f64 = counts
+                .iter()
+                .map(|&count| (count as f64 - expected).powi(2) / expected)
+                .sum();
+            assert!(chi_squared < 50.0, "range {}: chi-squared {}", range, chi_squared);
+        }
+    }
+
+    #[test]
+    #[ignore = "timing comparison; run with --ignored --nocapture in release mode"]
+    fn bench_lemire_against_modulo_rejection() {
+        use rand::{RngCore, SeedableRng};
+        use std::time::Instant;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let words: Vec<u64> = (0..4_000_000).map(|_| rng.next_u64()).collect();
+        let range = 1_000_000_007u64;
+
+        let start = Instant::now();
+        let lemire: u64 = words.iter().filter_map(|&word| lemire_bounded(word, range)).sum();
+        let lemire_time = start.elapsed();
+
+        let limit = u64::MAX - (u64::MAX % range + 1) % range;
+        let start = Instant::now();
+        let modulo: u64 = words.iter().filter(|&&word| word <= limit).map(|&word| word % range).sum();
+        let modulo_time = start.elapsed();
+
+        println!(
+            "{} words: lemire {:?}, modulo rejection {:?} (checksums {} {})",
+            words.len(),
+            lemire_time,
+            modulo_time,
+            lemire,
+            modulo
+        );
+    }
+}
@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: MIT
+//
+// QRNG Data Diode: High-Performance Quantum Entropy Bridge
+// Copyright (c) 2025 Valer Bocan, PhD, CSSLP
+// Email: valer.bocan@upt.ro
+//
+// Department of Computer and Information Technology
+// Politehnica University of Timisoara
+//
+// https://github.com/vbocan/qrng-data-diode
+
+//! On-disk spool for entropy that could not be delivered
+//!
+//! During long gateway outages the in-memory queues overflow. The spool keeps
+//! undeliverable payloads as timestamped files in a directory so they survive
+//! the outage (and restarts) and can be replayed oldest first. Total size is
+//! capped; the oldest files are evicted when the cap is exceeded.
+
+use crate::buffer::write_private_file;
+use crate::Result;
+use bytes::Bytes;
+use chrono::Utc;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+/// Magic header of spool files (format version 1)
+const SPOOL_MAGIC: &[u8; 8] = b"QRNGSPL1";
+
+/// Extension of spool files
+const SPOOL_EXTENSION: &str = "spool";
+
+/// Spooled file with its payload size
+#[derive(Debug)]
+struct SpoolEntry {
+    path: PathBuf,
+    size: u64,
+}
+
+#[derive(Debug, Default)]
+struct SpoolInner {
+    entries: VecDeque<SpoolEntry>,
+    total_bytes: u64,
+}
+
+/// Size-capped directory of undeliverable payloads, replayed oldest first
+///
+/// File names start with a zero-padded timestamp, so lexical order is
+/// chronological and the queue is rebuilt from the directory on open.
+#[derive(Debug)]
+pub struct SpoolStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    sequence: AtomicU64,
+    inner: Mutex<SpoolInner>,
+}
+
+impl SpoolStore {
+    /// Open (creating if needed) a spool directory holding at most `max_bytes` of payload
+    ///
+    /// Files left by a previous run are picked up in timestamp order.
+    pub fn open(dir: impl AsRef<Path>, max_bytes: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SPOOL_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut inner = SpoolInner::default();
+        for path in paths {
+            let size = std::fs::metadata(&path)?
+                .len()
+                .saturating_sub(SPOOL_MAGIC.len() as u64 + 4);
+            inner.total_bytes += size;
+            inner.entries.push_back(SpoolEntry { path, size });
+        }
+
+        let store = Self {
+            dir,
+            max_bytes,
+            sequence: AtomicU64::new(0),
+            inner: Mutex::new(inner),
+        };
+        store.evict_oldest(&mut store.inner.lock());
+        Ok(store)
+    }
+
+    /// Write a payload to the spool, returning the number of bytes evicted to stay under the cap
+    ///
+    /// A payload larger than the cap is evicted immediately.
+    pub fn enqueue(&self, data: &[u8]) -> Result<u64> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let name = format!(
+            "{:020}-{:010}.{}",
+            Utc::now().timestamp_micros(),
+            sequence,
+            SPOOL_EXTENSION
+        );
+        let path = self.dir.join(name);
+
+        let mut contents = Vec::with_capacity(SPOOL_MAGIC.len() + 4 + data.len());
+        contents.extend_from_slice(SPOOL_MAGIC);
+        contents.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+        contents.extend_from_slice(data);
+
+        // Write under a temporary name so a crash never leaves a partial spool file
+        let tmp_path = path.with_extension("tmp");
+        write_private_file(&tmp_path, &contents)?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        let mut inner = self.inner.lock();
+        inner.total_bytes += data.len() as u64;
+        inner.entries.push_back(SpoolEntry {
+            path,
+            size: data.len() as u64,
+        });
+        Ok(self.evict_oldest(&mut inner))
+    }
+
+    /// Remove and return the oldest payloads, up to `max_bytes` in total
+    ///
+    /// At least one payload is returned when the spool is not empty, even if it
+    /// exceeds `max_bytes`. Unreadable or corrupt files are logged and skipped.
+    pub fn drain(&self, max_bytes: usize) -> Vec<Bytes> {
+        let mut inner = self.inner.lock();
+        let mut drained = Vec::new();
+        let mut drained_bytes = 0usize;
+
+        while let Some(entry) = inner.entries.front() {
+            if !drained.is_empty() && drained_bytes + entry.size as usize > max_bytes {
+                break;
+            }
+
+            let entry = inner.entries.pop_front().unwrap();
+            inner.total_bytes -= entry.size;
+
+            let contents = match std::fs::read(&entry.path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    warn!("Failed to read spool file {}: {}", entry.path.display(), e);
+                    continue;
+                }
+            };
+            if let Err(e) = std::fs::remove_file(&entry.path) {
+                warn!("Failed to remove spool file {}: {}", entry.path.display(), e);
+            }
+
+            match decode_spool_file(&contents) {
+                Some(data) => {
+                    drained_bytes += data.len();
+                    drained.push(Bytes::copy_from_slice(data));
+                }
+                None => warn!("Discarding corrupt spool file {}", entry.path.display()),
+            }
+        }
+
+        drained
+    }
+
+    /// Number of spooled payloads
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    /// Check if the spool is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().entries.is_empty()
+    }
+
+    /// Total spooled payload bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.inner.lock().total_bytes
+    }
+
+    /// Delete the oldest files until the spool fits its cap, returning the evicted bytes
+    fn evict_oldest(&self, inner: &mut SpoolInner) -> u64 {
+        let mut evicted = 0;
+        while inner.total_bytes > self.max_bytes {
+            let Some(entry) = inner.entries.pop_front() else {
+                break;
+            };
+            inner.total_bytes -= entry.size;
+            evicted += entry.size;
+            if let Err(e) = std::fs::remove_file(&entry.path) {
+                warn!("Failed to remove spool file {}: {}", entry.path.display(), e);
+            }
+        }
+        evicted
+    }
+}
+
+/// Extract the payload of a spool file, or `None` if it is damaged
+fn decode_spool_file(contents: &[u8]) -> Option<&[u8]> {
+    let rest = contents.strip_prefix(SPOOL_MAGIC.as_slice())?;
+    let (crc, data) = rest.split_first_chunk::<4>()?;
+    (crc32fast::hash(data) == u32::from_le_bytes(*crc)).then_some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qrng-spool-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_enqueue_drain_order() {
+        let dir = temp_dir("order");
+        let spool = SpoolStore::open(&dir, 1024).unwrap();
+
+        for byte in 1..=3u8 {
+            spool.enqueue(&[byte; 10]).unwrap();
+        }
+        assert_eq!(spool.len(), 3);
+        assert_eq!(spool.size_bytes(), 30);
+
+        let drained = spool.drain(20);
+        assert_eq!(drained, vec![Bytes::from(vec![1u8; 10]), Bytes::from(vec![2u8; 10])]);
+
+        // Oversized payloads still drain one at a time
+        let drained = spool.drain(1);
+        assert_eq!(drained, vec![Bytes::from(vec![3u8; 10])]);
+        assert!(spool.is_empty());
+        assert!(spool.drain(1024).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_size_cap_evicts_oldest() {
+        let dir = temp_dir("cap");
+        let spool = SpoolStore::open(&dir, 25).unwrap();
+
+        assert_eq!(spool.enqueue(&[1u8; 10]).unwrap(), 0);
+        assert_eq!(spool.enqueue(&[2u8; 10]).unwrap(), 0);
+        assert_eq!(spool.enqueue(&[3u8; 10]).unwrap(), 10);
+        assert_eq!(spool.len(), 2);
+        assert_eq!(spool.size_bytes(), 20);
+
+        let drained = spool.drain(1024);
+        assert_eq!(drained, vec![Bytes::from(vec![2u8; 10]), Bytes::from(vec![3u8; 10])]);
+
+        // A payload over the cap is not kept
+        assert_eq!(spool.enqueue(&[4u8; 30]).unwrap(), 30);
+        assert!(spool.is_empty());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reopen_replays_in_order() {
+        let dir = temp_dir("reopen");
+        {
+            let spool = SpoolStore::open(&dir, 1024).unwrap();
+            spool.enqueue(b"first").unwrap();
+            spool.enqueue(b"second").unwrap();
+        }
+
+        let spool = SpoolStore::open(&dir, 1024).unwrap();
+        assert_eq!(spool.len(), 2);
+        assert_eq!(spool.size_bytes(), 11);
+        spool.enqueue(b"third").unwrap();
+
+        let drained = spool.drain(1024);
+        assert_eq!(drained, vec![
+            Bytes::from_static(b"first"),
+            Bytes::from_static(b"second"),
+            Bytes::from_static(b"third"),
+        ]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_file_skipped() {
+        let dir = temp_dir("corrupt");
+        let spool = SpoolStore::open(&dir, 1024).unwrap();
+        spool.enqueue(b"damaged").unwrap();
+        spool.enqueue(b"intact").unwrap();
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        let mut contents = std::fs::read(&files[0]).unwrap();
+        *contents.last_mut().unwrap() ^= 0xFF;
+        std::fs::write(&files[0], contents).unwrap();
+
+        assert_eq!(spool.drain(1024), vec![Bytes::from_static(b"intact")]);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -20,10 +20,12 @@
 //! - API key authentication
 //! - Rate limiting per client
 //! - Prometheus metrics
+//! - Optional OTLP trace and metric export
 //! - Health monitoring
 
 use anyhow::{Context, Result};
 use axum::{
+    extract::ws::{close_code as ws_close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
     extract::{ConnectInfo, Query, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
@@ -32,19 +34,40 @@ use axum::{
 };
 use clap::Parser;
 use qrng_core::{
-    buffer::EntropyBuffer,
+    bits::EntropyBitReader,
+    buffer::{BufferStats, EntropyBuffer, WatermarkLevel},
     config::GatewayConfig,
-    crypto::{encode_base64, encode_hex, PacketSigner},
+    crypto::{decode_hex, encode_base32, encode_base64, encode_base85, encode_hex, secrets_equal, PacketSigner},
+    config::MixingStrategy,
+    fetcher::{serial_correlation, source_for_appliance, EntropySource},
     metrics::Metrics,
-    protocol::{EncodingFormat, EntropyPacket, GatewayStatus, HealthStatus},
+    mixer::{EntropyMixer, MAX_DERIVE_LEN},
+    protocol::{EncodingFormat, EntropyPacket, GatewayStatus, HealthStatus, SeedLanguage, StatusDetail},
+    sampling::lemire_bounded_bits,
 };
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
+use tower_http::compression::{
+    predicate::{Predicate, SizeAbove},
+    CompressionLayer,
+};
+use opentelemetry::{metrics::MeterProvider as _, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    metrics::{PeriodicReader, SdkMeterProvider},
+    trace::{SdkTracer, SdkTracerProvider},
+    Resource,
+};
 use tower_http::cors::CorsLayer;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use zeroize::Zeroizing;
 
 #[derive(Parser, Debug)]
 #[command(name = "qrng-gateway")]
@@ -53,6 +76,10 @@ struct Args {
     /// Log level (trace, debug, info, warn, error)
     #[arg(short, long, default_value = "info")]
     log_level: String,
+
+    /// TOML configuration file; QRNG_* environment variables override its values
+    #[arg(short, long)]
+    config: Option<std::path::PathBuf>,
 }
 
 /// Application state shared across handlers
@@ -62,8 +89,91 @@ struct AppState {
     buffer: EntropyBuffer,
     metrics: Metrics,
     signer: Option<PacketSigner>,
+    encryption_key: Option<Zeroizing<Vec<u8>>>,
     start_time: Instant,
     rate_limiter: Arc<RateLimiter>,
+    replay_guard: Arc<ReplayGuard>,
+    in_flight: Arc<InFlightRequests>,
+    audit: Option<Arc<AuditLedger>>,
+    reservations: Arc<ReservationStore>,
+    fallback: Option<Arc<FallbackRng>>,
+}
+
+/// Counts requests whose response has not been fully sent
+///
+/// Shutdown waits on this, bounded by the grace period, so long streams are
+/// not cut off mid-response.
+#[derive(Default)]
+struct InFlightRequests {
+    count: AtomicUsize,
+    idle: tokio::sync::Notify,
+}
+
+impl InFlightRequests {
+    /// Register a request; it stays in flight until the guard is dropped
+    fn start(self: &Arc<Self>) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+
+    fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no request is in flight or `grace` elapses; returns the number still pending
+    async fn wait_idle(&self, grace: std::time::Duration) -> usize {
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let pending = self.count();
+            if pending == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.count();
+            }
+        }
+    }
+}
+
+/// Marks a request as finished when dropped
+struct InFlightGuard(Arc<InFlightRequests>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Response body that keeps its request in flight until the body is sent or dropped
+struct TrackedBody {
+    inner: axum::body::Body,
+    _guard: InFlightGuard,
+}
+
+impl axum::body::HttpBody for TrackedBody {
+    type Data = axum::body::Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<std::result::Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.get_mut().inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        self.inner.size_hint()
+    }
 }
 
 /// Application error type
@@ -82,9 +192,13 @@ impl From<StatusCode> for AppError {
 }
 
 /// Simple token-bucket rate limiter
+///
+/// Buckets refill at `rate` tokens per second and hold up to `burst` tokens,
+/// so an idle key can briefly exceed the sustained rate.
 struct RateLimiter {
     buckets: parking_lot::RwLock<std::collections::HashMap<String, TokenBucket>>,
     rate: u32,
+    burst: u32,
 }
 
 struct TokenBucket {
@@ -93,24 +207,24 @@ struct TokenBucket {
 }
 
 impl RateLimiter {
-    fn new(rate: u32) -> Self {
+    fn new(rate: u32, burst: u32) -> Self {
         Self {
             buckets: parking_lot::RwLock::new(std::collections::HashMap::new()),
             rate,
+            burst: burst.max(rate),
         }
     }
 
     fn check(&self, key: &str) -> bool {
         let mut buckets = self.buckets.write();
         let bucket = buckets.entry(key.to_string()).or_insert_with(|| TokenBucket {
-            tokens: self.rate as f64,
+            tokens: self.burst as f64,
             last_refill: Instant::now(),
         });
 
         // Refill tokens based on elapsed time
         let now = Instant::now();
-        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
-        bucket.tokens = (bucket.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        bucket.tokens = self.refilled(bucket, now);
         bucket.last_refill = now;
 
         // Try to consume a token
@@ -121,6 +235,131 @@ impl RateLimiter {
             false
         }
     }
+
+    /// Current token count for `key` without consuming one
+    ///
+    /// Keys that have not made a request yet have a full bucket.
+    fn peek(&self, key: &str) -> f64 {
+        self.buckets
+            .read()
+            .get(key)
+            .map_or(self.burst as f64, |bucket| self.refilled(bucket, Instant::now()))
+    }
+
+    /// Token count of `bucket` after refilling up to `now`, capped at the burst capacity
+    fn refilled(&self, bucket: &TokenBucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        (bucket.tokens + elapsed * self.rate as f64).min(self.burst as f64)
+    }
+}
+
+/// Outcome of checking a pushed packet's sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SequenceCheck {
+    /// Next expected sequence (or first packet seen)
+    InOrder,
+    /// Newer than expected; `missing` packets were skipped
+    Gap { missing: u64 },
+    /// Older than the highest accepted but not seen before
+    OutOfOrder,
+    /// Lower sequence with a newer timestamp: the collector restarted
+    Restarted,
+    /// Already accepted, or too old to be tracked
+    Replay,
+}
+
+/// Sequence tracker for detecting replayed and dropped push packets
+///
+/// Tracks the highest accepted sequence number and the set of sequences
+/// accepted within a sliding window below it. Packets are authenticated
+/// before they reach the guard, so the packet timestamp can be trusted to
+/// tell a collector restart (sequence reset) apart from a replay.
+struct ReplayGuard {
+    window: parking_lot::Mutex<ReplayWindow>,
+    window_size: u64,
+}
+
+#[derive(Default)]
+struct ReplayWindow {
+    highest: Option<u64>,
+    highest_timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    seen: std::collections::BTreeSet<u64>,
+    /// Packets created before the last collector restart are never accepted
+    epoch_start: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ReplayGuard {
+    fn new(window_size: usize) -> Self {
+        Self {
+            window: parking_lot::Mutex::new(ReplayWindow::default()),
+            window_size: window_size.max(1) as u64,
+        }
+    }
+
+    /// Check a packet's sequence number and record it if accepted
+    fn check(&self, sequence: u64, timestamp: chrono::DateTime<chrono::Utc>) -> SequenceCheck {
+        let mut window = self.window.lock();
+
+        if window.epoch_start.is_some_and(|start| timestamp < start) {
+            return SequenceCheck::Replay;
+        }
+
+        let (highest, highest_timestamp) = match (window.highest, window.highest_timestamp) {
+            (Some(h), Some(ts)) => (h, ts),
+            _ => {
+                window.advance(sequence, timestamp, self.window_size);
+                return SequenceCheck::InOrder;
+            }
+        };
+
+        if sequence > highest {
+            window.advance(sequence, timestamp, self.window_size);
+            return match sequence - highest - 1 {
+                0 => SequenceCheck::InOrder,
+                missing => SequenceCheck::Gap { missing },
+            };
+        }
+
+        // A lower sequence created after the highest accepted packet can only
+        // come from a collector that restarted its sequence counter
+        if timestamp > highest_timestamp {
+            *window = ReplayWindow {
+                epoch_start: Some(timestamp),
+                ..Default::default()
+            };
+            window.advance(sequence, timestamp, self.window_size);
+            return SequenceCheck::Restarted;
+        }
+
+        if highest - sequence >= self.window_size || !window.seen.insert(sequence) {
+            return SequenceCheck::Replay;
+        }
+
+        SequenceCheck::OutOfOrder
+    }
+}
+
+impl ReplayWindow {
+    fn advance(&mut self, sequence: u64, timestamp: chrono::DateTime<chrono::Utc>, window_size: u64) {
+        self.highest = Some(sequence);
+        self.highest_timestamp = Some(timestamp);
+        self.seen.insert(sequence);
+
+        // Forget sequences that fell out of the window
+        let floor = sequence.saturating_sub(window_size - 1);
+        self.seen = self.seen.split_off(&floor);
+    }
+}
+
+/// Whether `key` is one of the configured client API keys
+///
+/// Every configured key is compared in constant time, even after a match, so
+/// response timing reveals neither which key matched nor how much of one did.
+fn is_client_api_key(config: &GatewayConfig, key: &str) -> bool {
+    config
+        .api_keys
+        .iter()
+        .fold(false, |found, candidate| found | secrets_equal(candidate.as_bytes(), key.as_bytes()))
 }
 
 /// Extract and validate API key from request
@@ -129,7 +368,7 @@ fn extract_api_key(headers: &HeaderMap, config: &GatewayConfig) -> Result<String
     if let Some(auth) = headers.get("authorization") {
         let auth_str = auth.to_str().map_err(|_| StatusCode::UNAUTHORIZED)?;
         if let Some(key) = auth_str.strip_prefix("Bearer ") {
-            if config.api_keys.contains(&key.to_string()) {
+            if is_client_api_key(config, key) {
                 return Ok(key.to_string());
             }
         }
@@ -147,6 +386,14 @@ fn extract_user_agent(headers: &HeaderMap) -> String {
         .to_string()
 }
 
+/// Encoding requested via the Accept header, if it names a supported type
+fn accepted_encoding(headers: &HeaderMap) -> Option<EncodingFormat> {
+    headers
+        .get(hyper::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .and_then(EncodingFormat::from_accept)
+}
+
 /// Mask API key for logging (show last 4 chars only)
 fn mask_api_key(key: &str) -> String {
     if key.len() <= 4 {
@@ -166,7 +413,9 @@ fn log_client_request(
     status: StatusCode,
 ) {
     let masked_key = mask_api_key(api_key);
+    let request_id = current_request_id();
     info!(
+        request_id = %request_id,
         client_ip = %ip,
         user_agent = %user_agent,
         endpoint = %endpoint,
@@ -177,12 +426,251 @@ fn log_client_request(
     );
 }
 
+/// Correlation ID of the request being handled, or "-" outside a request
+fn current_request_id() -> String {
+    CURRENT_REQUEST_ID
+        .try_with(|id| id.0.clone())
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+/// Ledger entries the writer task may fall behind by before new ones are dropped
+const AUDIT_CHANNEL_CAPACITY: usize = 4096;
+
+/// One served request in the audit ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditEntry {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    api_key_masked: String,
+    endpoint: String,
+    bytes: usize,
+    request_id: String,
+}
+
+/// Append-only record of how much entropy was served to whom and when
+///
+/// Entries are handed to a writer task over a bounded channel, so a slow disk
+/// never blocks request handling; entries that do not fit are dropped and
+/// counted. The most recent entries are also kept in memory for `/admin/audit`.
+struct AuditLedger {
+    sender: Option<tokio::sync::mpsc::Sender<AuditEntry>>,
+    recent: parking_lot::Mutex<std::collections::VecDeque<AuditEntry>>,
+    ring_size: usize,
+    dropped: std::sync::atomic::AtomicU64,
+}
+
+impl AuditLedger {
+    /// Open the ledger configured by `audit_log` and `audit_ring_size`, or None if both are off
+    ///
+    /// Spawns the writer task, so a file-backed ledger needs a Tokio runtime.
+    fn from_config(config: &GatewayConfig) -> std::io::Result<Option<Arc<Self>>> {
+        if config.audit_log.is_none() && config.audit_ring_size == 0 {
+            return Ok(None);
+        }
+
+        let sender = match &config.audit_log {
+            Some(path) => {
+                let mut options = std::fs::OpenOptions::new();
+                options.append(true).create(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+                    options.mode(0o600);
+                }
+                let file = tokio::fs::File::from_std(options.open(path)?);
+                let (sender, receiver) = tokio::sync::mpsc::channel(AUDIT_CHANNEL_CAPACITY);
+                tokio::spawn(write_audit_log(receiver, file));
+                Some(sender)
+            }
+            None => None,
+        };
+
+        Ok(Some(Arc::new(Self {
+            sender,
+            recent: parking_lot::Mutex::new(std::collections::VecDeque::with_capacity(config.audit_ring_size)),
+            ring_size: config.audit_ring_size,
+            dropped: std::sync::atomic::AtomicU64::new(0),
+        })))
+    }
+
+    /// Record `bytes` of entropy served to `api_key` by `endpoint` in request `request_id`
+    fn record(&self, endpoint: &str, api_key: &str, bytes: usize, request_id: String) {
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now(),
+            api_key_masked: mask_api_key(api_key),
+            endpoint: endpoint.to_string(),
+            bytes,
+            request_id,
+        };
+
+        if self.ring_size > 0 {
+            let mut recent = self.recent.lock();
+            if recent.len() == self.ring_size {
+                recent.pop_front();
+            }
+            recent.push_back(entry.clone());
+        }
+
+        if let Some(sender) = &self.sender {
+            if sender.try_send(entry).is_err() {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                // Warn at powers of two so a stalled disk does not flood the log
+                if dropped.is_power_of_two() {
+                    warn!(dropped = dropped, "Audit log writer is behind, ledger entries dropped");
+                }
+            }
+        }
+    }
+
+    /// Entries in the in-memory ring, oldest first
+    fn recent(&self) -> Vec<AuditEntry> {
+        self.recent.lock().iter().cloned().collect()
+    }
+}
+
+/// Append ledger entries to the audit file as JSON lines until every sender is gone
+async fn write_audit_log(mut receiver: tokio::sync::mpsc::Receiver<AuditEntry>, mut file: tokio::fs::File) {
+    use tokio::io::AsyncWriteExt;
+
+    let mut batch = Vec::new();
+    while receiver.recv_many(&mut batch, AUDIT_CHANNEL_CAPACITY).await > 0 {
+        let mut lines = Vec::new();
+        for entry in batch.drain(..) {
+            serde_json::to_writer(&mut lines, &entry).expect("audit entries serialize");
+            lines.push(b'\n');
+        }
+        if let Err(e) = async {
+            file.write_all(&lines).await?;
+            file.flush().await
+        }
+        .await
+        {
+            error!(error = %e, "Failed to write audit log");
+        }
+    }
+}
+
+/// Header carrying the correlation ID of a request
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Response header reporting how many buffer bytes a request consumed
+const ENTROPY_CONSUMED_HEADER: &str = "x-entropy-bytes-consumed";
+
+/// Response header of `HEAD /api/random` reporting the bytes currently buffered
+const AVAILABLE_BYTES_HEADER: &str = "x-available-bytes";
+
+/// Response header of /api/integers stating the drawn range in interval notation
+const INTEGER_RANGE_HEADER: &str = "x-integer-range";
+
+/// Longest client-supplied request ID that is echoed back rather than replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation ID of a request, stored as a request extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RequestId(String);
+
+impl RequestId {
+    /// Use the client's `X-Request-Id` if it is short printable ASCII, else generate a UUID
+    ///
+    /// Untrusted IDs end up in logs, so anything that could forge log lines is replaced.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LEN
+                    && id.bytes().all(|b| b.is_ascii_graphic())
+            })
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(|| Self(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
+tokio::task_local! {
+    /// Request ID of the handler running on this task, for `log_client_request`
+    static CURRENT_REQUEST_ID: RequestId;
+
+    /// Buffer bytes audited by the handler running on this task, for the response envelope
+    static AUDITED_BYTES: std::cell::Cell<usize>;
+}
+
+/// Assign a request ID, expose it to handlers and logs, and echo it in the response
+async fn propagate_request_id(mut request: axum::extract::Request, next: axum::middleware::Next) -> Response {
+    let request_id = RequestId::from_headers(request.headers());
+    request.extensions_mut().insert(request_id.clone());
+
+    let mut response = CURRENT_REQUEST_ID.scope(request_id.clone(), next.run(request)).await;
+    if let Ok(value) = axum::http::HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Authenticate a client request and consume a rate-limit token
+///
+/// The API key is taken from the `api_key` query parameter when present,
+/// otherwise from the `Authorization: Bearer` header. Rejections are logged.
+fn authorize_request(
+    state: &AppState,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    endpoint: &str,
+    query_key: Option<String>,
+    request_info: &str,
+) -> Result<String, StatusCode> {
+    let api_key = authenticate_request(state, addr, headers, endpoint, query_key, request_info)?;
+
+    if !state.rate_limiter.check(&api_key) {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &extract_user_agent(headers),
+            endpoint,
+            &api_key,
+            request_info,
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    Ok(api_key)
+}
+
+/// Authenticate a client request without consuming a rate-limit token
+fn authenticate_request(
+    state: &AppState,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    endpoint: &str,
+    query_key: Option<String>,
+    request_info: &str,
+) -> Result<String, StatusCode> {
+    let user_agent = extract_user_agent(headers);
+
+    match query_key {
+        Some(key) if is_client_api_key(&state.config, &key) => Ok(key),
+        Some(_) => {
+            log_client_request(addr, &user_agent, endpoint, "", request_info, StatusCode::UNAUTHORIZED);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        None => extract_api_key(headers, &state.config).inspect_err(|&status| {
+            log_client_request(addr, &user_agent, endpoint, "", request_info, status);
+        }),
+    }
+}
+
 /// Query parameters for /api/random endpoint
 #[derive(serde::Deserialize)]
 struct RandomQuery {
     bytes: usize,
-    #[serde(default = "default_encoding")]
-    encoding: String,
+    /// Overrides the `Accept` header when set
+    #[serde(default)]
+    encoding: Option<String>,
+    /// Output format; `code` returns a seed snippet for `lang`
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    lang: Option<String>,
     #[serde(default)]
     api_key: Option<String>,
 }
@@ -191,6 +679,20 @@ fn default_encoding() -> String {
     "hex".to_string()
 }
 
+/// Query parameters for /api/stream endpoint
+#[derive(serde::Deserialize)]
+struct StreamQuery {
+    bytes: u64,
+    #[serde(default = "default_stream_encoding")]
+    encoding: String,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_stream_encoding() -> String {
+    "binary".to_string()
+}
+
 /// Query parameters for /api/integers endpoint
 #[derive(serde::Deserialize)]
 struct IntegersQuery {
@@ -199,10 +701,34 @@ struct IntegersQuery {
     min: i64,
     #[serde(default = "default_max")]
     max: i64,
+    /// Whether `max` itself can be drawn
+    #[serde(default = "default_bounds")]
+    bounds: String,
+    #[serde(default = "default_values_encoding")]
+    encoding: String,
+    #[serde(default = "default_endian")]
+    endian: String,
     #[serde(default)]
     api_key: Option<String>,
 }
 
+fn default_bounds() -> String {
+    "inclusive".to_string()
+}
+
+/// Maximum number of integers across all ranges of one /api/integers/multi request
+const MAX_MULTI_INTEGERS: usize = 1000;
+
+/// One inclusive range in a /api/integers/multi request
+#[derive(Debug, serde::Deserialize)]
+struct IntegerRange {
+    count: usize,
+    #[serde(default = "default_min")]
+    min: i64,
+    #[serde(default = "default_max")]
+    max: i64,
+}
+
 fn default_min() -> i64 {
     0
 }
@@ -216,271 +742,473 @@ fn default_max() -> i64 {
 struct FloatsQuery {
     count: usize,
     #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+    #[serde(default = "default_values_encoding")]
+    encoding: String,
+    #[serde(default = "default_endian")]
+    endian: String,
+    /// Bits of each word that reach the float: 53 (default) or 64
+    #[serde(default)]
+    precision: Option<u32>,
+    /// Convert the whole word directly instead of building the float bitwise
+    #[serde(default)]
+    raw: bool,
+    #[serde(default)]
     api_key: Option<String>,
 }
 
-/// Query parameters for /api/uuid endpoint
-#[derive(serde::Deserialize)]
-struct UuidQuery {
-    #[serde(default = "default_uuid_count")]
-    count: usize,
-    #[serde(default)]
-    api_key: Option<String>,
+/// How a 64-bit entropy word becomes a float in `[0, 1)`
+///
+/// All three modes stay below 1.0 and are uniform to within one part in 2^53;
+/// they differ in how finely values close to zero are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloatPrecision {
+    /// `precision=53` (default): the top 53 bits on an evenly spaced grid of
+    /// 2^-53. Exactly uniform over the grid, but no value in `(0, 2^-53)` can
+    /// occur and small values carry few significant bits.
+    Bits53,
+    /// `precision=64`: the leading one bit of the word selects the binade
+    /// `[2^-(k+1), 2^-k)` (each half as likely as the one above) and the next 52
+    /// bits fill the mantissa. Small values keep full relative precision; below
+    /// 2^-12 fewer than 52 bits remain and the missing low bits are zero, which
+    /// rounds those values down by less than 2^-64.
+    Bits64,
+    /// `raw=true`: the word converted to f64 with round-to-nearest and scaled by
+    /// 2^-64. Rounding gives values at the bottom of each binade slightly less
+    /// weight than their neighbours, and the top 2^10 words, which would round
+    /// to 1.0, are clamped to the largest float below it.
+    Raw,
 }
 
-fn default_uuid_count() -> usize {
-    1
+impl FloatPrecision {
+    /// Parse the `precision` and `raw` query parameters (they are exclusive)
+    fn parse(precision: Option<u32>, raw: bool) -> Option<Self> {
+        match (precision, raw) {
+            (None | Some(53), false) => Some(Self::Bits53),
+            (Some(64), false) => Some(Self::Bits64),
+            (None, true) => Some(Self::Raw),
+            _ => None,
+        }
+    }
+
+    /// Map an entropy word into `[0, 1)`
+    fn unit_float(self, word: u64) -> f64 {
+        match self {
+            Self::Bits53 => (word >> 11) as f64 * (1.0 / (1u64 << 53) as f64),
+            Self::Bits64 => {
+                if word == 0 {
+                    return 0.0;
+                }
+                let leading_zeros = word.leading_zeros() as u64;
+                // Drop the leading one and keep the 52 bits after it
+                let mantissa = ((word << leading_zeros) << 1) >> 12;
+                f64::from_bits(((1022 - leading_zeros) << 52) | mantissa)
+            }
+            Self::Raw => (word as f64 * (1.0 / 2f64.powi(64))).min(1.0f64.next_down()),
+        }
+    }
 }
 
-/// Query parameters for /api/status endpoint
-#[derive(serde::Deserialize)]
-struct StatusQuery {
-    #[serde(default)]
-    api_key: Option<String>,
+fn default_values_encoding() -> String {
+    "json".to_string()
 }
 
-/// GET /api/random - Serve random entropy
-async fn serve_random(
-    State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Query(params): Query<RandomQuery>,
-    headers: HeaderMap,
-) -> Result<Response, StatusCode> {
-    let start = Instant::now();
-    let user_agent = extract_user_agent(&headers);
+fn default_endian() -> String {
+    "le".to_string()
+}
 
-    // Extract API key (from header or query param)
-    let api_key = if let Some(key) = params.api_key {
-        if state.config.api_keys.contains(&key) {
-            key
-        } else {
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/random",
-                "",
-                &format!("bytes={}", params.bytes),
-                StatusCode::UNAUTHORIZED,
-            );
-            return Err(StatusCode::UNAUTHORIZED);
+/// Whether the `max` of /api/integers can be drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntegerBounds {
+    /// `[min, max]`, the historical default
+    Inclusive,
+    /// `[min, max)`, as in most RNG libraries
+    Exclusive,
+}
+
+impl IntegerBounds {
+    fn parse(bounds: &str) -> Option<Self> {
+        match bounds.to_lowercase().as_str() {
+            "inclusive" => Some(Self::Inclusive),
+            "exclusive" => Some(Self::Exclusive),
+            _ => None,
         }
-    } else {
-        match extract_api_key(&headers, &state.config) {
-            Ok(key) => key,
-            Err(status) => {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/random",
-                    "",
-                    &format!("bytes={}", params.bytes),
-                    status,
-                );
-                return Err(status);
-            }
+    }
+
+    /// Largest value that can be drawn below `max`
+    fn last(self, max: i64) -> i64 {
+        match self {
+            Self::Inclusive => max,
+            Self::Exclusive => max - 1,
         }
-    };
+    }
 
-    // Rate limiting
-    if !state.rate_limiter.check(&api_key) {
-        state.metrics.record_request_failure();
-        log_client_request(
-            addr,
-            &user_agent,
-            "/api/random",
-            &api_key,
-            &format!("bytes={}", params.bytes),
-            StatusCode::TOO_MANY_REQUESTS,
-        );
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+    /// Interval notation of the drawn range, e.g. `[1,7)`
+    fn describe(self, min: i64, max: i64) -> String {
+        match self {
+            Self::Inclusive => format!("[{},{}]", min, max),
+            Self::Exclusive => format!("[{},{})", min, max),
+        }
     }
+}
 
-    // Validate request size
-    if params.bytes == 0 || params.bytes > qrng_core::MAX_REQUEST_SIZE {
-        log_client_request(
-            addr,
-            &user_agent,
-            "/api/random",
-            &api_key,
-            &format!("bytes={} (invalid)", params.bytes),
-            StatusCode::BAD_REQUEST,
-        );
-        return Err(StatusCode::BAD_REQUEST);
-    }
+/// Output encoding for /api/integers and /api/floats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValuesEncoding {
+    /// JSON array
+    Json,
+    /// Packed 8-byte values in the requested byte order
+    Binary(Endian),
+}
 
-    // Parse encoding
-    let encoding = match EncodingFormat::parse(&params.encoding) {
-        Some(e) => e,
-        None => {
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/random",
-                &api_key,
-                &format!("bytes={} encoding={} (invalid)", params.bytes, params.encoding),
-                StatusCode::BAD_REQUEST,
-            );
-            return Err(StatusCode::BAD_REQUEST);
+/// Byte order of packed binary values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl ValuesEncoding {
+    fn parse(encoding: &str, endian: &str) -> Option<Self> {
+        let endian = match endian.to_lowercase().as_str() {
+            "le" | "little" => Endian::Little,
+            "be" | "big" => Endian::Big,
+            _ => return None,
+        };
+        match encoding.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "binary" | "raw" => Some(Self::Binary(endian)),
+            _ => None,
         }
-    };
+    }
+}
 
-    // Get entropy from buffer
-    let data = state.buffer.pop(params.bytes)
-        .ok_or_else(|| {
-            state.metrics.record_request_failure();
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/random",
-                &api_key,
-                &format!("bytes={} encoding={}", params.bytes, params.encoding),
-                StatusCode::SERVICE_UNAVAILABLE,
-            );
-            StatusCode::SERVICE_UNAVAILABLE
-        })?;
+/// Map an unbiased float in [0, 1) into [min, max)
+///
+/// Rounding can push `min + f * (max - min)` up to `max` when `f` is close to 1,
+/// so the result is clamped to the largest float below `max`.
+fn scale_unit_float(f: f64, min: f64, max: f64) -> f64 {
+    let value = min + f * (max - min);
+    if value < max {
+        value
+    } else {
+        max.next_down()
+    }
+}
 
-    // Encode based on format
-    let (body, content_type) = match encoding {
-        EncodingFormat::Binary => (data.to_vec(), encoding.mime_type()),
-        EncodingFormat::Hex => (encode_hex(&data).into_bytes(), encoding.mime_type()),
-        EncodingFormat::Base64 => (encode_base64(&data).into_bytes(), encoding.mime_type()),
+/// Read entropy as u64 words; independent of the output byte order
+fn entropy_words(data: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    data.chunks_exact(8).map(|chunk| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        u64::from_le_bytes(bytes)
+    })
+}
+
+/// Pack 8-byte values in the requested byte order
+fn pack_values<T, const N: usize>(
+    values: &[T],
+    endian: Endian,
+    to_le: fn(T) -> [u8; N],
+    to_be: fn(T) -> [u8; N],
+) -> Vec<u8>
+where
+    T: Copy,
+{
+    let convert = match endian {
+        Endian::Little => to_le,
+        Endian::Big => to_be,
     };
+    values.iter().flat_map(|&value| convert(value)).collect()
+}
 
-    // Record metrics
-    let latency = start.elapsed().as_micros() as u64;
-    state.metrics.record_request(params.bytes, latency);
+/// Render integer or float values in the requested output encoding
+fn values_response<T, const N: usize>(
+    values: &[T],
+    encoding: ValuesEncoding,
+    to_le: fn(T) -> [u8; N],
+    to_be: fn(T) -> [u8; N],
+) -> Response
+where
+    T: Copy + Serialize,
+{
+    match encoding {
+        ValuesEncoding::Json => (
+            StatusCode::OK,
+            [(hyper::header::CONTENT_TYPE, "application/json")],
+            serde_json::to_string(values).unwrap(),
+        )
+            .into_response(),
+        ValuesEncoding::Binary(endian) => (
+            StatusCode::OK,
+            [(hyper::header::CONTENT_TYPE, "application/octet-stream")],
+            pack_values(values, endian, to_le, to_be),
+        )
+            .into_response(),
+    }
+}
 
-    // Log successful request
-    log_client_request(
-        addr,
-        &user_agent,
-        "/api/random",
-        &api_key,
-        &format!("bytes={} encoding={}", params.bytes, params.encoding),
-        StatusCode::OK,
-    );
+/// Number of values in the inclusive range `[min, max]` (up to 2^64)
+fn integer_range(min: i64, max: i64) -> u128 {
+    (max as i128 - min as i128 + 1) as u128
+}
 
-    Ok((
-        StatusCode::OK,
-        [(hyper::header::CONTENT_TYPE, content_type)],
-        body,
+/// Fewest whole bytes that can index a range of `range` values (1 to 8)
+fn integer_width(range: u128) -> usize {
+    let bits = 128 - (range - 1).leading_zeros() as usize;
+    bits.div_ceil(8).max(1)
+}
+
+/// Draw `count` unbiased integers in `[min, max]` from the buffer
+///
+/// Each value uses only as many bytes as the range needs (one for a die,
+/// eight for a full 64-bit range); rejected draws pop more. Returns the values
+/// and the bytes consumed, or `None` if the buffer cannot supply enough entropy.
+fn generate_integers(buffer: &EntropyBuffer, count: usize, min: i64, max: i64) -> Option<(Vec<i64>, usize)> {
+    let mut words = EntropyWords::with_bytes(buffer, count * integer_width(integer_range(min, max)))?;
+    let integers = draw_integers(&mut words, count, min, max)?;
+    Some((integers, words.bytes_consumed))
+}
+
+/// Draw one result array per range, in request order, from a single entropy draw
+///
+/// Returns the arrays and the bytes consumed, like [`generate_integers`].
+fn generate_integer_ranges(buffer: &EntropyBuffer, ranges: &[IntegerRange]) -> Option<(Vec<Vec<i64>>, usize)> {
+    let needed = ranges
+        .iter()
+        .map(|range| range.count * integer_width(integer_range(range.min, range.max)))
+        .sum();
+    let mut words = EntropyWords::with_bytes(buffer, needed)?;
+    let results = ranges
+        .iter()
+        .map(|range| draw_integers(&mut words, range.count, range.min, range.max))
+        .collect::<Option<Vec<_>>>()?;
+    Some((results, words.bytes_consumed))
+}
+
+/// Unbiased integers in `[min, max]`, each using the fewest whole bytes the range needs
+fn draw_integers(words: &mut EntropyWords, count: usize, min: i64, max: i64) -> Option<Vec<i64>> {
+    let range = integer_range(min, max);
+    let width = integer_width(range);
+    (0..count)
+        .map(|_| {
+            let offset = words.uniform_narrow(range, width)?;
+            Some((min as i128 + offset as i128) as i64)
+        })
+        .collect()
+}
+
+/// Draw `count` floats in `[min, max)` from the buffer (8 bytes each)
+///
+/// See [`FloatPrecision`] for how each word is mapped into `[0, 1)`.
+fn generate_floats(
+    buffer: &EntropyBuffer,
+    count: usize,
+    min: f64,
+    max: f64,
+    precision: FloatPrecision,
+) -> Option<Vec<f64>> {
+    let data = buffer.pop(count * 8)?;
+    Some(
+        entropy_words(&data)
+            .map(|random_u64| precision.unit_float(random_u64))
+            .map(|unit| scale_unit_float(unit, min, max))
+            .collect(),
     )
-        .into_response())
 }
 
-/// GET /api/status - System status
-async fn get_status(
-    State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Query(params): Query<StatusQuery>,
-    headers: HeaderMap,
-) -> Result<Json<GatewayStatus>, StatusCode> {
-    let user_agent = extract_user_agent(&headers);
+/// UUID layout served by /api/uuid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UuidVersion {
+    /// Fully random (RFC 9562 version 4)
+    V4,
+    /// 48-bit Unix millisecond timestamp followed by random bits (RFC 9562 version 7)
+    V7,
+}
 
-    // Extract API key (from header or query param)
-    let api_key = if let Some(key) = params.api_key {
-        if state.config.api_keys.contains(&key) {
-            key
-        } else {
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/status",
-                "",
-                "status_check",
-                StatusCode::UNAUTHORIZED,
-            );
-            return Err(StatusCode::UNAUTHORIZED);
+impl UuidVersion {
+    /// Parse the `version` query parameter
+    fn from_number(version: u8) -> Option<Self> {
+        match version {
+            4 => Some(Self::V4),
+            7 => Some(Self::V7),
+            _ => None,
         }
-    } else {
-        match extract_api_key(&headers, &state.config) {
-            Ok(key) => key,
-            Err(status) => {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/status",
-                    "",
-                    "status_check",
-                    status,
-                );
-                return Err(status);
+    }
+
+    /// Buffer bytes consumed per UUID
+    ///
+    /// A v7 UUID carries 74 random bits after its timestamp, so 10 bytes suffice.
+    fn entropy_bytes(self) -> usize {
+        match self {
+            Self::V4 => 16,
+            Self::V7 => 10,
+        }
+    }
+}
+
+/// Draw `count` UUIDs of the given version from the buffer
+fn generate_uuids(buffer: &EntropyBuffer, count: usize, version: UuidVersion) -> Option<Vec<String>> {
+    let data = buffer.pop(count * version.entropy_bytes())?;
+    let unix_millis = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let uuids = data
+        .chunks_exact(version.entropy_bytes())
+        .map(|chunk| {
+            let mut bytes = [0u8; 16];
+            match version {
+                UuidVersion::V4 => {
+                    bytes.copy_from_slice(chunk);
+                    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                }
+                UuidVersion::V7 => {
+                    bytes[..6].copy_from_slice(&unix_millis.to_be_bytes()[2..]);
+                    bytes[6..].copy_from_slice(chunk);
+                    bytes[6] = (bytes[6] & 0x0f) | 0x70;
+                }
             }
+
+            // Set variant (RFC 9562)
+            bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+            uuid::Uuid::from_bytes(bytes).to_string()
+        })
+        .collect();
+    Some(uuids)
+}
+
+/// Check that a float range is finite and non-empty
+fn valid_float_range(min: f64, max: f64) -> bool {
+    min.is_finite() && max.is_finite() && (max - min).is_finite() && min < max
+}
+
+/// Query parameters for /api/uuid endpoint
+#[derive(serde::Deserialize)]
+struct UuidQuery {
+    #[serde(default = "default_uuid_count")]
+    count: usize,
+    /// UUID version: 4 (random) or 7 (time-ordered)
+    #[serde(default = "default_uuid_version")]
+    version: u8,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_uuid_count() -> usize {
+    1
+}
+
+fn default_uuid_version() -> u8 {
+    4
+}
+
+/// Query parameters for /api/status endpoint
+#[derive(serde::Deserialize)]
+struct StatusQuery {
+    #[serde(default)]
+    api_key: Option<String>,
+    /// Hold the request until this level is reached: "healthy" or "ready"
+    #[serde(default)]
+    wait_for: Option<String>,
+    /// How long to hold a `wait_for` request (default 10s, max 60s)
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Include buffer stats, latency percentiles and intake counters
+    #[serde(default)]
+    detail: bool,
+}
+
+/// Default hold time of GET /api/status?wait_for=
+const DEFAULT_STATUS_WAIT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Longest hold time of GET /api/status?wait_for=
+const MAX_STATUS_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Buffer fill above which the gateway is ready to serve entropy
+const READY_FILL_PERCENT: f64 = 5.0;
+
+/// Level a long-polling status request waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusWait {
+    /// Reported status is `healthy`
+    Healthy,
+    /// Readiness probe would pass
+    Ready,
+}
+
+impl StatusWait {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "healthy" => Some(Self::Healthy),
+            "ready" => Some(Self::Ready),
+            _ => None,
         }
-    };
+    }
 
-    let fill_percent = state.buffer.fill_percent();
-    let status = if fill_percent < 10.0 {
+    fn reached(&self, buffer: &EntropyBuffer) -> bool {
+        match self {
+            Self::Healthy => health_status(buffer.fill_percent()) == HealthStatus::Healthy,
+            Self::Ready => buffer.fill_percent() > READY_FILL_PERCENT,
+        }
+    }
+}
+
+/// Health reported by GET /api/status for a buffer fill level
+fn health_status(fill_percent: f64) -> HealthStatus {
+    if fill_percent < 10.0 {
         HealthStatus::Unhealthy
     } else if fill_percent < 30.0 {
         HealthStatus::Degraded
     } else {
         HealthStatus::Healthy
-    };
-
-    let mut warnings = Vec::new();
-    if fill_percent < 10.0 {
-        warnings.push("Buffer critically low".to_string());
-    }
-    if let Some(age) = state.buffer.freshness_seconds() {
-        if age > 300 {
-            warnings.push(format!("Data is {} seconds old", age));
-        }
     }
+}
 
-    log_client_request(
-        addr,
-        &user_agent,
-        "/api/status",
-        &api_key,
-        &format!("buffer_fill={:.1}%", fill_percent),
-        StatusCode::OK,
-    );
+/// HKDF salt separating per-key isolated output from other derivations
+const PER_KEY_ISOLATION_SALT: &[u8] = b"qrng-per-key-isolation";
 
-    Ok(Json(GatewayStatus {
-        status,        
-        buffer_fill_percent: fill_percent,
-        buffer_bytes_available: state.buffer.len(),
-        last_data_received: state.buffer.oldest_timestamp(),
-        data_freshness_seconds: state.buffer.freshness_seconds(),
-        uptime_seconds: state.start_time.elapsed().as_secs(),
-        total_requests_served: state.metrics.requests_total(),
-        total_bytes_served: state.metrics.bytes_served(),
-        requests_per_second: state.metrics.requests_per_second(),
-        warnings,
-    }))
-}
+/// Input block size of per-key isolation: the HKDF-SHA256 pseudorandom key length
+///
+/// HKDF-Extract compresses any input into a 32-byte key, so larger blocks would
+/// be stretched back out of 256 bits and lose entropy.
+const ISOLATION_BLOCK_BYTES: usize = 32;
 
-/// GET /health - Simple health check
-async fn health_check(State(state): State<AppState>) -> StatusCode {
-    if state.buffer.fill_percent() > 5.0 {
-        StatusCode::OK
-    } else {
-        StatusCode::SERVICE_UNAVAILABLE
+/// Derive an output stream specific to `api_key` from raw buffer bytes
+///
+/// Each 32-byte block of input is derived into a block of the same length
+/// with the key as `info`, so different keys see independent bytes even when
+/// given the same input, and the output carries the full input entropy.
+fn isolate_for_key(data: &[u8], api_key: &str) -> qrng_core::Result<Vec<u8>> {
+    let mixer = EntropyMixer::new(MixingStrategy::Hkdf);
+    let mut output = Vec::with_capacity(data.len());
+    for block in data.chunks(ISOLATION_BLOCK_BYTES) {
+        output.extend(mixer.derive(&[block.to_vec()], PER_KEY_ISOLATION_SALT, api_key.as_bytes(), block.len())?);
     }
+    Ok(output)
 }
 
-/// GET /api/integers - Generate random integers in range
-async fn serve_integers(
+/// GET /api/random - Serve random entropy
+async fn serve_random(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Query(params): Query<IntegersQuery>,
+    Query(params): Query<RandomQuery>,
     headers: HeaderMap,
 ) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let user_agent = extract_user_agent(&headers);
 
-    // Extract and validate API key
+    // Extract API key (from header or query param)
     let api_key = if let Some(key) = params.api_key {
-        if state.config.api_keys.contains(&key) {
+        if is_client_api_key(&state.config, &key) {
             key
         } else {
             log_client_request(
                 addr,
                 &user_agent,
-                "/api/integers",
+                "/api/random",
                 "",
-                &format!("count={} min={} max={}", params.count, params.min, params.max),
+                &format!("bytes={}", params.bytes),
                 StatusCode::UNAUTHORIZED,
             );
             return Err(StatusCode::UNAUTHORIZED);
@@ -492,9 +1220,9 @@ async fn serve_integers(
                 log_client_request(
                     addr,
                     &user_agent,
-                    "/api/integers",
+                    "/api/random",
                     "",
-                    &format!("count={} min={} max={}", params.count, params.min, params.max),
+                    &format!("bytes={}", params.bytes),
                     status,
                 );
                 return Err(status);
@@ -508,819 +1236,8302 @@ async fn serve_integers(
         log_client_request(
             addr,
             &user_agent,
-            "/api/integers",
+            "/api/random",
             &api_key,
-            &format!("count={} min={} max={}", params.count, params.min, params.max),
+            &format!("bytes={}", params.bytes),
             StatusCode::TOO_MANY_REQUESTS,
         );
         return Err(StatusCode::TOO_MANY_REQUESTS);
     }
 
-    // Validate parameters
-    if params.count == 0 || params.count > 1000 {
+    // Validate request size
+    if params.bytes == 0 || params.bytes > state.config.max_request_bytes() {
         log_client_request(
             addr,
             &user_agent,
-            "/api/integers",
+            "/api/random",
             &api_key,
-            &format!("count={} (invalid)", params.count),
+            &format!("bytes={} (invalid)", params.bytes),
             StatusCode::BAD_REQUEST,
         );
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    if params.min >= params.max {
+    // Parse encoding; an explicit query parameter wins over the Accept header
+    let encoding = match params.encoding.as_deref() {
+        Some(name) => EncodingFormat::parse(name),
+        None => Some(accepted_encoding(&headers).unwrap_or(EncodingFormat::Hex)),
+    };
+    let encoding = match encoding {
+        Some(e) => e,
+        None => {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/random",
+                &api_key,
+                &format!(
+                    "bytes={} encoding={} (invalid)",
+                    params.bytes,
+                    params.encoding.as_deref().unwrap_or_default()
+                ),
+                StatusCode::BAD_REQUEST,
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    // Parse seed snippet language (format=code)
+    let seed_language = match params.format.as_deref() {
+        None => None,
+        Some("code") => params
+            .lang
+            .as_deref()
+            .and_then(SeedLanguage::parse)
+            .filter(|lang| lang.required_seed_len().is_none_or(|len| len == params.bytes)),
+        Some(_) => None,
+    };
+    if params.format.is_some() && seed_language.is_none() {
         log_client_request(
             addr,
             &user_agent,
-            "/api/integers",
+            "/api/random",
             &api_key,
-            &format!("min={} max={} (invalid)", params.min, params.max),
+            &format!(
+                "bytes={} format={} lang={} (invalid)",
+                params.bytes,
+                params.format.as_deref().unwrap_or_default(),
+                params.lang.as_deref().unwrap_or_default()
+            ),
             StatusCode::BAD_REQUEST,
         );
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let range = (params.max - params.min + 1) as u64;
-
-    // Get entropy from buffer (8 bytes per integer)
-    let bytes_needed = params.count * 8;
-    let data = state.buffer.pop(bytes_needed)
+    // Get entropy from buffer
+    let (data, origin) = state
+        .draw("/api/random", params.bytes, |buffer| buffer.pop(params.bytes))
         .ok_or_else(|| {
             state.metrics.record_request_failure();
             log_client_request(
                 addr,
                 &user_agent,
-                "/api/integers",
+                "/api/random",
                 &api_key,
-                &format!("count={} min={} max={}", params.count, params.min, params.max),
+                &format!("bytes={} encoding={}", params.bytes, encoding.name()),
                 StatusCode::SERVICE_UNAVAILABLE,
             );
             StatusCode::SERVICE_UNAVAILABLE
         })?;
 
-    // Convert bytes to integers
-    let mut integers = Vec::with_capacity(params.count);
-    for chunk in data.chunks_exact(8) {
-        let mut bytes = [0u8; 8];
-        bytes.copy_from_slice(chunk);
-        let value = u64::from_le_bytes(bytes);
-        let result = params.min + (value % range) as i64;
-        integers.push(result);
-    }
+    // Give each API key its own stream when isolation is enabled
+    let data = if state.config.per_key_isolation {
+        axum::body::Bytes::from(isolate_for_key(&data, &api_key).map_err(|_| {
+            state.metrics.record_request_failure();
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?)
+    } else {
+        data
+    };
+
+    // Encode based on format
+    let (body, content_type) = match (seed_language, encoding) {
+        (Some(lang), _) => (lang.snippet(&data).into_bytes(), "text/plain; charset=utf-8"),
+        (None, EncodingFormat::Binary) => (data.to_vec(), encoding.mime_type()),
+        (None, EncodingFormat::Hex) => (encode_hex(&data).into_bytes(), encoding.mime_type()),
+        (None, EncodingFormat::Base64) => (encode_base64(&data).into_bytes(), encoding.mime_type()),
+        (None, EncodingFormat::Base32) => (encode_base32(&data).into_bytes(), encoding.mime_type()),
+        (None, EncodingFormat::Base85) => (encode_base85(&data).into_bytes(), encoding.mime_type()),
+    };
 
     // Record metrics
     let latency = start.elapsed().as_micros() as u64;
-    state.metrics.record_request(bytes_needed, latency);
+    let quantum_bytes = origin.quantum_bytes(params.bytes);
+    state.metrics.record_request(quantum_bytes, latency);
+    state.audit("/api/random", &api_key, quantum_bytes);
 
     // Log successful request
     log_client_request(
         addr,
         &user_agent,
-        "/api/integers",
+        "/api/random",
         &api_key,
-        &format!("count={} min={} max={}", params.count, params.min, params.max),
+        &format!("bytes={} encoding={}", params.bytes, encoding.name()),
         StatusCode::OK,
     );
 
-    // Return as JSON array
-    Ok((
-        StatusCode::OK,
-        [(hyper::header::CONTENT_TYPE, "application/json")],
-        serde_json::to_string(&integers).unwrap(),
-    )
-        .into_response())
+    Ok(origin.label(
+        (
+            StatusCode::OK,
+            [(hyper::header::CONTENT_TYPE, content_type)],
+            body,
+        )
+            .into_response(),
+    ))
 }
 
-/// GET /api/floats - Generate random floats in [0, 1)
-async fn serve_floats(
+/// HEAD /api/random - Report whether `bytes` could be served right now, consuming nothing
+///
+/// Returns 200 when at least `bytes` bytes are buffered and 503 otherwise, both
+/// with `X-Available-Bytes`. With the CSPRNG fallback enabled a GET would
+/// succeed anyway, so a short buffer also gets 200, labeled
+/// `X-Entropy-Source: fallback`. Pre-flight checks do not use a rate-limit
+/// token and are never throttled.
+async fn head_random(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Query(params): Query<FloatsQuery>,
+    Query(params): Query<RandomQuery>,
     headers: HeaderMap,
 ) -> Result<Response, StatusCode> {
-    let start = Instant::now();
     let user_agent = extract_user_agent(&headers);
+    let request_info = format!("HEAD bytes={}", params.bytes);
+    let api_key = authenticate_request(&state, addr, &headers, "/api/random", params.api_key, &request_info)?;
 
-    // Extract and validate API key
-    let api_key = if let Some(key) = params.api_key {
-        if state.config.api_keys.contains(&key) {
-            key
-        } else {
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/floats",
-                "",
-                &format!("count={}", params.count),
-                StatusCode::UNAUTHORIZED,
-            );
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    } else {
-        match extract_api_key(&headers, &state.config) {
-            Ok(key) => key,
-            Err(status) => {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/floats",
-                    "",
-                    &format!("count={}", params.count),
-                    status,
-                );
-                return Err(status);
-            }
-        }
-    };
-
-    // Rate limiting
-    if !state.rate_limiter.check(&api_key) {
-        state.metrics.record_request_failure();
+    if params.bytes == 0 || params.bytes > state.config.max_request_bytes() {
         log_client_request(
             addr,
             &user_agent,
-            "/api/floats",
+            "/api/random",
             &api_key,
-            &format!("count={}", params.count),
-            StatusCode::TOO_MANY_REQUESTS,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
         );
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+        return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Validate parameters
-    if params.count == 0 || params.count > 1000 {
-        log_client_request(
-            addr,
-            &user_agent,
-            "/api/floats",
-            &api_key,
-            &format!("count={} (invalid)", params.count),
-            StatusCode::BAD_REQUEST,
+    let available = state.buffer.len();
+    let mut response = if available >= params.bytes {
+        StatusCode::OK.into_response()
+    } else if state.falls_back("/api/random") {
+        EntropyOrigin::Fallback.label(StatusCode::OK.into_response())
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE.into_response()
+    };
+    log_client_request(addr, &user_agent, "/api/random", &api_key, &request_info, response.status());
+
+    response
+        .headers_mut()
+        .insert(AVAILABLE_BYTES_HEADER, axum::http::HeaderValue::from(available));
+    Ok(response)
+}
+
+/// Largest block a single /api/reserve call may reserve (1 MiB)
+const MAX_RESERVATION_BYTES: usize = 1024 * 1024;
+
+/// Most outstanding reservations per API key
+const MAX_RESERVATIONS_PER_KEY: usize = 4;
+
+/// Time a reservation stays retrievable before its entropy is discarded
+const RESERVATION_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Block of entropy popped for one client and retrieved in slices
+struct Reservation {
+    api_key: String,
+    data: axum::body::Bytes,
+    expires_at: Instant,
+    /// Byte ranges already handed out; each byte is served at most once
+    retrieved: Vec<std::ops::Range<usize>>,
+}
+
+/// Why a reservation could not be created
+#[derive(Debug, PartialEq, Eq)]
+enum ReserveError {
+    /// The key already holds `MAX_RESERVATIONS_PER_KEY` live reservations
+    TooMany,
+    /// The buffer cannot supply the block
+    Insufficient,
+}
+
+/// Why a reservation slice could not be retrieved
+#[derive(Debug, PartialEq, Eq)]
+enum SliceError {
+    /// Unknown or expired token, or one held by another key
+    NotFound,
+    /// The slice is empty or extends past the end of the reservation
+    OutOfRange,
+    /// Part of the slice was already retrieved
+    AlreadyRetrieved,
+}
+
+/// Exclusive entropy blocks keyed by unguessable tokens
+///
+/// Expired reservations are freed lazily whenever the store is used.
+struct ReservationStore {
+    ttl: std::time::Duration,
+    reservations: parking_lot::Mutex<std::collections::HashMap<String, Reservation>>,
+}
+
+impl ReservationStore {
+    fn new(ttl: std::time::Duration) -> Self {
+        Self {
+            ttl,
+            reservations: parking_lot::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// Pop `bytes` from `buffer` into a new reservation for `api_key`, returning its token
+    ///
+    /// The per-key cap is checked before popping, so a refused request wastes no entropy.
+    fn reserve(&self, buffer: &EntropyBuffer, api_key: &str, bytes: usize) -> Result<String, ReserveError> {
+        let mut reservations = self.reservations.lock();
+        let now = Instant::now();
+        reservations.retain(|_, reservation| reservation.expires_at > now);
+
+        let held = reservations.values().filter(|reservation| reservation.api_key == api_key).count();
+        if held >= MAX_RESERVATIONS_PER_KEY {
+            return Err(ReserveError::TooMany);
+        }
+
+        let data = buffer.pop(bytes).ok_or(ReserveError::Insufficient)?;
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        reservations.insert(
+            token.clone(),
+            Reservation {
+                api_key: api_key.to_string(),
+                data,
+                expires_at: now + self.ttl,
+                retrieved: Vec::new(),
+            },
         );
-        return Err(StatusCode::BAD_REQUEST);
+        Ok(token)
     }
 
-    // Get entropy from buffer (8 bytes per float)
-    let bytes_needed = params.count * 8;
-    let data = state.buffer.pop(bytes_needed)
-        .ok_or_else(|| {
-            state.metrics.record_request_failure();
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/floats",
-                &api_key,
-                &format!("count={}", params.count),
-                StatusCode::SERVICE_UNAVAILABLE,
-            );
-            StatusCode::SERVICE_UNAVAILABLE
-        })?;
+    /// Hand out `len` bytes (or the rest) at `offset` of a reservation, freeing it once every byte was retrieved
+    fn take_slice(
+        &self,
+        token: &str,
+        api_key: &str,
+        offset: usize,
+        len: Option<usize>,
+    ) -> Result<axum::body::Bytes, SliceError> {
+        let mut reservations = self.reservations.lock();
+        let now = Instant::now();
+        reservations.retain(|_, reservation| reservation.expires_at > now);
 
-    // Convert bytes to floats using proper precision
-    let mut floats = Vec::with_capacity(params.count);
-    for chunk in data.chunks_exact(8) {
-        let mut bytes = [0u8; 8];
-        bytes.copy_from_slice(chunk);
-        let random_u64 = u64::from_le_bytes(bytes);
-        // Use only top 53 bits to avoid rounding bias (same as Monte Carlo)
-        let float = (random_u64 >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
-        floats.push(float);
+        let reservation = reservations
+            .get_mut(token)
+            .filter(|reservation| reservation.api_key == api_key)
+            .ok_or(SliceError::NotFound)?;
+
+        let end = match len {
+            Some(len) => offset.checked_add(len).ok_or(SliceError::OutOfRange)?,
+            None => reservation.data.len(),
+        };
+        if end <= offset || end > reservation.data.len() {
+            return Err(SliceError::OutOfRange);
+        }
+        if reservation.retrieved.iter().any(|range| range.start < end && offset < range.end) {
+            return Err(SliceError::AlreadyRetrieved);
+        }
+
+        reservation.retrieved.push(offset..end);
+        let slice = reservation.data.slice(offset..end);
+        let retrieved: usize = reservation.retrieved.iter().map(|range| range.len()).sum();
+        if retrieved == reservation.data.len() {
+            reservations.remove(token);
+        }
+        Ok(slice)
     }
+}
 
-    // Record metrics
-    let latency = start.elapsed().as_micros() as u64;
-    state.metrics.record_request(bytes_needed, latency);
+/// Query parameters for POST /api/reserve
+#[derive(serde::Deserialize)]
+struct ReserveQuery {
+    bytes: usize,
+    #[serde(default)]
+    api_key: Option<String>,
+}
 
-    // Log successful request
-    log_client_request(
-        addr,
-        &user_agent,
-        "/api/floats",
-        &api_key,
-        &format!("count={}", params.count),
-        StatusCode::OK,
-    );
+/// Response of POST /api/reserve
+#[derive(Debug, Serialize, Deserialize)]
+struct ReservationResponse {
+    token: String,
+    bytes: usize,
+    expires_in_secs: u64,
+}
 
-    // Return as JSON array
-    Ok((
-        StatusCode::OK,
-        [(hyper::header::CONTENT_TYPE, "application/json")],
-        serde_json::to_string(&floats).unwrap(),
-    )
-        .into_response())
+/// Query parameters for GET /api/reserve/{token}
+#[derive(serde::Deserialize)]
+struct ReservationSliceQuery {
+    #[serde(default)]
+    offset: usize,
+    /// Defaults to the rest of the reservation
+    #[serde(default)]
+    len: Option<usize>,
+    #[serde(default = "default_encoding")]
+    encoding: String,
+    #[serde(default)]
+    api_key: Option<String>,
 }
 
-/// GET /api/uuid - Generate UUID v4
-async fn serve_uuid(
+/// POST /api/reserve - Pop a contiguous block into an exclusive, short-lived reservation
+///
+/// The block is retrieved in slices with GET /api/reserve/{token}; no other
+/// client can receive any of its bytes.
+async fn serve_reserve(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Query(params): Query<UuidQuery>,
+    Query(params): Query<ReserveQuery>,
     headers: HeaderMap,
 ) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let user_agent = extract_user_agent(&headers);
+    let request_info = format!("bytes={}", params.bytes);
 
-    // Extract and validate API key
-    let api_key = if let Some(key) = params.api_key {
-        if state.config.api_keys.contains(&key) {
-            key
-        } else {
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/uuid",
-                "",
-                &format!("count={}", params.count),
-                StatusCode::UNAUTHORIZED,
-            );
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    } else {
-        match extract_api_key(&headers, &state.config) {
-            Ok(key) => key,
-            Err(status) => {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/uuid",
-                    "",
-                    &format!("count={}", params.count),
-                    status,
-                );
-                return Err(status);
-            }
-        }
-    };
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/reserve",
+        params.api_key,
+        &request_info,
+    )?;
 
-    // Rate limiting
-    if !state.rate_limiter.check(&api_key) {
-        state.metrics.record_request_failure();
+    if params.bytes == 0 || params.bytes > MAX_RESERVATION_BYTES {
         log_client_request(
             addr,
             &user_agent,
-            "/api/uuid",
+            "/api/reserve",
             &api_key,
-            &format!("count={}", params.count),
-            StatusCode::TOO_MANY_REQUESTS,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
         );
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+        return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Validate parameters
-    if params.count == 0 || params.count > 100 {
+    let token = match state.reservations.reserve(&state.buffer, &api_key, params.bytes) {
+        Ok(token) => token,
+        Err(error) => {
+            let status = match error {
+                ReserveError::TooMany => StatusCode::TOO_MANY_REQUESTS,
+                ReserveError::Insufficient => {
+                    state.metrics.record_request_failure();
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+            };
+            log_client_request(addr, &user_agent, "/api/reserve", &api_key, &request_info, status);
+            return Err(status);
+        }
+    };
+
+    // The entropy leaves the buffer now, so it is counted here rather than per slice
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(params.bytes, latency);
+    state.audit("/api/reserve", &api_key, params.bytes);
+
+    log_client_request(addr, &user_agent, "/api/reserve", &api_key, &request_info, StatusCode::OK);
+
+    Ok(Json(ReservationResponse {
+        token,
+        bytes: params.bytes,
+        expires_in_secs: state.reservations.ttl.as_secs(),
+    })
+    .into_response())
+}
+
+/// GET /api/reserve/{token} - Retrieve a slice of a reservation
+///
+/// Each byte can be retrieved once: overlapping an earlier slice is 409
+/// Conflict, and the reservation is freed once fully retrieved.
+async fn serve_reservation_slice(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    axum::extract::Path(token): axum::extract::Path<String>,
+    Query(params): Query<ReservationSliceQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!(
+        "offset={} len={} encoding={}",
+        params.offset,
+        params.len.map_or_else(|| "rest".to_string(), |len| len.to_string()),
+        params.encoding
+    );
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/reserve",
+        params.api_key,
+        &request_info,
+    )?;
+
+    let Some(encoding) = EncodingFormat::parse(&params.encoding) else {
         log_client_request(
             addr,
             &user_agent,
-            "/api/uuid",
+            "/api/reserve",
             &api_key,
-            &format!("count={} (invalid)", params.count),
+            &format!("{} (invalid)", request_info),
             StatusCode::BAD_REQUEST,
         );
         return Err(StatusCode::BAD_REQUEST);
-    }
-
-    // Get entropy from buffer (16 bytes per UUID)
-    let bytes_needed = params.count * 16;
-    let data = state.buffer.pop(bytes_needed)
-        .ok_or_else(|| {
-            state.metrics.record_request_failure();
-            log_client_request(
-                addr,
-                &user_agent,
-                "/api/uuid",
-                &api_key,
-                &format!("count={}", params.count),
-                StatusCode::SERVICE_UNAVAILABLE,
-            );
-            StatusCode::SERVICE_UNAVAILABLE
-        })?;
+    };
 
-    // Convert bytes to UUIDs
-    let mut uuids = Vec::with_capacity(params.count);
-    for chunk in data.chunks_exact(16) {
-        let mut bytes = [0u8; 16];
-        bytes.copy_from_slice(chunk);
-        
-        // Set version (4) and variant (RFC 4122)
-        bytes[6] = (bytes[6] & 0x0f) | 0x40;
-        bytes[8] = (bytes[8] & 0x3f) | 0x80;
-        
-        let uuid = uuid::Uuid::from_bytes(bytes);
-        uuids.push(uuid.to_string());
-    }
+    let data = match state.reservations.take_slice(&token, &api_key, params.offset, params.len) {
+        Ok(data) => data,
+        Err(error) => {
+            let status = match error {
+                SliceError::NotFound => StatusCode::NOT_FOUND,
+                SliceError::OutOfRange => StatusCode::BAD_REQUEST,
+                SliceError::AlreadyRetrieved => StatusCode::CONFLICT,
+            };
+            log_client_request(addr, &user_agent, "/api/reserve", &api_key, &request_info, status);
+            return Err(status);
+        }
+    };
 
-    // Record metrics
-    let latency = start.elapsed().as_micros() as u64;
-    state.metrics.record_request(bytes_needed, latency);
+    log_client_request(addr, &user_agent, "/api/reserve", &api_key, &request_info, StatusCode::OK);
 
-    // Log successful request
-    log_client_request(
-        addr,
-        &user_agent,
-        "/api/uuid",
-        &api_key,
-        &format!("count={}", params.count),
+    let body = match encoding {
+        EncodingFormat::Binary => data.to_vec(),
+        EncodingFormat::Hex => encode_hex(&data).into_bytes(),
+        EncodingFormat::Base64 => encode_base64(&data).into_bytes(),
+        EncodingFormat::Base32 => encode_base32(&data).into_bytes(),
+        EncodingFormat::Base85 => encode_base85(&data).into_bytes(),
+    };
+    Ok((
         StatusCode::OK,
-    );
-
-    // Return as single string or JSON array
-    let response_body = if params.count == 1 {
-        uuids[0].clone()
-    } else {
-        serde_json::to_string(&uuids).unwrap()
-    };
-
-    Ok((
-        StatusCode::OK,
-        [(hyper::header::CONTENT_TYPE, if params.count == 1 { "text/plain" } else { "application/json" })],
-        response_body,
+        [(hyper::header::CONTENT_TYPE, encoding.mime_type())],
+        body,
     )
         .into_response())
 }
 
-/// GET /metrics - Prometheus metrics
-async fn get_metrics(State(state): State<AppState>) -> String {
-    state.metrics.prometheus_format()
-}
+/// Maximum total size of a single entropy stream (4 GiB)
+const MAX_STREAM_BYTES: u64 = 4 * 1024 * 1024 * 1024;
 
-/// Monte Carlo test parameters
-#[derive(Debug, Deserialize)]
-struct MonteCarloParams {
-    #[serde(default = "default_iterations")]
-    iterations: u64,
-    #[serde(default)]
-    api_key: Option<String>,
-}
+/// Maximum bytes popped from the buffer per stream chunk
+const STREAM_CHUNK_SIZE: usize = 48 * 1024;
 
-fn default_iterations() -> u64 {
-    1_000_000
-}
+/// Delay before retrying when the buffer or rate limiter cannot serve a chunk
+const STREAM_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(50);
 
-/// Monte Carlo test results
-#[derive(Debug, Serialize)]
-struct MonteCarloResult {
-    estimated_pi: f64,
-    error: f64,
-    error_percent: f64,
-    iterations: u64,
-    convergence_rate: String,
-    quality_assessment: String,
-    note: String,
-    quantum_vs_pseudo: Option<PseudoComparison>,
-}
+/// Abort a stream when no chunk could be served for this long
+const STREAM_STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
-#[derive(Debug, Serialize)]
-struct PseudoComparison {
-    quantum_error: f64,
-    pseudo_error: f64,
-    improvement_factor: f64,
+/// Progress of an entropy stream between chunks
+struct StreamProgress {
+    state: AppState,
+    api_key: String,
+    /// Polled outside the request's task-local scope, so the ID is kept here
+    request_id: String,
+    encoding: EncodingFormat,
+    remaining: u64,
 }
 
-/// GET /api/test/monte-carlo - Run Monte Carlo π estimation test
-async fn monte_carlo_test(
-    State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    headers: HeaderMap,
-    Query(params): Query<MonteCarloParams>,
-) -> Result<Json<MonteCarloResult>, AppError> {
-    let user_agent = extract_user_agent(&headers);
+impl StreamProgress {
+    /// Wait until the next chunk can be served, then pop and encode it
+    async fn next_chunk(&mut self) -> std::io::Result<axum::body::Bytes> {
+        let stall_deadline = Instant::now() + STREAM_STALL_TIMEOUT;
 
-    // Extract and validate API key
-    let api_key = match params.api_key {
-        Some(ref key) => {
-            if state.config.api_keys.contains(key) {
-                key.clone()
+        loop {
+            let wanted = self.remaining.min(STREAM_CHUNK_SIZE as u64) as usize;
+            let available = self.state.buffer.len().min(wanted);
+
+            // Padded encodings need whole input groups to concatenate cleanly,
+            // so every chunk but the last is a multiple of the group size
+            let group = match self.encoding {
+                EncodingFormat::Base64 => 3,
+                EncodingFormat::Base32 => 5,
+                EncodingFormat::Base85 => 4,
+                _ => 1,
+            };
+            let size = if (available as u64) < self.remaining {
+                available - available % group
             } else {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/test/monte-carlo",
-                    "",
-                    &format!("iterations={}", params.iterations),
-                    StatusCode::UNAUTHORIZED,
-                );
-                return Err(AppError(StatusCode::UNAUTHORIZED, "Invalid API key".to_string()));
+                available
+            };
+
+            if size > 0 && self.state.rate_limiter.check(&self.api_key) {
+                if let Some(data) = self.state.buffer.pop(size) {
+                    self.remaining -= size as u64;
+                    self.state.metrics.record_bytes_served(size);
+                    if let Some(audit) = &self.state.audit {
+                        audit.record("/api/stream", &self.api_key, size, self.request_id.clone());
+                    }
+
+                    let body = match self.encoding {
+                        EncodingFormat::Binary => data,
+                        EncodingFormat::Hex => encode_hex(&data).into(),
+                        EncodingFormat::Base64 => encode_base64(&data).into(),
+                        EncodingFormat::Base32 => encode_base32(&data).into(),
+                        EncodingFormat::Base85 => encode_base85(&data).into(),
+                    };
+                    return Ok(body);
+                }
             }
-        }
-        None => match extract_api_key(&headers, &state.config) {
-            Ok(key) => key,
-            Err(status) => {
-                log_client_request(
-                    addr,
-                    &user_agent,
-                    "/api/test/monte-carlo",
-                    "",
-                    &format!("iterations={}", params.iterations),
-                    status,
-                );
-                return Err(AppError(status, "Authentication required".to_string()));
+
+            if Instant::now() >= stall_deadline {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Entropy stream stalled waiting for buffer data",
+                ));
             }
-        },
-    };
 
-    // Rate limiting
-    if !state.rate_limiter.check(&api_key) {
-        log_client_request(
-            addr,
-            &user_agent,
-            "/api/test/monte-carlo",
-            &api_key,
-            &format!("iterations={}", params.iterations),
-            StatusCode::TOO_MANY_REQUESTS,
-        );
-        return Err(AppError(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded".to_string()));
+            tokio::time::sleep(STREAM_RETRY_DELAY).await;
+        }
     }
+}
 
-    // Validate iterations
-    const MAX_ITERATIONS: u64 = 10_000_000;
-    if params.iterations == 0 || params.iterations > MAX_ITERATIONS {
+/// GET /api/stream - Stream large amounts of entropy in a single response
+///
+/// The response uses chunked transfer encoding. Chunks are popped from the
+/// buffer as entropy becomes available, so a drained buffer slows the stream
+/// down instead of failing it. Each chunk consumes a rate-limit token.
+async fn serve_stream(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("bytes={} encoding={}", params.bytes, params.encoding);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/stream",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate stream size
+    if params.bytes == 0 || params.bytes > MAX_STREAM_BYTES {
         log_client_request(
             addr,
             &user_agent,
-            "/api/test/monte-carlo",
+            "/api/stream",
             &api_key,
-            &format!("iterations={} (invalid)", params.iterations),
+            &format!("bytes={} (invalid)", params.bytes),
             StatusCode::BAD_REQUEST,
         );
-        return Err(AppError(
-            StatusCode::BAD_REQUEST,
-            format!("iterations must be between 1 and {}", MAX_ITERATIONS),
-        ));
-    }
-
-    info!("Running Monte Carlo test with {} iterations", params.iterations);
-
-    // Generate random floats from quantum source
-    // Monte Carlo needs 2 floats (x, y) per iteration
-    let bytes_needed = (params.iterations * 16) as usize; // 16 bytes per iteration (2 × f64)
-    let data = state.buffer.pop(bytes_needed).ok_or_else(|| {
-        AppError(
-            StatusCode::INSUFFICIENT_STORAGE,
-            "Insufficient entropy in buffer".to_string(),
-        )
-    })?;
-
-    // Convert bytes to floats in [0,1)
-    let mut floats = Vec::with_capacity((params.iterations * 2) as usize);
-    for chunk in data.chunks_exact(8) {
-        let mut bytes = [0u8; 8];
-        bytes.copy_from_slice(chunk);
-        let random_u64 = u64::from_be_bytes(bytes);
-        // Convert to float in [0, 1) using proper precision
-        // Use only top 53 bits to avoid rounding bias
-        let float = (random_u64 >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
-        floats.push(float);
+        return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Perform Monte Carlo π estimation
-    let quantum_pi = estimate_pi(&floats);
-    let quantum_error = (quantum_pi - std::f64::consts::PI).abs();
-    let quantum_error_percent = (quantum_error / std::f64::consts::PI) * 100.0;
-
-    // Assess convergence rate
-    let convergence_rate = if quantum_error_percent < 0.01 {
-        "excellent".to_string()
-    } else if quantum_error_percent < 0.1 {
-        "good".to_string()
-    } else if quantum_error_percent < 1.0 {
-        "fair".to_string()
-    } else {
-        "poor".to_string()
-    };
-
-    let quality_assessment = if quantum_error_percent < 0.1 {
-        "high_quality".to_string()
-    } else if quantum_error_percent < 1.0 {
-        "acceptable".to_string()
-    } else {
-        "poor_quality".to_string()
-    };
-
-    // Compare with pseudo-random (for statistical demonstration only)
-    // Note: Pseudo-random can occasionally produce better Monte Carlo estimates
-    // due to statistical variance, but lacks cryptographic unpredictability
-    let comparison = if params.iterations <= 1_000_000 {
-        // Generate pseudo-random for comparison
-        use rand::Rng;
-        let mut rng = rand::rng();
-        // Need 2 floats per iteration (x, y coordinates)
-        let pseudo_floats: Vec<f64> = (0..(params.iterations * 2))
-            .map(|_| rng.random::<f64>())
-            .collect();
-        let pseudo_pi = estimate_pi(&pseudo_floats);
-        let pseudo_error = (pseudo_pi - std::f64::consts::PI).abs();
-
-        Some(PseudoComparison {
-            quantum_error,
-            pseudo_error,
-            improvement_factor: if pseudo_error > 0.0 {
-                pseudo_error / quantum_error.max(1e-10)
-            } else {
-                1.0
-            },
-        })
-    } else {
-        None
+    // Parse encoding
+    let encoding = match EncodingFormat::parse(&params.encoding) {
+        Some(e) => e,
+        None => {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/stream",
+                &api_key,
+                &format!("{} (invalid)", request_info),
+                StatusCode::BAD_REQUEST,
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
     };
 
-    info!(
-        "Monte Carlo test completed: π ≈ {:.6}, error: {:.6} ({:.4}%)",
-        quantum_pi, quantum_error, quantum_error_percent
-    );
+    // Bytes are recorded per chunk as the stream progresses
+    state.metrics.record_request(0, start.elapsed().as_micros() as u64);
 
-    // Log successful request
     log_client_request(
         addr,
         &user_agent,
-        "/api/test/monte-carlo",
+        "/api/stream",
         &api_key,
-        &format!("iterations={}", params.iterations),
+        &request_info,
         StatusCode::OK,
     );
 
-    Ok(Json(MonteCarloResult {
-        estimated_pi: quantum_pi,
-        error: quantum_error,
-        error_percent: quantum_error_percent,
-        iterations: params.iterations,
-        convergence_rate,
-        quality_assessment,
-        note: "Monte Carlo tests measure statistical uniformity, not cryptographic security. Both quantum and pseudo-random can pass these tests, but only quantum provides true unpredictability.".to_string(),
-        quantum_vs_pseudo: comparison,
-    }))
+    let progress = StreamProgress {
+        state,
+        api_key,
+        request_id: current_request_id(),
+        encoding,
+        remaining: params.bytes,
+    };
+
+    let stream = futures::stream::unfold(progress, |mut progress| async move {
+        if progress.remaining == 0 {
+            return None;
+        }
+        let chunk = progress.next_chunk().await;
+        if chunk.is_err() {
+            // End the stream after reporting the error
+            progress.remaining = 0;
+        }
+        Some((chunk, progress))
+    });
+
+    Ok((
+        StatusCode::OK,
+        [(hyper::header::CONTENT_TYPE, encoding.mime_type())],
+        axum::body::Body::from_stream(stream),
+    )
+        .into_response())
 }
 
-/// Estimate π using Monte Carlo method
-///
-/// Uses pairs of random numbers as (x, y) coordinates and checks if they fall
-/// inside a unit circle. The ratio of points inside vs total approximates π/4.
-fn estimate_pi(floats: &[f64]) -> f64 {
-    let pairs = floats.len() / 2;
-    let mut inside_circle = 0u64;
+/// Longest a /ws request waits for the buffer before the socket is closed
+const WS_ENTROPY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
 
-    for i in 0..pairs {
-        let x = floats[i * 2];
-        let y = floats[i * 2 + 1];
+/// Request frame of the /ws protocol
+#[derive(serde::Deserialize)]
+struct WsRequest {
+    bytes: usize,
+}
 
-        // Check if point (x, y) is inside unit circle
-        if x * x + y * y <= 1.0 {
-            inside_circle += 1;
+/// Pop `bytes` from the buffer, waiting up to `timeout` for the collector to refill it
+async fn pop_within(
+    buffer: &EntropyBuffer,
+    bytes: usize,
+    timeout: std::time::Duration,
+) -> Option<axum::body::Bytes> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(data) = buffer.pop(bytes) {
+            return Some(data);
+        }
+        if Instant::now() >= deadline {
+            return None;
         }
+        tokio::time::sleep(STREAM_RETRY_DELAY).await;
     }
-
-    // π/4 ≈ inside_circle / total_points
-    // π ≈ 4 * inside_circle / total_points
-    4.0 * (inside_circle as f64) / (pairs as f64)
 }
 
-/// POST /push - Receive entropy packets (push mode only)
-async fn receive_push(
+/// GET /ws - WebSocket delivering entropy on demand
+///
+/// The API key is checked during the upgrade (browsers pass it as `api_key`,
+/// since they cannot set headers). Each `{"bytes":N}` text frame is answered
+/// with a binary frame of N bytes and consumes a rate-limit token; invalid or
+/// rate-limited requests get a `{"error":...}` text frame. When the buffer
+/// cannot supply a request within a few seconds the socket is closed with
+/// code 1013 (try again later).
+async fn serve_websocket(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ApiKeyQuery>,
     headers: HeaderMap,
-    body: axum::body::Bytes,
-) -> StatusCode {
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
     let user_agent = extract_user_agent(&headers);
-    
-    let signer = match &state.signer {
-        Some(s) => s,
-        None => {
-            warn!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                "Push endpoint called but HMAC signer not configured"
+    let api_key = authenticate_request(&state, addr, &headers, "/ws", params.api_key, "upgrade")?;
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/ws",
+        &api_key,
+        "upgrade",
+        StatusCode::SWITCHING_PROTOCOLS,
+    );
+
+    // The session outlives this request; keep its ID for the session's log lines
+    let request_id = CURRENT_REQUEST_ID.try_with(Clone::clone).ok();
+    Ok(ws.on_upgrade(move |socket| async move {
+        let session = websocket_session(socket, state, addr, user_agent, api_key);
+        match request_id {
+            Some(request_id) => CURRENT_REQUEST_ID.scope(request_id, session).await,
+            None => session.await,
+        }
+    }))
+}
+
+/// Answer entropy requests on an upgraded /ws connection until either side closes it
+async fn websocket_session(
+    mut socket: WebSocket,
+    state: AppState,
+    addr: SocketAddr,
+    user_agent: String,
+    api_key: String,
+) {
+    let max_bytes = state.config.max_request_bytes();
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let request = match message {
+            WsMessage::Text(text) => serde_json::from_str::<WsRequest>(&text),
+            WsMessage::Binary(data) => serde_json::from_slice::<WsRequest>(&data),
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+        let start = Instant::now();
+
+        let request = match request {
+            Ok(request) if (1..=max_bytes).contains(&request.bytes) => request,
+            _ => {
+                log_client_request(addr, &user_agent, "/ws", &api_key, "invalid request", StatusCode::BAD_REQUEST);
+                let error = format!("Expected {{\"bytes\":N}} with N between 1 and {}", max_bytes);
+                if send_ws_error(&mut socket, &error).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+        let request_info = format!("bytes={}", request.bytes);
+
+        if !state.rate_limiter.check(&api_key) {
+            state.metrics.record_request_failure();
+            log_client_request(addr, &user_agent, "/ws", &api_key, &request_info, StatusCode::TOO_MANY_REQUESTS);
+            if send_ws_error(&mut socket, "Rate limit exceeded").await.is_err() {
+                break;
+            }
+            continue;
+        }
+
+        let Some(data) = pop_within(&state.buffer, request.bytes, WS_ENTROPY_TIMEOUT).await else {
+            state.metrics.record_request_failure();
+            log_client_request(addr, &user_agent, "/ws", &api_key, &request_info, StatusCode::SERVICE_UNAVAILABLE);
+            let close = CloseFrame {
+                code: ws_close_code::AGAIN,
+                reason: "Insufficient entropy".into(),
+            };
+            let _ = socket.send(WsMessage::Close(Some(close))).await;
+            break;
+        };
+
+        state.metrics.record_request(request.bytes, start.elapsed().as_micros() as u64);
+        state.audit("/ws", &api_key, request.bytes);
+        log_client_request(addr, &user_agent, "/ws", &api_key, &request_info, StatusCode::OK);
+        if socket.send(WsMessage::Binary(data)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Reply to a /ws request with a `{"error":...}` text frame
+async fn send_ws_error(socket: &mut WebSocket, error: &str) -> Result<(), axum::Error> {
+    let frame = serde_json::json!({ "error": error }).to_string();
+    socket.send(WsMessage::Text(frame.into())).await
+}
+
+/// Query parameters for /api/quota endpoint
+#[derive(serde::Deserialize)]
+struct QuotaQuery {
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Response of /api/quota
+#[derive(Debug, Serialize, Deserialize)]
+struct QuotaResponse {
+    /// Requests the caller can make right now before receiving 429
+    tokens_remaining: f64,
+    /// Refill rate
+    rate_limit_per_second: u32,
+    /// Bucket capacity, the most tokens that can accumulate while idle
+    burst: u32,
+}
+
+/// GET /api/quota - Caller's remaining rate-limit budget
+///
+/// Reads the token bucket without consuming a token.
+async fn serve_quota(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<QuotaQuery>,
+    headers: HeaderMap,
+) -> Result<Json<QuotaResponse>, StatusCode> {
+    let api_key = authenticate_request(&state, addr, &headers, "/api/quota", params.api_key, "")?;
+
+    let response = QuotaResponse {
+        tokens_remaining: state.rate_limiter.peek(&api_key),
+        rate_limit_per_second: state.config.rate_limit_per_second,
+        burst: state.config.rate_limit_burst(),
+    };
+
+    log_client_request(
+        addr,
+        &extract_user_agent(&headers),
+        "/api/quota",
+        &api_key,
+        &format!("tokens_remaining={:.2}", response.tokens_remaining),
+        StatusCode::OK,
+    );
+
+    Ok(Json(response))
+}
+
+/// GET /api/status - System status
+///
+/// With `wait_for=healthy|ready` the response is held until that level is
+/// reached or `timeout_ms` elapses, then the current status is returned.
+/// `detail=true` adds the `detail` section; the default response stays compact.
+async fn get_status(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<StatusQuery>,
+    headers: HeaderMap,
+) -> Result<Json<GatewayStatus>, StatusCode> {
+    let user_agent = extract_user_agent(&headers);
+
+    // Extract API key (from header or query param)
+    let api_key = if let Some(key) = params.api_key {
+        if is_client_api_key(&state.config, &key) {
+            key
+        } else {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/status",
+                "",
+                "status_check",
+                StatusCode::UNAUTHORIZED,
             );
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    } else {
+        match extract_api_key(&headers, &state.config) {
+            Ok(key) => key,
+            Err(status) => {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/status",
+                    "",
+                    "status_check",
+                    status,
+                );
+                return Err(status);
+            }
+        }
+    };
+
+    if let Some(wait_for) = &params.wait_for {
+        let Some(wait_for) = StatusWait::parse(wait_for) else {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/status",
+                &api_key,
+                &format!("wait_for={} (invalid)", wait_for),
+                StatusCode::BAD_REQUEST,
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        };
+        let timeout = params
+            .timeout_ms
+            .map_or(DEFAULT_STATUS_WAIT, std::time::Duration::from_millis)
+            .min(MAX_STATUS_WAIT);
+        state.buffer.wait_until(timeout, |buffer| wait_for.reached(buffer)).await;
+    }
+
+    let fill_percent = state.buffer.fill_percent();
+    let status = health_status(fill_percent);
+
+    let mut warnings = Vec::new();
+    if fill_percent < 10.0 {
+        warnings.push("Buffer critically low".to_string());
+    }
+    if let Some(age) = state.buffer.freshness_seconds() {
+        if age > 300 {
+            warnings.push(format!("Data is {} seconds old", age));
+        }
+    }
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/status",
+        &api_key,
+        &format!("buffer_fill={:.1}%", fill_percent),
+        StatusCode::OK,
+    );
+
+    Ok(Json(GatewayStatus {
+        status,        
+        buffer_fill_percent: fill_percent,
+        buffer_bytes_available: state.buffer.len(),
+        last_data_received: state.buffer.oldest_timestamp(),
+        data_freshness_seconds: state.buffer.freshness_seconds(),
+        uptime_seconds: state.start_time.elapsed().as_secs(),
+        total_requests_served: state.metrics.requests_total(),
+        total_bytes_served: state.metrics.bytes_served(),
+        requests_per_second: state.metrics.requests_per_second(),
+        lifetime_requests_per_second: state.metrics.lifetime_requests_per_second(),
+        max_request_bytes: state.config.max_request_bytes(),
+        source_tags: state.buffer.source_tags(),
+        warnings,
+        detail: params.detail.then(|| StatusDetail {
+            buffer_stats: state.buffer.stats(),
+            latency_p50_micros: state.metrics.latency_p50(),
+            latency_p95_micros: state.metrics.latency_p95(),
+            latency_p99_micros: state.metrics.latency_p99(),
+            pushes_total: state.metrics.pushes_total(),
+            bytes_pushed: state.metrics.bytes_pushed(),
+            fetches_total: state.metrics.fetches_total(),
+            fetches_failed: state.metrics.fetches_failed(),
+            bytes_fetched: state.metrics.bytes_fetched(),
+        }),
+    }))
+}
+
+/// Longest a liveness probe waits for the buffer lock before reporting failure
+const LIVENESS_LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// GET /health/live - Liveness probe: the process is up and the buffer is not wedged
+///
+/// Succeeds on an empty buffer so orchestrators do not restart a gateway that is
+/// merely waiting for its first push.
+async fn liveness_check(State(state): State<AppState>) -> StatusCode {
+    if state.buffer.try_len(LIVENESS_LOCK_TIMEOUT).is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// GET /health/ready (and /health) - Readiness probe: the buffer has usable entropy
+async fn readiness_check(State(state): State<AppState>) -> StatusCode {
+    if StatusWait::Ready.reached(&state.buffer) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Response of GET /admin/buffer
+#[derive(Debug, Serialize, Deserialize)]
+struct AdminBufferResponse {
+    bytes_available: usize,
+    capacity: usize,
+    fill_percent: f64,
+    watermark: WatermarkLevel,
+    oldest_timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    freshness_seconds: Option<u64>,
+    stats: BufferStats,
+}
+
+/// Response of POST /admin/buffer/clear
+#[derive(Debug, Serialize, Deserialize)]
+struct AdminClearResponse {
+    bytes_cleared: usize,
+}
+
+/// Authenticate an admin request by its bearer token
+///
+/// Client API keys are never accepted. Admin endpoints answer 404 when no
+/// admin key is configured.
+fn authorize_admin(
+    state: &AppState,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    endpoint: &str,
+) -> Result<String, StatusCode> {
+    let Some(admin_key) = state.config.admin_api_key.as_deref() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match token {
+        Some(token) if secrets_equal(token.as_bytes(), admin_key.as_bytes()) => Ok(token.to_string()),
+        _ => {
+            log_client_request(
+                addr,
+                &extract_user_agent(headers),
+                endpoint,
+                "",
+                "admin",
+                StatusCode::UNAUTHORIZED,
+            );
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// GET /admin/buffer - Detailed buffer state for operators
+async fn admin_buffer(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<AdminBufferResponse>, StatusCode> {
+    let admin_key = authorize_admin(&state, addr, &headers, "/admin/buffer")?;
+
+    let response = AdminBufferResponse {
+        bytes_available: state.buffer.len(),
+        capacity: state.buffer.capacity(),
+        fill_percent: state.buffer.fill_percent(),
+        watermark: state.buffer.watermark(),
+        oldest_timestamp: state.buffer.oldest_timestamp(),
+        freshness_seconds: state.buffer.freshness_seconds(),
+        stats: state.buffer.stats(),
+    };
+
+    log_client_request(
+        addr,
+        &extract_user_agent(&headers),
+        "/admin/buffer",
+        &admin_key,
+        &format!("bytes_available={}", response.bytes_available),
+        StatusCode::OK,
+    );
+
+    Ok(Json(response))
+}
+
+/// POST /admin/buffer/clear - Discard all buffered entropy
+async fn admin_clear_buffer(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<AdminClearResponse>, StatusCode> {
+    let admin_key = authorize_admin(&state, addr, &headers, "/admin/buffer/clear")?;
+    let user_agent = extract_user_agent(&headers);
+
+    let bytes_cleared = state.buffer.len();
+    state.buffer.clear();
+
+    warn!(
+        client_ip = %addr,
+        user_agent = %user_agent,
+        bytes_cleared = bytes_cleared,
+        "Audit: entropy buffer cleared by admin"
+    );
+    log_client_request(
+        addr,
+        &user_agent,
+        "/admin/buffer/clear",
+        &admin_key,
+        &format!("bytes_cleared={}", bytes_cleared),
+        StatusCode::OK,
+    );
+
+    Ok(Json(AdminClearResponse { bytes_cleared }))
+}
+
+/// GET /admin/audit - Most recent audit ledger entries, oldest first
+///
+/// Returns 404 unless `audit_ring_size` keeps entries in memory.
+async fn admin_audit(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AuditEntry>>, StatusCode> {
+    let admin_key = authorize_admin(&state, addr, &headers, "/admin/audit")?;
+    let entries = state
+        .audit
+        .as_ref()
+        .filter(|audit| audit.ring_size > 0)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .recent();
+
+    log_client_request(
+        addr,
+        &extract_user_agent(&headers),
+        "/admin/audit",
+        &admin_key,
+        &format!("entries={}", entries.len()),
+        StatusCode::OK,
+    );
+
+    Ok(Json(entries))
+}
+
+/// Query parameters for /api/peek endpoint
+#[derive(serde::Deserialize)]
+struct PeekQuery {
+    bytes: usize,
+}
+
+/// GET /api/peek - Hex preview of the next bytes to be served, without consuming them
+///
+/// Admin-only: the previewed bytes are still handed out to clients afterwards.
+async fn serve_peek(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<PeekQuery>,
+    headers: HeaderMap,
+) -> Result<String, StatusCode> {
+    let admin_key = authorize_admin(&state, addr, &headers, "/api/peek")?;
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("bytes={}", params.bytes);
+
+    if params.bytes == 0 || params.bytes > state.config.max_request_bytes() {
+        log_client_request(addr, &user_agent, "/api/peek", &admin_key, &request_info, StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let Some(data) = state.buffer.peek(params.bytes) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/peek",
+            &admin_key,
+            &request_info,
+            StatusCode::INSUFFICIENT_STORAGE,
+        );
+        return Err(StatusCode::INSUFFICIENT_STORAGE);
+    };
+
+    log_client_request(addr, &user_agent, "/api/peek", &admin_key, &request_info, StatusCode::OK);
+    Ok(encode_hex(&data))
+}
+
+/// Response of POST /api/verify
+#[derive(Debug, Serialize, Deserialize)]
+struct VerifyPacketResponse {
+    /// Signature and checksum both verify
+    valid: bool,
+    signature_valid: bool,
+    checksum_valid: bool,
+    sequence: u64,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    age_seconds: i64,
+    /// Older than the buffer TTL; always false when no TTL is configured
+    stale: bool,
+    encrypted: bool,
+    payload_bytes: usize,
+}
+
+/// POST /api/verify - Check a captured packet against the gateway's verification key
+///
+/// Admin-only. The packet is only inspected: it is not decrypted, buffered or
+/// recorded by the replay guard.
+async fn verify_packet(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<VerifyPacketResponse>, StatusCode> {
+    let admin_key = authorize_admin(&state, addr, &headers, "/api/verify")?;
+    let user_agent = extract_user_agent(&headers);
+
+    let Some(signer) = &state.signer else {
+        log_client_request(addr, &user_agent, "/api/verify", &admin_key, "no_key", StatusCode::SERVICE_UNAVAILABLE);
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let Ok((packet, _format)) = EntropyPacket::decode(&body) else {
+        log_client_request(addr, &user_agent, "/api/verify", &admin_key, "malformed", StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    // A signature that cannot even be checked (e.g. wrong length) does not verify
+    let signature_valid = signer.verify_packet(&packet).unwrap_or(false);
+    let checksum_valid = packet.verify_checksum();
+    let response = VerifyPacketResponse {
+        valid: signature_valid && checksum_valid,
+        signature_valid,
+        checksum_valid,
+        sequence: packet.sequence,
+        timestamp: packet.timestamp,
+        age_seconds: chrono::Utc::now().signed_duration_since(packet.timestamp).num_seconds(),
+        stale: state.config.buffer_ttl().is_some_and(|ttl| packet.is_stale(ttl)),
+        encrypted: packet.is_encrypted(),
+        payload_bytes: packet.payload_size(),
+    };
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/verify",
+        &admin_key,
+        &format!("sequence={} valid={}", response.sequence, response.valid),
+        StatusCode::OK,
+    );
+
+    Ok(Json(response))
+}
+
+/// GET /api/integers - Generate random integers in range
+async fn serve_integers(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<IntegersQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+
+    // Extract and validate API key
+    let api_key = if let Some(key) = params.api_key {
+        if is_client_api_key(&state.config, &key) {
+            key
+        } else {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/integers",
+                "",
+                &format!("count={} min={} max={}", params.count, params.min, params.max),
+                StatusCode::UNAUTHORIZED,
+            );
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    } else {
+        match extract_api_key(&headers, &state.config) {
+            Ok(key) => key,
+            Err(status) => {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/integers",
+                    "",
+                    &format!("count={} min={} max={}", params.count, params.min, params.max),
+                    status,
+                );
+                return Err(status);
+            }
+        }
+    };
+
+    // Rate limiting
+    if !state.rate_limiter.check(&api_key) {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers",
+            &api_key,
+            &format!("count={} min={} max={}", params.count, params.min, params.max),
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // Validate parameters
+    if params.count == 0 || params.count > 1000 {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers",
+            &api_key,
+            &format!("count={} (invalid)", params.count),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if params.min >= params.max {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers",
+            &api_key,
+            &format!("min={} max={} (invalid)", params.min, params.max),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let Some(encoding) = ValuesEncoding::parse(&params.encoding, &params.endian) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers",
+            &api_key,
+            &format!("encoding={} endian={} (invalid)", params.encoding, params.endian),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let Some(bounds) = IntegerBounds::parse(&params.bounds) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers",
+            &api_key,
+            &format!("bounds={} (invalid)", params.bounds),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    // Get entropy from buffer (as few bytes per integer as the range needs)
+    let last = bounds.last(params.max);
+    let needed = params.count * integer_width(integer_range(params.min, last));
+    let ((integers, bytes_consumed), origin) = state
+        .draw("/api/integers", needed, |buffer| generate_integers(buffer, params.count, params.min, last))
+        .ok_or_else(|| {
+            state.metrics.record_request_failure();
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/integers",
+                &api_key,
+                &format!("count={} min={} max={}", params.count, params.min, params.max),
+                StatusCode::SERVICE_UNAVAILABLE,
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    // Record metrics
+    let bytes_consumed = origin.quantum_bytes(bytes_consumed);
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes_consumed, latency);
+    state.audit("/api/integers", &api_key, bytes_consumed);
+
+    // Log successful request
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/integers",
+        &api_key,
+        &format!("count={} min={} max={}", params.count, params.min, params.max),
+        StatusCode::OK,
+    );
+
+    let mut response = values_response(&integers, encoding, i64::to_le_bytes, i64::to_be_bytes);
+    let headers = response.headers_mut();
+    headers.insert(ENTROPY_CONSUMED_HEADER, axum::http::HeaderValue::from(bytes_consumed));
+    if let Ok(range) = axum::http::HeaderValue::from_str(&bounds.describe(params.min, params.max)) {
+        headers.insert(INTEGER_RANGE_HEADER, range);
+    }
+    Ok(origin.label(response))
+}
+
+/// POST /api/integers/multi - Generate integers for several independent ranges at once
+///
+/// The body lists `{"min", "max", "count"}` ranges (inclusive, defaults as for
+/// /api/integers); the response holds one array per range, in request order.
+async fn serve_integers_multi(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ApiKeyQuery>,
+    headers: HeaderMap,
+    Json(ranges): Json<Vec<IntegerRange>>,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let total: usize = ranges.iter().map(|range| range.count).fold(0, usize::saturating_add);
+    let request_info = format!("ranges={} total={}", ranges.len(), total);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/integers/multi",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    let valid = !ranges.is_empty()
+        && total <= MAX_MULTI_INTEGERS
+        && ranges.iter().all(|range| range.count > 0 && range.min < range.max);
+    if !valid {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/integers/multi",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let needed = ranges
+        .iter()
+        .map(|range| range.count * integer_width(integer_range(range.min, range.max)))
+        .sum();
+    let ((results, bytes_consumed), origin) = state
+        .draw("/api/integers/multi", needed, |buffer| generate_integer_ranges(buffer, &ranges))
+        .ok_or_else(|| {
+            state.metrics.record_request_failure();
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/integers/multi",
+                &api_key,
+                &request_info,
+                StatusCode::SERVICE_UNAVAILABLE,
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    // Record metrics
+    let bytes_consumed = origin.quantum_bytes(bytes_consumed);
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes_consumed, latency);
+    state.audit("/api/integers/multi", &api_key, bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/integers/multi",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    let mut response = Json(results).into_response();
+    response
+        .headers_mut()
+        .insert(ENTROPY_CONSUMED_HEADER, axum::http::HeaderValue::from(bytes_consumed));
+    Ok(origin.label(response))
+}
+
+/// GET /api/floats - Generate random floats in [min, max) (default [0, 1))
+async fn serve_floats(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<FloatsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+
+    // Extract and validate API key
+    let api_key = if let Some(key) = params.api_key {
+        if is_client_api_key(&state.config, &key) {
+            key
+        } else {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/floats",
+                "",
+                &format!("count={}", params.count),
+                StatusCode::UNAUTHORIZED,
+            );
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    } else {
+        match extract_api_key(&headers, &state.config) {
+            Ok(key) => key,
+            Err(status) => {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/floats",
+                    "",
+                    &format!("count={}", params.count),
+                    status,
+                );
+                return Err(status);
+            }
+        }
+    };
+
+    // Rate limiting
+    if !state.rate_limiter.check(&api_key) {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/floats",
+            &api_key,
+            &format!("count={}", params.count),
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // Validate parameters
+    if params.count == 0 || params.count > 1000 {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/floats",
+            &api_key,
+            &format!("count={} (invalid)", params.count),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let min = params.min.unwrap_or(0.0);
+    let max = params.max.unwrap_or(1.0);
+    if !valid_float_range(min, max) {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/floats",
+            &api_key,
+            &format!("min={} max={} (invalid)", min, max),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let Some(encoding) = ValuesEncoding::parse(&params.encoding, &params.endian) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/floats",
+            &api_key,
+            &format!("encoding={} endian={} (invalid)", params.encoding, params.endian),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let Some(precision) = FloatPrecision::parse(params.precision, params.raw) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/floats",
+            &api_key,
+            &format!("precision={:?} raw={} (invalid)", params.precision, params.raw),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    // Get entropy from buffer (8 bytes per float)
+    let bytes_needed = params.count * 8;
+    let (floats, origin) = state
+        .draw("/api/floats", bytes_needed, |buffer| generate_floats(buffer, params.count, min, max, precision))
+        .ok_or_else(|| {
+            state.metrics.record_request_failure();
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/floats",
+                &api_key,
+                &format!("count={}", params.count),
+                StatusCode::SERVICE_UNAVAILABLE,
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    let quantum_bytes = origin.quantum_bytes(bytes_needed);
+    state.metrics.record_request(quantum_bytes, latency);
+    state.audit("/api/floats", &api_key, quantum_bytes);
+
+    // Log successful request
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/floats",
+        &api_key,
+        &format!("count={}", params.count),
+        StatusCode::OK,
+    );
+
+    Ok(origin.label(values_response(&floats, encoding, f64::to_le_bytes, f64::to_be_bytes)))
+}
+
+/// GET /api/uuid - Generate UUID v4 or v7
+async fn serve_uuid(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<UuidQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("count={}, version={}", params.count, params.version);
+
+    // Extract and validate API key
+    let api_key = if let Some(key) = params.api_key {
+        if is_client_api_key(&state.config, &key) {
+            key
+        } else {
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/uuid",
+                "",
+                &request_info,
+                StatusCode::UNAUTHORIZED,
+            );
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    } else {
+        match extract_api_key(&headers, &state.config) {
+            Ok(key) => key,
+            Err(status) => {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/uuid",
+                    "",
+                    &request_info,
+                    status,
+                );
+                return Err(status);
+            }
+        }
+    };
+
+    // Rate limiting
+    if !state.rate_limiter.check(&api_key) {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/uuid",
+            &api_key,
+            &request_info,
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // Validate parameters
+    let version = UuidVersion::from_number(params.version);
+    if params.count == 0 || params.count > 100 || version.is_none() {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/uuid",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Get entropy from buffer (16 bytes per v4 UUID, 10 per v7)
+    let version = version.unwrap_or(UuidVersion::V4);
+    let bytes_needed = params.count * version.entropy_bytes();
+    let (uuids, origin) = state
+        .draw("/api/uuid", bytes_needed, |buffer| generate_uuids(buffer, params.count, version))
+        .ok_or_else(|| {
+            state.metrics.record_request_failure();
+            log_client_request(
+                addr,
+                &user_agent,
+                "/api/uuid",
+                &api_key,
+                &request_info,
+                StatusCode::SERVICE_UNAVAILABLE,
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    let quantum_bytes = origin.quantum_bytes(bytes_needed);
+    state.metrics.record_request(quantum_bytes, latency);
+    state.audit("/api/uuid", &api_key, quantum_bytes);
+
+    // Log successful request
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/uuid",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    // Return as single string or JSON array
+    let response_body = if params.count == 1 {
+        uuids[0].clone()
+    } else {
+        serde_json::to_string(&uuids).unwrap()
+    };
+
+    Ok(origin.label(
+        (
+            StatusCode::OK,
+            [(hyper::header::CONTENT_TYPE, if params.count == 1 { "text/plain" } else { "application/json" })],
+            response_body,
+        )
+            .into_response(),
+    ))
+}
+
+/// Maximum number of items accepted by /api/choice
+const MAX_CHOICE_ITEMS: usize = 10_000;
+
+/// Maximum number of selections per /api/choice request
+const MAX_CHOICE_COUNT: usize = 1000;
+
+/// Query parameters for JSON POST endpoints (/api/choice, /api/shuffle, /api/batch)
+#[derive(serde::Deserialize)]
+struct ApiKeyQuery {
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Request body for /api/choice endpoint
+#[derive(serde::Deserialize)]
+struct ChoiceRequest {
+    items: Vec<serde_json::Value>,
+    #[serde(default)]
+    weights: Option<Vec<f64>>,
+    #[serde(default = "default_choice_count")]
+    count: usize,
+}
+
+fn default_choice_count() -> usize {
+    1
+}
+
+/// Draws u64 words from the entropy buffer, popping more when rejection sampling needs it
+struct EntropyWords<'a> {
+    buffer: &'a EntropyBuffer,
+    data: axum::body::Bytes,
+    bytes_consumed: usize,
+}
+
+impl<'a> EntropyWords<'a> {
+    /// Pop `words` words up front; returns None if the buffer cannot supply them
+    fn new(buffer: &'a EntropyBuffer, words: usize) -> Option<Self> {
+        let data = buffer.pop(words * 8)?;
+        Some(Self {
+            buffer,
+            bytes_consumed: data.len(),
+            data,
+        })
+    }
+
+    /// Pop `bytes` bytes up front, for draws narrower than a word
+    fn with_bytes(buffer: &'a EntropyBuffer, bytes: usize) -> Option<Self> {
+        let data = buffer.pop(bytes)?;
+        Some(Self {
+            buffer,
+            bytes_consumed: data.len(),
+            data,
+        })
+    }
+
+    fn next_word(&mut self) -> Option<u64> {
+        self.next_uint(8)
+    }
+
+    /// Little-endian integer from the next `width` (1 to 8) bytes
+    fn next_uint(&mut self, width: usize) -> Option<u64> {
+        if self.data.len() < width {
+            self.data = self.buffer.pop(width)?;
+            self.bytes_consumed += width;
+        }
+        let mut word = [0u8; 8];
+        word[..width].copy_from_slice(&self.data.split_to(width));
+        Some(u64::from_le_bytes(word))
+    }
+
+    /// Unbiased integer in [0, n) from `width`-byte draws, for n up to 2^(8 * width)
+    ///
+    /// Uses Lemire's multiply-shift reduction, which redraws only for the
+    /// `2^(8 * width) mod n` biased inputs.
+    fn uniform_narrow(&mut self, n: u128, width: usize) -> Option<u64> {
+        let bits = 8 * width as u32;
+        loop {
+            let value = self.next_uint(width)?;
+            // A range covering every draw needs no reduction (and 2^64 does not fit in a u64)
+            if n == 1u128 << bits {
+                return Some(value);
+            }
+            if let Some(offset) = lemire_bounded_bits(value, n as u64, bits) {
+                return Some(offset);
+            }
+        }
+    }
+
+    /// Unbiased index in [0, n) by rejection sampling
+    fn uniform_index(&mut self, n: usize) -> Option<usize> {
+        let n = n as u64;
+        // Reject the top 2^64 mod n values so every residue is equally likely
+        let limit = u64::MAX - (u64::MAX % n + 1) % n;
+        loop {
+            let word = self.next_word()?;
+            if word <= limit {
+                return Some((word % n) as usize);
+            }
+        }
+    }
+
+    /// Unbiased integer in [0, n) from two words, for ranges wider than 64 bits
+    fn uniform_u128(&mut self, n: u128) -> Option<u128> {
+        let limit = u128::MAX - (u128::MAX % n + 1) % n;
+        loop {
+            let word = ((self.next_word()? as u128) << 64) | self.next_word()? as u128;
+            if word <= limit {
+                return Some(word % n);
+            }
+        }
+    }
+
+    /// Fisher-Yates over the first `k` positions of `items`
+    ///
+    /// Afterwards `items[..k]` is a uniform random sample without replacement,
+    /// in random order; with `k >= items.len() - 1` the whole slice is a uniform
+    /// permutation. Draws one word per position, plus any rejections.
+    fn partial_shuffle<T>(&mut self, items: &mut [T], k: usize) -> Option<()> {
+        for i in 0..k.min(items.len()) {
+            let j = i + self.uniform_index(items.len() - i)?;
+            items.swap(i, j);
+        }
+        Some(())
+    }
+
+    /// Uniform float in [0, 1) from the top 53 bits of a word
+    fn unit_float(&mut self) -> Option<f64> {
+        Some((self.next_word()? >> 11) as f64 * (1.0 / (1u64 << 53) as f64))
+    }
+
+    /// Uniform float in (0, 1), re-drawing zero so it is safe to take logarithms
+    fn nonzero_unit_float(&mut self) -> Option<f64> {
+        loop {
+            let unit = self.unit_float()?;
+            if unit > 0.0 {
+                return Some(unit);
+            }
+        }
+    }
+
+    /// Index drawn with probability proportional to its weight
+    fn weighted_index(&mut self, cumulative: &[f64]) -> Option<usize> {
+        let total = *cumulative.last()?;
+        let target = self.unit_float()? * total;
+        // First bucket whose cumulative weight exceeds the draw; zero weights are never hit
+        Some(cumulative.partition_point(|&c| c <= target).min(cumulative.len() - 1))
+    }
+}
+
+/// Validate weights and build their cumulative distribution
+fn cumulative_weights(weights: &[f64], items: usize) -> Option<Vec<f64>> {
+    if weights.len() != items || weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+        return None;
+    }
+    let cumulative: Vec<f64> = weights
+        .iter()
+        .scan(0.0, |sum, w| {
+            *sum += w;
+            Some(*sum)
+        })
+        .collect();
+    match cumulative.last() {
+        Some(total) if *total > 0.0 && total.is_finite() => Some(cumulative),
+        _ => None,
+    }
+}
+
+/// POST /api/choice - Select items uniformly or by weight
+async fn serve_choice(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ApiKeyQuery>,
+    headers: HeaderMap,
+    Json(request): Json<ChoiceRequest>,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!(
+        "items={} count={} weighted={}",
+        request.items.len(),
+        request.count,
+        request.weights.is_some()
+    );
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/choice",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    let cumulative = match &request.weights {
+        Some(weights) => cumulative_weights(weights, request.items.len()).map(Some),
+        None => Some(None),
+    };
+    let valid_sizes = !request.items.is_empty()
+        && request.items.len() <= MAX_CHOICE_ITEMS
+        && request.count > 0
+        && request.count <= MAX_CHOICE_COUNT;
+    let Some(cumulative) = cumulative.filter(|_| valid_sizes) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/choice",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    // Draw one word per selection; rejection sampling pops more if needed
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/choice",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut words = EntropyWords::new(&state.buffer, request.count).ok_or_else(unavailable)?;
+
+    let mut selected = Vec::with_capacity(request.count);
+    for _ in 0..request.count {
+        let index = match &cumulative {
+            Some(cumulative) => words.weighted_index(cumulative),
+            None => words.uniform_index(request.items.len()),
+        }
+        .ok_or_else(unavailable)?;
+        selected.push(&request.items[index]);
+    }
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit("/api/choice", &api_key, words.bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/choice",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(selected).into_response())
+}
+
+/// Maximum number of items accepted by /api/shuffle
+const MAX_SHUFFLE_ITEMS: usize = 10_000;
+
+/// Shuffle `items` in place uniformly, or None if the buffer runs dry
+fn shuffle_items<T>(words: &mut EntropyWords, items: &mut [T]) -> Option<()> {
+    words.partial_shuffle(items, items.len().saturating_sub(1))
+}
+
+/// POST /api/shuffle - Uniformly shuffled copy of a JSON array
+///
+/// Elements are returned byte for byte as sent (no number reformatting); arrays of
+/// zero or one element need no entropy.
+async fn serve_shuffle(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ApiKeyQuery>,
+    headers: HeaderMap,
+    Json(mut items): Json<Vec<Box<serde_json::value::RawValue>>>,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("items={}", items.len());
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/shuffle",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    if items.len() > MAX_SHUFFLE_ITEMS {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/shuffle",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // One word per swap; rejection sampling pops more if needed
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/shuffle",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut words = EntropyWords::new(&state.buffer, items.len().saturating_sub(1)).ok_or_else(unavailable)?;
+    shuffle_items(&mut words, &mut items).ok_or_else(unavailable)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit("/api/shuffle", &api_key, words.bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/shuffle",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(items).into_response())
+}
+
+/// Maximum number of samples per /api/exponential or /api/poisson request
+const MAX_DISTRIBUTION_COUNT: usize = 1000;
+
+/// Largest Poisson rate; Knuth's algorithm draws about `lambda + 1` uniforms per sample
+const MAX_POISSON_LAMBDA: f64 = 100.0;
+
+/// Query parameters for /api/exponential and /api/poisson endpoints
+#[derive(serde::Deserialize)]
+struct RateQuery {
+    count: usize,
+    lambda: f64,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Exponential sample with rate `lambda` by inversion: -ln(1 - u) / lambda
+fn exponential_sample(words: &mut EntropyWords, lambda: f64) -> Option<f64> {
+    let unit = words.nonzero_unit_float()?;
+    Some(-(1.0 - unit).ln() / lambda)
+}
+
+/// Poisson sample with mean `lambda` (Knuth): count uniforms until their product drops below e^-lambda
+fn poisson_sample(words: &mut EntropyWords, lambda: f64) -> Option<u64> {
+    let threshold = (-lambda).exp();
+    let mut product = 1.0;
+    let mut k = 0;
+    loop {
+        product *= words.nonzero_unit_float()?;
+        if product <= threshold {
+            return Some(k);
+        }
+        k += 1;
+    }
+}
+
+/// GET /api/exponential - Exponentially distributed floats with rate `lambda`
+async fn serve_exponential(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<RateQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_rate_samples(&state, addr, &headers, params, "/api/exponential", f64::MAX, exponential_sample)
+}
+
+/// GET /api/poisson - Poisson-distributed counts with mean `lambda`
+async fn serve_poisson(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<RateQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_rate_samples(&state, addr, &headers, params, "/api/poisson", MAX_POISSON_LAMBDA, poisson_sample)
+}
+
+/// Shared body of the rate-parameterised distribution endpoints
+fn serve_rate_samples<T: serde::Serialize>(
+    state: &AppState,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    params: RateQuery,
+    endpoint: &str,
+    max_lambda: f64,
+    sample: impl Fn(&mut EntropyWords, f64) -> Option<T>,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(headers);
+    let request_info = format!("count={} lambda={}", params.count, params.lambda);
+
+    let api_key = authorize_request(state, addr, headers, endpoint, params.api_key, &request_info)?;
+
+    // Validate parameters
+    let valid_lambda = params.lambda.is_finite() && params.lambda > 0.0 && params.lambda <= max_lambda;
+    if !valid_lambda || params.count == 0 || params.count > MAX_DISTRIBUTION_COUNT {
+        log_client_request(
+            addr,
+            &user_agent,
+            endpoint,
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // One word per sample up front; re-draws and Knuth's extra uniforms pop more
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            endpoint,
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut words = EntropyWords::new(&state.buffer, params.count).ok_or_else(unavailable)?;
+    let samples = (0..params.count)
+        .map(|_| sample(&mut words, params.lambda))
+        .collect::<Option<Vec<T>>>()
+        .ok_or_else(unavailable)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit(endpoint, &api_key, words.bytes_consumed);
+
+    log_client_request(addr, &user_agent, endpoint, &api_key, &request_info, StatusCode::OK);
+
+    Ok(Json(samples).into_response())
+}
+
+/// Largest count served by /api/bool and /api/coinflip
+const MAX_BOOL_COUNT: usize = 10_000;
+
+/// True with probability `threshold / 2^64`
+///
+/// Compares random bits with the threshold's binary expansion, most
+/// significant first, and stops at the first difference or once the
+/// remaining threshold bits are zero: one bit per draw for a threshold of
+/// 1/2 and at most two on average otherwise.
+fn bernoulli(bits: &mut EntropyBitReader, threshold: u64) -> Option<bool> {
+    for shift in (0..64).rev() {
+        let threshold_bit = (threshold >> shift) & 1 == 1;
+        if (bits.read_bits(1)? == 1) != threshold_bit {
+            // The random value is below the threshold where it has a 0 against a 1
+            return Some(threshold_bit);
+        }
+        if threshold & ((1u64 << shift) - 1) == 0 {
+            // Equal so far and nothing left in the threshold: not below it
+            return Some(false);
+        }
+    }
+    Some(false)
+}
+
+/// Fixed-point threshold for `bernoulli` of a probability in (0, 1)
+fn probability_threshold(probability: f64) -> u64 {
+    (probability * 2f64.powi(64)) as u64
+}
+
+/// Query parameters for /api/bool endpoint
+#[derive(serde::Deserialize)]
+struct BoolQuery {
+    #[serde(default = "default_bool_count")]
+    count: usize,
+    #[serde(default = "default_probability")]
+    probability: f64,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Query parameters for /api/coinflip endpoint
+#[derive(serde::Deserialize)]
+struct CoinflipQuery {
+    #[serde(default = "default_bool_count")]
+    count: usize,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_bool_count() -> usize {
+    1
+}
+
+fn default_probability() -> f64 {
+    0.5
+}
+
+/// GET /api/bool - Booleans that are true with `probability`, drawn bit by bit
+async fn serve_bool(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<BoolQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let request_info = format!("count={} probability={}", params.count, params.probability);
+    let values = serve_bools(
+        &state,
+        addr,
+        &headers,
+        "/api/bool",
+        params.api_key,
+        params.count,
+        params.probability,
+        &request_info,
+    )?;
+    Ok(Json(values).into_response())
+}
+
+/// GET /api/coinflip - Fair coin flips as "heads"/"tails", one bit each
+async fn serve_coinflip(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<CoinflipQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let request_info = format!("count={}", params.count);
+    let values = serve_bools(
+        &state,
+        addr,
+        &headers,
+        "/api/coinflip",
+        params.api_key,
+        params.count,
+        0.5,
+        &request_info,
+    )?;
+    let flips: Vec<&str> = values.into_iter().map(|heads| if heads { "heads" } else { "tails" }).collect();
+    Ok(Json(flips).into_response())
+}
+
+/// Shared body of the boolean endpoints
+#[allow(clippy::too_many_arguments)]
+fn serve_bools(
+    state: &AppState,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    endpoint: &str,
+    query_key: Option<String>,
+    count: usize,
+    probability: f64,
+    request_info: &str,
+) -> Result<Vec<bool>, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(headers);
+
+    let api_key = authorize_request(state, addr, headers, endpoint, query_key, request_info)?;
+
+    // Validate parameters; the comparison also rejects NaN
+    if !(probability > 0.0 && probability < 1.0) || count == 0 || count > MAX_BOOL_COUNT {
+        log_client_request(
+            addr,
+            &user_agent,
+            endpoint,
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Refuse up front unless the expected bits are buffered (one per fair draw, two per biased draw)
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            endpoint,
+            &api_key,
+            request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let expected_bits = if probability == 0.5 { count } else { 2 * count };
+    if state.buffer.len() < expected_bits.div_ceil(8) {
+        return Err(unavailable());
+    }
+    let mut bits = EntropyBitReader::new(&state.buffer);
+    let threshold = probability_threshold(probability);
+    let values = (0..count)
+        .map(|_| bernoulli(&mut bits, threshold))
+        .collect::<Option<Vec<bool>>>()
+        .ok_or_else(unavailable)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bits.bytes_consumed(), latency);
+    state.audit(endpoint, &api_key, bits.bytes_consumed());
+
+    log_client_request(addr, &user_agent, endpoint, &api_key, request_info, StatusCode::OK);
+
+    Ok(values)
+}
+
+/// Most decimal digits per /api/decimal value (10^30 fits comfortably in a u128)
+const MAX_DECIMAL_DIGITS: u32 = 30;
+
+/// Query parameters for /api/decimal endpoint
+#[derive(serde::Deserialize)]
+struct DecimalQuery {
+    #[serde(default = "default_decimal_count")]
+    count: usize,
+    digits: u32,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_decimal_count() -> usize {
+    1
+}
+
+/// Uniform decimal in [0, 1) with exactly `digits` fractional digits, e.g. "0.0420"
+///
+/// Formatted from an integer in [0, 10^digits) so no binary float rounding is involved.
+fn random_decimal(words: &mut EntropyWords, digits: u32) -> Option<String> {
+    let value = words.uniform_u128(10u128.pow(digits))?;
+    Some(format!("0.{:0width$}", value, width = digits as usize))
+}
+
+/// GET /api/decimal - Uniform decimal fractions as exact strings
+async fn serve_decimal(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<DecimalQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("count={} digits={}", params.count, params.digits);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/decimal",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    if !(1..=1000).contains(&params.count) || !(1..=MAX_DECIMAL_DIGITS).contains(&params.digits) {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/decimal",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Two words per value; rejection sampling pops more if needed
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/decimal",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut words = EntropyWords::new(&state.buffer, params.count * 2).ok_or_else(unavailable)?;
+    let decimals = (0..params.count)
+        .map(|_| random_decimal(&mut words, params.digits))
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(unavailable)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit("/api/decimal", &api_key, words.bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/decimal",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(decimals).into_response())
+}
+
+/// Longest string served by /api/string
+const MAX_STRING_LENGTH: usize = 4096;
+
+/// Largest custom alphabet for /api/string; one byte is drawn per character
+const MAX_ALPHABET_SIZE: usize = 256;
+
+/// Bitcoin base58 alphabet (no 0, O, I or l)
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Query parameters for /api/string endpoint
+#[derive(serde::Deserialize)]
+struct StringQuery {
+    length: usize,
+    #[serde(default = "default_alphabet")]
+    alphabet: String,
+    /// Characters of a `custom` alphabet
+    #[serde(default)]
+    chars: Option<String>,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_alphabet() -> String {
+    "alnum".to_string()
+}
+
+/// Characters of a preset alphabet, or of `chars` for `custom`
+///
+/// Returns None for an unknown preset, or a custom set that is too small,
+/// too large or contains duplicates (which would bias the output).
+fn resolve_alphabet(name: &str, chars: Option<&str>) -> Option<Vec<char>> {
+    let alphabet: Vec<char> = match name {
+        "base58" => BASE58_ALPHABET.chars().collect(),
+        "alnum" | "alphanumeric" => ('0'..='9').chain('A'..='Z').chain('a'..='z').collect(),
+        "hex" => ('0'..='9').chain('a'..='f').collect(),
+        "lowercase" => ('a'..='z').collect(),
+        "custom" => chars?.chars().collect(),
+        _ => return None,
+    };
+
+    let unique: std::collections::HashSet<char> = alphabet.iter().copied().collect();
+    let valid = (2..=MAX_ALPHABET_SIZE).contains(&alphabet.len()) && unique.len() == alphabet.len();
+    valid.then_some(alphabet)
+}
+
+/// Uniform random string over `alphabet`, with the number of entropy bytes it consumed
+///
+/// Each character comes from one byte; bytes at or above the largest multiple
+/// of the alphabet size are rejected so every character is equally likely.
+fn random_string(buffer: &EntropyBuffer, alphabet: &[char], length: usize) -> Option<(String, usize)> {
+    let n = alphabet.len();
+    let limit = 256 - 256 % n;
+    let mut output = String::with_capacity(length);
+    let mut produced = 0;
+    let mut consumed = 0;
+
+    while produced < length {
+        // Expected bytes for the remaining characters given the rejection rate
+        let wanted = ((length - produced) * 256).div_ceil(limit);
+        let data = buffer.pop(wanted)?;
+        consumed += data.len();
+        for &byte in data.iter().filter(|&&byte| (byte as usize) < limit) {
+            output.push(alphabet[byte as usize % n]);
+            produced += 1;
+            if produced == length {
+                break;
+            }
+        }
+    }
+
+    Some((output, consumed))
+}
+
+/// GET /api/string - Random string over a preset or custom alphabet, as plain text
+async fn serve_string(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<StringQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("length={} alphabet={}", params.length, params.alphabet);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/string",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    let alphabet = resolve_alphabet(&params.alphabet, params.chars.as_deref());
+    let Some(alphabet) = alphabet.filter(|_| (1..=MAX_STRING_LENGTH).contains(&params.length)) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/string",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let Some((string, bytes_consumed)) = random_string(&state.buffer, &alphabet, params.length) else {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/string",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes_consumed, latency);
+    state.audit("/api/string", &api_key, bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/string",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok((
+        StatusCode::OK,
+        [(hyper::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        string,
+    )
+        .into_response())
+}
+
+/// Most dice in one /api/dice roll
+const MAX_DICE: u32 = 100;
+
+/// Most sides on a die for /api/dice
+const MAX_DIE_SIDES: u64 = 1_000_000;
+
+/// Largest absolute modifier for /api/dice
+const MAX_DICE_MODIFIER: i64 = 1_000_000;
+
+/// Query parameters for /api/dice endpoint
+#[derive(serde::Deserialize)]
+struct DiceQuery {
+    notation: String,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Parsed dice notation `NdS+M`
+#[derive(Debug, PartialEq, Eq)]
+struct DiceNotation {
+    count: u32,
+    sides: u64,
+    modifier: i64,
+}
+
+/// Parse standard dice notation: `[N]dS[+M|-M]`, with `d%` for `d100`
+///
+/// The dice count defaults to 1. Returns None for malformed notation or
+/// values outside the limits.
+fn parse_dice_notation(notation: &str) -> Option<DiceNotation> {
+    let notation = notation.trim().to_ascii_lowercase();
+    let (count, rest) = notation.split_once('d')?;
+    let (sides, modifier) = match rest.find(['+', '-']) {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, ""),
+    };
+
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let count = match count {
+        "" => 1,
+        count if is_number(count) => count.parse().ok()?,
+        _ => return None,
+    };
+    let sides = match sides {
+        "%" => 100,
+        sides if is_number(sides) => sides.parse().ok()?,
+        _ => return None,
+    };
+    let modifier = match modifier.split_at_checked(1) {
+        None => 0,
+        Some((sign, value)) if is_number(value) => {
+            let value: i64 = value.parse().ok()?;
+            if sign == "-" { -value } else { value }
+        }
+        Some(_) => return None,
+    };
+
+    let valid = (1..=MAX_DICE).contains(&count)
+        && (2..=MAX_DIE_SIDES).contains(&sides)
+        && modifier.abs() <= MAX_DICE_MODIFIER;
+    valid.then_some(DiceNotation { count, sides, modifier })
+}
+
+/// Roll the dice of `notation` with rejection sampling, returning the faces and the bytes consumed
+fn roll_dice(buffer: &EntropyBuffer, notation: &DiceNotation) -> Option<(Vec<u64>, usize)> {
+    let range = notation.sides as u128;
+    let width = integer_width(range);
+    let mut words = EntropyWords::with_bytes(buffer, notation.count as usize * width)?;
+    let rolls = (0..notation.count)
+        .map(|_| Some(words.uniform_narrow(range, width)? + 1))
+        .collect::<Option<Vec<_>>>()?;
+    Some((rolls, words.bytes_consumed))
+}
+
+/// Response of /api/dice
+#[derive(Debug, Serialize, Deserialize)]
+struct DiceResponse {
+    rolls: Vec<u64>,
+    modifier: i64,
+    total: i64,
+}
+
+/// GET /api/dice - Roll dice given in standard notation such as `3d6+2`
+async fn serve_dice(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<DiceQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("notation={}", params.notation);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/dice",
+        params.api_key,
+        &request_info,
+    )?;
+
+    let Some(notation) = parse_dice_notation(&params.notation) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/dice",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let needed = notation.count as usize * integer_width(notation.sides as u128);
+    let Some(((rolls, bytes_consumed), origin)) = state.draw("/api/dice", needed, |buffer| roll_dice(buffer, &notation)) else {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/dice",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
+
+    // Record metrics
+    let bytes_consumed = origin.quantum_bytes(bytes_consumed);
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes_consumed, latency);
+    state.audit("/api/dice", &api_key, bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/dice",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    // At most 100 dice of 10^6 sides, so the sum cannot overflow
+    let total = rolls.iter().sum::<u64>() as i64 + notation.modifier;
+    Ok(origin.label(
+        Json(DiceResponse {
+            rolls,
+            modifier: notation.modifier,
+            total,
+        })
+        .into_response(),
+    ))
+}
+
+/// Seed sizes served by /api/seed, in bits (powers of two)
+const SEED_BITS_RANGE: std::ops::RangeInclusive<u32> = 64..=4096;
+
+/// Query parameters for /api/seed endpoint
+#[derive(serde::Deserialize)]
+struct SeedQuery {
+    #[serde(default = "default_seed_bits")]
+    bits: u32,
+    #[serde(default = "default_encoding")]
+    encoding: String,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_seed_bits() -> u32 {
+    256
+}
+
+/// Response of /api/seed
+#[derive(Debug, Serialize, Deserialize)]
+struct SeedResponse {
+    /// Seed bytes in the requested text encoding
+    seed: String,
+    bits: u32,
+    encoding: String,
+    /// Identifier to log and cite alongside results produced from this seed
+    seed_id: String,
+    timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// GET /api/seed - Single PRNG seed with an identifier for reproducible runs
+///
+/// The gateway does not keep the seed; clients record it with its `seed_id`.
+async fn serve_seed(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<SeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("bits={} encoding={}", params.bits, params.encoding);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/seed",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters: a power-of-two bit count and a text encoding
+    let encoding = EncodingFormat::parse(&params.encoding)
+        .filter(|encoding| *encoding != EncodingFormat::Binary)
+        .filter(|_| SEED_BITS_RANGE.contains(&params.bits) && params.bits.is_power_of_two());
+    let Some(encoding) = encoding else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/seed",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let bytes = (params.bits / 8) as usize;
+    let (data, _) = state.draw("/api/seed", bytes, |buffer| buffer.pop(bytes)).ok_or_else(|| {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/seed",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    let response = SeedResponse {
+        seed: match encoding {
+            EncodingFormat::Base64 => encode_base64(&data),
+            EncodingFormat::Base32 => encode_base32(&data),
+            EncodingFormat::Base85 => encode_base85(&data),
+            _ => encode_hex(&data),
+        },
+        bits: params.bits,
+        encoding: encoding.name().to_string(),
+        seed_id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now(),
+    };
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes, latency);
+    state.audit("/api/seed", &api_key, bytes);
+    state.metrics.record_seed(params.bits);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/seed",
+        &api_key,
+        &format!("{} seed_id={}", request_info, response.seed_id),
+        StatusCode::OK,
+    );
+
+    Ok((
+        StatusCode::OK,
+        [(hyper::header::CACHE_CONTROL, "no-store")],
+        Json(response),
+    )
+        .into_response())
+}
+
+/// Seed length of the /api/prng generators in bytes
+const PRNG_SEED_BYTES: usize = 32;
+
+/// Deterministic generators offered by /api/prng
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrngAlgorithm {
+    ChaCha8,
+    ChaCha12,
+    ChaCha20,
+}
+
+impl PrngAlgorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "chacha8" => Some(Self::ChaCha8),
+            "chacha12" => Some(Self::ChaCha12),
+            "chacha20" => Some(Self::ChaCha20),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::ChaCha8 => "chacha8",
+            Self::ChaCha12 => "chacha12",
+            Self::ChaCha20 => "chacha20",
+        }
+    }
+
+    /// The first `len` bytes of the generator's stream from `seed`
+    ///
+    /// Matches `rand_chacha`'s `fill_bytes`, so clients can replay it locally.
+    fn expand(self, seed: [u8; PRNG_SEED_BYTES], len: usize) -> Vec<u8> {
+        use rand::{RngCore, SeedableRng};
+        let mut output = vec![0u8; len];
+        match self {
+            Self::ChaCha8 => rand_chacha::ChaCha8Rng::from_seed(seed).fill_bytes(&mut output),
+            Self::ChaCha12 => rand_chacha::ChaCha12Rng::from_seed(seed).fill_bytes(&mut output),
+            Self::ChaCha20 => rand_chacha::ChaCha20Rng::from_seed(seed).fill_bytes(&mut output),
+        }
+        output
+    }
+}
+
+/// Query parameters for /api/prng endpoint
+#[derive(serde::Deserialize)]
+struct PrngQuery {
+    /// Hex seed to replay; a quantum seed is drawn when absent
+    #[serde(default)]
+    seed: Option<String>,
+    bytes: usize,
+    #[serde(default = "default_prng_algo")]
+    algo: String,
+    #[serde(default = "default_encoding")]
+    encoding: String,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_prng_algo() -> String {
+    "chacha20".to_string()
+}
+
+/// Response of /api/prng
+#[derive(Debug, Serialize, Deserialize)]
+struct PrngResponse {
+    /// Hex seed; pass it back as `seed` to replay the stream
+    seed: String,
+    /// `quantum` when drawn from the buffer, `client` when supplied
+    seed_source: String,
+    algo: String,
+    bytes: usize,
+    encoding: String,
+    /// Expanded stream in the requested text encoding
+    data: String,
+    /// Always `prng`: `data` is deterministic expansion, not raw quantum entropy
+    output: String,
+}
+
+/// GET /api/prng - Reproducible CSPRNG stream expanded from a quantum or supplied seed
+///
+/// Only the seed comes from the quantum buffer; the response carries
+/// `X-Entropy-Source: prng` so the stream is never mistaken for raw entropy.
+async fn serve_prng(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<PrngQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!(
+        "bytes={} algo={} encoding={} seeded={}",
+        params.bytes,
+        params.algo,
+        params.encoding,
+        params.seed.is_some()
+    );
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/prng",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters: a known generator, a text encoding and a 32-byte hex seed if given
+    let algo = PrngAlgorithm::parse(&params.algo)
+        .filter(|_| params.bytes > 0 && params.bytes <= state.config.max_request_bytes());
+    let encoding = EncodingFormat::parse(&params.encoding).filter(|encoding| *encoding != EncodingFormat::Binary);
+    let client_seed = params
+        .seed
+        .as_deref()
+        .map(|seed| {
+            let well_formed = seed.len() == 2 * PRNG_SEED_BYTES && seed.bytes().all(|b| b.is_ascii_hexdigit());
+            well_formed
+                .then(|| decode_hex(seed).ok())
+                .flatten()
+                .and_then(|seed| <[u8; PRNG_SEED_BYTES]>::try_from(seed).ok())
+                .ok_or(())
+        })
+        .transpose();
+    let (Some(algo), Some(encoding), Ok(client_seed)) = (algo, encoding, client_seed) else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/prng",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let (seed, seed_source, consumed) = match client_seed {
+        Some(seed) => (seed, "client", 0),
+        None => {
+            let (data, _) = state
+                .draw("/api/prng", PRNG_SEED_BYTES, |buffer| buffer.pop(PRNG_SEED_BYTES))
+                .ok_or_else(|| {
+                    state.metrics.record_request_failure();
+                    log_client_request(
+                        addr,
+                        &user_agent,
+                        "/api/prng",
+                        &api_key,
+                        &request_info,
+                        StatusCode::SERVICE_UNAVAILABLE,
+                    );
+                    StatusCode::SERVICE_UNAVAILABLE
+                })?;
+            let mut seed = [0u8; PRNG_SEED_BYTES];
+            seed.copy_from_slice(&data);
+            (seed, "quantum", PRNG_SEED_BYTES)
+        }
+    };
+
+    let stream = algo.expand(seed, params.bytes);
+    let response = PrngResponse {
+        seed: encode_hex(&seed),
+        seed_source: seed_source.to_string(),
+        algo: algo.name().to_string(),
+        bytes: params.bytes,
+        encoding: encoding.name().to_string(),
+        data: match encoding {
+            EncodingFormat::Base64 => encode_base64(&stream),
+            EncodingFormat::Base32 => encode_base32(&stream),
+            EncodingFormat::Base85 => encode_base85(&stream),
+            _ => encode_hex(&stream),
+        },
+        output: "prng".to_string(),
+    };
+
+    // Record metrics; only the seed counts as entropy served
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(consumed, latency);
+    if consumed > 0 {
+        state.audit("/api/prng", &api_key, consumed);
+    }
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/prng",
+        &api_key,
+        &format!("{} seed_source={}", request_info, seed_source),
+        StatusCode::OK,
+    );
+
+    Ok((
+        StatusCode::OK,
+        [
+            (hyper::header::CACHE_CONTROL, "no-store"),
+            (hyper::header::HeaderName::from_static(ENTROPY_SOURCE_HEADER), "prng"),
+        ],
+        Json(response),
+    )
+        .into_response())
+}
+
+/// Largest permutation served by /api/permutation
+const MAX_PERMUTATION_SIZE: usize = 100_000;
+
+/// Query parameters for /api/permutation endpoint
+#[derive(serde::Deserialize)]
+struct PermutationQuery {
+    n: usize,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Uniform random permutation of 0..n, or None if the buffer runs dry
+fn random_permutation(words: &mut EntropyWords, n: usize) -> Option<Vec<usize>> {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    words.partial_shuffle(&mut permutation, n.saturating_sub(1))?;
+    Some(permutation)
+}
+
+/// GET /api/permutation - Uniformly shuffled range 0..n
+async fn serve_permutation(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<PermutationQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("n={}", params.n);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/permutation",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    if params.n == 0 || params.n > MAX_PERMUTATION_SIZE {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/permutation",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // One word per swap; rejection sampling pops more if needed
+    let insufficient = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/permutation",
+            &api_key,
+            &request_info,
+            StatusCode::INSUFFICIENT_STORAGE,
+        );
+        StatusCode::INSUFFICIENT_STORAGE
+    };
+    let mut words = EntropyWords::new(&state.buffer, params.n - 1).ok_or_else(insufficient)?;
+    let permutation = random_permutation(&mut words, params.n).ok_or_else(insufficient)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit("/api/permutation", &api_key, words.bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/permutation",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(permutation).into_response())
+}
+
+/// Largest range 1..=n accepted by /api/sample
+const MAX_SAMPLE_RANGE: usize = 100_000;
+
+/// Query parameters for /api/sample endpoint
+#[derive(serde::Deserialize)]
+struct SampleQuery {
+    n: usize,
+    k: usize,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// Entropy words needed to draw `k` of `n` values; the last position of a full shuffle is fixed
+fn sample_swaps(n: usize, k: usize) -> usize {
+    k.min(n.saturating_sub(1))
+}
+
+/// `k` distinct values from 1..=n in random order, or None if the buffer runs dry
+fn random_sample(words: &mut EntropyWords, n: usize, k: usize) -> Option<Vec<usize>> {
+    let mut values: Vec<usize> = (1..=n).collect();
+    words.partial_shuffle(&mut values, sample_swaps(n, k))?;
+    values.truncate(k);
+    Some(values)
+}
+
+/// GET /api/sample - `k` distinct integers from 1..=n (sampling without replacement)
+async fn serve_sample(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<SampleQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("n={} k={}", params.n, params.k);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/sample",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    if params.k == 0 || params.k > params.n || params.n > MAX_SAMPLE_RANGE {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/sample",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // One word per selected position; rejection sampling pops more if needed
+    let insufficient = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/sample",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut words = EntropyWords::new(&state.buffer, sample_swaps(params.n, params.k))
+        .ok_or_else(insufficient)?;
+    let sample = random_sample(&mut words, params.n, params.k).ok_or_else(insufficient)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(words.bytes_consumed, latency);
+    state.audit("/api/sample", &api_key, words.bytes_consumed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/sample",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(sample).into_response())
+}
+
+/// Maximum number of operations per /api/batch request
+const MAX_BATCH_OPERATIONS: usize = 100;
+
+/// One draw in a /api/batch request, with the same bounds as the single-draw endpoints
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum BatchOperation {
+    Integers {
+        count: usize,
+        #[serde(default = "default_min")]
+        min: i64,
+        #[serde(default = "default_max")]
+        max: i64,
+    },
+    Floats {
+        count: usize,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    Uuid {
+        #[serde(default = "default_uuid_count")]
+        count: usize,
+    },
+}
+
+impl BatchOperation {
+    /// Check the parameters against the limits of the matching endpoint
+    fn is_valid(&self) -> bool {
+        match *self {
+            Self::Integers { count, min, max } => (1..=1000).contains(&count) && min < max,
+            Self::Floats { count, min, max } => {
+                (1..=1000).contains(&count)
+                    && valid_float_range(min.unwrap_or(0.0), max.unwrap_or(1.0))
+            }
+            Self::Uuid { count } => (1..=100).contains(&count),
+        }
+    }
+
+    /// Entropy bytes consumed by this operation
+    fn entropy_bytes(&self) -> usize {
+        match *self {
+            Self::Integers { count, min, max } => count * integer_width(integer_range(min, max)),
+            Self::Floats { count, .. } => count * 8,
+            Self::Uuid { count } => count * 16,
+        }
+    }
+
+    fn execute(&self, buffer: &EntropyBuffer) -> Option<BatchResult> {
+        match *self {
+            Self::Integers { count, min, max } => {
+                generate_integers(buffer, count, min, max)
+                    .map(|(integers, _)| BatchResult::Integers(integers))
+            }
+            Self::Floats { count, min, max } => {
+                generate_floats(buffer, count, min.unwrap_or(0.0), max.unwrap_or(1.0), FloatPrecision::Bits53)
+                    .map(BatchResult::Floats)
+            }
+            Self::Uuid { count } => generate_uuids(buffer, count, UuidVersion::V4).map(BatchResult::Uuids),
+        }
+    }
+}
+
+/// Result of one batch operation, serialized as a plain JSON array
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum BatchResult {
+    Integers(Vec<i64>),
+    Floats(Vec<f64>),
+    Uuids(Vec<String>),
+}
+
+/// POST /api/batch - Run several independent draws in one request
+///
+/// The whole batch counts as a single rate-limit token. Results are returned
+/// in request order; the request fails if the buffer cannot serve all of them.
+async fn serve_batch(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ApiKeyQuery>,
+    headers: HeaderMap,
+    Json(operations): Json<Vec<BatchOperation>>,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!("operations={}", operations.len());
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/batch",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    let valid = !operations.is_empty()
+        && operations.len() <= MAX_BATCH_OPERATIONS
+        && operations.iter().all(BatchOperation::is_valid);
+    if !valid {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/batch",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let unavailable = || {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/batch",
+            &api_key,
+            &request_info,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    // Check availability up front so a short buffer does not consume a partial batch
+    let bytes_needed: usize = operations.iter().map(BatchOperation::entropy_bytes).sum();
+    if state.buffer.len() < bytes_needed {
+        return Err(unavailable());
+    }
+
+    let results = operations
+        .iter()
+        .map(|operation| operation.execute(&state.buffer))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(unavailable)?;
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes_needed, latency);
+    state.audit("/api/batch", &api_key, bytes_needed);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/batch",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok(Json(results).into_response())
+}
+
+/// Entropy popped as HKDF input key material per /api/derive request
+///
+/// Matches the SHA-256 pseudorandom key size, which bounds the output's entropy anyway.
+const DERIVE_IKM_LEN: usize = 32;
+
+/// Maximum length of the /api/derive `info` context string
+const MAX_DERIVE_INFO_LEN: usize = 1024;
+
+/// Query parameters for /api/derive endpoint
+#[derive(serde::Deserialize)]
+struct DeriveQuery {
+    bytes: usize,
+    /// HKDF context string for domain separation
+    #[serde(default)]
+    info: String,
+    #[serde(default = "default_encoding")]
+    encoding: String,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// GET /api/derive - Derive key material from fresh entropy with HKDF-SHA256
+///
+/// Each request pops new entropy, so outputs are independent; `info` separates
+/// keys derived for different purposes.
+async fn serve_derive(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<DeriveQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let request_info = format!(
+        "bytes={} info_len={} encoding={}",
+        params.bytes,
+        params.info.len(),
+        params.encoding
+    );
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/derive",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    let encoding = EncodingFormat::parse(&params.encoding).filter(|_| {
+        (1..=MAX_DERIVE_LEN).contains(&params.bytes) && params.info.len() <= MAX_DERIVE_INFO_LEN
+    });
+    let Some(encoding) = encoding else {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/derive",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let unavailable = |status| {
+        state.metrics.record_request_failure();
+        log_client_request(addr, &user_agent, "/api/derive", &api_key, &request_info, status);
+        status
+    };
+    let ikm = state
+        .buffer
+        .pop(DERIVE_IKM_LEN)
+        .ok_or_else(|| unavailable(StatusCode::SERVICE_UNAVAILABLE))?;
+    let derived = EntropyMixer::new(MixingStrategy::Hkdf)
+        .derive(&[ikm.to_vec()], &[], params.info.as_bytes(), params.bytes)
+        .map_err(|_| unavailable(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    let body = match encoding {
+        EncodingFormat::Binary => derived,
+        EncodingFormat::Hex => encode_hex(&derived).into_bytes(),
+        EncodingFormat::Base64 => encode_base64(&derived).into_bytes(),
+        EncodingFormat::Base32 => encode_base32(&derived).into_bytes(),
+        EncodingFormat::Base85 => encode_base85(&derived).into_bytes(),
+    };
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(DERIVE_IKM_LEN, latency);
+    state.audit("/api/derive", &api_key, DERIVE_IKM_LEN);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/derive",
+        &api_key,
+        &request_info,
+        StatusCode::OK,
+    );
+
+    Ok((
+        StatusCode::OK,
+        [(hyper::header::CONTENT_TYPE, encoding.mime_type())],
+        body,
+    )
+        .into_response())
+}
+
+/// GET /metrics - Prometheus metrics
+async fn get_metrics(State(state): State<AppState>) -> String {
+    state.metrics.prometheus_format()
+}
+
+/// Monte Carlo test parameters
+#[derive(Debug, Deserialize)]
+struct MonteCarloParams {
+    #[serde(default = "default_iterations")]
+    iterations: u64,
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+fn default_iterations() -> u64 {
+    1_000_000
+}
+
+/// Monte Carlo test results
+#[derive(Debug, Serialize)]
+struct MonteCarloResult {
+    estimated_pi: f64,
+    error: f64,
+    error_percent: f64,
+    iterations: u64,
+    convergence_rate: String,
+    quality_assessment: String,
+    note: String,
+    quantum_vs_pseudo: Option<PseudoComparison>,
+}
+
+#[derive(Debug, Serialize)]
+struct PseudoComparison {
+    quantum_error: f64,
+    pseudo_error: f64,
+    improvement_factor: f64,
+}
+
+/// GET /api/test/monte-carlo - Run Monte Carlo π estimation test
+async fn monte_carlo_test(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<MonteCarloParams>,
+) -> Result<Json<MonteCarloResult>, AppError> {
+    let user_agent = extract_user_agent(&headers);
+
+    // Extract and validate API key
+    let api_key = match params.api_key {
+        Some(ref key) => {
+            if is_client_api_key(&state.config, key) {
+                key.clone()
+            } else {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/test/monte-carlo",
+                    "",
+                    &format!("iterations={}", params.iterations),
+                    StatusCode::UNAUTHORIZED,
+                );
+                return Err(AppError(StatusCode::UNAUTHORIZED, "Invalid API key".to_string()));
+            }
+        }
+        None => match extract_api_key(&headers, &state.config) {
+            Ok(key) => key,
+            Err(status) => {
+                log_client_request(
+                    addr,
+                    &user_agent,
+                    "/api/test/monte-carlo",
+                    "",
+                    &format!("iterations={}", params.iterations),
+                    status,
+                );
+                return Err(AppError(status, "Authentication required".to_string()));
+            }
+        },
+    };
+
+    // Rate limiting
+    if !state.rate_limiter.check(&api_key) {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/test/monte-carlo",
+            &api_key,
+            &format!("iterations={}", params.iterations),
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        return Err(AppError(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded".to_string()));
+    }
+
+    // Validate iterations
+    const MAX_ITERATIONS: u64 = 10_000_000;
+    if params.iterations == 0 || params.iterations > MAX_ITERATIONS {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/test/monte-carlo",
+            &api_key,
+            &format!("iterations={} (invalid)", params.iterations),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(AppError(
+            StatusCode::BAD_REQUEST,
+            format!("iterations must be between 1 and {}", MAX_ITERATIONS),
+        ));
+    }
+
+    info!("Running Monte Carlo test with {} iterations", params.iterations);
+
+    // Generate random floats from quantum source
+    // Monte Carlo needs 2 floats (x, y) per iteration
+    let bytes_needed = (params.iterations * 16) as usize; // 16 bytes per iteration (2 × f64)
+    let data = state.buffer.pop(bytes_needed).ok_or_else(|| {
+        AppError(
+            StatusCode::INSUFFICIENT_STORAGE,
+            "Insufficient entropy in buffer".to_string(),
+        )
+    })?;
+    state.audit("/api/test/monte-carlo", &api_key, bytes_needed);
+
+    // Convert bytes to floats in [0,1)
+    let mut floats = Vec::with_capacity((params.iterations * 2) as usize);
+    for chunk in data.chunks_exact(8) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        let random_u64 = u64::from_be_bytes(bytes);
+        // Convert to float in [0, 1) using proper precision
+        // Use only top 53 bits to avoid rounding bias
+        let float = (random_u64 >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+        floats.push(float);
+    }
+
+    // Perform Monte Carlo π estimation
+    let quantum_pi = estimate_pi(&floats);
+    let quantum_error = (quantum_pi - std::f64::consts::PI).abs();
+    let quantum_error_percent = (quantum_error / std::f64::consts::PI) * 100.0;
+
+    // Assess convergence rate
+    let convergence_rate = if quantum_error_percent < 0.01 {
+        "excellent".to_string()
+    } else if quantum_error_percent < 0.1 {
+        "good".to_string()
+    } else if quantum_error_percent < 1.0 {
+        "fair".to_string()
+    } else {
+        "poor".to_string()
+    };
+
+    let quality_assessment = if quantum_error_percent < 0.1 {
+        "high_quality".to_string()
+    } else if quantum_error_percent < 1.0 {
+        "acceptable".to_string()
+    } else {
+        "poor_quality".to_string()
+    };
+
+    // Compare with pseudo-random (for statistical demonstration only)
+    // Note: Pseudo-random can occasionally produce better Monte Carlo estimates
+    // due to statistical variance, but lacks cryptographic unpredictability
+    let comparison = if params.iterations <= 1_000_000 {
+        // Generate pseudo-random for comparison
+        use rand::Rng;
+        let mut rng = rand::rng();
+        // Need 2 floats per iteration (x, y coordinates)
+        let pseudo_floats: Vec<f64> = (0..(params.iterations * 2))
+            .map(|_| rng.random::<f64>())
+            .collect();
+        let pseudo_pi = estimate_pi(&pseudo_floats);
+        let pseudo_error = (pseudo_pi - std::f64::consts::PI).abs();
+
+        Some(PseudoComparison {
+            quantum_error,
+            pseudo_error,
+            improvement_factor: if pseudo_error > 0.0 {
+                pseudo_error / quantum_error.max(1e-10)
+            } else {
+                1.0
+            },
+        })
+    } else {
+        None
+    };
+
+    info!(
+        "Monte Carlo test completed: π ≈ {:.6}, error: {:.6} ({:.4}%)",
+        quantum_pi, quantum_error, quantum_error_percent
+    );
+
+    // Log successful request
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/test/monte-carlo",
+        &api_key,
+        &format!("iterations={}", params.iterations),
+        StatusCode::OK,
+    );
+
+    Ok(Json(MonteCarloResult {
+        estimated_pi: quantum_pi,
+        error: quantum_error,
+        error_percent: quantum_error_percent,
+        iterations: params.iterations,
+        convergence_rate,
+        quality_assessment,
+        note: "Monte Carlo tests measure statistical uniformity, not cryptographic security. Both quantum and pseudo-random can pass these tests, but only quantum provides true unpredictability.".to_string(),
+        quantum_vs_pseudo: comparison,
+    }))
+}
+
+/// Estimate π using Monte Carlo method
+///
+/// Uses pairs of random numbers as (x, y) coordinates and checks if they fall
+/// inside a unit circle. The ratio of points inside vs total approximates π/4.
+fn estimate_pi(floats: &[f64]) -> f64 {
+    let pairs = floats.len() / 2;
+    let mut inside_circle = 0u64;
+
+    for i in 0..pairs {
+        let x = floats[i * 2];
+        let y = floats[i * 2 + 1];
+
+        // Check if point (x, y) is inside unit circle
+        if x * x + y * y <= 1.0 {
+            inside_circle += 1;
+        }
+    }
+
+    // π/4 ≈ inside_circle / total_points
+    // π ≈ 4 * inside_circle / total_points
+    4.0 * (inside_circle as f64) / (pairs as f64)
+}
+
+/// Smallest /api/entropy-report sample; every byte value is expected at least 5 times
+const MIN_ENTROPY_REPORT_BYTES: usize = 1280;
+
+/// Largest /api/entropy-report sample
+const MAX_ENTROPY_REPORT_BYTES: usize = 1024 * 1024;
+
+/// Sample size of /api/entropy-report when `bytes` is not given
+const DEFAULT_ENTROPY_REPORT_BYTES: usize = 65536;
+
+/// Significance level of the /api/entropy-report tests
+const REPORT_SIGNIFICANCE: f64 = 0.01;
+
+/// Two-sided standard normal critical value at `REPORT_SIGNIFICANCE`
+const NORMAL_CRITICAL_VALUE: f64 = 2.5758;
+
+/// Chi-square critical value for 255 degrees of freedom at `REPORT_SIGNIFICANCE`
+const CHI_SQUARE_CRITICAL_VALUE: f64 = 310.457;
+
+/// Query parameters for /api/entropy-report
+#[derive(Debug, Deserialize)]
+struct EntropyReportQuery {
+    bytes: Option<usize>,
+    api_key: Option<String>,
+}
+
+/// Outcome of one statistical test
+///
+/// The test passes when the magnitude of `statistic` is at most `threshold`.
+/// `statistic` is absent when the test is not applicable to the sample.
+#[derive(Debug, Serialize, Deserialize)]
+struct TestOutcome {
+    statistic: Option<f64>,
+    threshold: f64,
+    passed: bool,
+}
+
+impl TestOutcome {
+    fn new(statistic: f64, threshold: f64) -> Self {
+        Self {
+            statistic: Some(statistic),
+            threshold,
+            passed: statistic.abs() <= threshold,
+        }
+    }
+}
+
+/// Response of GET /api/entropy-report
+#[derive(Debug, Serialize, Deserialize)]
+struct EntropyReport {
+    bytes: usize,
+    significance: f64,
+    passed: bool,
+    /// Normalized excess of ones over zeros
+    monobit: TestOutcome,
+    /// Normalized deviation of the bit run count from its expectation
+    runs: TestOutcome,
+    /// Chi-square of the byte value distribution
+    chi_square: TestOutcome,
+    /// Correlation coefficient of consecutive bytes
+    serial_correlation: TestOutcome,
+}
+
+/// Run the monobit, runs, chi-square and serial correlation tests on `data`
+///
+/// Bits are read most significant first. The runs test follows NIST SP 800-22
+/// and is not applicable when the sample fails the monobit prerequisite.
+fn entropy_report(data: &[u8]) -> EntropyReport {
+    let n_bits = data.len() as f64 * 8.0;
+    let ones: u64 = data.iter().map(|byte| byte.count_ones() as u64).sum();
+
+    let monobit = TestOutcome::new((2.0 * ones as f64 - n_bits) / n_bits.sqrt(), NORMAL_CRITICAL_VALUE);
+
+    let pi = ones as f64 / n_bits;
+    let runs = if (pi - 0.5).abs() < 2.0 / n_bits.sqrt() {
+        let mut transitions = 0u64;
+        let mut previous: Option<u8> = None;
+        for &byte in data {
+            // Adjacent bits inside the byte, then the boundary with the previous byte
+            transitions += ((byte ^ (byte >> 1)) & 0x7F).count_ones() as u64;
+            if let Some(previous) = previous {
+                transitions += (previous & 1 != byte >> 7) as u64;
+            }
+            previous = Some(byte);
+        }
+        let observed = transitions as f64 + 1.0;
+        let spread = pi * (1.0 - pi);
+        let z = (observed - 2.0 * n_bits * spread) / (2.0 * n_bits.sqrt() * spread);
+        TestOutcome::new(z, NORMAL_CRITICAL_VALUE)
+    } else {
+        TestOutcome {
+            statistic: None,
+            threshold: NORMAL_CRITICAL_VALUE,
+            passed: false,
+        }
+    };
+
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let expected = data.len() as f64 / 256.0;
+    let chi_square: f64 = counts
+        .iter()
+        .map(|&observed| (observed as f64 - expected).powi(2) / expected)
+        .sum();
+    let chi_square = TestOutcome::new(chi_square, CHI_SQUARE_CRITICAL_VALUE);
+
+    // Circular lag-1 correlation as computed by `ent`; constant data is fully correlated
+    let n = data.len() as f64;
+    let correlation = serial_correlation(data);
+    let serial_correlation = TestOutcome::new(correlation, NORMAL_CRITICAL_VALUE / n.sqrt());
+
+    EntropyReport {
+        bytes: data.len(),
+        significance: REPORT_SIGNIFICANCE,
+        passed: monobit.passed && runs.passed && chi_square.passed && serial_correlation.passed,
+        monobit,
+        runs,
+        chi_square,
+        serial_correlation,
+    }
+}
+
+/// GET /api/entropy-report - Statistical quality report on freshly drawn entropy
+///
+/// The sampled bytes are consumed but never returned to the client.
+async fn serve_entropy_report(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<EntropyReportQuery>,
+    headers: HeaderMap,
+) -> Result<Json<EntropyReport>, StatusCode> {
+    let start = Instant::now();
+    let user_agent = extract_user_agent(&headers);
+    let bytes = params.bytes.unwrap_or(DEFAULT_ENTROPY_REPORT_BYTES);
+    let request_info = format!("bytes={}", bytes);
+
+    let api_key = authorize_request(
+        &state,
+        addr,
+        &headers,
+        "/api/entropy-report",
+        params.api_key,
+        &request_info,
+    )?;
+
+    // Validate parameters
+    if !(MIN_ENTROPY_REPORT_BYTES..=MAX_ENTROPY_REPORT_BYTES).contains(&bytes) {
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/entropy-report",
+            &api_key,
+            &format!("{} (invalid)", request_info),
+            StatusCode::BAD_REQUEST,
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let Some(data) = state.buffer.pop(bytes) else {
+        state.metrics.record_request_failure();
+        log_client_request(
+            addr,
+            &user_agent,
+            "/api/entropy-report",
+            &api_key,
+            &request_info,
+            StatusCode::INSUFFICIENT_STORAGE,
+        );
+        return Err(StatusCode::INSUFFICIENT_STORAGE);
+    };
+    let report = entropy_report(&data);
+
+    // Record metrics
+    let latency = start.elapsed().as_micros() as u64;
+    state.metrics.record_request(bytes, latency);
+    state.audit("/api/entropy-report", &api_key, bytes);
+
+    log_client_request(
+        addr,
+        &user_agent,
+        "/api/entropy-report",
+        &api_key,
+        &format!("{} passed={}", request_info, report.passed),
+        StatusCode::OK,
+    );
+
+    Ok(Json(report))
+}
+
+/// Direct mode: fetch from the appliance into the buffer, bypassing the collector
+///
+/// Fetches are skipped while the buffer is full; failures are logged and the
+/// next tick simply tries again, since the source retries transient errors itself.
+async fn direct_fetch_loop(
+    buffer: EntropyBuffer,
+    metrics: Metrics,
+    source: Box<dyn EntropySource>,
+    chunk_size: usize,
+    period: std::time::Duration,
+) {
+    let mut ticker = tokio::time::interval(period);
+    loop {
+        ticker.tick().await;
+        if buffer.fill_percent() >= 100.0 {
+            continue;
+        }
+
+        match source.fetch(chunk_size).await {
+            Ok(data) => {
+                metrics.record_fetch(data.len());
+                match buffer.push(data) {
+                    Ok(bytes) => info!(
+                        bytes = bytes,
+                        buffer_fill_percent = buffer.fill_percent(),
+                        "Fetched entropy in direct mode"
+                    ),
+                    Err(e) => error!(error = %e, "Failed to buffer directly fetched entropy"),
+                }
+            }
+            Err(e) => {
+                metrics.record_fetch_failure();
+                warn!(error = %e, "Direct mode fetch failed");
+            }
+        }
+    }
+}
+
+/// How often the watermark monitor samples the buffer
+const WATERMARK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Minimum time between two watermark alerts
+const WATERMARK_ALERT_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// JSON body POSTed to the alert webhook
+#[derive(Debug, Serialize, Deserialize)]
+struct WatermarkAlert {
+    level: WatermarkLevel,
+    fill_percent: f64,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    hostname: String,
+}
+
+/// Host name reported in alerts
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Watermark monitor: POST an alert when the buffer enters the low level
+///
+/// A full buffer (the critical level) is healthy and never alerts. The level at
+/// startup is the baseline, so an empty buffer at boot does not alert. After an
+/// alert the monitor only re-arms once the buffer is back above the low
+/// threshold, and never alerts twice within `cooldown`.
+/// Delivery is best effort: a failed POST is logged and not retried.
+async fn watermark_alert_loop(
+    buffer: EntropyBuffer,
+    client: reqwest::Client,
+    webhook_url: String,
+    period: std::time::Duration,
+    cooldown: std::time::Duration,
+) {
+    let hostname = hostname();
+    let is_alarm = |level: WatermarkLevel| level == WatermarkLevel::Low;
+    let mut armed = !is_alarm(buffer.watermark());
+    let mut last_alert: Option<Instant> = None;
+    let mut ticker = tokio::time::interval(period);
+
+    loop {
+        ticker.tick().await;
+        let level = buffer.watermark();
+        if !is_alarm(level) {
+            armed = true;
+            continue;
+        }
+        if !armed || last_alert.is_some_and(|at| at.elapsed() < cooldown) {
+            continue;
+        }
+        armed = false;
+        last_alert = Some(Instant::now());
+
+        let alert = WatermarkAlert {
+            level,
+            fill_percent: buffer.fill_percent(),
+            timestamp: chrono::Utc::now(),
+            hostname: hostname.clone(),
+        };
+        warn!(level = ?alert.level, fill_percent = alert.fill_percent, "Buffer watermark alert");
+        let body = serde_json::to_vec(&alert).unwrap_or_default();
+        let request = client
+            .post(&webhook_url)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => warn!(status = %response.status(), "Alert webhook rejected watermark alert"),
+            Err(e) => warn!(error = %e, "Failed to send watermark alert"),
+        }
+    }
+}
+
+/// POST /push - Receive entropy packets (push mode)
+async fn receive_push(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> StatusCode {
+    let user_agent = extract_user_agent(&headers);
+    
+    let signer = match &state.signer {
+        Some(s) => s,
+        None => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                "Push endpoint called but no packet verification key configured"
+            );
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    // Deserialize packet
+    let mut packet = match EntropyPacket::decode(&body) {
+        Ok((p, _format)) => p,
+        Err(e) => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                error = %e,
+                "Failed to deserialize entropy packet"
+            );
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    // Verify signature
+    match signer.verify_packet(&packet) {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Invalid packet signature"
+            );
+            return StatusCode::UNAUTHORIZED;
+        }
+        Err(e) => {
+            error!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                error = %e,
+                "Signature verification error"
+            );
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    // Verify checksum if present
+    if !packet.verify_checksum() {
+        warn!(
+            client_ip = %addr,
+            user_agent = %user_agent,
+            endpoint = "/push",
+            sequence = packet.sequence,
+            "Checksum mismatch"
+        );
+        return StatusCode::BAD_REQUEST;
+    }
+
+    // Decrypt payload (the signature above covers the ciphertext and nonce)
+    match (&state.encryption_key, packet.is_encrypted()) {
+        (Some(key), true) => {
+            if let Err(e) = packet.decrypt_payload(key) {
+                warn!(
+                    client_ip = %addr,
+                    user_agent = %user_agent,
+                    endpoint = "/push",
+                    sequence = packet.sequence,
+                    error = %e,
+                    "Payload decryption failed"
+                );
+                return StatusCode::BAD_REQUEST;
+            }
+        }
+        (Some(_), false) => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Unencrypted packet rejected, encryption key is configured"
+            );
+            return StatusCode::BAD_REQUEST;
+        }
+        (None, true) => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Encrypted packet received but no encryption key is configured"
+            );
+            return StatusCode::BAD_REQUEST;
+        }
+        (None, false) => {}
+    }
+
+    // Check freshness
+    if let Some(ttl) = state.config.buffer_ttl() {
+        if packet.is_stale(ttl) {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Packet is stale"
+            );
+            return StatusCode::BAD_REQUEST;
+        }
+    }
+
+    // Detect replayed and dropped packets
+    match state.replay_guard.check(packet.sequence, packet.timestamp) {
+        SequenceCheck::InOrder | SequenceCheck::OutOfOrder => {}
+        SequenceCheck::Gap { missing } => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                missing = missing,
+                "Sequence gap detected, packets may have been dropped"
+            );
+        }
+        SequenceCheck::Restarted => {
+            info!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Sequence reset detected, collector restarted"
+            );
+        }
+        SequenceCheck::Replay => {
+            warn!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                "Replayed packet rejected"
+            );
+            return StatusCode::CONFLICT;
+        }
+    }
+
+    // Push to buffer
+    match state.buffer.push_tagged(packet.data.clone(), packet.source_tag.as_deref()) {
+        Ok(bytes) => {
+            if bytes > 0 {
+                state.metrics.record_push(bytes);
+            }
+            if bytes == 0 {
+                warn!(
+                    client_ip = %addr,
+                    user_agent = %user_agent,
+                    endpoint = "/push",
+                    sequence = packet.sequence,
+                    buffer_fill_percent = state.buffer.fill_percent(),
+                    "Discarded packet, buffer full"
+                );
+                StatusCode::INSUFFICIENT_STORAGE
+            } else if bytes < packet.data.len() {
+                info!(
+                    client_ip = %addr,
+                    user_agent = %user_agent,
+                    endpoint = "/push",
+                    sequence = packet.sequence,
+                    bytes_stored = bytes,
+                    bytes_total = packet.data.len(),
+                    buffer_fill_percent = state.buffer.fill_percent(),
+                    "Received packet (partial)"
+                );
+                StatusCode::OK
+            } else {
+                info!(
+                    client_ip = %addr,
+                    user_agent = %user_agent,
+                    endpoint = "/push",
+                    sequence = packet.sequence,
+                    bytes = bytes,
+                    buffer_fill_percent = state.buffer.fill_percent(),
+                    "Received packet"
+                );
+                StatusCode::OK
+            }
+        }
+        Err(e) => {
+            error!(
+                client_ip = %addr,
+                user_agent = %user_agent,
+                endpoint = "/push",
+                sequence = packet.sequence,
+                error = %e,
+                "Failed to push to buffer"
+            );
+            StatusCode::INSUFFICIENT_STORAGE
+        }
+    }
+}
+
+impl AppState {
+    fn new(
+        config: GatewayConfig,
+        buffer: EntropyBuffer,
+        signer: Option<PacketSigner>,
+        encryption_key: Option<Zeroizing<Vec<u8>>>,
+    ) -> Self {
+        let metrics = Metrics::new();
+        let eviction_metrics = metrics.clone();
+        let buffer = buffer.with_eviction_hook(move |reason, bytes| {
+            debug!("Evicted {} buffered bytes ({})", bytes, reason.as_str());
+            eviction_metrics.record_eviction(reason, bytes);
+        });
+
+        Self {
+            rate_limiter: Arc::new(RateLimiter::new(config.rate_limit_per_second, config.rate_limit_burst())),
+            replay_guard: Arc::new(ReplayGuard::new(config.replay_window_size)),
+            in_flight: Arc::new(InFlightRequests::default()),
+            reservations: Arc::new(ReservationStore::new(RESERVATION_TTL)),
+            fallback: config.fallback_csprng.then(|| Arc::new(FallbackRng::new())),
+            config,
+            buffer,
+            metrics,
+            signer,
+            encryption_key,
+            start_time: Instant::now(),
+            audit: None,
+        }
+    }
+
+    /// Record served entropy in the audit ledger
+    fn with_audit(mut self, audit: Option<Arc<AuditLedger>>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Record `bytes` of entropy served to `api_key` by `endpoint` in the audit ledger, if enabled
+    fn audit(&self, endpoint: &str, api_key: &str, bytes: usize) {
+        if let Some(audit) = &self.audit {
+            audit.record(endpoint, api_key, bytes, current_request_id());
+        }
+        let _ = AUDITED_BYTES.try_with(|audited| audited.set(audited.get() + bytes));
+    }
+
+    /// Whether `endpoint` is served from the CSPRNG when the buffer runs dry
+    fn falls_back(&self, endpoint: &str) -> bool {
+        self.fallback.is_some() && FALLBACK_ENDPOINTS.contains(&endpoint)
+    }
+
+    /// Run `generate` on the quantum buffer, or on CSPRNG output if that runs dry
+    ///
+    /// The fallback is only used when enabled and `endpoint` is in
+    /// `FALLBACK_ENDPOINTS`. `needed` is the number of bytes `generate`
+    /// normally pops; the fallback buffer leaves room for rejected draws.
+    /// Callers count only [`EntropyOrigin::quantum_bytes`] as served.
+    fn draw<T>(
+        &self,
+        endpoint: &str,
+        needed: usize,
+        generate: impl Fn(&EntropyBuffer) -> Option<T>,
+    ) -> Option<(T, EntropyOrigin)> {
+        // Measured before the draw, so it reflects the oldest bytes handed out
+        let freshness_seconds = self.buffer.freshness_seconds();
+        // A generator that fails part way has already popped what it read, so
+        // only try the quantum buffer when it holds enough for the whole draw
+        if self.buffer.len() >= needed {
+            if let Some(value) = generate(&self.buffer) {
+                return Some((value, EntropyOrigin::Quantum { freshness_seconds }));
+            }
+        }
+        let fallback = self.fallback.as_ref().filter(|_| self.falls_back(endpoint))?;
+        let scratch = fallback.scratch_buffer(&self.buffer, needed * 2 + FALLBACK_SLACK_BYTES);
+        generate(&scratch).map(|value| (value, EntropyOrigin::Fallback))
+    }
+}
+
+/// Response header naming where the entropy came from: quantum, fallback or prng
+const ENTROPY_SOURCE_HEADER: &str = "x-entropy-source";
+
+/// Response header with the age in seconds of the oldest quantum bytes served
+const ENTROPY_FRESHNESS_HEADER: &str = "x-entropy-freshness-seconds";
+
+/// Endpoints that may fall back to the CSPRNG; seed, derive, reserve and prng never
+/// do, since their output is used as key material
+const FALLBACK_ENDPOINTS: &[&str] = &[
+    "/api/random",
+    "/api/integers",
+    "/api/integers/multi",
+    "/api/floats",
+    "/api/uuid",
+    "/api/dice",
+];
+
+/// Extra fallback bytes beyond twice the expected need, so rejection sampling cannot run dry
+const FALLBACK_SLACK_BYTES: usize = 64;
+
+/// Minimum time between reseeds of the fallback CSPRNG from the quantum buffer
+const FALLBACK_RESEED_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Where the entropy of a response came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntropyOrigin {
+    /// Popped from the quantum buffer, whose oldest entry was `freshness_seconds` old
+    Quantum { freshness_seconds: Option<u64> },
+    Fallback,
+}
+
+impl EntropyOrigin {
+    /// Bytes of a `bytes`-byte draw that came from the quantum buffer: none for the fallback
+    fn quantum_bytes(self, bytes: usize) -> usize {
+        match self {
+            Self::Quantum { .. } => bytes,
+            Self::Fallback => 0,
+        }
+    }
+
+    /// Set `X-Entropy-Source`, plus `X-Entropy-Freshness-Seconds` for quantum entropy
+    fn label(self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        match self {
+            Self::Quantum { freshness_seconds } => {
+                headers.insert(ENTROPY_SOURCE_HEADER, axum::http::HeaderValue::from_static("quantum"));
+                if let Some(age) = freshness_seconds {
+                    headers.insert(ENTROPY_FRESHNESS_HEADER, axum::http::HeaderValue::from(age));
+                }
+            }
+            Self::Fallback => {
+                headers.insert(ENTROPY_SOURCE_HEADER, axum::http::HeaderValue::from_static("fallback"));
+            }
+        }
+        response
+    }
+}
+
+/// ChaCha20 generator serving requests the quantum buffer cannot satisfy (`fallback_csprng`)
+///
+/// Seeded from the OS and reseeded from quantum bytes whenever some are left
+/// in the buffer, at most every `FALLBACK_RESEED_INTERVAL`.
+struct FallbackRng {
+    state: parking_lot::Mutex<(rand_chacha::ChaCha20Rng, Instant)>,
+}
+
+impl FallbackRng {
+    fn new() -> Self {
+        use rand::SeedableRng;
+        let rng = rand_chacha::ChaCha20Rng::from_rng(&mut rand::rng());
+        Self {
+            state: parking_lot::Mutex::new((rng, Instant::now())),
+        }
+    }
+
+    /// Buffer holding `bytes` fresh CSPRNG bytes
+    fn scratch_buffer(&self, quantum: &EntropyBuffer, bytes: usize) -> EntropyBuffer {
+        use rand::{RngCore, SeedableRng};
+        let mut state = self.state.lock();
+        let (rng, last_reseed) = &mut *state;
+
+        if last_reseed.elapsed() >= FALLBACK_RESEED_INTERVAL {
+            if let Some(fresh) = quantum.pop(32) {
+                // XOR into generator output so a reseed never weakens the state
+                let mut seed = [0u8; 32];
+                rng.fill_bytes(&mut seed);
+                seed.iter_mut().zip(fresh.iter()).for_each(|(byte, fresh)| *byte ^= fresh);
+                *rng = rand_chacha::ChaCha20Rng::from_seed(seed);
+                *last_reseed = Instant::now();
+            }
+        }
+
+        let mut data = vec![0u8; bytes];
+        rng.fill_bytes(&mut data);
+        let scratch = EntropyBuffer::new(bytes);
+        let _ = scratch.push(data);
+        scratch
+    }
+}
+
+/// Gzip/deflate for text responses (hex, base64) only
+///
+/// Binary entropy is incompressible, so compressing it would only burn CPU.
+fn text_compression_layer() -> CompressionLayer<impl Predicate> {
+    let is_text = |_: StatusCode, _: axum::http::Version, headers: &HeaderMap, _: &axum::http::Extensions| {
+        headers
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/"))
+    };
+    CompressionLayer::new()
+        .no_br()
+        .no_zstd()
+        .compress_when(SizeAbove::default().and(is_text))
+}
+
+/// Count every routed response by endpoint and status for Prometheus
+async fn record_endpoint_metrics(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let response = next.run(request).await;
+    if let Some(endpoint) = endpoint {
+        state.metrics.record_endpoint(&endpoint, response.status().as_u16());
+    }
+    response
+}
+
+/// Refuse entropy requests while the oldest buffered entry exceeds `max_served_age_secs`
+///
+/// Stale entries stay queued until consumed or evicted by the buffer TTL, which
+/// config validation requires alongside the limit so fresh pushes take over.
+/// Requests without a valid API key pass through to get their 401.
+async fn reject_stale_entropy(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let Some(max_age) = state.config.max_served_age_secs else {
+        return next.run(request).await;
+    };
+    if !has_client_api_key(&state, &request) {
+        return next.run(request).await;
+    }
+    if let Some(age) = state.buffer.freshness_seconds() {
+        if age > max_age {
+            debug!(
+                "Refusing {}: buffered entropy is {} seconds old (limit {})",
+                request.uri().path(),
+                age,
+                max_age
+            );
+            state.metrics.record_request_failure();
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!(
+                    "Buffered entropy is {} seconds old, exceeding the {} second freshness limit",
+                    age, max_age
+                ),
+            )
+                .into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Endpoints whose responses are never wrapped in an envelope: they stream without end
+const UNENVELOPED_ENDPOINTS: &[&str] = &["/api/stream", "/ws"];
+
+/// Query parameter selecting the JSON envelope
+#[derive(serde::Deserialize)]
+struct EnvelopeQuery {
+    #[serde(default)]
+    envelope: bool,
+    #[serde(default)]
+    encoding: Option<String>,
+}
+
+/// Metadata accompanying an enveloped response
+#[derive(Debug, Serialize)]
+struct EnvelopeMeta {
+    /// Buffer bytes consumed to produce `data`
+    bytes_consumed: usize,
+    /// Age of the oldest quantum bytes served; absent for fallback or PRNG output
+    freshness_seconds: Option<u64>,
+    request_id: String,
+    /// `json` for structured data, `base64` for binary bodies, otherwise the text encoding
+    encoding: String,
+}
+
+/// Wrapped generator output: `{"data": ..., "meta": {...}}`
+#[derive(Debug, Serialize)]
+struct Envelope {
+    data: serde_json::Value,
+    meta: EnvelopeMeta,
+}
+
+/// Build an enveloped response from the parts and body of a generator response
+///
+/// JSON bodies are embedded as JSON, text bodies as a string and binary
+/// bodies as base64. Headers other than the content type and length are kept.
+fn envelope_response(
+    mut parts: axum::http::response::Parts,
+    body: &[u8],
+    text_encoding: Option<&str>,
+    mut meta: EnvelopeMeta,
+) -> Response {
+    let content_type = parts
+        .headers
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let data = if content_type.starts_with("application/json") {
+        meta.encoding = "json".to_string();
+        serde_json::from_slice(body).unwrap_or_else(|_| String::from_utf8_lossy(body).into())
+    } else if content_type.starts_with("text/") {
+        meta.encoding = text_encoding.unwrap_or("text").to_ascii_lowercase();
+        String::from_utf8_lossy(body).into()
+    } else {
+        meta.encoding = "base64".to_string();
+        encode_base64(body).into()
+    };
+
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+    parts.headers.insert(
+        hyper::header::CONTENT_TYPE,
+        axum::http::HeaderValue::from_static("application/json"),
+    );
+    let body = serde_json::to_vec(&Envelope { data, meta }).unwrap();
+    Response::from_parts(parts, axum::body::Body::from(body))
+}
+
+/// Wrap successful generator responses in a JSON envelope when `envelope=true`
+///
+/// Bare output stays the default. The consumed byte count comes from the
+/// `X-Entropy-Bytes-Consumed` header when the handler sets one, otherwise from
+/// the bytes the handler recorded in the audit ledger. The freshness comes from
+/// the handler's `X-Entropy-Freshness-Seconds`, measured before it popped.
+async fn wrap_in_envelope(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let query = match Query::<EnvelopeQuery>::try_from_uri(request.uri()) {
+        Ok(Query(query)) => query,
+        Err(rejection) => return rejection.into_response(),
+    };
+    if !query.envelope || UNENVELOPED_ENDPOINTS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    // Handlers that label their source report the freshness of what they served; for the
+    // rest, the buffer's age before they pop is the age of the oldest bytes handed out
+    let freshness_before = state.buffer.freshness_seconds();
+    let (response, audited) = AUDITED_BYTES
+        .scope(std::cell::Cell::new(0), async {
+            let response = next.run(request).await;
+            (response, AUDITED_BYTES.with(std::cell::Cell::get))
+        })
+        .await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let bytes_consumed = parts
+        .headers
+        .get(ENTROPY_CONSUMED_HEADER)
+        .and_then(|value| value.to_str().ok()?.parse().ok())
+        .unwrap_or(audited);
+    let freshness_seconds = match parts.headers.get(ENTROPY_SOURCE_HEADER) {
+        Some(source) if source == "quantum" => parts
+            .headers
+            .get(ENTROPY_FRESHNESS_HEADER)
+            .and_then(|value| value.to_str().ok()?.parse().ok()),
+        Some(_) => None,
+        None => freshness_before,
+    };
+    let meta = EnvelopeMeta {
+        bytes_consumed,
+        freshness_seconds,
+        request_id: current_request_id(),
+        encoding: String::new(),
+    };
+    envelope_response(parts, &body, query.encoding.as_deref(), meta)
+}
+
+/// Longest `Retry-After` sent by the low-buffer throttle, at an empty buffer
+const THROTTLE_MAX_RETRY_AFTER_SECS: f64 = 10.0;
+
+/// Share of entropy requests refused at `fill_percent` below the throttle watermark
+///
+/// Grows linearly from 0 at the watermark to 1 at an empty buffer.
+fn throttle_ratio(fill_percent: f64, watermark: f64) -> f64 {
+    ((watermark - fill_percent) / watermark).clamp(0.0, 1.0)
+}
+
+/// Whether `request` carries a valid client API key, in the query or the `Authorization` header
+///
+/// Checked without logging or consuming a rate-limit token, so middleware can
+/// leave rejected keys to the handler's own 401.
+fn has_client_api_key(state: &AppState, request: &axum::extract::Request) -> bool {
+    match Query::<ApiKeyQuery>::try_from_uri(request.uri()).ok().and_then(|Query(query)| query.api_key) {
+        Some(key) => is_client_api_key(&state.config, &key),
+        None => extract_api_key(request.headers(), &state.config).is_ok(),
+    }
+}
+
+/// Whether `request` can be answered without drawing from the quantum buffer
+///
+/// HEAD /api/random only reads the fill level, /api/prng with a client seed
+/// expands that seed, and fallback endpoints switch to the CSPRNG when the
+/// buffer runs dry.
+fn spares_quantum_buffer(state: &AppState, request: &axum::extract::Request) -> bool {
+    let path = request.uri().path();
+    let client_seed = || {
+        Query::<std::collections::HashMap<String, String>>::try_from_uri(request.uri())
+            .is_ok_and(|Query(query)| query.contains_key("seed"))
+    };
+    (request.method() == axum::http::Method::HEAD && path == "/api/random")
+        || (path == "/api/prng" && client_seed())
+        || state.falls_back(path)
+}
+
+/// Ration entropy while the buffer is below `throttle_fill_percent`
+///
+/// A share of requests proportional to how far the fill has dropped gets 429
+/// with a `Retry-After` that also grows as the buffer drains, so clients back
+/// off gradually instead of all hitting 503 once the buffer is empty. Requests
+/// without a valid API key pass through to get their 401, and requests that
+/// spare the quantum buffer are never throttled.
+async fn throttle_low_buffer(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let Some(watermark) = state.config.throttle_fill_percent else {
+        return next.run(request).await;
+    };
+    if !has_client_api_key(&state, &request) || spares_quantum_buffer(&state, &request) {
+        return next.run(request).await;
+    }
+
+    let fill_percent = state.buffer.fill_percent();
+    let ratio = throttle_ratio(fill_percent, watermark);
+    if ratio > 0.0 && rand::random::<f64>() < ratio {
+        let retry_after = (ratio * THROTTLE_MAX_RETRY_AFTER_SECS).ceil().max(1.0) as u64;
+        debug!(
+            "Throttling {}: buffer at {:.1}% (watermark {:.1}%)",
+            request.uri().path(),
+            fill_percent,
+            watermark
+        );
+        state.metrics.record_request_failure();
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(hyper::header::RETRY_AFTER, retry_after.to_string())],
+            format!("Entropy buffer low ({:.1}%), retry in {} seconds", fill_percent, retry_after),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Answer 504 when a handler has not produced its response within the request deadline
+///
+/// Only the time to the response head counts, so `/api/stream` and `/ws` keep
+/// streaming after their handlers return. A long-polling `/api/status?wait_for=`
+/// gets its longest hold time on top of the deadline.
+async fn enforce_request_timeout(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let mut deadline = state.config.request_timeout();
+    let long_poll = request.uri().path() == "/api/status"
+        && request.uri().query().is_some_and(|query| query.contains("wait_for="));
+    if long_poll {
+        deadline += MAX_STATUS_WAIT;
+    }
+
+    let path = request.uri().path().to_string();
+    match tokio::time::timeout(deadline, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("Aborting {}: no response within {} ms", path, deadline.as_millis());
+            state.metrics.record_request_failure();
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Request exceeded the {} ms deadline", deadline.as_millis()),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Run each routed request in a span recording its route, status and request ID
+///
+/// With OTLP export enabled these spans carry handler latency and outcome to the collector.
+async fn trace_request(request: axum::extract::Request, next: axum::middleware::Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+    let method = request.method().clone();
+
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {}", method, route),
+        otel.status_code = tracing::field::Empty,
+        http.request.method = %method,
+        http.route = %route,
+        http.response.status_code = tracing::field::Empty,
+        request_id = %request_id,
+    );
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    if response.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
+
+/// Track each request until its response body has been sent
+async fn track_in_flight(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let guard = state.in_flight.start();
+    next.run(request)
+        .await
+        .map(|inner| axum::body::Body::new(TrackedBody { inner, _guard: guard }))
+}
+
+/// Build the HTTP router for the gateway API
+fn build_router(state: AppState) -> Router {
+    let compression_enabled = state.config.compression_enabled;
+    // Routes handing out entropy, subject to the freshness limit
+    let entropy_routes = Router::new()
+        .route("/api/random", get(serve_random).head(head_random))
+        .route("/api/stream", get(serve_stream))
+        .route("/api/reserve", post(serve_reserve))
+        .route("/api/integers", get(serve_integers))
+        .route("/api/integers/multi", post(serve_integers_multi))
+        .route("/api/floats", get(serve_floats))
+        .route("/api/uuid", get(serve_uuid))
+        .route("/api/choice", post(serve_choice))
+        .route("/api/shuffle", post(serve_shuffle))
+        .route("/api/decimal", get(serve_decimal))
+        .route("/api/string", get(serve_string))
+        .route("/api/dice", get(serve_dice))
+        .route("/api/seed", get(serve_seed))
+        .route("/api/prng", get(serve_prng))
+        .route("/api/permutation", get(serve_permutation))
+        .route("/api/sample", get(serve_sample))
+        .route("/api/exponential", get(serve_exponential))
+        .route("/api/poisson", get(serve_poisson))
+        .route("/api/bool", get(serve_bool))
+        .route("/api/coinflip", get(serve_coinflip))
+        .route("/api/batch", post(serve_batch))
+        .route("/api/derive", get(serve_derive))
+        .route("/ws", get(serve_websocket))
+        .route("/api/test/monte-carlo", get(monte_carlo_test))
+        .route("/api/entropy-report", get(serve_entropy_report))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), wrap_in_envelope))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), throttle_low_buffer))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), reject_stale_entropy));
+
+    let router = Router::new()
+        .merge(entropy_routes)
+        .route("/api/status", get(get_status))
+        .route("/api/quota", get(serve_quota))
+        .route("/api/reserve/{token}", get(serve_reservation_slice))
+        .route("/api/peek", get(serve_peek))
+        .route("/api/verify", post(verify_packet))
+        .route("/health", get(readiness_check))
+        .route("/health/live", get(liveness_check))
+        .route("/health/ready", get(readiness_check))
+        .route("/metrics", get(get_metrics))
+        .route("/push", post(receive_push))
+        .route("/admin/buffer", get(admin_buffer))
+        .route("/admin/buffer/clear", post(admin_clear_buffer))
+        .route("/admin/audit", get(admin_audit))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), enforce_request_timeout))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), record_endpoint_metrics))
+        .route_layer(axum::middleware::from_fn(trace_request))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), track_in_flight))
+        .layer(axum::middleware::from_fn(propagate_request_id))
+        .layer(CorsLayer::permissive())
+        .with_state(state);
+
+    if compression_enabled {
+        router.layer(text_compression_layer())
+    } else {
+        router
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Parse arguments
+    let args = Args::parse();
+
+    // Load configuration from file (if given) or environment variables; it is
+    // needed before logging starts because it may enable OTLP export
+    let config = match &args.config {
+        Some(path) => GatewayConfig::from_file(path)
+            .with_context(|| format!("Failed to load configuration from {}", path.display()))?,
+        None => GatewayConfig::from_env()
+            .context("Failed to load configuration from environment")?,
+    };
+
+    let telemetry = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| OtlpTelemetry::new(endpoint, config.otlp_export_interval()))
+        .transpose()?;
+
+    // Initialize tracing: JSON logs, plus span export when OTLP is configured
+    let log_level = args.log_level.parse::<tracing::Level>()
+        .unwrap_or(tracing::Level::INFO);
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::filter::LevelFilter::from_level(log_level))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_ids(true)
+                .json()
+                .with_current_span(false)
+                .with_span_list(false),
+        )
+        .with(telemetry.as_ref().map(OtlpTelemetry::tracing_layer))
+        .init();
+
+    info!("QRNG Gateway v{}", env!("CARGO_PKG_VERSION"));
+    info!("The gateway acts as a data diode for the Quantis Appliance and receives pushed data from the collector.");
+    info!("Developed by Valer BOCAN, PhD, CSSLP - www.bocan.ro");
+
+    match &args.config {
+        Some(path) => info!("Loaded configuration from {}", path.display()),
+        None => info!("Loaded configuration from environment variables"),
+    }
+    if let Some(endpoint) = &config.otlp_endpoint {
+        info!("Exporting traces and metrics over OTLP to {}", endpoint);
+    }
+
+    info!("Listen address: {}", config.listen_address);
+
+    // Create buffer with overflow policy, restoring persisted entropy if available
+    let buffer = match restore_buffer(&config) {
+        Some(buffer) => buffer,
+        None => if let Some(ttl) = config.buffer_ttl() {
+            EntropyBuffer::with_ttl(config.buffer_size, ttl)
+        } else {
+            EntropyBuffer::new(config.buffer_size)
+        },
+    }
+    .with_overflow_policy(config.overflow_policy());
+
+    info!("Buffer overflow policy: {:?}", config.overflow_policy());
+
+    // Create packet verifier for push mode
+    let signer = config.packet_verifier()
+        .context("Invalid push verification key")?;
+    if let Some(signer) = &signer {
+        info!("Pushed packets are verified with {:?} signatures", signer.scheme());
+    }
+
+    // Decode optional payload encryption key
+    let encryption_key = config.encryption_key_bytes()
+        .context("Invalid encryption key")?;
+    if encryption_key.is_some() {
+        info!("Payload encryption enabled (ChaCha20-Poly1305)");
+    }
+
+    // Create application state
+    let audit = AuditLedger::from_config(&config).context("Failed to open audit log")?;
+    if let Some(path) = &config.audit_log {
+        info!("Recording served entropy in audit log {}", path);
+    }
+    let state = AppState::new(config.clone(), buffer.clone(), signer, encryption_key).with_audit(audit);
+    let in_flight = state.in_flight.clone();
+    if let Some(telemetry) = &telemetry {
+        telemetry.observe_metrics(&state.metrics);
+    }
+
+    // Fetch from the appliance ourselves in direct mode; /push keeps working alongside
+    let direct_fetcher = match &config.direct_mode {
+        Some(direct) => {
+            let source = source_for_appliance(&direct.appliance(), direct.fetch_chunk_size)
+                .context("Failed to create direct mode entropy source")?;
+            info!(
+                "Direct mode enabled, fetching {} bytes every {} ms from {}",
+                direct.fetch_chunk_size, direct.fetch_interval_ms, direct.appliance_url
+            );
+            Some(tokio::spawn(direct_fetch_loop(
+                buffer.clone(),
+                state.metrics.clone(),
+                source,
+                direct.fetch_chunk_size,
+                direct.fetch_interval(),
+            )))
+        }
+        None => None,
+    };
+
+    // Notify the operator's webhook when the buffer runs low or overfills
+    let watermark_monitor = config.alert_webhook_url.clone().map(|webhook_url| {
+        info!("Sending buffer watermark alerts to {}", webhook_url);
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        tokio::spawn(watermark_alert_loop(
+            buffer.clone(),
+            client,
+            webhook_url,
+            WATERMARK_CHECK_INTERVAL,
+            WATERMARK_ALERT_COOLDOWN,
+        ))
+    });
+
+    // Parse listen address
+    let addr: SocketAddr = config.listen_address.parse()
+        .context("Invalid listen address")?;
+
+    // Load TLS certificate and key when HTTPS is configured
+    let tls_config = match config.tls_paths() {
+        Some((cert_path, key_path)) => {
+            let tls = load_tls_config(cert_path, key_path).await?;
+            info!("TLS enabled (certificate: {})", cert_path);
+            Some(tls)
+        }
+        None => None,
+    };
+
+    // Create cancellation token for graceful shutdown
+    let cancel_token = CancellationToken::new();
+    let cancel_token_signal = cancel_token.clone();
+
+    // Build HTTP router for gateway API
+    let app = build_router(state);
+
+    info!(
+        "Gateway server starting on {} ({})",
+        addr,
+        if tls_config.is_some() { "HTTPS" } else { "HTTP" }
+    );
+
+    // Handle Ctrl+C for graceful shutdown
+    tokio::spawn(async move {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => {
+                info!("Received Ctrl+C, shutting down");
+                cancel_token_signal.cancel();
+            }
+            Err(e) => error!("Failed to listen for Ctrl+C: {}", e),
+        }
+    });
+
+    // Start server; on shutdown, drain in-flight requests within the grace period
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let pending = serve_until_shutdown(
+        listener,
+        app,
+        tls_config,
+        cancel_token,
+        in_flight,
+        config.shutdown_grace_period(),
+    )
+    .await;
+    if pending > 0 {
+        warn!("Shutdown grace period elapsed with {} requests still in flight", pending);
+    }
+    if let Some(direct_fetcher) = direct_fetcher {
+        direct_fetcher.abort();
+    }
+    if let Some(watermark_monitor) = watermark_monitor {
+        watermark_monitor.abort();
+    }
+
+    // Persist remaining entropy for the next start
+    if let Some(path) = config.buffer_persist_path.as_ref() {
+        match buffer.save_to_path(path) {
+            Ok(bytes) => info!("Saved {} buffered bytes to {}", bytes, path),
+            Err(e) => error!("Failed to save buffer to {}: {}", path, e),
+        }
+    }
+
+    // Flush spans and metrics still waiting for export
+    if let Some(telemetry) = telemetry {
+        let _ = tokio::task::spawn_blocking(move || telemetry.shutdown()).await;
+    }
+
+    Ok(())
+}
+
+/// Serve `app` until `shutdown` is cancelled, then stop accepting connections and
+/// wait up to `grace` for in-flight requests
+///
+/// Returns the number of requests cut off when the grace period elapsed.
+async fn serve_until_shutdown(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls_config: Option<axum_server::tls_rustls::RustlsConfig>,
+    shutdown: CancellationToken,
+    in_flight: Arc<InFlightRequests>,
+    grace: std::time::Duration,
+) -> usize {
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server_shutdown = shutdown.clone();
+    let mut server = tokio::spawn(async move {
+        match tls_config {
+            Some(tls_config) => {
+                let handle = axum_server::Handle::new();
+                let shutdown_handle = handle.clone();
+                tokio::spawn(async move {
+                    server_shutdown.cancelled().await;
+                    shutdown_handle.graceful_shutdown(Some(grace));
+                });
+
+                axum_server::from_tcp_rustls(listener.into_std()?, tls_config)
+                    .handle(handle)
+                    .serve(app)
+                    .await
+            }
+            None => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async move { server_shutdown.cancelled().await })
+                    .await
+            }
+        }
+    });
+
+    let drained = async {
+        shutdown.cancelled().await;
+        info!(
+            "Server is shutting down, waiting up to {:?} for {} in-flight requests",
+            grace,
+            in_flight.count()
+        );
+        in_flight.wait_idle(grace).await
+    };
+
+    let (result, pending) = tokio::select! {
+        result = &mut server => (result, 0),
+        pending = drained => {
+            // Idle connections close promptly once requests are done; force the rest
+            if pending > 0 {
+                server.abort();
+            }
+            (server.await, pending)
+        }
+    };
+
+    match result {
+        Ok(Err(e)) => error!("Server error: {}", e),
+        Err(e) if !e.is_cancelled() => error!("Server task failed: {}", e),
+        _ => {}
+    }
+    pending
+}
+
+/// OTLP trace and metric pipelines exporting to an OTLP/HTTP collector
+///
+/// Exports run on the SDK's own threads; `shutdown` flushes what is pending.
+struct OtlpTelemetry {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl OtlpTelemetry {
+    /// Build exporters sending to the `/v1/traces` and `/v1/metrics` paths under `endpoint`
+    fn new(endpoint: &str, export_interval: std::time::Duration) -> Result<Self> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let resource = Resource::builder()
+            .with_service_name(env!("CARGO_PKG_NAME"))
+            .build();
+
+        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()
+            .context("Failed to create OTLP span exporter")?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .build()
+            .context("Failed to create OTLP metric exporter")?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(metric_exporter).with_interval(export_interval).build())
+            .with_resource(resource)
+            .build();
+
+        Ok(Self { tracer_provider, meter_provider })
+    }
+
+    /// Layer forwarding `tracing` spans to the trace pipeline
+    fn tracing_layer<S>(&self) -> tracing_opentelemetry::OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.tracer_provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Export the counters of `metrics` under their Prometheus names, read at each collection
+    fn observe_metrics(&self, metrics: &Metrics) {
+        let meter = self.meter_provider.meter(env!("CARGO_PKG_NAME"));
+        let counters = [
+            ("qrng_requests_total", "Total number of requests", Metrics::requests_total as fn(&Metrics) -> u64),
+            ("qrng_requests_failed", "Total number of failed requests", Metrics::requests_failed),
+            ("qrng_bytes_served", "Total bytes served", Metrics::bytes_served),
+            ("qrng_seeds_issued_total", "Seeds issued by /api/seed", Metrics::seeds_issued),
+        ];
+        for (name, description, read) in counters {
+            let metrics = metrics.clone();
+            meter
+                .u64_observable_counter(name)
+                .with_description(description)
+                .with_callback(move |observer| observer.observe(read(&metrics), &[]))
+                .build();
+        }
+
+        let endpoint_metrics = metrics.clone();
+        meter
+            .u64_observable_counter("qrng_endpoint_requests_total")
+            .with_description("Requests by endpoint and HTTP status")
+            .with_callback(move |observer| {
+                for (endpoint, status, count) in endpoint_metrics.endpoint_request_counts() {
+                    observer.observe(count, &[
+                        KeyValue::new("endpoint", endpoint),
+                        KeyValue::new("status", i64::from(status)),
+                    ]);
+                }
+            })
+            .build();
+
+        let uptime_metrics = metrics.clone();
+        meter
+            .u64_observable_gauge("qrng_uptime_seconds")
+            .with_description("Service uptime in seconds")
+            .with_callback(move |observer| observer.observe(uptime_metrics.uptime_seconds(), &[]))
+            .build();
+    }
+
+    /// Flush pending spans and metrics and stop exporting; blocks until the exporters finish
+    fn shutdown(&self) {
+        if let Err(e) = self.tracer_provider.shutdown() {
+            warn!("Failed to flush OTLP spans: {}", e);
+        }
+        if let Err(e) = self.meter_provider.shutdown() {
+            warn!("Failed to flush OTLP metrics: {}", e);
+        }
+    }
+}
+
+/// Load and validate the PEM certificate chain and private key for HTTPS
+async fn load_tls_config(
+    cert_path: &str,
+    key_path: &str,
+) -> Result<axum_server::tls_rustls::RustlsConfig> {
+    // Several crates enable rustls; select the provider explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let cert = std::fs::read(cert_path)
+        .with_context(|| format!("Failed to read TLS certificate {}", cert_path))?;
+    let key = std::fs::read(key_path)
+        .with_context(|| format!("Failed to read TLS private key {}", key_path))?;
+
+    axum_server::tls_rustls::RustlsConfig::from_pem(cert, key)
+        .await
+        .with_context(|| {
+            format!("Invalid TLS certificate {} or private key {}", cert_path, key_path)
+        })
+}
+
+/// Restore the buffer snapshot saved at the last shutdown, if any
+///
+/// The snapshot is deleted once loaded so the same entropy is never served twice,
+/// even if the gateway later crashes before saving a new one.
+fn restore_buffer(config: &GatewayConfig) -> Option<EntropyBuffer> {
+    let path = config.buffer_persist_path.as_ref()?;
+    if !std::path::Path::new(path).exists() {
+        info!("No buffer snapshot at {}, starting empty", path);
+        return None;
+    }
+
+    let restored = EntropyBuffer::load_from_path(path, config.buffer_size, config.buffer_ttl());
+    // Serving restored bytes while the snapshot survives would let a later restart serve them again
+    if let Err(e) = std::fs::remove_file(path) {
+        error!("Failed to remove buffer snapshot {}, discarding its contents: {}", path, e);
+        return None;
+    }
+
+    match restored {
+        Ok(buffer) => {
+            info!("Restored {} bytes from buffer snapshot {}", buffer.len(), path);
+            Some(buffer)
+        }
+        Err(e) => {
+            warn!("Ignoring unreadable buffer snapshot {}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrng_core::crypto::HmacAlgo;
+    use qrng_core::protocol::ChecksumAlgorithm;
+    use axum::body::Body;
+    use axum::extract::connect_info::MockConnectInfo;
+    use axum::http::Request;
+    use chrono::{Duration, Utc};
+    use tower::ServiceExt;
+
+    const TEST_API_KEY: &str = "test-key";
+
+    fn test_state(buffer_size: usize) -> AppState {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": buffer_size,
+        }))
+        .unwrap();
+        let buffer = EntropyBuffer::new(config.buffer_size);
+        AppState::new(config, buffer, None, None)
+    }
+
+    fn test_router(state: AppState) -> Router {
+        build_router(state).layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))))
+    }
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        use rand::Rng;
+        let mut data = vec![0u8; len];
+        rand::rng().fill(&mut data[..]);
+        data
+    }
+
+    async fn get(router: Router, uri: &str) -> Response {
+        router
+            .oneshot(
+                Request::get(uri)
+                    .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replay_guard_in_order() {
+        let guard = ReplayGuard::new(8);
+        let now = Utc::now();
+        for seq in 0..20 {
+            let ts = now + Duration::milliseconds(seq as i64);
+            assert_eq!(guard.check(seq, ts), SequenceCheck::InOrder);
+        }
+    }
+
+    #[test]
+    fn test_replay_guard_duplicate() {
+        let guard = ReplayGuard::new(8);
+        let now = Utc::now();
+        assert_eq!(guard.check(0, now), SequenceCheck::InOrder);
+        assert_eq!(guard.check(1, now + Duration::milliseconds(1)), SequenceCheck::InOrder);
+
+        // Exact replay of an accepted packet
+        assert_eq!(guard.check(1, now + Duration::milliseconds(1)), SequenceCheck::Replay);
+        assert_eq!(guard.check(0, now), SequenceCheck::Replay);
+    }
+
+    #[test]
+    fn test_replay_guard_out_of_order_and_gap() {
+        let guard = ReplayGuard::new(8);
+        let now = Utc::now();
+        assert_eq!(guard.check(0, now), SequenceCheck::InOrder);
+
+        // Packets 1 and 2 are overtaken by packet 3
+        let ts = |seq: i64| now + Duration::milliseconds(seq);
+        assert_eq!(guard.check(3, ts(3)), SequenceCheck::Gap { missing: 2 });
+        assert_eq!(guard.check(2, ts(2)), SequenceCheck::OutOfOrder);
+        assert_eq!(guard.check(1, ts(1)), SequenceCheck::OutOfOrder);
+
+        // Late arrivals are only accepted once
+        assert_eq!(guard.check(2, ts(2)), SequenceCheck::Replay);
+        assert_eq!(guard.check(4, ts(4)), SequenceCheck::InOrder);
+    }
+
+    #[test]
+    fn test_replay_guard_outside_window() {
+        let guard = ReplayGuard::new(4);
+        let now = Utc::now();
+        for seq in 0..10 {
+            guard.check(seq, now + Duration::milliseconds(seq as i64));
+        }
+
+        // Sequence 2 was never seen but is too old to be tracked
+        assert_eq!(guard.check(2, now + Duration::milliseconds(2)), SequenceCheck::Replay);
+    }
+
+    #[test]
+    fn test_replay_guard_collector_restart() {
+        let guard = ReplayGuard::new(8);
+        let now = Utc::now();
+        for seq in 0..5 {
+            guard.check(seq, now + Duration::milliseconds(seq as i64));
+        }
+
+        // A restarted collector begins again at 0 with a newer timestamp
+        let later = now + Duration::seconds(10);
+        assert_eq!(guard.check(0, later), SequenceCheck::Restarted);
+        assert_eq!(guard.check(1, later + Duration::milliseconds(1)), SequenceCheck::InOrder);
+
+        // Old packets from before the restart are still rejected
+        assert_eq!(guard.check(1, now + Duration::milliseconds(1)), SequenceCheck::Replay);
+        assert_eq!(guard.check(4, now + Duration::milliseconds(4)), SequenceCheck::Replay);
+    }
+
+    #[tokio::test]
+    async fn test_stream_one_mebibyte() {
+        const STREAM_BYTES: usize = 1024 * 1024;
+        let state = test_state(2 * STREAM_BYTES);
+        let payload = random_bytes(STREAM_BYTES + 100);
+        state.buffer.push(payload.clone()).unwrap();
+
+        let response = get(
+            test_router(state.clone()),
+            &format!("/api/stream?bytes={}", STREAM_BYTES),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), STREAM_BYTES);
+        assert_eq!(body.as_ref(), &payload[..STREAM_BYTES]);
+        assert_eq!(state.buffer.len(), 100);
+        assert_eq!(state.metrics.bytes_served(), STREAM_BYTES as u64);
+    }
+
+    #[tokio::test]
+    async fn test_stream_waits_for_entropy() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1000)).unwrap();
+
+        // The rest of the stream arrives after the request has started
+        let buffer = state.buffer.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            buffer.push(random_bytes(1000)).unwrap();
+        });
+
+        let response = get(test_router(state), "/api/stream?bytes=1500&encoding=hex").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), 3000);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_stream() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1000)).unwrap();
+        let buffer = state.buffer.clone();
+        let in_flight = state.in_flight.clone();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = CancellationToken::new();
+        let grace = std::time::Duration::from_secs(5);
+        let server = tokio::spawn(serve_until_shutdown(
+            listener,
+            build_router(state),
+            None,
+            shutdown.clone(),
+            in_flight.clone(),
+            grace,
+        ));
+
+        // The stream stalls waiting for entropy, so it is still in flight at shutdown
+        let response = reqwest::get(format!(
+            "http://{}/api/stream?bytes=1500&api_key={}",
+            addr, TEST_API_KEY
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(in_flight.count(), 1);
+
+        let started = Instant::now();
+        shutdown.cancel();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        buffer.push(random_bytes(1000)).unwrap();
+
+        assert_eq!(response.bytes().await.unwrap().len(), 1500);
+        assert_eq!(server.await.unwrap(), 0);
+        assert!(started.elapsed() < grace);
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_otlp_exports_request_spans_and_metrics() {
+        // Mock OTLP/HTTP collector recording each export's path and protobuf body
+        let exports = Arc::new(parking_lot::Mutex::new(Vec::<(String, Vec<u8>)>::new()));
+        let recorder = exports.clone();
+        let collector = Router::new().fallback(move |uri: axum::http::Uri, body: axum::body::Bytes| {
+            let recorder = recorder.clone();
+            async move {
+                recorder.lock().push((uri.path().to_string(), body.to_vec()));
+                StatusCode::OK
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, collector).await });
+
+        let telemetry = OtlpTelemetry::new(&endpoint, std::time::Duration::from_secs(60)).unwrap();
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        telemetry.observe_metrics(&state.metrics);
+
+        let subscriber = tracing_subscriber::registry().with(telemetry.tracing_layer());
+        let guard = tracing::subscriber::set_default(subscriber);
+        let response = get(test_router(state), "/api/random?bytes=16").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        drop(guard);
+
+        tokio::task::spawn_blocking(move || telemetry.shutdown()).await.unwrap();
+
+        let exports = exports.lock();
+        let contains = |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).any(|w| w == needle);
+        let traces: Vec<_> = exports.iter().filter(|(path, _)| path == "/v1/traces").collect();
+        assert!(traces.iter().any(|(_, body)| contains(body, b"GET /api/random")));
+        let metrics: Vec<_> = exports.iter().filter(|(path, _)| path == "/v1/metrics").collect();
+        assert!(metrics.iter().any(|(_, body)| contains(body, b"qrng_requests_total")));
+    }
+
+    /// Serve `state` on a local port, returning its address
+    async fn spawn_server(state: AppState) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = build_router(state).into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_websocket_entropy_requests() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::{self, Message};
+
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1000)).unwrap();
+        let addr = spawn_server(state.clone()).await;
+
+        // The upgrade itself requires an API key
+        match tokio_tungstenite::connect_async(format!("ws://{}/ws", addr)).await {
+            Err(tungstenite::Error::Http(response)) => assert_eq!(response.status(), StatusCode::UNAUTHORIZED),
+            other => panic!("unexpected handshake result: {:?}", other.map(|(_, response)| response)),
+        }
+
+        let url = format!("ws://{}/ws?api_key={}", addr, TEST_API_KEY);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        for bytes in [16, 100] {
+            socket.send(Message::text(format!(r#"{{"bytes":{}}}"#, bytes))).await.unwrap();
+            match socket.next().await.unwrap().unwrap() {
+                Message::Binary(data) => assert_eq!(data.len(), bytes),
+                other => panic!("unexpected frame: {:?}", other),
+            }
+        }
+        assert_eq!(state.buffer.len(), 884);
+
+        // Invalid requests get an error frame and the socket stays open
+        for request in [r#"{"bytes":0}"#, "not json"] {
+            socket.send(Message::text(request)).await.unwrap();
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => assert!(text.contains("error"), "{}", text),
+                other => panic!("unexpected frame: {:?}", other),
+            }
+        }
+
+        // A request the buffer cannot satisfy closes the socket with "try again later"
+        socket.send(Message::text(r#"{"bytes":2000}"#)).await.unwrap();
+        match socket.next().await.unwrap().unwrap() {
+            Message::Close(Some(frame)) => assert_eq!(u16::from(frame.code), 1013),
+            other => panic!("unexpected frame: {:?}", other),
+        }
+        assert_eq!(state.buffer.len(), 884);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_rate_limits_each_message() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "rate_limit_per_second": 2,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(1000)).unwrap();
+        let addr = spawn_server(state).await;
+
+        let url = format!("ws://{}/ws?api_key={}", addr, TEST_API_KEY);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            socket.send(Message::text(r#"{"bytes":8}"#)).await.unwrap();
+            frames.push(socket.next().await.unwrap().unwrap());
+        }
+        assert!(matches!(frames[0], Message::Binary(_)));
+        assert!(matches!(frames[1], Message::Binary(_)));
+        assert!(matches!(&frames[2], Message::Text(text) if text.contains("Rate limit")));
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_wait_idle_times_out() {
+        let in_flight = Arc::new(InFlightRequests::default());
+        assert_eq!(in_flight.wait_idle(std::time::Duration::from_millis(10)).await, 0);
+
+        let guard = in_flight.start();
+        assert_eq!(in_flight.wait_idle(std::time::Duration::from_millis(50)).await, 1);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert_eq!(in_flight.wait_idle(std::time::Duration::from_secs(5)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_rejects_invalid_size() {
+        let state = test_state(1024);
+        let response = get(test_router(state.clone()), "/api/stream?bytes=0").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let uri = format!("/api/stream?bytes={}", MAX_STREAM_BYTES + 1);
+        let response = get(test_router(state), &uri).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    const TEST_HMAC_KEY: &[u8] = b"test-hmac-key";
+
+    fn push_state(encryption_key: Option<Vec<u8>>) -> AppState {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+        }))
+        .unwrap();
+        let buffer = EntropyBuffer::new(config.buffer_size);
+        let signer = PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256);
+        AppState::new(config, buffer, Some(signer), encryption_key.map(Zeroizing::new))
+    }
+
+    fn signed_packet(sequence: u64, data: Vec<u8>, encryption_key: Option<&[u8]>) -> Vec<u8> {
+        let mut packet = EntropyPacket::new(sequence, data);
+        if let Some(key) = encryption_key {
+            packet.encrypt_payload(key).unwrap();
+        }
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+        PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256)
+            .sign_packet(&mut packet)
+            .unwrap();
+        packet.to_msgpack().unwrap()
+    }
+
+    async fn push(router: Router, body: Vec<u8>) -> StatusCode {
+        router
+            .oneshot(
+                Request::post("/push")
+                    .header("content-type", "application/msgpack")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_push_encrypted_packet() {
+        let key = vec![7u8; 32];
+        let state = push_state(Some(key.clone()));
+        let router = test_router(state.clone());
+
+        let data = random_bytes(256);
+        let status = push(router, signed_packet(0, data.clone(), Some(&key))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // The buffer holds the decrypted payload
+        assert_eq!(state.buffer.pop(data.len()).unwrap().to_vec(), data);
+    }
+
+    #[tokio::test]
+    async fn test_push_tagged_packets_in_status() {
+        let state = push_state(None);
+        let router = test_router(state.clone());
+
+        for (sequence, tag) in [(0, Some("us-east")), (1, None), (2, Some("eu-west"))] {
+            let mut packet = EntropyPacket::new(sequence, random_bytes(256));
+            packet.source_tag = tag.map(str::to_string);
+            packet.set_checksum(ChecksumAlgorithm::Crc32);
+            PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256).sign_packet(&mut packet).unwrap();
+            assert_eq!(push(router.clone(), packet.to_msgpack().unwrap()).await, StatusCode::OK);
+        }
+
+        let response = get(router.clone(), "/api/status").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let status: GatewayStatus = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(status.source_tags, ["eu-west", "us-east"]);
+
+        // Once the us-east bytes are served its tag is gone
+        state.buffer.pop(256).unwrap();
+        let response = get(router, "/api/status").await;
+        let status: GatewayStatus = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(status.source_tags, ["eu-west"]);
+    }
+
+    #[tokio::test]
+    async fn test_push_ed25519_packet() {
+        let collector_signer = PacketSigner::ed25519(&[3u8; 32]).unwrap();
+        let public_key = collector_signer.ed25519_public_key().unwrap();
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "signature_scheme": "ed25519",
+            "ed25519_public_key": encode_hex(&public_key),
+        }))
+        .unwrap();
+        let verifier = config.packet_verifier().unwrap();
+        let buffer = EntropyBuffer::new(config.buffer_size);
+        let router = test_router(AppState::new(config, buffer, verifier, None));
+
+        let sign = |signer: &PacketSigner, sequence: u64| {
+            let mut packet = EntropyPacket::new(sequence, random_bytes(64));
+            packet.set_checksum(ChecksumAlgorithm::Crc32);
+            signer.sign_packet(&mut packet).unwrap();
+            packet
+        };
+
+        let packet = sign(&collector_signer, 0);
+        assert_eq!(push(router.clone(), packet.to_msgpack().unwrap()).await, StatusCode::OK);
+
+        // Signed by another private key
+        let other = PacketSigner::ed25519(&[4u8; 32]).unwrap();
+        let packet = sign(&other, 1);
+        assert_eq!(push(router.clone(), packet.to_msgpack().unwrap()).await, StatusCode::UNAUTHORIZED);
+
+        // HMAC-signed packets are not accepted under the Ed25519 scheme
+        let packet = sign(&PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256), 2);
+        assert_eq!(push(router.clone(), packet.to_msgpack().unwrap()).await, StatusCode::UNAUTHORIZED);
+
+        // Tampered payload
+        let mut packet = sign(&collector_signer, 3);
+        packet.data[0] ^= 0xFF;
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+        assert_eq!(push(router, packet.to_msgpack().unwrap()).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_verify_packet_endpoint() {
+        const ADMIN_KEY: &str = "admin-key";
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "admin_api_key": ADMIN_KEY,
+            "buffer_size": 4096,
+        }))
+        .unwrap();
+        let signer = PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256);
+        let state = AppState::new(config, EntropyBuffer::new(4096), Some(signer), None);
+        let router = test_router(state.clone());
+
+        let verify = |body: Vec<u8>, key: &str| {
+            let request = Request::post("/api/verify")
+                .header("authorization", format!("Bearer {}", key))
+                .header("content-type", "application/msgpack")
+                .body(Body::from(body))
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+        let verdict = |response: Response| async move {
+            assert_eq!(response.status(), StatusCode::OK);
+            serde_json::from_str::<VerifyPacketResponse>(&body_text(response).await).unwrap()
+        };
+
+        let result = verdict(verify(signed_packet(42, vec![1; 64], None), ADMIN_KEY).await.unwrap()).await;
+        assert!(result.valid && result.signature_valid && result.checksum_valid);
+        assert_eq!(result.sequence, 42);
+        assert_eq!(result.payload_bytes, 64);
+        assert!(!result.stale && !result.encrypted);
+        assert!(result.age_seconds <= 1);
+
+        // Tampering with the payload breaks both the checksum and the signature
+        let mut packet = EntropyPacket::from_msgpack(&signed_packet(43, vec![1; 64], None)).unwrap();
+        packet.data[0] ^= 0xFF;
+        let result = verdict(verify(packet.to_msgpack().unwrap(), ADMIN_KEY).await.unwrap()).await;
+        assert!(!result.valid && !result.signature_valid && !result.checksum_valid);
+
+        // A packet signed with another key has an intact checksum but a bad signature
+        let mut packet = EntropyPacket::new(44, vec![2; 64]);
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+        PacketSigner::new(b"some-other-key".to_vec(), HmacAlgo::Sha256).sign_packet(&mut packet).unwrap();
+        let result = verdict(verify(packet.to_msgpack().unwrap(), ADMIN_KEY).await.unwrap()).await;
+        assert!(!result.valid && !result.signature_valid && result.checksum_valid);
+
+        // Verified packets are never buffered
+        assert!(state.buffer.is_empty());
+
+        let response = verify(b"not a packet".to_vec(), ADMIN_KEY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = verify(signed_packet(45, vec![1; 64], None), TEST_API_KEY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_push_encryption_mismatch() {
+        let key = vec![7u8; 32];
+
+        // Plaintext packets are rejected when encryption is required
+        let router = test_router(push_state(Some(key.clone())));
+        let status = push(router.clone(), signed_packet(0, random_bytes(64), None)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Packets encrypted with another key fail authentication
+        let status = push(router, signed_packet(1, random_bytes(64), Some(&[9u8; 32]))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Encrypted packets are rejected when no key is configured
+        let router = test_router(push_state(None));
+        let status = push(router, signed_packet(0, random_bytes(64), Some(&key))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_random_accept_header() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state);
+
+        let request = |uri: &str, accept: Option<&str>| {
+            let mut request = Request::get(uri).header("authorization", format!("Bearer {}", TEST_API_KEY));
+            if let Some(accept) = accept {
+                request = request.header("accept", accept);
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let content_type = |response: &Response| {
+            response.headers()[hyper::header::CONTENT_TYPE].to_str().unwrap().to_string()
+        };
+
+        let response = request("/api/random?bytes=16", Some("application/octet-stream")).await.unwrap();
+        assert_eq!(content_type(&response), "application/octet-stream");
+        assert_eq!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), 16);
+
+        let response = request("/api/random?bytes=16", Some("text/plain")).await.unwrap();
+        let body = body_text(response).await;
+        assert_eq!(body.len(), 32);
+        assert!(qrng_core::crypto::decode_hex(&body).is_ok());
+
+        let response = request("/api/random?bytes=16", Some("application/base64")).await.unwrap();
+        assert_eq!(qrng_core::crypto::decode_base64(&body_text(response).await).unwrap().len(), 16);
+
+        // Unsupported or missing Accept falls back to hex
+        for accept in [Some("application/json"), None] {
+            let response = request("/api/random?bytes=16", accept).await.unwrap();
+            assert_eq!(body_text(response).await.len(), 32);
+        }
+
+        // The query parameter wins over the header
+        let response = request("/api/random?bytes=16&encoding=hex", Some("application/octet-stream"))
+            .await
+            .unwrap();
+        assert_eq!(content_type(&response), "text/plain; charset=utf-8");
+        assert_eq!(body_text(response).await.len(), 32);
+        let response = request("/api/random?bytes=16&encoding=bogus", Some("text/plain")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_random_base32_base85() {
+        let state = test_state(4096);
+        let data = random_bytes(20);
+        state.buffer.push(data.clone()).unwrap();
+        let router = test_router(state);
+
+        let response = get(router.clone(), "/api/random?bytes=10&encoding=base32").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        let body = body_text(response).await;
+        assert_eq!(body.len(), 16);
+        assert_eq!(qrng_core::crypto::decode_base32(&body).unwrap(), data[..10]);
+
+        let response = get(router.clone(), "/api/random?bytes=10&encoding=ascii85").await;
+        let body = body_text(response).await;
+        assert_eq!(body.len(), 13);
+        assert_eq!(qrng_core::crypto::decode_base85(&body).unwrap(), data[10..]);
+    }
+
+    #[tokio::test]
+    async fn test_hex_response_compression() {
+        use std::io::Read;
+
+        let state = test_state(64 * 1024);
+        let data = random_bytes(16 * 1024);
+        state.buffer.push(data.clone()).unwrap();
+        let router = test_router(state.clone());
+
+        let request = |uri: &str| {
+            Request::get(uri)
+                .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                .header("accept-encoding", "gzip")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(request("/api/random?bytes=8192&encoding=hex")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[hyper::header::CONTENT_ENCODING], "gzip");
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(compressed.len() < 16 * 1024);
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut text).unwrap();
+        assert_eq!(text, encode_hex(&data[..8192]));
+
+        // Binary payloads are sent as-is
+        let response = router.oneshot(request("/api/random?bytes=4096&encoding=binary")).await.unwrap();
+        assert!(response.headers().get(hyper::header::CONTENT_ENCODING).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.to_vec(), data[8192..12288]);
+
+        // Disabled by configuration
+        let mut config = state.config.clone();
+        config.compression_enabled = false;
+        let state = AppState::new(config, state.buffer.clone(), None, None);
+        let response = test_router(state).oneshot(request("/api/random?bytes=1024&encoding=hex")).await.unwrap();
+        assert!(response.headers().get(hyper::header::CONTENT_ENCODING).is_none());
+        assert_eq!(body_text(response).await, encode_hex(&data[12288..13312]));
+    }
+
+    #[tokio::test]
+    async fn test_random_seed_snippets() {
+        let state = test_state(4096);
+        let router = test_router(state.clone());
+
+        let seed: Vec<u8> = (0..32).collect();
+        let hex = encode_hex(&seed);
+
+        state.buffer.push(seed.clone()).unwrap();
+        let response = get(router.clone(), "/api/random?bytes=32&format=code&lang=python").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_text(response).await.contains(&format!("random.seed(0x{})", hex)));
+
+        state.buffer.push(seed.clone()).unwrap();
+        let response = get(router.clone(), "/api/random?bytes=32&format=code&lang=js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_text(response).await.contains(&format!("seedrandom(\"{}\")", hex)));
+
+        state.buffer.push(seed.clone()).unwrap();
+        let response = get(router, "/api/random?bytes=32&format=code&lang=rust").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let snippet = body_text(response).await;
+        assert!(snippet.contains("let seed: [u8; 32] = [0x00, 0x01, 0x02,"));
+        assert!(snippet.contains("0x1e, 0x1f];"));
+    }
+
+    #[tokio::test]
+    async fn test_random_seed_snippet_validation() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        for uri in [
+            "/api/random?bytes=32&format=code",
+            "/api/random?bytes=32&format=code&lang=cobol",
+            "/api/random?bytes=32&format=snippet&lang=python",
+            "/api/random?bytes=16&format=code&lang=rust",
+        ] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+
+        // Rejected requests do not consume entropy
+        assert_eq!(state.buffer.len(), 1024);
+    }
+
+    #[test]
+    fn test_pack_values_endianness() {
+        let integers = [1i64, -2, 0x0102_0304_0506_0708];
+        let le = pack_values(&integers, Endian::Little, i64::to_le_bytes, i64::to_be_bytes);
+        let be = pack_values(&integers, Endian::Big, i64::to_le_bytes, i64::to_be_bytes);
+        assert_eq!(&le[..8], &[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&be[..8], &[0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(&le[8..16], &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(&be[16..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let floats = [1.0f64];
+        let be = pack_values(&floats, Endian::Big, f64::to_le_bytes, f64::to_be_bytes);
+        assert_eq!(be, vec![0x3F, 0xF0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_integers_packed_endianness() {
+        let state = test_state(4096);
+        let router = test_router(state.clone());
+
+        // Same entropy must decode to the same values regardless of output byte order
+        let entropy = random_bytes(32);
+        state.buffer.push(entropy.clone()).unwrap();
+        let json: Vec<i64> = serde_json::from_str(
+            &body_text(get(router.clone(), "/api/integers?count=4&min=0&max=1000000").await).await,
+        )
+        .unwrap();
+
+        // Narrow ranges leave unused entropy behind; start each draw from the same bytes
+        state.buffer.clear();
+        state.buffer.push(entropy.clone()).unwrap();
+        let response = get(router.clone(), "/api/integers?count=4&min=0&max=1000000&encoding=binary&endian=be").await;
+        assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], "application/octet-stream");
+        let be = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let decoded: Vec<i64> = be
+            .chunks_exact(8)
+            .map(|chunk| i64::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(decoded, json);
+
+        state.buffer.clear();
+        state.buffer.push(entropy).unwrap();
+        let response = get(router.clone(), "/api/integers?count=4&min=0&max=1000000&encoding=binary").await;
+        let le = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let decoded: Vec<i64> = le
+            .chunks_exact(8)
+            .map(|chunk| i64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(decoded, json);
+
+        let response = get(router, "/api/floats?count=1&encoding=binary&endian=middle").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_integer_entropy_consumption() {
+        assert_eq!(integer_width(6), 1);
+        assert_eq!(integer_width(256), 1);
+        assert_eq!(integer_width(257), 2);
+        assert_eq!(integer_width(1_000_000_000_000_000_000), 8);
+        assert_eq!(integer_width(integer_range(i64::MIN, i64::MAX)), 8);
+
+        let state = test_state(1 << 16);
+        state.buffer.push(random_bytes(1 << 16)).unwrap();
+        let router = test_router(state.clone());
+        let consumed = |response: &Response| -> usize {
+            response.headers()[ENTROPY_CONSUMED_HEADER].to_str().unwrap().parse().unwrap()
+        };
+
+        // A die needs one byte per roll, plus the rare rejection of 252..=255
+        let response = get(router.clone(), "/api/integers?count=1000&min=1&max=6").await;
+        let dice_bytes = consumed(&response);
+        let rolls: Vec<i64> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert!(rolls.iter().all(|roll| (1..=6).contains(roll)));
+        assert!((1000..1100).contains(&dice_bytes), "{}", dice_bytes);
+
+        // A range of 10^18 needs all eight bytes per value
+        let before = state.buffer.len();
+        let response = get(router.clone(), "/api/integers?count=1000&min=1&max=1000000000000000000").await;
+        let wide_bytes = consumed(&response);
+        assert!((8000..8800).contains(&wide_bytes), "{}", wide_bytes);
+        assert_eq!(before - state.buffer.len(), wide_bytes);
+        assert!(wide_bytes > 7 * dice_bytes);
+
+        // The full i64 range never rejects
+        let response = get(router, &format!("/api/integers?count=10&min={}&max={}", i64::MIN, i64::MAX)).await;
+        assert_eq!(consumed(&response), 80);
+
+        // Every face of the die is equally likely
+        let buffer = EntropyBuffer::new(1 << 20);
+        buffer.push(random_bytes(1 << 20)).unwrap();
+        let (rolls, _) = generate_integers(&buffer, 60_000, 1, 6).unwrap();
+        for face in 1..=6 {
+            let hits = rolls.iter().filter(|&&roll| roll == face).count();
+            assert!((9_400..10_600).contains(&hits), "face {} rolled {} times", face, hits);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_integer_bounds() {
+        let state = test_state(1 << 16);
+        state.buffer.push(random_bytes(1 << 16)).unwrap();
+        let router = test_router(state);
+
+        let draw = |query: &str| {
+            let router = router.clone();
+            let uri = format!("/api/integers?count=1000&min=1&max=3{}", query);
+            async move {
+                let response = get(router, &uri).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                let range = response.headers()[INTEGER_RANGE_HEADER].to_str().unwrap().to_string();
+                let values: Vec<i64> = serde_json::from_str(&body_text(response).await).unwrap();
+                (range, values)
+            }
+        };
+
+        // Inclusive by default: both ends appear
+        for query in ["", "&bounds=inclusive"] {
+            let (range, values) = draw(query).await;
+            assert_eq!(range, "[1,3]");
+            assert!(values.iter().all(|value| (1..=3).contains(value)));
+            assert!(values.contains(&1) && values.contains(&3));
+        }
+
+        // Exclusive: max never appears
+        let (range, values) = draw("&bounds=exclusive").await;
+        assert_eq!(range, "[1,3)");
+        assert!(values.iter().all(|value| (1..3).contains(value)));
+        assert!(values.contains(&1) && values.contains(&2));
+
+        // min == max - 1 leaves a single value
+        let response = get(router.clone(), "/api/integers?count=5&min=4&max=5&bounds=exclusive").await;
+        let values: Vec<i64> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(values, [4; 5]);
+
+        let response = get(router, "/api/integers?count=5&bounds=open").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_csprng_fallback() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "fallback_csprng": true,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        let router = test_router(state.clone());
+
+        // An empty buffer is served from the CSPRNG, labeled as such
+        for uri in [
+            "/api/random?bytes=32&encoding=binary",
+            "/api/integers?count=10&min=1&max=6",
+            "/api/floats?count=4",
+            "/api/uuid?count=2",
+            "/api/dice?notation=3d6",
+        ] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "fallback", "{}", uri);
+        }
+        let response = get(router.clone(), "/api/random?bytes=32").await;
+        assert_eq!(body_text(response).await.len(), 64);
+        // CSPRNG bytes are not counted or audited as served entropy
+        assert_eq!(state.metrics.bytes_served(), 0);
+
+        // A buffer too short for the whole draw is left untouched, not partly consumed
+        state.buffer.push(random_bytes(3)).unwrap();
+        let response = get(router.clone(), "/api/integers?count=10&min=0&max=1000000").await;
+        assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "fallback");
+        assert_eq!(state.buffer.len(), 3);
+        state.buffer.pop(3).unwrap();
+
+        // Key material never falls back
+        let response = get(router.clone(), "/api/seed?bits=256").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Quantum responses are labeled as such
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let response = get(router.clone(), "/api/random?bytes=32").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "quantum");
+
+        // When due, the generator reseeds from whatever quantum bytes remain
+        let fallback = state.fallback.as_ref().unwrap();
+        fallback.state.lock().1 = Instant::now().checked_sub(FALLBACK_RESEED_INTERVAL).unwrap();
+        let available = state.buffer.len();
+        let response = get(router, &format!("/api/random?bytes={}", available + 1)).await;
+        assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "fallback");
+        assert_eq!(state.buffer.len(), available - 32);
+        assert!(fallback.state.lock().1.elapsed() < FALLBACK_RESEED_INTERVAL);
+
+        // Disabled by default: an empty buffer is a 503
+        let router = test_router(test_state(4096));
+        let response = get(router, "/api/random?bytes=32").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_floats_packed_endianness() {
+        let state = test_state(4096);
+        let router = test_router(state.clone());
+
+        // All-ones entropy yields the largest float below 1.0
+        state.buffer.push(vec![0xFF; 8]).unwrap();
+        let response = get(router, "/api/floats?count=1&encoding=binary&endian=be").await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let expected = (u64::MAX >> 11) as f64 / (1u64 << 53) as f64;
+        assert_eq!(body.to_vec(), expected.to_be_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_float_precision_modes() {
+        let modes = [FloatPrecision::Bits53, FloatPrecision::Bits64, FloatPrecision::Raw];
+        let below_one = 1.0f64.next_down();
+
+        // All-ones entropy yields the largest float below 1.0 in every mode
+        for mode in modes {
+            assert_eq!(mode.unit_float(u64::MAX), below_one, "{:?}", mode);
+            assert_eq!(mode.unit_float(0), 0.0, "{:?}", mode);
+        }
+
+        // Small words vanish on the 53-bit grid but keep their value at full width
+        assert_eq!(FloatPrecision::Bits53.unit_float(3), 0.0);
+        assert_eq!(FloatPrecision::Bits64.unit_float(3), 3.0 * 2f64.powi(-64));
+        assert_eq!(FloatPrecision::Raw.unit_float(3), 3.0 * 2f64.powi(-64));
+        let word = (1u64 << 40) | 1;
+        assert_eq!(FloatPrecision::Bits64.unit_float(word), word as f64 * 2f64.powi(-64));
+
+        let grid = 2f64.powi(53);
+        let mut off_grid = [0usize; 3];
+        for word in entropy_words(&random_bytes(8000)) {
+            for (i, mode) in modes.into_iter().enumerate() {
+                let value = mode.unit_float(word);
+                assert!((0.0..1.0).contains(&value), "{:?} gave {}", mode, value);
+                if (value * grid).fract() != 0.0 {
+                    off_grid[i] += 1;
+                }
+            }
+        }
+        // Values below 0.5 resolve finer than 2^-53 unless limited to 53 bits
+        assert_eq!(off_grid[0], 0);
+        assert!(off_grid[1] > 200, "{:?}", off_grid);
+        assert!(off_grid[2] > 200, "{:?}", off_grid);
+
+        assert_eq!(FloatPrecision::parse(None, false), Some(FloatPrecision::Bits53));
+        assert_eq!(FloatPrecision::parse(Some(64), false), Some(FloatPrecision::Bits64));
+        assert_eq!(FloatPrecision::parse(None, true), Some(FloatPrecision::Raw));
+        assert_eq!(FloatPrecision::parse(Some(32), false), None);
+        assert_eq!(FloatPrecision::parse(Some(53), true), None);
+    }
+
+    #[tokio::test]
+    async fn test_floats_precision_parameter() {
+        let state = test_state(4096);
+        let router = test_router(state.clone());
+
+        let mut word = Vec::new();
+        word.extend_from_slice(&3u64.to_le_bytes());
+        let tiny = 3.0 * 2f64.powi(-64);
+        for (query, expected) in [("", 0.0), ("&precision=64", tiny), ("&raw=true", tiny)] {
+            state.buffer.push(word.clone()).unwrap();
+            let uri = format!("/api/floats?count=1&encoding=binary{}", query);
+            let response = get(router.clone(), &uri).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", query);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(body.to_vec(), expected.to_le_bytes().to_vec(), "{}", query);
+        }
+
+        for query in ["precision=32", "precision=64&raw=true"] {
+            let response = get(router.clone(), &format!("/api/floats?count=1&{}", query)).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_scale_unit_float_clamps_below_max() {
+        assert_eq!(scale_unit_float(0.0, -5.0, 5.0), -5.0);
+        assert_eq!(scale_unit_float(0.5, -5.0, 5.0), 0.0);
+
+        // With a float spacing of 2 near 1e16, f close to 1 rounds up to max
+        let largest_unit = 1.0 - f64::EPSILON / 2.0;
+        let (min, max) = (1e16, 1e16 + 2.0);
+        assert!(min + largest_unit * (max - min) >= max);
+        assert_eq!(scale_unit_float(largest_unit, min, max), min);
+    }
+
+    #[tokio::test]
+    async fn test_floats_in_range_are_uniform() {
+        let state = test_state(16 * 1024);
+        state.buffer.push(random_bytes(8000)).unwrap();
+        let router = test_router(state);
+
+        let response = get(router, "/api/floats?count=1000&min=-5&max=5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let floats: Vec<f64> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(floats.len(), 1000);
+
+        let mut bins = [0usize; 10];
+        for value in floats {
+            assert!((-5.0..5.0).contains(&value), "{} out of range", value);
+            bins[(value + 5.0) as usize] += 1;
+        }
+        // Expected 100 per bin; the bounds are more than 4 standard deviations away
+        for count in bins {
+            assert!((60..=140).contains(&count), "bins {:?}", bins);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_floats_range_validation() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(64)).unwrap();
+        let router = test_router(state);
+
+        for uri in [
+            "/api/floats?count=1&min=5&max=5",
+            "/api/floats?count=1&min=2&max=1",
+            "/api/floats?count=1&min=-inf&max=0",
+            "/api/floats?count=1&min=0&max=NaN",
+            "/api/floats?count=1&min=-1e308&max=1e308",
+        ] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    async fn post_json(router: Router, uri: &str, body: serde_json::Value) -> Response {
+        router
+            .oneshot(
+                Request::post(uri)
+                    .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn choose(router: Router, body: serde_json::Value) -> Vec<String> {
+        let response = post_json(router, "/api/choice", body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        serde_json::from_str(&body_text(response).await).unwrap()
+    }
+
+    #[test]
+    fn test_shuffle_positions_uniform() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(50_000)).unwrap();
+
+        // counts[position][value] over 3000 shuffles of [0, 1, 2]
+        let mut counts = [[0usize; 3]; 3];
+        let mut words = EntropyWords::new(&state.buffer, 0).unwrap();
+        for _ in 0..3000 {
+            let mut items = [serde_json::json!(0), serde_json::json!(1), serde_json::json!(2)];
+            shuffle_items(&mut words, &mut items).unwrap();
+            for (position, value) in items.iter().enumerate() {
+                counts[position][value.as_u64().unwrap() as usize] += 1;
+            }
+        }
+
+        // Expected 1000 each; the bounds are more than 5 standard deviations away
+        for row in counts {
+            for count in row {
+                assert!((870..=1130).contains(&count), "counts {:?}", counts);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authentication() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": ["first-key", TEST_API_KEY],
+            "buffer_size": 4096,
+        }))
+        .unwrap();
+        assert!(is_client_api_key(&config, "first-key"));
+        assert!(is_client_api_key(&config, TEST_API_KEY));
+        for key in ["", "test", "test-key-", "TEST-KEY", "first-key "] {
+            assert!(!is_client_api_key(&config, key), "{:?} accepted", key);
+        }
+
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state);
+        let request = |auth: &str, query: &str| {
+            router.clone().oneshot(
+                Request::get(format!("/api/random?bytes=4{}", query))
+                    .header("authorization", auth)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = request("Bearer first-key", "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = request("", "&api_key=test-key").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = request("Bearer first", "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = request("", "&api_key=test-key-1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_shuffle_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        // Values come back exactly, including nested and large numbers
+        let items = serde_json::json!(["a", 1, 2.5, null, true, {"k": [1, 2]}, 18446744073709551615u64, "a"]);
+        let response = post_json(router.clone(), "/api/shuffle", items.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let shuffled: Vec<serde_json::Value> = serde_json::from_str(&body_text(response).await).unwrap();
+        let sorted = |values: &[serde_json::Value]| {
+            let mut keys: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(sorted(&shuffled), sorted(items.as_array().unwrap()));
+
+        // Empty and single-element arrays consume nothing
+        let available = state.buffer.len();
+        for items in [serde_json::json!([]), serde_json::json!([{"only": 1}])] {
+            let response = post_json(router.clone(), "/api/shuffle", items.clone()).await;
+            assert_eq!(body_text(response).await, items.to_string());
+        }
+        assert_eq!(state.buffer.len(), available);
+
+        let too_many = serde_json::Value::Array(vec![serde_json::json!(0); MAX_SHUFFLE_ITEMS + 1]);
+        let response = post_json(router.clone(), "/api/shuffle", too_many).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Elements are echoed verbatim, not reparsed into f64 or reformatted
+        let response = router
+            .oneshot(
+                Request::post("/api/shuffle")
+                    .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                    .header("content-type", "application/json")
+                    .body(Body::from("[123456789012345678901234567890, 1.0e2]"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_text(response).await;
+        assert!(
+            body == "[123456789012345678901234567890,1.0e2]" || body == "[1.0e2,123456789012345678901234567890]",
+            "{}",
+            body
+        );
+    }
+
+    /// Deterministic uniform bytes, so the report outcomes cannot flake
+    fn seeded_bytes(len: usize) -> Vec<u8> {
+        use rand::{RngCore, SeedableRng};
+        let mut data = vec![0u8; len];
+        rand_chacha::ChaCha20Rng::seed_from_u64(1840).fill_bytes(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_entropy_report_outcomes() {
+        let report = entropy_report(&seeded_bytes(65536));
+        assert!(report.passed, "{:?}", report);
+
+        // Low bit always clear: fewer ones and half the byte values never occur
+        let biased: Vec<u8> = seeded_bytes(65536).iter().map(|byte| byte & 0xFE).collect();
+        let report = entropy_report(&biased);
+        assert!(!report.monobit.passed);
+        assert!(!report.chi_square.passed);
+        assert!(!report.passed);
+
+        // A counter is perfectly balanced but each byte predicts the next
+        let counter: Vec<u8> = (0..65536).map(|i| i as u8).collect();
+        let report = entropy_report(&counter);
+        assert!(report.monobit.passed);
+        assert!(report.chi_square.passed);
+        assert!(!report.serial_correlation.passed);
+        assert!(!report.passed);
+
+        // Alternating bits have as many ones as zeros but far too many runs
+        let report = entropy_report(&[0x55; 4096]);
+        assert_eq!(report.monobit.statistic, Some(0.0));
+        assert!(!report.runs.passed);
+        assert_eq!(report.serial_correlation.statistic, Some(1.0));
+
+        // All ones fail the monobit prerequisite of the runs test
+        let report = entropy_report(&[0xFF; 4096]);
+        assert_eq!(report.runs.statistic, None);
+        assert!(!report.runs.passed);
+    }
+
+    #[tokio::test]
+    async fn test_entropy_report_endpoint() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(seeded_bytes(4096)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/entropy-report?bytes=2048").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let report: EntropyReport = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(report.bytes, 2048);
+        assert_eq!(report.significance, REPORT_SIGNIFICANCE);
+        assert_eq!(state.buffer.len(), 2048);
+
+        // The default sample is larger than what is left
+        let response = get(router.clone(), "/api/entropy-report").await;
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(state.buffer.len(), 2048);
+
+        for bytes in [MIN_ENTROPY_REPORT_BYTES - 1, MAX_ENTROPY_REPORT_BYTES + 1] {
+            let response = get(router.clone(), &format!("/api/entropy-report?bytes={}", bytes)).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_choice_uniform_frequencies() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(40_000)).unwrap();
+        let router = test_router(state);
+
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..4 {
+            let picks = choose(router.clone(), serde_json::json!({
+                "items": ["a", "b", "c", "d"],
+                "count": 1000,
+            }))
+            .await;
+            assert_eq!(picks.len(), 1000);
+            for pick in picks {
+                *counts.entry(pick).or_insert(0usize) += 1;
+            }
+        }
+
+        // Expected 1000 each; the bounds are more than 5 standard deviations away
+        assert_eq!(counts.len(), 4);
+        for (item, count) in &counts {
+            assert!((860..=1140).contains(count), "{} chosen {} times", item, count);
+        }
+    }
+
+    #[test]
+    fn test_permutation_positions_uniform() {
+        let state = test_state(128 * 1024);
+        state.buffer.push(random_bytes(120_000)).unwrap();
+
+        // counts[position][value] over 4000 permutations of 0..4
+        let mut counts = [[0usize; 4]; 4];
+        let mut words = EntropyWords::new(&state.buffer, 0).unwrap();
+        for _ in 0..4000 {
+            let permutation = random_permutation(&mut words, 4).unwrap();
+            let mut sorted = permutation.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, vec![0, 1, 2, 3]);
+            for (position, value) in permutation.into_iter().enumerate() {
+                counts[position][value] += 1;
+            }
+        }
+
+        // Expected 1000 each; the bounds are more than 5 standard deviations away
+        for (position, row) in counts.iter().enumerate() {
+            for (value, count) in row.iter().enumerate() {
+                assert!((860..=1140).contains(count), "{} at position {} {} times", value, position, count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_covers_range_uniformly() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(60_000)).unwrap();
+
+        // Each of 1..=10 is expected in 3/10 of 2000 draws of 3
+        let mut counts = [0usize; 10];
+        let mut words = EntropyWords::new(&state.buffer, 0).unwrap();
+        for _ in 0..2000 {
+            let sample = random_sample(&mut words, 10, 3).unwrap();
+            assert_eq!(sample.len(), 3);
+            assert!(sample[0] != sample[1] && sample[1] != sample[2] && sample[0] != sample[2]);
+            for value in sample {
+                counts[value - 1] += 1;
+            }
+        }
+
+        // Expected 600 each; the bounds are more than 4.5 standard deviations away
+        for (value, count) in counts.iter().enumerate() {
+            assert!((505..=695).contains(count), "{} drawn {} times", value + 1, count);
+        }
+    }
+
+    #[test]
+    fn test_exponential_and_poisson_means() {
+        let state = test_state(1024 * 1024);
+        state.buffer.push(random_bytes(1024 * 1024)).unwrap();
+        let mut words = EntropyWords::new(&state.buffer, 0).unwrap();
+
+        // Exponential: mean 1/lambda, standard deviation of the mean 1/(lambda * sqrt(n))
+        let n = 20_000;
+        let lambda = 2.0;
+        let samples: Vec<f64> = (0..n).map(|_| exponential_sample(&mut words, lambda).unwrap()).collect();
+        assert!(samples.iter().all(|x| x.is_finite() && *x >= 0.0));
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        assert!((mean - 0.5).abs() < 5.0 * 0.5 / (n as f64).sqrt(), "exponential mean {}", mean);
+
+        // Poisson: mean and variance lambda
+        let n = 5_000;
+        let lambda = 4.0;
+        let total: u64 = (0..n).map(|_| poisson_sample(&mut words, lambda).unwrap()).sum();
+        let mean = total as f64 / n as f64;
+        assert!((mean - lambda).abs() < 5.0 * (lambda / n as f64).sqrt(), "poisson mean {}", mean);
+    }
+
+    #[test]
+    fn test_bernoulli_rates_and_bit_usage() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(32 * 1024)).unwrap();
+        let n = 20_000;
+
+        // Fair draws use exactly one bit each
+        let mut bits = EntropyBitReader::new(&state.buffer);
+        let threshold = probability_threshold(0.5);
+        let trues = (0..n).filter(|_| bernoulli(&mut bits, threshold).unwrap()).count();
+        assert_eq!(bits.bytes_consumed(), n / 8);
+        // Bounds are 5 standard deviations: sqrt(n p (1 - p))
+        assert!((trues as f64 - 10_000.0).abs() < 5.0 * 70.8, "p=0.5 gave {} trues", trues);
+
+        // Biased draws use two bits on average
+        let mut bits = EntropyBitReader::new(&state.buffer);
+        let threshold = probability_threshold(0.1);
+        let trues = (0..n).filter(|_| bernoulli(&mut bits, threshold).unwrap()).count();
+        assert!((trues as f64 - 2_000.0).abs() < 5.0 * 42.5, "p=0.1 gave {} trues", trues);
+        let bits_per_draw = (bits.bytes_consumed() * 8) as f64 / n as f64;
+        assert!((1.9..2.1).contains(&bits_per_draw), "{} bits per draw", bits_per_draw);
+    }
+
+    #[tokio::test]
+    async fn test_bool_and_coinflip_endpoints() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(2048)).unwrap();
+        let router = test_router(state.clone());
+
+        let available = state.buffer.len();
+        let response = get(router.clone(), "/api/bool?count=800").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let values: Vec<bool> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(values.len(), 800);
+        assert_eq!(available - state.buffer.len(), 100);
+
+        let response = get(router.clone(), "/api/bool?count=50&probability=0.25").await;
+        let values: Vec<bool> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(values.len(), 50);
+
+        let response = get(router.clone(), "/api/coinflip?count=10").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let flips: Vec<String> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(flips.len(), 10);
+        assert!(flips.iter().all(|flip| flip == "heads" || flip == "tails"));
+
+        for uri in [
+            "/api/bool?probability=0",
+            "/api/bool?probability=1",
+            "/api/bool?probability=1.5",
+            "/api/bool?probability=NaN",
+            "/api/bool?count=0",
+            "/api/bool?count=10001",
+            "/api/coinflip?count=10001",
+        ] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_distribution_endpoints() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(32 * 1024)).unwrap();
+        let router = test_router(state);
+
+        let response = get(router.clone(), "/api/exponential?count=10&lambda=0.5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let values: Vec<f64> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(values.len(), 10);
+        assert!(values.iter().all(|x| *x >= 0.0));
+
+        let response = get(router.clone(), "/api/poisson?count=10&lambda=3").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let values: Vec<u64> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(values.len(), 10);
+
+        for uri in [
+            "/api/exponential?count=10&lambda=0",
+            "/api/exponential?count=10&lambda=-1",
+            "/api/exponential?count=0&lambda=1",
+            "/api/poisson?count=1001&lambda=1",
+            "/api/poisson?count=10&lambda=101",
+            "/api/poisson?count=10&lambda=NaN",
+        ] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_liveness_and_readiness() {
+        let state = test_state(1000);
+        let router = test_router(state.clone());
+
+        // Empty but alive: not ready to serve entropy
+        assert_eq!(get(router.clone(), "/health/live").await.status(), StatusCode::OK);
+        assert_eq!(get(router.clone(), "/health/ready").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(get(router.clone(), "/health").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        state.buffer.push(random_bytes(500)).unwrap();
+        for uri in ["/health/live", "/health/ready", "/health"] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_propagation() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state);
+
+        let request = |request_id: Option<&str>| {
+            let mut request = Request::get("/api/random?bytes=8")
+                .header("authorization", format!("Bearer {}", TEST_API_KEY));
+            if let Some(id) = request_id {
+                request = request.header(REQUEST_ID_HEADER, id);
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        // A client-supplied ID is echoed back
+        let response = request(Some("trace-42")).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-42");
+
+        // Otherwise a UUID is generated, also for rejected requests
+        let response = request(None).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+        let long_id = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+        let response = request(Some(&long_id)).await.unwrap();
+        assert_ne!(response.headers()[REQUEST_ID_HEADER], long_id.as_str());
+
+        let response = get(router.clone(), "/api/random?bytes=0").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+    }
+
+    #[test]
+    fn test_uuid_versions() {
+        let buffer = EntropyBuffer::new(4096);
+        buffer.push(random_bytes(4096)).unwrap();
+
+        for uuid in generate_uuids(&buffer, 50, UuidVersion::V4).unwrap() {
+            let uuid = uuid::Uuid::parse_str(&uuid).unwrap();
+            assert_eq!(uuid.get_version_num(), 4);
+            assert_eq!(uuid.get_variant(), uuid::Variant::RFC4122);
+        }
+        assert_eq!(buffer.len(), 4096 - 50 * 16);
+
+        // v7 UUIDs carry the current Unix milliseconds and sort by creation time
+        let before = chrono::Utc::now().timestamp_millis() as u64;
+        let mut created = Vec::new();
+        for _ in 0..5 {
+            created.extend(generate_uuids(&buffer, 1, UuidVersion::V7).unwrap());
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        assert_eq!(buffer.len(), 4096 - 50 * 16 - 5 * 10);
+        let mut sorted = created.clone();
+        sorted.sort();
+        assert_eq!(sorted, created);
+        for uuid in &created {
+            let uuid = uuid::Uuid::parse_str(uuid).unwrap();
+            assert_eq!(uuid.get_version_num(), 7);
+            assert_eq!(uuid.get_variant(), uuid::Variant::RFC4122);
+            let bytes = uuid.as_bytes();
+            let mut millis = [0u8; 8];
+            millis[2..].copy_from_slice(&bytes[..6]);
+            assert!(u64::from_be_bytes(millis) >= before);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uuid_version_parameter() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/uuid?version=7").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let uuid = uuid::Uuid::parse_str(&body_text(response).await).unwrap();
+        assert_eq!(uuid.get_version_num(), 7);
+        assert_eq!(state.buffer.len(), 1024 - 10);
+
+        let response = get(router.clone(), "/api/uuid?count=2&version=4").await;
+        let uuids: Vec<String> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert!(uuids.iter().all(|uuid| uuid::Uuid::parse_str(uuid).unwrap().get_version_num() == 4));
+
+        let response = get(router, "/api/uuid?version=5").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_metrics() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        get(router.clone(), "/api/integers?count=5").await;
+        get(router.clone(), "/api/integers?count=0").await;
+        get(router.clone(), "/api/uuid?count=500").await;
+        get(router.clone(), "/api/random?bytes=4096").await;
+        get(router.clone(), "/no-such-endpoint").await;
+
+        assert_eq!(state.metrics.endpoint_requests("/api/integers", 200), 1);
+        assert_eq!(state.metrics.endpoint_requests("/api/integers", 400), 1);
+        assert_eq!(state.metrics.endpoint_requests("/api/uuid", 400), 1);
+        assert_eq!(state.metrics.endpoint_requests("/api/random", 503), 1);
+
+        let metrics = body_text(get(router, "/metrics").await).await;
+        assert!(metrics.contains("qrng_endpoint_requests_total{endpoint=\"/api/integers\",status=\"200\"} 1\n"));
+        assert!(!metrics.contains("no-such-endpoint"));
+    }
+
+    #[test]
+    fn test_decimal_digits_unbiased() {
+        let state = test_state(512 * 1024);
+        state.buffer.push(random_bytes(400_000)).unwrap();
+        let mut words = EntropyWords::new(&state.buffer, 0).unwrap();
+
+        // D=1: each of the ten values about 1000 times in 10000 draws
+        let mut counts = [0usize; 10];
+        for _ in 0..10_000 {
+            let decimal = random_decimal(&mut words, 1).unwrap();
+            assert_eq!(decimal.len(), 3);
+            counts[decimal[2..].parse::<usize>().unwrap()] += 1;
+        }
+        // Standard deviation is 30; the bounds are 5 standard deviations away
+        assert!(counts.iter().all(|c| (850..=1150).contains(c)), "{:?}", counts);
+
+        // D=5: leading and trailing digits are both uniform
+        let mut leading = [0usize; 10];
+        let mut trailing = [0usize; 10];
+        for _ in 0..10_000 {
+            let decimal = random_decimal(&mut words, 5).unwrap();
+            assert_eq!(decimal.len(), 7);
+            assert!(decimal.starts_with("0."));
+            let value: u32 = decimal[2..].parse().unwrap();
+            assert!(value < 100_000);
+            leading[(value / 10_000) as usize] += 1;
+            trailing[(value % 10) as usize] += 1;
+        }
+        assert!(leading.iter().all(|c| (850..=1150).contains(c)), "{:?}", leading);
+        assert!(trailing.iter().all(|c| (850..=1150).contains(c)), "{:?}", trailing);
+    }
+
+    #[tokio::test]
+    async fn test_decimal_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(2048)).unwrap();
+        let router = test_router(state);
+
+        let response = get(router.clone(), "/api/decimal?count=5&digits=30").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let decimals: Vec<String> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(decimals.len(), 5);
+        for decimal in decimals {
+            assert_eq!(decimal.len(), 32);
+            assert!(decimal.starts_with("0.") && decimal[2..].bytes().all(|b| b.is_ascii_digit()));
+        }
+
+        for uri in ["/api/decimal?digits=0", "/api/decimal?digits=31", "/api/decimal?count=0&digits=2"] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_string_characters_uniform() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(40_000)).unwrap();
+        let alphabet = resolve_alphabet("custom", Some("abc")).unwrap();
+
+        // Each of three characters about 10000 times in 30000
+        let (string, consumed) = random_string(&state.buffer, &alphabet, 30_000).unwrap();
+        assert_eq!(string.len(), 30_000);
+        assert!(consumed >= 30_000);
+        let mut counts = [0usize; 3];
+        for c in string.chars() {
+            counts[(c as u8 - b'a') as usize] += 1;
+        }
+        // Standard deviation is 82; the bounds are 5 standard deviations away
+        assert!(counts.iter().all(|c| (9_590..=10_410).contains(c)), "{:?}", counts);
+    }
+
+    #[tokio::test]
+    async fn test_string_endpoint() {
+        let state = test_state(16 * 1024);
+        state.buffer.push(random_bytes(16 * 1024)).unwrap();
+        let router = test_router(state);
+
+        for (uri, alphabet) in [
+            ("/api/string?length=64", "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz"),
+            ("/api/string?length=100&alphabet=base58", BASE58_ALPHABET),
+            ("/api/string?length=32&alphabet=hex", "0123456789abcdef"),
+            ("/api/string?length=10&alphabet=lowercase", "abcdefghijklmnopqrstuvwxyz"),
+            ("/api/string?length=50&alphabet=custom&chars=%E2%9C%93xy", "\u{2713}xy"),
+        ] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], "text/plain; charset=utf-8");
+            let string = body_text(response).await;
+            let length: usize = uri.split(['=', '&']).nth(1).unwrap().parse().unwrap();
+            assert_eq!(string.chars().count(), length, "{}", uri);
+            assert!(string.chars().all(|c| alphabet.contains(c)), "{}: {}", uri, string);
+        }
+
+        for uri in [
+            "/api/string?length=0",
+            "/api/string?length=4097",
+            "/api/string?length=8&alphabet=emoji",
+            "/api/string?length=8&alphabet=custom",
+            "/api/string?length=8&alphabet=custom&chars=a",
+            "/api/string?length=8&alphabet=custom&chars=abca",
+        ] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_parse_dice_notation() {
+        let dice = |count, sides, modifier| Some(DiceNotation { count, sides, modifier });
+        assert_eq!(parse_dice_notation("3d6+2"), dice(3, 6, 2));
+        assert_eq!(parse_dice_notation("2d20-1"), dice(2, 20, -1));
+        assert_eq!(parse_dice_notation("d100"), dice(1, 100, 0));
+        assert_eq!(parse_dice_notation("d%"), dice(1, 100, 0));
+        assert_eq!(parse_dice_notation(" 4D8 "), dice(4, 8, 0));
+        assert_eq!(parse_dice_notation("1d6-0"), dice(1, 6, 0));
+        assert_eq!(parse_dice_notation("100d1000000+1000000"), dice(100, 1_000_000, 1_000_000));
+
+        for invalid in [
+            "", "d", "3d", "3x6", "0d6", "101d6", "3d1", "3d0", "3d1000001", "-1d6", "3d6+",
+            "3d6+-2", "3d6+2+1", "3d6 + 2", "3d6+1000001", "3d6.5", "+3d6", "3dd6",
+        ] {
+            assert_eq!(parse_dice_notation(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dice_endpoint() {
+        let state = test_state(1 << 17);
+        state.buffer.push(random_bytes(1 << 17)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/dice?notation=3d6%2B2").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let roll: DiceResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(roll.rolls.len(), 3);
+        assert!(roll.rolls.iter().all(|face| (1..=6).contains(face)));
+        assert_eq!(roll.modifier, 2);
+        assert_eq!(roll.total, roll.rolls.iter().sum::<u64>() as i64 + 2);
+
+        let response = get(router.clone(), "/api/dice?notation=2d20-1").await;
+        let roll: DiceResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(roll.total, roll.rolls.iter().sum::<u64>() as i64 - 1);
+
+        for uri in ["/api/dice?notation=3d6%2B", "/api/dice?notation=1000d6", "/api/dice"] {
+            assert!(get(router.clone(), uri).await.status().is_client_error(), "{}", uri);
+        }
+
+        // Every face of a single d6 is equally likely
+        let die = parse_dice_notation("d6").unwrap();
+        let mut faces = [0usize; 6];
+        for _ in 0..60_000 {
+            let (rolls, consumed) = roll_dice(&state.buffer, &die).unwrap();
+            assert!(consumed >= 1);
+            faces[rolls[0] as usize - 1] += 1;
+        }
+        for (face, &hits) in faces.iter().enumerate() {
+            assert!((9_400..10_600).contains(&hits), "face {} rolled {} times", face + 1, hits);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seed_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let seed = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                let response = get(router, uri).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.headers()[hyper::header::CACHE_CONTROL], "no-store");
+                serde_json::from_str::<SeedResponse>(&body_text(response).await).unwrap()
+            }
+        };
+
+        let first = seed("/api/seed").await;
+        let second = seed("/api/seed").await;
+        assert_eq!(first.bits, 256);
+        assert_eq!(qrng_core::crypto::decode_hex(&first.seed).unwrap().len(), 32);
+        assert_ne!(first.seed, second.seed);
+        assert_ne!(first.seed_id, second.seed_id);
+
+        let wide = seed("/api/seed?bits=1024&encoding=base64").await;
+        assert_eq!(qrng_core::crypto::decode_base64(&wide.seed).unwrap().len(), 128);
+        assert_eq!(state.metrics.seeds_issued(), 3);
+
+        for uri in ["/api/seed?bits=100", "/api/seed?bits=32", "/api/seed?bits=8192", "/api/seed?encoding=binary"] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_prng_expansion() {
+        let seed = [7u8; PRNG_SEED_BYTES];
+        for algo in [PrngAlgorithm::ChaCha8, PrngAlgorithm::ChaCha12, PrngAlgorithm::ChaCha20] {
+            assert_eq!(PrngAlgorithm::parse(algo.name()), Some(algo));
+            let stream = algo.expand(seed, 100);
+            assert_eq!(stream, algo.expand(seed, 100));
+            // Longer requests extend the same stream
+            assert_eq!(algo.expand(seed, 40), stream[..40]);
+            assert_ne!(algo.expand([8u8; PRNG_SEED_BYTES], 100), stream);
+        }
+        assert_ne!(
+            PrngAlgorithm::ChaCha8.expand(seed, 32),
+            PrngAlgorithm::ChaCha20.expand(seed, 32)
+        );
+        assert_eq!(PrngAlgorithm::parse("xorshift"), None);
+    }
+
+    #[tokio::test]
+    async fn test_prng_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let prng = |uri: String| {
+            let router = router.clone();
+            async move {
+                let response = get(router, &uri).await;
+                assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+                assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "prng");
+                serde_json::from_str::<PrngResponse>(&body_text(response).await).unwrap()
+            }
+        };
+
+        // A quantum seed is drawn from the buffer
+        let first = prng("/api/prng?bytes=64".to_string()).await;
+        assert_eq!(state.buffer.len(), 1024 - PRNG_SEED_BYTES);
+        assert_eq!((first.seed_source.as_str(), first.algo.as_str(), first.output.as_str()), ("quantum", "chacha20", "prng"));
+        assert_eq!(decode_hex(&first.data).unwrap().len(), 64);
+
+        // Replaying the seed reproduces the stream without consuming entropy
+        let replay = prng(format!("/api/prng?bytes=64&seed={}", first.seed)).await;
+        assert_eq!(replay.seed_source, "client");
+        assert_eq!(replay.data, first.data);
+        assert_eq!(state.buffer.len(), 1024 - PRNG_SEED_BYTES);
+
+        let other = prng("/api/prng?bytes=64".to_string()).await;
+        assert_ne!(other.seed, first.seed);
+        assert_ne!(other.data, first.data);
+
+        let short_seed = "ab".repeat(PRNG_SEED_BYTES - 1);
+        for uri in [
+            "/api/prng?bytes=0".to_string(),
+            "/api/prng?bytes=16&algo=mt19937".to_string(),
+            "/api/prng?bytes=16&encoding=binary".to_string(),
+            format!("/api/prng?bytes=16&seed={}", short_seed),
+            "/api/prng?bytes=16&seed=not-hex".to_string(),
+            // Odd length and a multi-byte character at a pair boundary must not panic
+            "/api/prng?bytes=16&seed=abc".to_string(),
+            format!("/api/prng?bytes=16&seed=a%C3%A9{}", "a".repeat(2 * PRNG_SEED_BYTES - 3)),
+        ] {
+            assert_eq!(get(router.clone(), &uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permutation_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/permutation?n=50").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut permutation: Vec<usize> = serde_json::from_str(&body_text(response).await).unwrap();
+        permutation.sort_unstable();
+        assert_eq!(permutation, (0..50).collect::<Vec<_>>());
+
+        // A single element needs no entropy
+        let response = get(router.clone(), "/api/permutation?n=1").await;
+        assert_eq!(body_text(response).await, "[0]");
+
+        for uri in ["/api/permutation?n=0", "/api/permutation?n=100001"] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST);
+        }
+
+        // 1000 swaps need 8000 bytes; nothing is consumed on failure
+        let available = state.buffer.len();
+        let response = get(router, "/api/permutation?n=1001").await;
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(state.buffer.len(), available);
+    }
+
+    #[tokio::test]
+    async fn test_sample_endpoint() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/sample?n=49&k=6").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let sample: Vec<usize> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(sample.len(), 6);
+        assert!(sample.iter().all(|v| (1..=49).contains(v)));
+        let distinct: std::collections::HashSet<_> = sample.iter().collect();
+        assert_eq!(distinct.len(), 6);
+
+        // One word per selected value, plus any rejections
+        let available = state.buffer.len();
+        let response = get(router.clone(), "/api/sample?n=52&k=5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(available - state.buffer.len() >= 40);
+
+        // Drawing the whole range is a permutation
+        let response = get(router.clone(), "/api/sample?n=5&k=5").await;
+        let mut sample: Vec<usize> = serde_json::from_str(&body_text(response).await).unwrap();
+        sample.sort_unstable();
+        assert_eq!(sample, vec![1, 2, 3, 4, 5]);
+
+        for uri in ["/api/sample?n=5&k=6", "/api/sample?n=5&k=0", "/api/sample?n=100001&k=1"] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+
+        // Nothing is consumed when the buffer cannot supply the draw
+        let available = state.buffer.len();
+        let response = get(router, "/api/sample?n=1000&k=500").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(state.buffer.len(), available);
+    }
+
+    #[tokio::test]
+    async fn test_choice_skewed_weights() {
+        let state = test_state(64 * 1024);
+        state.buffer.push(random_bytes(8000)).unwrap();
+        let router = test_router(state);
+
+        let picks = choose(router, serde_json::json!({
+            "items": ["rare", "never", "common"],
+            "weights": [1.0, 0.0, 99.0],
+            "count": 1000,
+        }))
+        .await;
+
+        let rare = picks.iter().filter(|p| *p == "rare").count();
+        assert!(!picks.iter().any(|p| p == "never"));
+        assert!(rare <= 40, "rare chosen {} times", rare);
+        assert_eq!(picks.iter().filter(|p| *p == "common").count(), 1000 - rare);
+    }
+
+    #[tokio::test]
+    async fn test_choice_validation() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        for body in [
+            serde_json::json!({"items": []}),
+            serde_json::json!({"items": [1, 2], "count": 0}),
+            serde_json::json!({"items": [1, 2], "count": 1001}),
+            serde_json::json!({"items": [1, 2], "weights": [1.0]}),
+            serde_json::json!({"items": [1, 2], "weights": [1.0, -1.0]}),
+            serde_json::json!({"items": [1, 2], "weights": [0.0, 0.0]}),
+        ] {
+            let response = post_json(router.clone(), "/api/choice", body.clone()).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+        assert_eq!(state.buffer.len(), 1024);
+    }
+
+    #[tokio::test]
+    async fn test_push_cbor_packet() {
+        let state = push_state(None);
+        let router = test_router(state.clone());
+
+        let data = random_bytes(128);
+        let mut packet = EntropyPacket::new(0, data.clone());
+        packet.set_checksum(ChecksumAlgorithm::Crc32);
+        PacketSigner::new(TEST_HMAC_KEY.to_vec(), HmacAlgo::Sha256)
+            .sign_packet(&mut packet)
+            .unwrap();
+
+        let body = packet.encode(qrng_core::protocol::SerializationFormat::Cbor).unwrap();
+        assert_eq!(push(router, body).await, StatusCode::OK);
+        assert_eq!(state.buffer.pop(data.len()).unwrap().to_vec(), data);
+    }
+
+    #[tokio::test]
+    async fn test_load_tls_config() {
+        let dir = std::env::temp_dir().join(format!("qrng-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem").to_string_lossy().into_owned();
+        let key_path = dir.join("key.pem").to_string_lossy().into_owned();
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+        assert!(load_tls_config(&cert_path, &key_path).await.is_ok());
+
+        // Missing files and swapped or garbage PEM data fail with context
+        let missing = dir.join("missing.pem").to_string_lossy().into_owned();
+        let error = load_tls_config(&missing, &key_path).await.unwrap_err();
+        assert!(error.to_string().contains("Failed to read TLS certificate"));
+        assert!(load_tls_config(&key_path, &cert_path).await.is_err());
+        std::fs::write(&key_path, "not a key").unwrap();
+        assert!(load_tls_config(&cert_path, &key_path).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_integers_multi() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state.clone());
+
+        // One d6, two d20s and a d100
+        let response = post_json(router.clone(), "/api/integers/multi", serde_json::json!([
+            {"min": 1, "max": 6, "count": 1},
+            {"min": 1, "max": 20, "count": 2},
+            {"min": 1, "max": 100, "count": 1},
+            {"min": -1_000_000, "max": 1_000_000, "count": 3},
+        ]))
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let consumed: usize = response.headers()[ENTROPY_CONSUMED_HEADER].to_str().unwrap().parse().unwrap();
+        let results: Vec<Vec<i64>> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(results.iter().map(Vec::len).collect::<Vec<_>>(), [1, 2, 1, 3]);
+        assert!((1..=6).contains(&results[0][0]));
+        assert!(results[1].iter().all(|n| (1..=20).contains(n)));
+        assert!((1..=100).contains(&results[2][0]));
+        assert!(results[3].iter().all(|n| (-1_000_000..=1_000_000).contains(n)));
+
+        // One byte per small-range integer and three per wide one, plus rejections
+        assert!(consumed >= 4 + 3 * 3);
+        assert_eq!(4096 - state.buffer.len(), consumed);
+
+        // Per-range bounds and the total cap are enforced
+        for body in [
+            serde_json::json!([]),
+            serde_json::json!([{"min": 1, "max": 6, "count": 1}, {"min": 6, "max": 6, "count": 1}]),
+            serde_json::json!([{"min": 1, "max": 6, "count": 0}]),
+            serde_json::json!([{"count": 600}, {"count": 401}]),
+        ] {
+            let response = post_json(router.clone(), "/api/integers/multi", body).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let response = post_json(router, "/api/integers/multi", serde_json::json!([{"count": 600}, {"count": 400}])).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_integer_ranges_share_sampling_with_single_range() {
+        // The same entropy yields the same values through either path
+        let data = random_bytes(64);
+        let single = EntropyBuffer::new(64);
+        single.push(data.clone()).unwrap();
+        let multi = EntropyBuffer::new(64);
+        multi.push(data).unwrap();
+
+        let (first, _) = generate_integers(&single, 3, 1, 6).unwrap();
+        let (second, _) = generate_integers(&single, 2, 1, 20).unwrap();
+        let ranges = [
+            IntegerRange { count: 3, min: 1, max: 6 },
+            IntegerRange { count: 2, min: 1, max: 20 },
+        ];
+        let (results, _) = generate_integer_ranges(&multi, &ranges).unwrap();
+        assert_eq!(results, [first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_response_envelope() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+        let envelope = |response: Response| async move {
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], "application/json");
+            serde_json::from_str::<serde_json::Value>(&body_text(response).await).unwrap()
+        };
+
+        // Text output is embedded as a string, with its encoding and the audited byte count
+        let request = Request::get("/api/random?bytes=8&encoding=hex&envelope=true")
+            .header("authorization", format!("Bearer {}", TEST_API_KEY))
+            .header(REQUEST_ID_HEADER, "envelope-1")
+            .body(Body::empty())
+            .unwrap();
+        let body = envelope(router.clone().oneshot(request).await.unwrap()).await;
+        assert_eq!(body["data"].as_str().unwrap().len(), 16);
+        assert_eq!(body["meta"]["bytes_consumed"], 8);
+        assert_eq!(body["meta"]["encoding"], "hex");
+        assert_eq!(body["meta"]["request_id"], "envelope-1");
+        assert!(body["meta"]["freshness_seconds"].is_u64());
+
+        // Freshness describes the bytes served, not what the buffer holds afterwards
+        for uri in ["/api/random?bytes=8&envelope=true", "/api/bool?count=8&envelope=true"] {
+            let aged = test_state(4096);
+            aged.buffer
+                .push_with_timestamp(random_bytes(8), Utc::now() - Duration::seconds(100))
+                .unwrap();
+            aged.buffer.push(random_bytes(8)).unwrap();
+            let body = envelope(get(test_router(aged), uri).await).await;
+            assert!(body["meta"]["freshness_seconds"].as_u64().unwrap() >= 100, "{}", uri);
+        }
+
+        // JSON output is embedded as JSON; the consumed count comes from the handler's header
+        let body = envelope(get(router.clone(), "/api/integers?count=3&min=1&max=6&envelope=true").await).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 3);
+        assert_eq!(body["meta"]["encoding"], "json");
+        assert!(body["meta"]["bytes_consumed"].as_u64().unwrap() >= 3);
+
+        // Binary output becomes base64
+        let body = envelope(get(router.clone(), "/api/random?bytes=4&encoding=binary&envelope=true").await).await;
+        assert_eq!(body["meta"]["encoding"], "base64");
+        assert_eq!(qrng_core::crypto::decode_base64(body["data"].as_str().unwrap()).unwrap().len(), 4);
+
+        // Bare output is the default and with envelope=false
+        for uri in ["/api/integers?count=3", "/api/integers?count=3&envelope=false"] {
+            let text = body_text(get(router.clone(), uri).await).await;
+            assert_eq!(serde_json::from_str::<Vec<i64>>(&text).unwrap().len(), 3);
+        }
+
+        // Errors pass through unwrapped
+        let response = get(router, "/api/integers?count=0&envelope=true").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_head_random_reports_availability() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(100)).unwrap();
+        let router = test_router(state.clone());
+        let head = |uri: &str| {
+            let request = Request::head(uri)
+                .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let response = head("/api/random?bytes=100").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[AVAILABLE_BYTES_HEADER], "100");
+        assert!(body_text(response).await.is_empty());
+
+        let response = head("/api/random?bytes=101").await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[AVAILABLE_BYTES_HEADER], "100");
+
+        let response = head("/api/random?bytes=0").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = Request::head("/api/random?bytes=10").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Nothing was consumed, and GET still draws from the buffer
+        assert_eq!(state.buffer.len(), 100);
+        let response = get(router.clone(), "/api/random?bytes=10").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.buffer.len(), 90);
+
+        // With the CSPRNG fallback a short buffer still reports that a GET would succeed
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "fallback_csprng": true,
+        }))
+        .unwrap();
+        let router = test_router(AppState::new(config, EntropyBuffer::new(4096), None, None));
+        let request = Request::head("/api/random?bytes=10")
+            .header("authorization", format!("Bearer {}", TEST_API_KEY))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[AVAILABLE_BYTES_HEADER], "0");
+        assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "fallback");
+    }
+
+    #[test]
+    fn test_buffer_evictions_are_counted() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 64,
+        }))
+        .unwrap();
+        let buffer = EntropyBuffer::new(config.buffer_size)
+            .with_overflow_policy(qrng_core::OverflowPolicy::Replace);
+        let state = AppState::new(config, buffer, None, None);
+
+        state.buffer.push(random_bytes(40)).unwrap();
+        state.buffer.push(random_bytes(40)).unwrap();
+
+        assert_eq!(state.metrics.bytes_evicted(qrng_core::EvictionReason::Overflow), 40);
+        assert_eq!(state.metrics.bytes_evicted(qrng_core::EvictionReason::Ttl), 0);
+        assert!(state
+            .metrics
+            .prometheus_format()
+            .contains("qrng_buffer_evicted_bytes_total{reason=\"overflow\"} 40\n"));
+    }
+
+    #[tokio::test]
+    async fn test_status_detail() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state);
+        assert_eq!(get(router.clone(), "/api/random?bytes=32").await.status(), StatusCode::OK);
+
+        let compact = body_text(get(router.clone(), "/api/status").await).await;
+        let status: GatewayStatus = serde_json::from_str(&compact).unwrap();
+        assert!(status.detail.is_none());
+        assert!(!compact.contains("buffer_stats"));
+        assert!(!compact.contains("latency_p50_micros"));
+
+        let detailed = body_text(get(router, "/api/status?detail=true").await).await;
+        let value: serde_json::Value = serde_json::from_str(&detailed).unwrap();
+        for field in ["buffer_stats", "latency_p50_micros", "latency_p95_micros", "latency_p99_micros", "pushes_total", "fetches_total"] {
+            assert!(value["detail"].get(field).is_some(), "missing {}", field);
+        }
+        let detail = serde_json::from_value::<GatewayStatus>(value).unwrap().detail.unwrap();
+        assert_eq!(detail.buffer_stats.bytes_pushed, 1024);
+        assert_eq!(detail.buffer_stats.bytes_popped, 32);
+        assert!(detail.latency_p50_micros.is_some());
+        assert_eq!(detail.fetches_total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_entropy_freshness_header() {
+        let state = test_state(4096);
+        let pushed_at = chrono::Utc::now() - chrono::Duration::seconds(5);
+        state.buffer.push_with_timestamp(random_bytes(2048), pushed_at).unwrap();
+        let router = test_router(state);
+
+        for uri in [
+            "/api/random?bytes=32",
+            "/api/integers?count=10&min=1&max=6",
+            "/api/floats?count=4",
+            "/api/uuid?count=2",
+        ] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "quantum", "{}", uri);
+            let freshness: u64 = response.headers()[ENTROPY_FRESHNESS_HEADER]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((5..60).contains(&freshness), "{}: {}", uri, freshness);
+        }
+    }
+
+    #[test]
+    fn test_buffer_snapshot_restored_once() {
+        let path = std::env::temp_dir().join(format!("qrng-snapshot-{}.bin", std::process::id()));
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 1024,
+            "buffer_persist_path": path.to_str().unwrap(),
+        }))
+        .unwrap();
+
+        let buffer = EntropyBuffer::new(1024);
+        buffer.push(random_bytes(256)).unwrap();
+        buffer.save_to_path(&path).unwrap();
+
+        let restored = restore_buffer(&config).expect("snapshot should be restored");
+        assert_eq!(restored.len(), 256);
+        assert!(!path.exists(), "snapshot must be gone before its bytes are served");
+        assert!(restore_buffer(&config).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch_mixed_operations() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state.clone());
+
+        let response = post_json(router, "/api/batch", serde_json::json!([
+            {"op": "integers", "count": 6, "min": 1, "max": 49},
+            {"op": "floats", "count": 3, "min": -1.0, "max": 1.0},
+            {"op": "uuid", "count": 2},
+            {"op": "integers", "count": 1},
+        ]))
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let results: Vec<serde_json::Value> =
+            serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(results.len(), 4);
+
+        let lottery: Vec<i64> = serde_json::from_value(results[0].clone()).unwrap();
+        assert_eq!(lottery.len(), 6);
+        assert!(lottery.iter().all(|n| (1..=49).contains(n)));
+
+        let floats: Vec<f64> = serde_json::from_value(results[1].clone()).unwrap();
+        assert_eq!(floats.len(), 3);
+        assert!(floats.iter().all(|f| (-1.0..1.0).contains(f)));
+
+        let uuids: Vec<String> = serde_json::from_value(results[2].clone()).unwrap();
+        assert_eq!(uuids.len(), 2);
+        for uuid in &uuids {
+            assert_eq!(uuid::Uuid::parse_str(uuid).unwrap().get_version_num(), 4);
+        }
+
+        let defaults: Vec<i64> = serde_json::from_value(results[3].clone()).unwrap();
+        assert!((0..=100).contains(&defaults[0]));
+
+        // One byte per small-range integer plus rejections, eight per float, sixteen per UUID
+        let consumed = 4096 - state.buffer.len();
+        assert!((3 * 8 + 2 * 16 + 6 + 1..=3 * 8 + 2 * 16 + 6 + 1 + 16).contains(&consumed), "{}", consumed);
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_invalid_requests() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state.clone());
+
+        let too_many: Vec<_> = (0..=MAX_BATCH_OPERATIONS)
+            .map(|_| serde_json::json!({"op": "uuid"}))
+            .collect();
+        for body in [
+            serde_json::json!([]),
+            serde_json::json!(too_many),
+            serde_json::json!([{"op": "uuid"}, {"op": "integers", "count": 2, "min": 5, "max": 5}]),
+            serde_json::json!([{"op": "floats", "count": 1001}]),
+        ] {
+            let response = post_json(router.clone(), "/api/batch", body).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let response = post_json(router.clone(), "/api/batch", serde_json::json!([{"op": "dice"}])).await;
+        assert!(response.status().is_client_error());
+
+        // A batch larger than the buffer fails without consuming entropy
+        let response = post_json(router, "/api/batch", serde_json::json!([
+            {"op": "uuid", "count": 100},
+            {"op": "integers", "count": 1000, "min": 0, "max": 1_000_000_000_000i64},
+        ]))
+        .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(state.buffer.len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn test_batch_counts_as_one_rate_limit_token() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "rate_limit_per_second": 1,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state);
+
+        let operations: Vec<_> = (0..10)
+            .map(|_| serde_json::json!({"op": "integers", "count": 1}))
+            .collect();
+        let response = post_json(router.clone(), "/api/batch", serde_json::json!(operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = post_json(router, "/api/batch", serde_json::json!(operations)).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_configured_max_request_bytes() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "max_request_bytes": 100,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state);
+
+        let response = get(router.clone(), "/api/random?bytes=99&encoding=binary").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get(router.clone(), "/api/random?bytes=100&encoding=binary").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), 100);
+        let response = get(router.clone(), "/api/random?bytes=101&encoding=binary").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get(router, "/api/status").await;
+        let status: GatewayStatus = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(status.max_request_bytes, 100);
+    }
+
+    #[tokio::test]
+    async fn test_status_long_poll() {
+        let state = test_state(1000);
+        let router = test_router(state.clone());
+
+        let buffer = state.buffer.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            buffer.push(random_bytes(100)).unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            buffer.push(random_bytes(300)).unwrap();
+        });
+
+        let start = Instant::now();
+        let response = get(router.clone(), "/api/status?wait_for=healthy&timeout_ms=5000").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let status: GatewayStatus = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(status.status, HealthStatus::Healthy);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+
+        // On timeout the current status is returned
+        state.buffer.pop(350).unwrap();
+        let response = get(router.clone(), "/api/status?wait_for=ready&timeout_ms=50").await;
+        let status: GatewayStatus = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(status.status, HealthStatus::Unhealthy);
+
+        let response = get(router, "/api/status?wait_for=forever").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_audit_ledger_records_served_entropy() {
+        const ADMIN_KEY: &str = "admin-key";
+        let path = std::env::temp_dir().join(format!("qrng-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "admin_api_key": ADMIN_KEY,
+            "buffer_size": 4096,
+            "audit_log": path.to_str().unwrap(),
+            "audit_ring_size": 2,
+        }))
+        .unwrap();
+        let audit = AuditLedger::from_config(&config).unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None).with_audit(audit);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state);
+
+        let request = |uri: &str, request_id: &str| {
+            Request::get(uri)
+                .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                .header(REQUEST_ID_HEADER, request_id)
+                .body(Body::empty())
+                .unwrap()
+        };
+        for (uri, request_id) in [
+            ("/api/random?bytes=32", "audit-1"),
+            ("/api/uuid?count=2", "audit-2"),
+            ("/api/integers?count=4&min=1&max=6", "audit-3"),
+        ] {
+            let response = router.clone().oneshot(request(uri, request_id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
         }
-    };
+        // Failed requests serve nothing and are not recorded
+        let response = router.clone().oneshot(request("/api/random?bytes=0", "audit-4")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    // Deserialize packet
-    let packet = match EntropyPacket::from_msgpack(&body) {
-        Ok(p) => p,
-        Err(e) => {
-            warn!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                error = %e,
-                "Failed to deserialize entropy packet"
-            );
-            return StatusCode::BAD_REQUEST;
+        // The writer task appends every entry to the file
+        let lines = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let contents = std::fs::read_to_string(&path).unwrap_or_default();
+                if contents.lines().count() >= 3 {
+                    return contents;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("audit log was not written");
+        let entries: Vec<AuditEntry> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.endpoint.as_str(), entry.request_id.as_str()))
+            .collect();
+        assert_eq!(summary, [("/api/random", "audit-1"), ("/api/uuid", "audit-2"), ("/api/integers", "audit-3")]);
+        assert_eq!(entries[0].bytes, 32);
+        assert_eq!(entries[1].bytes, 32);
+        assert!(entries[2].bytes >= 4);
+        assert!(entries.iter().all(|entry| entry.api_key_masked == "****-key"));
+        assert!(!lines.contains(TEST_API_KEY));
+
+        // The in-memory ring keeps the most recent entries for admins only
+        let admin = Request::get("/admin/audit")
+            .header("authorization", format!("Bearer {}", ADMIN_KEY))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(admin).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let recent: Vec<AuditEntry> = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].request_id, "audit-2");
+        assert_eq!(recent[1].request_id, "audit-3");
+        assert_eq!(get(router, "/admin/audit").await.status(), StatusCode::UNAUTHORIZED);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_admin_buffer_inspect_and_clear() {
+        const ADMIN_KEY: &str = "admin-key";
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "admin_api_key": ADMIN_KEY,
+            "buffer_size": 4096,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(1000)).unwrap();
+        state.buffer.push(random_bytes(3000)).unwrap();
+        let router = test_router(state.clone());
+
+        let admin = |method: &str, uri: &str, key: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", key))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(admin("GET", "/admin/buffer", ADMIN_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let buffer: AdminBufferResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(buffer.bytes_available, 4000);
+        assert_eq!(buffer.capacity, 4096);
+        assert_eq!(buffer.watermark, WatermarkLevel::Critical);
+        assert_eq!(buffer.stats.total_pushes, 2);
+        assert_eq!(buffer.stats.bytes_pushed, 4000);
+        assert!(buffer.oldest_timestamp.is_some());
+
+        // Client keys are not admin keys
+        for (method, uri) in [("GET", "/admin/buffer"), ("POST", "/admin/buffer/clear")] {
+            let response = router.clone().oneshot(admin(method, uri, TEST_API_KEY)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
         }
-    };
+        assert_eq!(state.buffer.len(), 4000);
 
-    // Verify signature
-    match signer.verify_packet(&packet) {
-        Ok(true) => {}
-        Ok(false) => {
-            warn!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                sequence = packet.sequence,
-                "Invalid packet signature"
-            );
-            return StatusCode::UNAUTHORIZED;
+        let response = router.clone().oneshot(admin("POST", "/admin/buffer/clear", ADMIN_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cleared: AdminClearResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(cleared.bytes_cleared, 4000);
+        assert!(state.buffer.is_empty());
+
+        let response = router.oneshot(admin("GET", "/admin/buffer", ADMIN_KEY)).await.unwrap();
+        let buffer: AdminBufferResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(buffer.bytes_available, 0);
+        assert_eq!(buffer.watermark, WatermarkLevel::Low);
+        assert!(buffer.oldest_timestamp.is_none());
+
+        // Without an admin key the endpoints do not exist
+        let response = test_router(test_state(1024))
+            .oneshot(admin("GET", "/admin/buffer", TEST_API_KEY))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_peek_does_not_consume() {
+        const ADMIN_KEY: &str = "admin-key";
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "admin_api_key": ADMIN_KEY,
+            "buffer_size": 4096,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        let data = random_bytes(64);
+        state.buffer.push(data.clone()).unwrap();
+        let router = test_router(state.clone());
+
+        let peek = |uri: &str, key: &str| {
+            router.clone().oneshot(
+                Request::get(uri)
+                    .header("authorization", format!("Bearer {}", key))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // Repeated peeks see the same bytes and leave the buffer untouched
+        for _ in 0..2 {
+            let response = peek("/api/peek?bytes=32", ADMIN_KEY).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(body_text(response).await, encode_hex(&data[..32]));
+            assert_eq!(state.buffer.len(), 64);
         }
-        Err(e) => {
-            error!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                sequence = packet.sequence,
-                error = %e,
-                "Signature verification error"
-            );
-            return StatusCode::INTERNAL_SERVER_ERROR;
+
+        // The bytes served next are the ones previewed
+        let response = get(router.clone(), "/api/random?bytes=32&encoding=hex").await;
+        assert_eq!(body_text(response).await, encode_hex(&data[..32]));
+
+        let response = peek("/api/peek?bytes=33", ADMIN_KEY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(state.buffer.len(), 32);
+
+        let response = peek("/api/peek?bytes=0", ADMIN_KEY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Regular clients cannot preview entropy
+        let response = peek("/api/peek?bytes=8", TEST_API_KEY).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_watermark_alert_webhook() {
+        type Alerts = Arc<parking_lot::Mutex<Vec<WatermarkAlert>>>;
+        let alerts: Alerts = Arc::default();
+        let webhook = Router::new()
+            .route(
+                "/alert",
+                post(|State(alerts): State<Alerts>, Json(alert): Json<WatermarkAlert>| async move {
+                    alerts.lock().push(alert);
+                    StatusCode::NO_CONTENT
+                }),
+            )
+            .with_state(alerts.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/alert", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move { axum::serve(listener, webhook).await });
+
+        let buffer = EntropyBuffer::new(1000);
+        buffer.push(random_bytes(500)).unwrap();
+        let monitor = tokio::spawn(watermark_alert_loop(
+            buffer.clone(),
+            reqwest::Client::new(),
+            url,
+            std::time::Duration::from_millis(5),
+            std::time::Duration::ZERO,
+        ));
+        let wait_for_alerts = |count: usize| {
+            let alerts = alerts.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                    while alerts.lock().len() < count {
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+                .expect("no watermark alert received");
+            }
+        };
+
+        // Let the monitor take the medium level as its baseline
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // Dropping below the low threshold fires one alert
+        buffer.pop(450).unwrap();
+        wait_for_alerts(1).await;
+        {
+            let alerts = alerts.lock();
+            assert_eq!(alerts[0].level, WatermarkLevel::Low);
+            assert_eq!(alerts[0].fill_percent, 5.0);
+            assert!(!alerts[0].hostname.is_empty());
         }
+
+        // Staying low does not alert again
+        buffer.pop(20).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(alerts.lock().len(), 1);
+
+        // Refilling to the brim re-arms the alert without firing one
+        buffer.push(random_bytes(970)).unwrap();
+        assert_eq!(buffer.watermark(), WatermarkLevel::Critical);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(alerts.lock().len(), 1);
+        buffer.pop(975).unwrap();
+        wait_for_alerts(2).await;
+        assert_eq!(alerts.lock()[1].level, WatermarkLevel::Low);
+
+        monitor.abort();
+        server.abort();
     }
 
-    // Verify checksum if present
-    if !packet.verify_checksum() {
-        warn!(
-            client_ip = %addr,
-            user_agent = %user_agent,
-            endpoint = "/push",
-            sequence = packet.sequence,
-            "Checksum mismatch"
-        );
-        return StatusCode::BAD_REQUEST;
+    #[tokio::test]
+    async fn test_direct_mode_fills_buffer() {
+        // A file-backed appliance holding 256 bytes, exhausted after four fetches
+        let appliance = random_bytes(256);
+        let path = std::env::temp_dir().join(format!("qrng-direct-{}", std::process::id()));
+        std::fs::write(&path, &appliance).unwrap();
+
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 1024,
+            "direct_mode": {
+                "appliance_url": format!("file://{}", path.display()),
+                "fetch_chunk_size": 64,
+                "fetch_interval_ms": 5,
+            },
+        }))
+        .unwrap();
+        config.validate().unwrap();
+        let direct = config.direct_mode.clone().unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(1024), None, None);
+        let source = source_for_appliance(&direct.appliance(), direct.fetch_chunk_size).unwrap();
+        let fetcher = tokio::spawn(direct_fetch_loop(
+            state.buffer.clone(),
+            state.metrics.clone(),
+            source,
+            direct.fetch_chunk_size,
+            direct.fetch_interval(),
+        ));
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while state.metrics.fetches_failed() == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("direct mode never exhausted the appliance");
+        fetcher.abort();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(state.buffer.len(), 256);
+        assert_eq!(state.metrics.bytes_fetched(), 256);
+
+        // The fetched entropy is served in order
+        let response = get(test_router(state.clone()), "/api/random?bytes=256&encoding=hex").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, hex::encode(&appliance));
     }
 
-    // Check freshness
-    if let Some(ttl) = state.config.buffer_ttl() {
-        if packet.is_stale(ttl) {
-            warn!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                sequence = packet.sequence,
-                "Packet is stale"
-            );
-            return StatusCode::BAD_REQUEST;
+    #[tokio::test]
+    async fn test_stale_entropy_is_refused() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "max_served_age_secs": 60,
+            "buffer_ttl_secs": 60,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        let backdated = chrono::Utc::now() - chrono::Duration::seconds(120);
+        state.buffer.push_with_timestamp(random_bytes(64), backdated).unwrap();
+        state.buffer.push(random_bytes(64)).unwrap();
+        let router = test_router(state.clone());
+
+        for uri in ["/api/random?bytes=16", "/api/integers?count=4", "/api/uuid"] {
+            let response = get(router.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+            assert!(body_text(response).await.contains("freshness limit"));
         }
+        assert_eq!(state.buffer.len(), 128);
+
+        // Invalid keys are rejected as such, not as stale entropy
+        let response = get(router.clone(), "/api/random?bytes=16&api_key=wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Status endpoints stay available for diagnosis
+        assert_eq!(get(router.clone(), "/api/status").await.status(), StatusCode::OK);
+
+        // Once the stale entry is gone, the fresh one is served
+        state.buffer.pop(64).unwrap();
+        let response = get(router, "/api/random?bytes=16").await;
+        assert_eq!(response.status(), StatusCode::OK);
     }
 
-    // Push to buffer
-    match state.buffer.push(packet.data.clone()) {
-        Ok(bytes) => {
-            if bytes == 0 {
-                warn!(
-                    client_ip = %addr,
-                    user_agent = %user_agent,
-                    endpoint = "/push",
-                    sequence = packet.sequence,
-                    buffer_fill_percent = state.buffer.fill_percent(),
-                    "Discarded packet, buffer full"
-                );
-                StatusCode::INSUFFICIENT_STORAGE
-            } else if bytes < packet.data.len() {
-                info!(
-                    client_ip = %addr,
-                    user_agent = %user_agent,
-                    endpoint = "/push",
-                    sequence = packet.sequence,
-                    bytes_stored = bytes,
-                    bytes_total = packet.data.len(),
-                    buffer_fill_percent = state.buffer.fill_percent(),
-                    "Received packet (partial)"
-                );
-                StatusCode::OK
-            } else {
-                info!(
-                    client_ip = %addr,
-                    user_agent = %user_agent,
-                    endpoint = "/push",
-                    sequence = packet.sequence,
-                    bytes = bytes,
-                    buffer_fill_percent = state.buffer.fill_percent(),
-                    "Received packet"
-                );
-                StatusCode::OK
-            }
+    #[test]
+    fn test_isolate_for_key() {
+        let data = random_bytes(MAX_DERIVE_LEN + 100);
+
+        let alice = isolate_for_key(&data, "alice").unwrap();
+        let bob = isolate_for_key(&data, "bob").unwrap();
+        assert_eq!(alice.len(), data.len());
+        assert_ne!(alice, bob);
+        assert_ne!(alice, data);
+
+        // Reproducible for the same key and input, including the trailing block
+        assert_eq!(isolate_for_key(&data, "alice").unwrap(), alice);
+        assert_ne!(alice[MAX_DERIVE_LEN..], bob[MAX_DERIVE_LEN..]);
+    }
+
+    #[test]
+    fn test_isolation_preserves_entropy() {
+        // Distinct 32-byte input blocks map to distinct output blocks
+        let blocks = 512;
+        let data: Vec<u8> = (0..blocks as u32)
+            .flat_map(|i| {
+                let mut block = [0u8; ISOLATION_BLOCK_BYTES];
+                block[..4].copy_from_slice(&i.to_le_bytes());
+                block
+            })
+            .collect();
+        let output = isolate_for_key(&data, "alice").unwrap();
+        let distinct: std::collections::HashSet<&[u8]> = output.chunks(ISOLATION_BLOCK_BYTES).collect();
+        assert_eq!(distinct.len(), blocks);
+
+        // Each output block depends on its own input block only, never on a shared compressed key
+        let mut changed = data.clone();
+        changed[5 * ISOLATION_BLOCK_BYTES] ^= 1;
+        let changed = isolate_for_key(&changed, "alice").unwrap();
+        for (index, (before, after)) in output
+            .chunks(ISOLATION_BLOCK_BYTES)
+            .zip(changed.chunks(ISOLATION_BLOCK_BYTES))
+            .enumerate()
+        {
+            assert_eq!(before == after, index != 5, "block {}", index);
         }
-        Err(e) => {
-            error!(
-                client_ip = %addr,
-                user_agent = %user_agent,
-                endpoint = "/push",
-                sequence = packet.sequence,
-                error = %e,
-                "Failed to push to buffer"
-            );
-            StatusCode::INSUFFICIENT_STORAGE
+    }
+
+    #[tokio::test]
+    async fn test_per_key_isolation_on_random() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY, "other-key"],
+            "buffer_size": 4096,
+            "per_key_isolation": true,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        let data = random_bytes(32);
+        let router = test_router(state.clone());
+
+        let mut outputs = Vec::new();
+        for key in [TEST_API_KEY, "other-key"] {
+            // Both keys draw from identical buffer contents
+            state.buffer.push(data.clone()).unwrap();
+            let uri = format!("/api/random?bytes=32&encoding=hex&api_key={}", key);
+            let response = get(router.clone(), &uri).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = body_text(response).await;
+            assert_eq!(body, encode_hex(&isolate_for_key(&data, key).unwrap()));
+            outputs.push(body);
         }
+        assert_ne!(outputs[0], outputs[1]);
+        assert_ne!(outputs[0], encode_hex(&data));
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Parse arguments
-    let args = Args::parse();
+    #[test]
+    fn test_throttle_ratio() {
+        assert_eq!(throttle_ratio(50.0, 20.0), 0.0);
+        assert_eq!(throttle_ratio(20.0, 20.0), 0.0);
+        assert_eq!(throttle_ratio(15.0, 20.0), 0.25);
+        assert_eq!(throttle_ratio(5.0, 20.0), 0.75);
+        assert_eq!(throttle_ratio(0.0, 20.0), 1.0);
+    }
 
-    // Initialize tracing
-    let log_level = args.log_level.parse::<tracing::Level>()
-        .unwrap_or(tracing::Level::INFO);
+    #[tokio::test]
+    async fn test_low_buffer_throttle() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 1000,
+            "throttle_fill_percent": 50.0,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(1000), None, None);
+        let router = Router::new()
+            .route("/entropy", axum::routing::get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), throttle_low_buffer));
 
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .with_target(false)
-        .with_thread_ids(true)
-        .json()
-        .init();
+        // Drain the buffer step by step and measure the share of refused requests
+        state.buffer.push(random_bytes(800)).unwrap();
+        let mut ratios = Vec::new();
+        for fill in [800, 400, 250, 100, 0] {
+            state.buffer.pop(state.buffer.len() - fill).unwrap();
+            let mut throttled = 0;
+            for _ in 0..400 {
+                let response = router
+                    .clone()
+                    .oneshot(
+                        Request::get("/entropy")
+                            .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    let retry_after: u64 = response.headers()[hyper::header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+                    assert!((1..=10).contains(&retry_after));
+                    throttled += 1;
+                }
+            }
+            ratios.push(throttled as f64 / 400.0);
+        }
 
-    info!("QRNG Gateway v{}", env!("CARGO_PKG_VERSION"));
-    info!("The gateway acts as a data diode for the Quantis Appliance and receives pushed data from the collector.");
-    info!("Developed by Valer BOCAN, PhD, CSSLP - www.bocan.ro");
+        // Above the watermark nothing is refused; below it the share tracks the drop
+        assert_eq!(ratios[0], 0.0);
+        assert!((0.1..0.3).contains(&ratios[1]), "{:?}", ratios);
+        assert!((0.35..0.65).contains(&ratios[2]), "{:?}", ratios);
+        assert!((0.7..0.9).contains(&ratios[3]), "{:?}", ratios);
+        assert_eq!(ratios[4], 1.0);
+        assert!(ratios.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", ratios);
 
-    // Load configuration from environment variables
-    info!("Loading configuration from environment variables");
-    let config = GatewayConfig::from_env()
-        .context("Failed to load configuration from environment")?;
-    
-    info!("Listen address: {}", config.listen_address);
+        // With an empty buffer every quantum request is throttled, but invalid keys
+        // still get 401 without counting as failures
+        let router = test_router(state.clone());
+        let failures = state.metrics.requests_failed();
+        let response = get(router.clone(), "/api/random?bytes=16").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = get(router.clone(), "/api/random?bytes=16&api_key=wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = router
+            .clone()
+            .oneshot(Request::get("/api/random?bytes=16").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(state.metrics.requests_failed(), failures + 1);
 
-    // Create buffer with overflow policy
-    let buffer = if let Some(ttl) = config.buffer_ttl() {
-        EntropyBuffer::with_ttl(config.buffer_size, ttl)
-            .with_overflow_policy(config.overflow_policy())
-    } else {
-        EntropyBuffer::new(config.buffer_size)
-            .with_overflow_policy(config.overflow_policy())
-    };
+        // Requests that draw no quantum bytes are not throttled
+        let response = router
+            .clone()
+            .oneshot(
+                Request::head("/api/random?bytes=16")
+                    .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let seeded = format!("/api/prng?bytes=16&seed={}", "ab".repeat(PRNG_SEED_BYTES));
+        assert_eq!(get(router.clone(), &seeded).await.status(), StatusCode::OK);
 
-    info!("Buffer overflow policy: {:?}", config.overflow_policy());
+        // Neither are endpoints that fall back to the CSPRNG
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 1000,
+            "throttle_fill_percent": 50.0,
+            "fallback_csprng": true,
+        }))
+        .unwrap();
+        let router = test_router(AppState::new(config, EntropyBuffer::new(1000), None, None));
+        let response = get(router.clone(), "/api/random?bytes=16").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ENTROPY_SOURCE_HEADER], "fallback");
+        let response = get(router, "/api/bool?count=8").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
 
-    // Create signer for push mode
-    let signer = if let Some(key) = config.hmac_secret_key.as_ref() {
-        let key_bytes = hex::decode(key)
-            .context("Invalid HMAC key (must be hex-encoded)")?;
-        Some(PacketSigner::new(key_bytes))
-    } else {
-        None
-    };
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 1000,
+            "request_timeout_ms": 50,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(1000), None, None);
 
-    // Create application state
-    let state = AppState {
-        config: config.clone(),
-        buffer: buffer.clone(),
-        metrics: Metrics::new(),
-        signer,
-        start_time: Instant::now(),
-        rate_limiter: Arc::new(RateLimiter::new(config.rate_limit_per_second)),
-    };
+        let slow = Router::new()
+            .route(
+                "/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .route("/fast", axum::routing::get(|| async { "done" }))
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), enforce_request_timeout));
+        let call = |uri: &'static str| {
+            slow.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
 
-    // Parse listen address
-    let addr: SocketAddr = config.listen_address.parse()
-        .context("Invalid listen address")?;
+        let start = Instant::now();
+        let response = call("/slow").await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(call("/fast").await.unwrap().status(), StatusCode::OK);
 
-    // Create cancellation token for graceful shutdown
-    let cancel_token = CancellationToken::new();
-    let cancel_token_signal = cancel_token.clone();
+        // A status long-poll may outlast the ordinary deadline
+        let router = test_router(state.clone());
+        let buffer = state.buffer.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+            buffer.push(random_bytes(500)).unwrap();
+        });
+        let response = get(router, "/api/status?wait_for=healthy&timeout_ms=2000").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 
-    // Build HTTP router for gateway API
-    let app = Router::new()
-        .route("/api/random", get(serve_random))
-        .route("/api/integers", get(serve_integers))
-        .route("/api/floats", get(serve_floats))
-        .route("/api/uuid", get(serve_uuid))
-        .route("/api/status", get(get_status))
-        .route("/api/test/monte-carlo", get(monte_carlo_test))
-        .route("/health", get(health_check))
-        .route("/metrics", get(get_metrics))
-        .route("/push", post(receive_push))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+    #[test]
+    fn test_rate_limiter_burst() {
+        let take_all = |limiter: &RateLimiter| (0..100).take_while(|_| limiter.check("key")).count();
 
-    info!("Gateway server starting on {}", addr);
+        // A fresh key starts with a full burst, then refills at the sustained rate
+        let limiter = RateLimiter::new(10, 40);
+        assert_eq!(take_all(&limiter), 40);
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        assert!((2..=4).contains(&take_all(&limiter)));
 
-    // Handle Ctrl+C for graceful shutdown
-    tokio::spawn(async move {
-        match tokio::signal::ctrl_c().await {
-            Ok(()) => {
-                info!("Received Ctrl+C, shutting down");
-                cancel_token_signal.cancel();
-            }
-            Err(e) => error!("Failed to listen for Ctrl+C: {}", e),
+        // Idling accumulates tokens beyond one second of rate, up to the burst
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert!((15..=20).contains(&take_all(&limiter)));
+        assert!(limiter.peek("key") < 1.0);
+
+        // Without a separate burst the bucket holds one second of rate
+        let limiter = RateLimiter::new(10, 10);
+        assert_eq!(take_all(&limiter), 10);
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert_eq!(take_all(&limiter), 10);
+    }
+
+    #[tokio::test]
+    async fn test_quota_reports_remaining_tokens() {
+        let config: GatewayConfig = serde_json::from_value(serde_json::json!({
+            "api_keys": [TEST_API_KEY],
+            "buffer_size": 4096,
+            "rate_limit_per_second": 5,
+        }))
+        .unwrap();
+        let state = AppState::new(config, EntropyBuffer::new(4096), None, None);
+        state.buffer.push(random_bytes(4096)).unwrap();
+        let router = test_router(state);
+
+        let quota = |response: Response| async {
+            serde_json::from_str::<QuotaResponse>(&body_text(response).await).unwrap()
+        };
+
+        // A fresh key has a full bucket, and checking does not consume tokens
+        for _ in 0..3 {
+            let response = get(router.clone(), "/api/quota").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let quota = quota(response).await;
+            assert_eq!(quota.tokens_remaining, 5.0);
+            assert_eq!(quota.rate_limit_per_second, 5);
+            assert_eq!(quota.burst, 5);
         }
-    });
 
-    // Start server with graceful shutdown
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    let server = axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(async move {
-        cancel_token.cancelled().await;
-        info!("Server is shutting down");
-    });
+        let mut previous = 5.0;
+        for _ in 0..3 {
+            let response = get(router.clone(), "/api/random?bytes=8").await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let level = quota(get(router.clone(), "/api/quota").await).await.tokens_remaining;
+            // One token consumed; refill during the test adds only a fraction
+            assert!(level < previous - 0.5, "{} not below {}", level, previous);
+            previous = level;
+        }
+        assert!(previous < 3.0);
 
-    if let Err(e) = server.await {
-        error!("Server error: {}", e);
+        let response = get(router.clone(), "/api/quota?api_key=wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn test_derive_endpoint() {
+        let state = test_state(4096);
+        let ikm = random_bytes(2 * DERIVE_IKM_LEN);
+        state.buffer.push(ikm.clone()).unwrap();
+        let router = test_router(state.clone());
+
+        let response = get(router.clone(), "/api/derive?bytes=64&info=signing%20key").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let derived = qrng_core::crypto::decode_hex(&body_text(response).await).unwrap();
+        assert_eq!(derived.len(), 64);
+
+        // Output is HKDF over the popped entropy with the given context
+        let expected = EntropyMixer::new(MixingStrategy::Hkdf)
+            .derive(&[ikm[..DERIVE_IKM_LEN].to_vec()], &[], b"signing key", 64)
+            .unwrap();
+        assert_eq!(derived, expected);
+        assert_eq!(state.buffer.len(), DERIVE_IKM_LEN);
+
+        // Invalid sizes are rejected before entropy is consumed
+        for uri in [
+            "/api/derive?bytes=0",
+            &format!("/api/derive?bytes={}", MAX_DERIVE_LEN + 1),
+            "/api/derive?bytes=16&encoding=octal",
+        ] {
+            assert_eq!(get(router.clone(), uri).await.status(), StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(state.buffer.len(), DERIVE_IKM_LEN);
+
+        let response = get(router.clone(), "/api/derive?bytes=16&encoding=binary").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(state.buffer.is_empty());
+
+        let response = get(router, "/api/derive?bytes=16").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    async fn reserve(router: Router, bytes: usize) -> Response {
+        router
+            .oneshot(
+                Request::post(format!("/api/reserve?bytes={}", bytes))
+                    .header("authorization", format!("Bearer {}", TEST_API_KEY))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_partial_retrieval() {
+        let state = test_state(4096);
+        let data = random_bytes(1024);
+        state.buffer.push(data.clone()).unwrap();
+        let router = test_router(state.clone());
+
+        let response = reserve(router.clone(), 64).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let reservation: ReservationResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(reservation.bytes, 64);
+        assert_eq!(reservation.expires_in_secs, RESERVATION_TTL.as_secs());
+        assert_eq!(state.buffer.len(), 1024 - 64);
+
+        // Slices come back in any order and reassemble the reserved block
+        let uri = |offset: usize, len: usize| {
+            format!("/api/reserve/{}?offset={}&len={}&encoding=binary", reservation.token, offset, len)
+        };
+        let tail = get(router.clone(), &uri(32, 32)).await;
+        assert_eq!(tail.status(), StatusCode::OK);
+        let tail = axum::body::to_bytes(tail.into_body(), usize::MAX).await.unwrap();
+        let head = get(router.clone(), &uri(0, 32)).await;
+        assert_eq!(head.status(), StatusCode::OK);
+        let head = axum::body::to_bytes(head.into_body(), usize::MAX).await.unwrap();
+        assert_eq!([head, tail].concat(), data[..64]);
+
+        // Fully retrieved reservations are freed
+        assert_eq!(get(router.clone(), &uri(0, 1)).await.status(), StatusCode::NOT_FOUND);
+
+        // Bad sizes and unknown tokens
+        assert_eq!(reserve(router.clone(), 0).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(reserve(router.clone(), MAX_RESERVATION_BYTES + 1).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(reserve(router.clone(), 2048).await.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = get(router, "/api/reserve/unknown?len=1").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_reservation_double_retrieval() {
+        let state = test_state(4096);
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state);
+
+        let response = reserve(router.clone(), 16).await;
+        let reservation: ReservationResponse = serde_json::from_str(&body_text(response).await).unwrap();
+        let uri = |offset: usize, len: usize| format!("/api/reserve/{}?offset={}&len={}", reservation.token, offset, len);
+
+        assert_eq!(get(router.clone(), &uri(0, 8)).await.status(), StatusCode::OK);
+        // The same bytes are never served twice, even partially
+        assert_eq!(get(router.clone(), &uri(0, 8)).await.status(), StatusCode::CONFLICT);
+        assert_eq!(get(router.clone(), &uri(4, 8)).await.status(), StatusCode::CONFLICT);
+        assert_eq!(get(router.clone(), &uri(8, 16)).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(get(router.clone(), &uri(8, 0)).await.status(), StatusCode::BAD_REQUEST);
+
+        let response = get(router, &uri(8, 8)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await.len(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_reservation_cap_and_expiry() {
+        let mut state = test_state(4096);
+        state.reservations = Arc::new(ReservationStore::new(std::time::Duration::from_millis(50)));
+        state.buffer.push(random_bytes(1024)).unwrap();
+        let router = test_router(state.clone());
+
+        let mut tokens = Vec::new();
+        for _ in 0..MAX_RESERVATIONS_PER_KEY {
+            let response = reserve(router.clone(), 8).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let reservation: ReservationResponse = serde_json::from_str(&body_text(response).await).unwrap();
+            tokens.push(reservation.token);
+        }
+        // The cap is enforced before any entropy is popped
+        let available = state.buffer.len();
+        assert_eq!(reserve(router.clone(), 8).await.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(state.buffer.len(), available);
+
+        // Reservations belong to the key that made them
+        assert_eq!(
+            state.reservations.take_slice(&tokens[0], "other-key", 0, None),
+            Err(SliceError::NotFound)
+        );
+
+        // Expired reservations are freed and no longer count against the cap
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let response = get(router.clone(), &format!("/api/reserve/{}?len=8", tokens[0])).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(reserve(router, 8).await.status(), StatusCode::OK);
+    }
 }
@@ -22,7 +22,9 @@
 //! - `get_random_bytes`: Fetch random bytes
 //! - `get_random_integers`: Generate random integers in range
 //! - `get_random_floats`: Generate random floats
-//! - `get_random_uuid`: Generate UUID v4
+//! - `get_random_uuid`: Generate UUID v4 or time-ordered v7
+//! - `get_random_bool`: Flip a (possibly biased) coin
+//! - `get_random_gaussian`: Generate normally distributed values
 //! - `get_status`: Query gateway status
 //! - `get_data_quality`: Test random data quality using Monte Carlo simulation
 
@@ -48,12 +50,14 @@ pub struct QrngMcpServer {
     gateway_url: String,
     gateway_api_key: String,
     http_client: reqwest::Client,
+    /// Gateway's `/api/random` size limit, learned from its status on first use
+    max_request_bytes: std::sync::Arc<tokio::sync::OnceCell<usize>>,
 }
 
 /// Arguments for get_random_bytes tool
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GetRandomBytesArgs {
-    #[schemars(description = "Number of bytes to fetch (1-65536)")]
+    #[schemars(description = "Number of bytes to fetch (1 up to the gateway's limit, 65536 by default)")]
     pub count: usize,
     #[schemars(description = "Output encoding format: hex or base64")]
     pub encoding: Option<String>,
@@ -82,6 +86,74 @@ pub struct GetRandomFloatsArgs {
 pub struct GetRandomUuidArgs {
     #[schemars(description = "Number of UUIDs to generate")]
     pub count: Option<usize>,
+    #[schemars(description = "UUID version: 4 (random, default) or 7 (time-ordered, suited to database keys)")]
+    pub version: Option<u8>,
+}
+
+/// Arguments for get_data_quality tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetDataQualityArgs {
+    #[schemars(description = "Monte Carlo iterations (1-10000000, default 500000); more is slower but more accurate")]
+    pub iterations: Option<u64>,
+}
+
+/// Arguments for get_random_bool tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetRandomBoolArgs {
+    #[schemars(description = "Probability of true, between 0 and 1 (default 0.5)")]
+    pub p: Option<f64>,
+    #[schemars(description = "Number of booleans to generate (default 1)")]
+    pub count: Option<usize>,
+}
+
+/// Arguments for get_random_gaussian tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetRandomGaussianArgs {
+    #[schemars(description = "Number of values to generate")]
+    pub count: usize,
+    #[schemars(description = "Mean of the distribution (default 0)")]
+    pub mean: Option<f64>,
+    #[schemars(description = "Standard deviation of the distribution (default 1)")]
+    pub stddev: Option<f64>,
+}
+
+/// Convert 8 random bytes to a uniform float in [0, 1) using the top 53 bits
+fn unit_float(bytes: &[u8]) -> f64 {
+    let word = u64::from_le_bytes(bytes.try_into().expect("8-byte chunk"));
+    (word >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Request size limit assumed for gateways whose status does not report one
+const DEFAULT_MAX_REQUEST_BYTES: usize = 65_536;
+
+/// Request size limit advertised in a gateway status document
+fn max_request_bytes_from_status(status: &serde_json::Value) -> usize {
+    status
+        .get("max_request_bytes")
+        .and_then(serde_json::Value::as_u64)
+        .filter(|&max| max > 0)
+        .map_or(DEFAULT_MAX_REQUEST_BYTES, |max| max as usize)
+}
+
+/// Booleans that are true with probability `p`, one per 8 random bytes
+fn bools_from_bytes(bytes: &[u8], p: f64) -> Vec<bool> {
+    bytes.chunks_exact(8).map(|chunk| unit_float(chunk) < p).collect()
+}
+
+/// Normally distributed values via the Box-Muller transform, two per 16 random bytes
+fn gaussians_from_bytes(bytes: &[u8], count: usize, mean: f64, stddev: f64) -> Vec<f64> {
+    let mut values = Vec::with_capacity(count + 1);
+    for pair in bytes.chunks_exact(16) {
+        // 1 - u lies in (0, 1], keeping the logarithm finite
+        let u1 = 1.0 - unit_float(&pair[..8]);
+        let u2 = unit_float(&pair[8..]);
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let angle = 2.0 * std::f64::consts::PI * u2;
+        values.push(mean + stddev * radius * angle.cos());
+        values.push(mean + stddev * radius * angle.sin());
+    }
+    values.truncate(count);
+    values
 }
 
 
@@ -95,15 +167,84 @@ impl QrngMcpServer {
             gateway_url,
             gateway_api_key,
             http_client: reqwest::Client::new(),
+            max_request_bytes: Default::default(),
         }
     }
 
+    /// Base URL of the gateway requests are forwarded to
+    pub fn gateway_url(&self) -> &str {
+        &self.gateway_url
+    }
+
+    /// Largest byte count the gateway serves in one request, queried once from `/api/status`
+    async fn max_request_bytes(&self) -> Result<usize, ErrorData> {
+        self.max_request_bytes
+            .get_or_try_init(|| async {
+                let url = format!("{}/api/status", self.gateway_url);
+                let response = self.http_client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {}", self.gateway_api_key))
+                    .send()
+                    .await
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to contact gateway: {}", e), None))?;
+
+                if !response.status().is_success() {
+                    return Err(ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Gateway returned error: {}", response.status()),
+                        None
+                    ));
+                }
+
+                let body = response.bytes().await
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to read response: {}", e), None))?;
+                let status: serde_json::Value = serde_json::from_slice(&body)
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Invalid status response: {}", e), None))?;
+                Ok(max_request_bytes_from_status(&status))
+            })
+            .await
+            .copied()
+    }
+
+    /// Fetch raw random bytes from the gateway for local transformations
+    async fn fetch_entropy(&self, count: usize) -> Result<Vec<u8>, ErrorData> {
+        let url = format!("{}/api/random?bytes={}&encoding=binary", self.gateway_url, count);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.gateway_api_key))
+            .send()
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to contact gateway: {}", e), None))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Gateway returned error: {}", status),
+                None
+            ));
+        }
+
+        let bytes = response.bytes().await
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to read response: {}", e), None))?;
+        if bytes.len() != count {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Gateway returned {} bytes, expected {}", bytes.len(), count),
+                None
+            ));
+        }
+        Ok(bytes.to_vec())
+    }
+
     /// Fetch random bytes from quantum entropy source via gateway
     #[tool(description = "Fetch random bytes from quantum entropy source")]
     async fn get_random_bytes(&self, Parameters(args): Parameters<GetRandomBytesArgs>) -> Result<String, ErrorData> {
-        // Validate count
-        if args.count == 0 || args.count > 65536 {
-            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Count must be between 1 and 65536", None));
+        // Validate count against the gateway's limit
+        let max = self.max_request_bytes().await?;
+        if args.count == 0 || args.count > max {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, format!("Count must be between 1 and {}", max), None));
         }
 
         let encoding = args.encoding.as_deref().unwrap_or("hex");
@@ -203,18 +344,22 @@ impl QrngMcpServer {
             .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to read response: {}", e), None))
     }
 
-    /// Generate random UUID v4 via gateway
-    #[tool(description = "Generate random UUID v4")]
+    /// Generate random UUID v4 or v7 via gateway
+    #[tool(description = "Generate random UUID v4, or time-ordered UUID v7")]
     async fn get_random_uuid(&self, Parameters(args): Parameters<GetRandomUuidArgs>) -> Result<String, ErrorData> {
         let count = args.count.unwrap_or(1);
+        let version = args.version.unwrap_or(4);
 
-        // Validate count
+        // Validate arguments
         if count == 0 || count > 100 {
             return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Count must be between 1 and 100", None));
         }
+        if version != 4 && version != 7 {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Version must be 4 or 7", None));
+        }
 
         // Call gateway API
-        let url = format!("{}/api/uuid?count={}", self.gateway_url, count);
+        let url = format!("{}/api/uuid?count={}&version={}", self.gateway_url, count, version);
         
         let response = self.http_client
             .get(&url)
@@ -236,6 +381,43 @@ impl QrngMcpServer {
             .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Failed to read response: {}", e), None))
     }
 
+    /// Flip a coin that lands true with probability p, using gateway entropy
+    #[tool(description = "Generate random booleans (coin flips) that are true with probability p (default 0.5)")]
+    async fn get_random_bool(&self, Parameters(args): Parameters<GetRandomBoolArgs>) -> Result<String, ErrorData> {
+        let count = args.count.unwrap_or(1);
+        let p = args.p.unwrap_or(0.5);
+
+        // Validate arguments
+        if count == 0 || count > 1000 {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Count must be between 1 and 1000", None));
+        }
+        if !(0.0..=1.0).contains(&p) {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Probability p must be between 0 and 1", None));
+        }
+
+        let bytes = self.fetch_entropy(count * 8).await?;
+        Ok(serde_json::to_string(&bools_from_bytes(&bytes, p)).unwrap())
+    }
+
+    /// Generate normally distributed values from gateway entropy
+    #[tool(description = "Generate normally distributed random numbers with the given mean (default 0) and standard deviation (default 1)")]
+    async fn get_random_gaussian(&self, Parameters(args): Parameters<GetRandomGaussianArgs>) -> Result<String, ErrorData> {
+        let mean = args.mean.unwrap_or(0.0);
+        let stddev = args.stddev.unwrap_or(1.0);
+
+        // Validate arguments
+        if args.count == 0 || args.count > 1000 {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Count must be between 1 and 1000", None));
+        }
+        if !mean.is_finite() || !stddev.is_finite() || stddev <= 0.0 {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Mean must be finite and stddev must be a positive number", None));
+        }
+
+        // Box-Muller yields two values per 16 bytes
+        let bytes = self.fetch_entropy(args.count.div_ceil(2) * 16).await?;
+        Ok(serde_json::to_string(&gaussians_from_bytes(&bytes, args.count, mean, stddev)).unwrap())
+    }
+
     /// Get entropy buffer status and health from gateway
     #[tool(description = "Get entropy buffer status and health")]
     async fn get_status(&self) -> Result<String, ErrorData> {
@@ -263,13 +445,19 @@ impl QrngMcpServer {
     }
 
     /// Test random data quality using Monte Carlo π estimation (via gateway)
-    #[tool(description = "Test the quality of quantum random data using Monte Carlo π estimation. Returns statistical metrics about randomness quality.")]
-    async fn get_data_quality(&self) -> Result<String, ErrorData> {
-        // Use default iterations (500k) for quality testing
-        const ITERATIONS: u64 = 500_000;
-        
+    #[tool(description = "Test the quality of quantum random data using Monte Carlo π estimation. The iteration count (default 500000, up to 10000000) trades speed for accuracy. Returns statistical metrics about randomness quality.")]
+    async fn get_data_quality(&self, Parameters(args): Parameters<GetDataQualityArgs>) -> Result<String, ErrorData> {
+        // Same bound as the gateway's Monte Carlo endpoint
+        const DEFAULT_ITERATIONS: u64 = 500_000;
+        const MAX_ITERATIONS: u64 = 10_000_000;
+
+        let iterations = args.iterations.unwrap_or(DEFAULT_ITERATIONS);
+        if iterations == 0 || iterations > MAX_ITERATIONS {
+            return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "Iterations must be between 1 and 10000000", None));
+        }
+
         // Call gateway's Monte Carlo endpoint
-        let url = format!("{}/api/test/monte-carlo?iterations={}", self.gateway_url, ITERATIONS);
+        let url = format!("{}/api/test/monte-carlo?iterations={}", self.gateway_url, iterations);
         
         let response = self.http_client
             .get(&url)
@@ -312,12 +500,57 @@ impl ServerHandler for QrngMcpServer {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic stand-in for gateway entropy
+    fn pseudo_random_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
 
+    #[test]
+    fn test_max_request_bytes_from_status() {
+        let status = serde_json::json!({"status": "healthy", "max_request_bytes": 1024});
+        assert_eq!(max_request_bytes_from_status(&status), 1024);
 
+        // Older gateways do not report a limit
+        let status = serde_json::json!({"status": "healthy"});
+        assert_eq!(max_request_bytes_from_status(&status), DEFAULT_MAX_REQUEST_BYTES);
+        let status = serde_json::json!({"max_request_bytes": 0});
+        assert_eq!(max_request_bytes_from_status(&status), DEFAULT_MAX_REQUEST_BYTES);
+    }
 
+    #[test]
+    fn test_bools_follow_probability() {
+        let bytes = pseudo_random_bytes(8 * 10_000);
+        let ratio = |p| bools_from_bytes(&bytes, p).iter().filter(|&&b| b).count() as f64 / 10_000.0;
 
+        assert_eq!(ratio(0.0), 0.0);
+        assert_eq!(ratio(1.0), 1.0);
+        assert!((ratio(0.5) - 0.5).abs() < 0.03);
+        assert!((ratio(0.2) - 0.2).abs() < 0.03);
+    }
 
-
-
-
-
+    #[test]
+    fn test_gaussians_match_parameters() {
+        let count: usize = 9_999;
+        let bytes = pseudo_random_bytes(count.div_ceil(2) * 16);
+        let values = gaussians_from_bytes(&bytes, count, 10.0, 2.0);
+        assert_eq!(values.len(), count);
+        assert!(values.iter().all(|v| v.is_finite()));
+
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+        assert!((mean - 10.0).abs() < 0.1, "mean {}", mean);
+        assert!((variance.sqrt() - 2.0).abs() < 0.1, "stddev {}", variance.sqrt());
+    }
+}
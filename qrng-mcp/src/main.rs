@@ -17,6 +17,8 @@
 //! This is a thin AI-friendly wrapper around the QRNG Gateway API.
 //! It has no local buffer or QRNG logic - all operations are delegated to the gateway.
 
+use anyhow::Context;
+use clap::Parser;
 use qrng_mcp::QrngMcpServer;
 use rmcp::transport::streamable_http_server::{
     session::local::LocalSessionManager, tower::StreamableHttpService,
@@ -27,8 +29,37 @@ use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use axum::{Router, routing::{get, post, delete}};
 
+#[derive(Parser, Debug)]
+#[command(name = "qrng-mcp")]
+#[command(about = "QRNG MCP Server - Exposes the QRNG Gateway as MCP tools", long_about = None)]
+struct Args {
+    /// Address and port to listen on
+    #[arg(short, long, env = "MCP_BIND_ADDR", default_value = "0.0.0.0:8080")]
+    bind: SocketAddr,
+
+    /// QRNG Gateway URL to forward requests to
+    #[arg(long, env = "QRNG_GATEWAY_URL", default_value = "http://qrng-gateway:7764")]
+    gateway_url: String,
+
+    /// API key for the gateway (one of its QRNG_API_KEYS)
+    #[arg(long, env = "QRNG_GATEWAY_API_KEY", hide_env_values = true)]
+    gateway_api_key: Option<String>,
+}
+
+impl Args {
+    /// The gateway API key, or an error explaining how to supply one
+    fn gateway_api_key(&self) -> anyhow::Result<String> {
+        self.gateway_api_key
+            .clone()
+            .filter(|key| !key.is_empty())
+            .context("No gateway API key: pass --gateway-api-key or set QRNG_GATEWAY_API_KEY")
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -40,16 +71,10 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting QRNG MCP Server with SSE and Streamable HTTP transports");
 
-    // Parse configuration from environment
-    let bind_addr: SocketAddr = std::env::var("MCP_BIND_ADDR")
-        .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
-        .parse()?;
-    
-    let gateway_url = std::env::var("QRNG_GATEWAY_URL")
-        .unwrap_or_else(|_| "http://qrng-gateway:7764".to_string());
-    
-    let gateway_api_key = std::env::var("QRNG_GATEWAY_API_KEY")
-        .expect("QRNG_GATEWAY_API_KEY must be set");
+    // Command-line arguments, falling back to the environment
+    let bind_addr = args.bind;
+    let gateway_url = args.gateway_url.clone();
+    let gateway_api_key = args.gateway_api_key()?;
 
     tracing::info!("Gateway URL: {}", gateway_url);
     tracing::info!("MCP server will forward all requests to the gateway");
@@ -135,3 +160,39 @@ async fn legacy_message_handler(
     tracing::warn!("Legacy /message endpoint called - not fully implemented");
     Err(axum::http::StatusCode::NOT_IMPLEMENTED)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_from_args() {
+        let args = Args::try_parse_from([
+            "qrng-mcp",
+            "--bind",
+            "127.0.0.1:9090",
+            "--gateway-url",
+            "http://localhost:7764",
+            "--gateway-api-key",
+            "test-key",
+        ])
+        .unwrap();
+        assert_eq!(args.bind, SocketAddr::from(([127, 0, 0, 1], 9090)));
+
+        let server = QrngMcpServer::new(args.gateway_url.clone(), args.gateway_api_key().unwrap());
+        assert_eq!(server.gateway_url(), "http://localhost:7764");
+
+        assert!(Args::try_parse_from(["qrng-mcp", "--bind", "not-an-address"]).is_err());
+    }
+
+    #[test]
+    fn test_missing_api_key_is_an_error() {
+        let mut args = Args::try_parse_from(["qrng-mcp"]).unwrap();
+        args.gateway_api_key = None;
+        let error = args.gateway_api_key().unwrap_err();
+        assert!(error.to_string().contains("--gateway-api-key"));
+
+        args.gateway_api_key = Some(String::new());
+        assert!(args.gateway_api_key().is_err());
+    }
+}